@@ -1,5 +1,5 @@
 use std::fmt;
-use syn::{Attribute, Ident, Lit, Meta, NestedMeta};
+use syn::{Attribute, Ident, Lit, LitStr, Meta, NestedMeta};
 use syn::parse::Parse;
 
 #[derive(Debug)]
@@ -36,6 +36,22 @@ pub(crate) fn get_field_attr_once<'a, A, T>(
   sub_key: &str,
   known_subkeys: &[&str]
 ) -> Result<T, AttrError> where A: IntoIterator<Item = &'a Attribute>, T: Parse {
+  let strlit = get_field_lit_str_once(field_ident, attrs, key, sub_key, known_subkeys)?;
+  strlit.parse().map_err(|_| AttrError::CannotParseAttribute(field_ident.clone(), key.to_owned(), sub_key.to_owned()))
+}
+
+/// Get an attribute on a field or a variant that must appear only once with the following syntax:
+///
+///   #[key(sub_key = "lit")]
+///
+/// As opposed to `get_field_attr_once`, the string literal is not parsed and is returned as-is.
+pub(crate) fn get_field_lit_str_once<'a, A>(
+  field_ident: &Ident,
+  attrs: A,
+  key: &str,
+  sub_key: &str,
+  known_subkeys: &[&str]
+) -> Result<LitStr, AttrError> where A: IntoIterator<Item = &'a Attribute> {
   let mut lit = None;
 
   for attr in attrs.into_iter() {
@@ -51,7 +67,7 @@ pub(crate) fn get_field_attr_once<'a, A, T>(
               }
 
               if let Lit::Str(ref strlit) = mnv.lit {
-                lit = Some(strlit.parse().map_err(|_| AttrError::CannotParseAttribute(field_ident.clone(), key.to_owned(), sub_key.to_owned()))?);
+                lit = Some(strlit.clone());
               }
             } else {
               let ident_str = mnv.path.segments.first().map(|seg| seg.ident.to_string()).unwrap_or_else(String::new);
@@ -21,9 +21,17 @@
 //!
 //! [See the full documentation here](https://docs.rs/luminance-derive/latest/luminance_derive/derive.UniformInterface.html)
 //!
+//! # `UniformBlock`
+//!
+//! This macro allows to derive the [`UniformBlock`] trait for a custom `struct` type, checking its
+//! *std140* layout at compile time.
+//!
+//! [See the full documentation here](https://docs.rs/luminance-derive/latest/luminance_derive/derive.UniformBlock.html)
+//!
 //! [luminance]: https://docs.rs/luminance
 //! [`Vertex`]: https://docs.rs/luminance/latest/luminance/vertex/trait.Vertex.html
 //! [`Semantics`]: https://docs.rs/luminance/latest/luminance/vertex/trait.Semantics.html
+//! [`UniformBlock`]: https://docs.rs/luminance/latest/luminance/buffer/trait.UniformBlock.html
 
 #![deny(missing_docs)]
 
@@ -31,10 +39,12 @@ extern crate proc_macro;
 
 mod attrib;
 mod semantics;
+mod uniform_block;
 mod uniform_interface;
 mod vertex;
 
 use crate::semantics::generate_enum_semantics_impl;
+use crate::uniform_block::generate_uniform_block_impl;
 use crate::uniform_interface::generate_uniform_interface_impl;
 use crate::vertex::generate_vertex_impl;
 use proc_macro::TokenStream;
@@ -68,7 +78,10 @@ use syn::{self, Data, DeriveInput, parse_macro_input};
 ///     type family that associates a single constant (i.e. the semantics) that the vertex attribute
 ///     uses.
 ///
-/// Once all those requirements are met, you can derive [`Vertex`] pretty easily.
+/// Once all those requirements are met, you can derive [`Vertex`] pretty easily. Those
+/// requirements are checked at compile-time: a field whose type is not a [`VertexAttrib`], or
+/// whose `HasSemantics::Sem` is not the semantics type of the vertex, fails to compile with an
+/// error pointing at that field’s type.
 ///
 /// > Note: feel free to look at the [`Semantics`] proc-macro as well, that provides a way
 /// > to generate semantics types in order to completely both implement [`Semantics`] for an
@@ -131,7 +144,35 @@ pub fn derive_vertex(input: TokenStream) -> TokenStream {
 
 /// The [`Semantics`] derive proc-macro.
 ///
+/// Each variant requires a `#[sem(name = "…", repr = "…", wrapper = "…")]` attribute, giving the
+/// name of the semantics in shaders, the type of its values and the name of the vertex attribute
+/// type to generate.
+///
+/// ## Interpolation intent
+///
+/// A variant can also declare how the fragment shader input carrying its attribute must be
+/// interpolated, with the `interpolation` sub key – `"smooth"`, `"flat"`, `"noperspective"`,
+/// `"centroid"` or `"sample"`. That input is expected to be named after the semantics, prefixed
+/// with `v_`; use the `varying` sub key to name it otherwise. Programs using the semantics warn
+/// about inputs declared with another [`Interpolation`].
+///
+/// ```rust
+/// # use luminance_derive::Semantics;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Semantics)]
+/// pub enum Semantics {
+///   #[sem(name = "position", repr = "[f32; 3]", wrapper = "VertexPosition")]
+///   Position,
+///   // atlas coordinates must not be extrapolated out of the primitive with MSAA
+///   #[sem(name = "uv", repr = "[f32; 2]", wrapper = "VertexUV", interpolation = "centroid")]
+///   UV,
+///   #[sem(name = "material", repr = "u32", wrapper = "VertexMaterial", interpolation = "flat", varying = "v_mat")]
+///   Material,
+/// }
+/// ```
+///
 /// [`Semantics`]: https://docs.rs/luminance/latest/luminance/vertex/trait.Semantics.html
+/// [`Interpolation`]: https://docs.rs/luminance/latest/luminance/vertex/enum.Interpolation.html
 #[proc_macro_derive(Semantics, attributes(sem))]
 pub fn derive_semantics(input: TokenStream) -> TokenStream {
   let di: DeriveInput = parse_macro_input!(input);
@@ -202,8 +243,25 @@ pub fn derive_semantics(input: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// The mapping can be any GLSL uniform name, such as an array item or a struct member, so that
+/// you don’t have to rename your Rust fields to adapt existing shaders:
+///
+/// ```
+/// # use luminance::shader::program::Uniform;
+/// # use luminance_derive::UniformInterface;
+///
+/// #[derive(Debug, UniformInterface)]
+/// struct MyIface {
+///   #[uniform(name = "u_Time")]
+///   time: Uniform<f32>,
+///   #[uniform(name = "lights[0].color")]
+///   first_light_color: Uniform<[f32; 3]> // maps the color of the first light
+/// }
+/// ```
+///
 /// Finally, you can mix both attributes if you want to change the mapping and have an unbound
-/// uniform if it cannot be mapped:
+/// uniform if it cannot be mapped. In that case, the failure is reported as a warning of the
+/// built program instead of an error:
 ///
 /// ```
 /// # use luminance::shader::program::Uniform;
@@ -234,3 +292,57 @@ pub fn derive_uniform_interface(input: TokenStream) -> TokenStream {
     _ => panic!("only structs are currently supported for deriving UniformInterface")
   }
 }
+
+/// The [`UniformBlock`] derive proc-macro.
+///
+/// Uniform blocks declared with `layout (std140)` in *GLSL* follow peculiar alignment rules: `vec3`
+/// and `vec4` members, structures and matrix columns are aligned to 16 bytes. A Rust type which
+/// layout doesn’t match them silently corrupts the data read by shaders.
+///
+/// This proc-macro implements [`UniformBlock`] and [`Std140`] for a `#[repr(C)]` struct, checking
+/// at compile time that every field is at its *std140* offset. Fields must implement [`Std140`],
+/// which scalars, vectors, `M44` and other derived uniform blocks – nested structures – do. If a
+/// field is misplaced, compilation fails with an error naming it; explicit padding fields fix
+/// that:
+///
+/// ```
+/// # use luminance_derive::UniformBlock;
+///
+/// #[derive(Clone, Copy, UniformBlock)]
+/// #[repr(C)]
+/// struct Light {
+///   color: [f32; 3],
+///   intensity: f32, // fits in the padding of the vec3
+///   position: [f32; 3],
+///   _pad: f32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use luminance_derive::UniformBlock;
+///
+/// #[derive(Clone, Copy, UniformBlock)]
+/// #[repr(C)]
+/// struct Light {
+///   intensity: f32,
+///   color: [f32; 3], // std140 aligns it to 16 bytes: add 12 bytes of padding before it
+/// }
+/// ```
+///
+/// [`UniformBlock`]: https://docs.rs/luminance/latest/luminance/buffer/trait.UniformBlock.html
+/// [`Std140`]: https://docs.rs/luminance/latest/luminance/buffer/trait.Std140.html
+#[proc_macro_derive(UniformBlock)]
+pub fn derive_uniform_block(input: TokenStream) -> TokenStream {
+  let di: DeriveInput = parse_macro_input!(input);
+
+  match di.data {
+    Data::Struct(struct_) => {
+      match generate_uniform_block_impl(di.ident, &di.attrs, &di.generics, struct_) {
+        Ok(impl_) => impl_,
+        Err(e) => panic!("{}", e)
+      }
+    }
+
+    _ => panic!("only structs are currently supported for deriving UniformBlock")
+  }
+}
@@ -1,10 +1,11 @@
-use crate::attrib::{AttrError, get_field_attr_once};
+use crate::attrib::{AttrError, get_field_attr_once, get_field_lit_str_once};
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
 use std::fmt;
 use syn::{Attribute, DataEnum, Ident, Type};
 
-const KNOWN_SUBKEYS: &[&str] = &["name", "repr", "wrapper"];
+const KNOWN_SUBKEYS: &[&str] = &["name", "repr", "wrapper", "interpolation", "varying"];
 
 #[derive(Debug)]
 pub(crate) enum SemanticsImplError {
@@ -44,13 +45,58 @@ where A: Iterator<Item = &'a Attribute> + Clone {
   Ok((sem_name, sem_repr, sem_wrapper))
 }
 
+/// Get the optional interpolation intent of a semantics.
+///
+///   (interpolation variant, varying name)
+fn get_vertex_sem_interpolation<'a, A>(
+  var_name: &Ident,
+  sem_name: &str,
+  attrs: A
+) -> Result<Option<(Ident, String)>, AttrError>
+where A: Iterator<Item = &'a Attribute> + Clone {
+  let interpolation = match get_optional_lit_str(var_name, attrs.clone(), "interpolation")? {
+    Some(interpolation) => interpolation,
+    None => return Ok(None),
+  };
+
+  let variant = match interpolation.as_str() {
+    "smooth" => "Smooth",
+    "flat" => "Flat",
+    "noperspective" => "NoPerspective",
+    "centroid" => "Centroid",
+    "sample" => "Sample",
+    _ => {
+      return Err(AttrError::CannotParseAttribute(var_name.clone(), "sem".to_owned(), "interpolation".to_owned()));
+    }
+  };
+
+  // varyings are named after their semantics by default
+  let varying = get_optional_lit_str(var_name, attrs, "varying")?.unwrap_or_else(|| format!("v_{}", sem_name));
+
+  Ok(Some((Ident::new(variant, Span::call_site()), varying)))
+}
+
+fn get_optional_lit_str<'a, A>(
+  var_name: &Ident,
+  attrs: A,
+  sub_key: &str
+) -> Result<Option<String>, AttrError>
+where A: Iterator<Item = &'a Attribute> + Clone {
+  match get_field_lit_str_once(var_name, attrs, "sem", sub_key, KNOWN_SUBKEYS) {
+    Ok(lit) => Ok(Some(lit.value())),
+    Err(AttrError::CannotFindAttribute(..)) => Ok(None),
+    Err(e) => Err(e),
+  }
+}
+
 pub(crate) fn generate_enum_semantics_impl(
   ident: Ident,
   enum_: DataEnum
 ) -> Result<TokenStream, SemanticsImplError> {
   let fields = enum_.variants.into_iter().map(|var| {
-    get_vertex_sem_attribs(&var.ident, var.attrs.iter()).map(|attrs| {
-      (var.ident, attrs.0, attrs.1, attrs.2)
+    get_vertex_sem_attribs(&var.ident, var.attrs.iter()).and_then(|attrs| {
+      let interpolation = get_vertex_sem_interpolation(&var.ident, &attrs.0.to_string(), var.attrs.iter())?;
+      Ok((var.ident, attrs.0, attrs.1, attrs.2, interpolation))
     })
   });
 
@@ -69,6 +115,15 @@ pub(crate) fn generate_enum_semantics_impl(
         let sem_name = field.1.to_string();
         let repr_ty_name = field.2;
         let ty_name = field.3;
+        let interpolation = match field.4 {
+          Some((variant, varying)) => quote!{
+            Some(luminance::vertex::SemanticsInterpolation {
+              varying: #varying.to_owned(),
+              interpolation: luminance::vertex::Interpolation::#variant
+            })
+          },
+          None => quote!{ None }
+        };
 
         // dynamic branch used for parsing the semantics from a string
         parse_branches.push(quote!{
@@ -83,7 +138,8 @@ pub(crate) fn generate_enum_semantics_impl(
         semantics_set.push(quote!{
           luminance::vertex::SemanticsDesc {
             index: #index,
-            name: #sem_name.to_owned()
+            name: #sem_name.to_owned(),
+            interpolation: #interpolation
           }
         });
 
@@ -0,0 +1,126 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use std::fmt;
+use syn::{Attribute, DataStruct, Fields, Generics, Ident, Meta, NestedMeta};
+
+#[derive(Debug)]
+pub(crate) enum DeriveUniformBlockError {
+  UnsupportedUnit,
+  UnsupportedGenerics,
+  MissingReprC,
+}
+
+impl fmt::Display for DeriveUniformBlockError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      DeriveUniformBlockError::UnsupportedUnit => f.write_str("unsupported unit struct"),
+      DeriveUniformBlockError::UnsupportedGenerics => f.write_str("unsupported generic struct"),
+      DeriveUniformBlockError::MissingReprC => {
+        f.write_str("uniform blocks must be #[repr(C)] for their layout to be checked")
+      }
+    }
+  }
+}
+
+pub(crate) fn generate_uniform_block_impl(
+  ident: Ident,
+  attrs: &[Attribute],
+  generics: &Generics,
+  struct_: DataStruct
+) -> Result<TokenStream, DeriveUniformBlockError> {
+  if !generics.params.is_empty() {
+    return Err(DeriveUniformBlockError::UnsupportedGenerics);
+  }
+
+  if !attrs.iter().any(is_repr_c) {
+    return Err(DeriveUniformBlockError::MissingReprC);
+  }
+
+  // fields as (display name, type), in declaration order
+  let fields: Vec<_> = match struct_.fields {
+    Fields::Named(named_fields) => named_fields.named.into_iter().map(|field| {
+      (field.ident.unwrap().to_string(), field.ty)
+    }).collect(),
+
+    Fields::Unnamed(unnamed_fields) => {
+      let fields = unnamed_fields.unnamed.into_iter().enumerate();
+      fields.map(|(i, field)| (i.to_string(), field.ty)).collect()
+    }
+
+    Fields::Unit => return Err(DeriveUniformBlockError::UnsupportedUnit),
+  };
+
+  let fields_types: Vec<_> = fields.iter().map(|(_, ty)| ty.clone()).collect();
+
+  // the offset of each field is computed with both the #[repr(C)] and the std140 rules; they must
+  // be the same
+  let fields_checks = fields.iter().map(|(name, ty)| {
+    let msg = format!(
+      "field `{}` of `{}` is not at its std140 offset; add padding before it",
+      name,
+      ident
+    );
+
+    quote!{
+      rust_offset = round_up(rust_offset, core::mem::align_of::<#ty>());
+      std140_offset = round_up(std140_offset, <#ty as luminance::buffer::Std140>::STD140_ALIGNMENT);
+      assert!(rust_offset == std140_offset, #msg);
+      rust_offset += core::mem::size_of::<#ty>();
+      std140_offset += <#ty as luminance::buffer::Std140>::STD140_SIZE;
+    }
+  });
+
+  let output = quote!{
+    unsafe impl luminance::buffer::UniformBlock for #ident {}
+
+    // structures are aligned to the largest alignment of their fields, rounded up to 16 bytes, and
+    // padded to a multiple of it
+    unsafe impl luminance::buffer::Std140 for #ident {
+      const STD140_ALIGNMENT: usize = {
+        let mut alignment = 16;
+        #(
+          if <#fields_types as luminance::buffer::Std140>::STD140_ALIGNMENT > alignment {
+            alignment = <#fields_types as luminance::buffer::Std140>::STD140_ALIGNMENT;
+          }
+        )*
+        alignment
+      };
+
+      // the offsets of every field are checked on the way to the end of the last one
+      #[allow(unused_assignments, unused_mut, clippy::manual_div_ceil)]
+      const STD140_SIZE: usize = {
+        const fn round_up(offset: usize, alignment: usize) -> usize {
+          (offset + alignment - 1) / alignment * alignment
+        }
+
+        let mut rust_offset = 0;
+        let mut std140_offset = 0;
+        #(#fields_checks)*
+
+        round_up(std140_offset, <Self as luminance::buffer::Std140>::STD140_ALIGNMENT)
+      };
+    }
+
+    // associated constants are only evaluated when used; this fails to compile with the message
+    // of the first misplaced field
+    const _: usize = <#ident as luminance::buffer::Std140>::STD140_SIZE;
+  };
+
+  Ok(output.into())
+}
+
+// Whether an attribute is #[repr(C)], possibly along with other representation hints.
+fn is_repr_c(attr: &Attribute) -> bool {
+  if !attr.path.is_ident("repr") {
+    return false;
+  }
+
+  match attr.parse_meta() {
+    Ok(Meta::List(list)) => list.nested.iter().any(|nested| match nested {
+      NestedMeta::Meta(Meta::Path(path)) => path.is_ident("C"),
+      _ => false,
+    }),
+
+    _ => false,
+  }
+}
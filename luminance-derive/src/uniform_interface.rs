@@ -1,4 +1,4 @@
-use crate::attrib::{AttrError, get_field_attr_once, get_field_flag_once};
+use crate::attrib::{AttrError, get_field_flag_once, get_field_lit_str_once};
 use proc_macro::TokenStream;
 use quote::quote;
 use std::fmt;
@@ -47,14 +47,16 @@ pub(crate) fn generate_uniform_interface_impl(
           "unbound",
           KNOWN_SUBKEYS
         ).map_err(DeriveUniformInterfaceError::UnboundError)?;
-        let name = get_field_attr_once(
+        // the name is taken as-is so that it can be any GLSL name, such as an array item or a
+        // struct member
+        let name = get_field_lit_str_once(
           &ident,
           field.attrs.iter(),
           "uniform",
           "name",
           KNOWN_SUBKEYS
-        ).map(|ident: Ident| {
-          ident.to_string()
+        ).map(|lit| {
+          lit.value()
         }).or_else(|e| match e {
           AttrError::CannotFindAttribute(..) => {
             Ok(field_ident.to_string())
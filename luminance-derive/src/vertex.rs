@@ -63,7 +63,7 @@ where A: Iterator<Item = &'a Attribute> + Clone {
         )?;
       }
 
-      let output = process_struct(ident, indexed_vertex_attrib_descs, Vec::new(), fields_types);
+      let output = process_struct(ident, &sem_type, indexed_vertex_attrib_descs, Vec::new(), fields_types);
       Ok(output.into())
     }
 
@@ -86,7 +86,7 @@ where A: Iterator<Item = &'a Attribute> + Clone {
         )?;
       }
 
-      let output = process_struct(ident, indexed_vertex_attrib_descs, fields_names, fields_types);
+      let output = process_struct(ident, &sem_type, indexed_vertex_attrib_descs, fields_names, fields_types);
       Ok(output.into())
     }
 
@@ -148,6 +148,7 @@ where FN: Into<Option<&'a mut Vec<Ident>>> {
 /// If fields_names is empty, it is assumed to be a struct-tuple.
 fn process_struct(
   struct_name: Ident,
+  sem_type: &Type,
   indexed_vertex_attrib_descs: Vec<proc_macro2::TokenStream>,
   fields_names: Vec<Ident>,
   fields_types: Vec<Type>
@@ -173,7 +174,26 @@ fn process_struct(
     }
   };
 
+  // compile-time validation of the fields; this gives much better error messages than the ones
+  // coming from the generated vertex descriptor when a field is not a vertex attribute or when it
+  // uses the wrong semantics type
+  let fields_validation = quote! {
+    const _: () = {
+      fn assert_vertex_attrib_field<T, S>()
+      where T: luminance::vertex::VertexAttrib + luminance::vertex::HasSemantics<Sem = S>,
+            S: luminance::vertex::Semantics {
+      }
+
+      #[allow(dead_code)]
+      fn validate_fields() {
+        #(assert_vertex_attrib_field::<#fields_types, #sem_type>();)*
+      }
+    };
+  };
+
   quote! {
+    #fields_validation
+
     // Vertex impl
     unsafe impl luminance::vertex::Vertex for #struct_name {
       fn vertex_desc() -> luminance::vertex::VertexDesc {
@@ -0,0 +1,59 @@
+use luminance::buffer::Std140;
+use luminance::linear::M44;
+use luminance_derive::UniformBlock;
+
+#[test]
+fn derive_uniform_block() {
+  #[derive(Clone, Copy, UniformBlock)]
+  #[repr(C)]
+  struct Block {
+    _color: [f32; 4],
+    _time: f32,
+  }
+
+  assert_eq!(<Block as Std140>::STD140_ALIGNMENT, 16);
+  assert_eq!(<Block as Std140>::STD140_SIZE, 32);
+}
+
+#[test]
+fn derive_packed_vec3_uniform_block() {
+  #[derive(Clone, Copy, UniformBlock)]
+  #[repr(C)]
+  struct Light {
+    _color: [f32; 3],
+    _intensity: f32,
+    _position: [f32; 3],
+  }
+
+  assert_eq!(<Light as Std140>::STD140_SIZE, 32);
+}
+
+#[test]
+fn derive_nested_uniform_block() {
+  #[derive(Clone, Copy, UniformBlock)]
+  #[repr(C)]
+  struct Light {
+    _color: [f32; 4],
+    _position: [f32; 4],
+  }
+
+  #[derive(Clone, Copy, UniformBlock)]
+  #[repr(C)]
+  struct Scene {
+    _view: M44,
+    _light: Light,
+    _light_nb: u32,
+  }
+
+  assert_eq!(<Scene as Std140>::STD140_SIZE, 112);
+}
+
+#[test]
+fn derive_tuple_uniform_block() {
+  #[derive(Clone, Copy, UniformBlock)]
+  #[repr(C)]
+  struct Time(f32, f32);
+
+  assert_eq!(<Time as Std140>::STD140_ALIGNMENT, 16);
+  assert_eq!(<Time as Std140>::STD140_SIZE, 16);
+}
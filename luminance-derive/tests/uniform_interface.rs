@@ -37,3 +37,14 @@ fn derive_unbound_renamed_uniform_interface() {
     _t2: Uniform<f32>
   }
 }
+
+#[test]
+fn derive_glsl_renamed_uniform_interface() {
+  #[derive(UniformInterface)]
+  struct SimpleUniformInterface {
+    #[uniform(name = "lights[0].color")]
+    _color: Uniform<[f32; 3]>,
+    #[uniform(name = "Matrices.proj", unbound)]
+    _proj: Uniform<[[f32; 4]; 4]>
+  }
+}
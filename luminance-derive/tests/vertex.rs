@@ -1,5 +1,6 @@
 use luminance::vertex::{
-  HasSemantics, Semantics, Vertex, VertexAttrib, VertexBufferDesc, VertexInstancing
+  HasSemantics, Interpolation, Semantics, SemanticsInterpolation, Vertex, VertexAttrib,
+  VertexBufferDesc, VertexInstancing
 };
 use luminance_derive::{Semantics, Vertex};
 
@@ -62,3 +63,55 @@ fn derive_struct_tuple_vertex() {
   #[vertex(sem = "Semantics")]
   struct Vertex(VertexPosition, VertexNormal, #[vertex(normalized = "true")] VertexColor);
 }
+
+#[test]
+fn derive_normalized_named_vertex() {
+  #[derive(Clone, Copy, Debug, Eq, PartialEq, Semantics)]
+  pub enum Semantics {
+    #[sem(name = "position", repr = "[f32; 2]", wrapper = "VertexPosition")]
+    Position,
+    #[sem(name = "color", repr = "[u8; 3]", wrapper = "VertexColor")]
+    Color
+  }
+
+  #[derive(Clone, Copy, Debug, Vertex)]
+  #[repr(C)]
+  #[vertex(sem = "Semantics")]
+  struct Vertex {
+    pos: VertexPosition,
+    #[vertex(normalized = "true")]
+    col: VertexColor
+  }
+
+  let expected_desc = vec![
+    VertexBufferDesc::new(Semantics::Position, VertexInstancing::Off, <[f32; 2] as VertexAttrib>::VERTEX_ATTRIB_DESC),
+    VertexBufferDesc::new(Semantics::Color, VertexInstancing::Off, <[u8; 3] as VertexAttrib>::VERTEX_ATTRIB_DESC.normalize()),
+  ];
+
+  assert_eq!(Vertex::vertex_desc(), expected_desc);
+}
+
+#[test]
+fn derive_semantics_interpolation() {
+  #[derive(Clone, Copy, Debug, Eq, PartialEq, Semantics)]
+  pub enum Semantics {
+    #[sem(name = "position", repr = "[f32; 3]", wrapper = "VertexPosition")]
+    Position,
+    #[sem(name = "uv", repr = "[f32; 2]", wrapper = "VertexUV", interpolation = "centroid")]
+    UV,
+    #[sem(name = "material", repr = "u32", wrapper = "VertexMaterial", interpolation = "flat", varying = "v_mat")]
+    Material
+  }
+
+  let set = Semantics::semantics_set();
+
+  assert_eq!(set[0].interpolation, None);
+  assert_eq!(set[1].interpolation, Some(SemanticsInterpolation {
+    varying: "v_uv".to_owned(),
+    interpolation: Interpolation::Centroid
+  }));
+  assert_eq!(set[2].interpolation, Some(SemanticsInterpolation {
+    varying: "v_mat".to_owned(),
+    interpolation: Interpolation::Flat
+  }));
+}
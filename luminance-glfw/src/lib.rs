@@ -7,8 +7,11 @@ use gl;
 use glfw::{self, Context, CursorMode as GlfwCursorMode, SwapInterval, Window, WindowMode};
 use luminance::context::GraphicsContext;
 use luminance::state::GraphicsState;
+use luminance::sync::FrameLatencyLimiter;
 pub use luminance::state::StateQueryError;
-pub use luminance_windowing::{CursorMode, Surface, WindowDim, WindowOpt};
+pub use luminance_windowing::{
+  CursorMode, GlDriver, Monitor, Surface, VideoMode, WindowDim, WindowOpt
+};
 use std::cell::RefCell;
 use std::fmt;
 use std::os::raw::c_void;
@@ -30,6 +33,8 @@ pub enum GlfwSurfaceError {
   NoPrimaryMonitor,
   /// No available video mode.
   NoVideoMode,
+  /// No monitor at the given index.
+  UnknownMonitor(usize),
   /// The graphics state is not available.
   ///
   /// This error is generated when the initialization code is called on a thread on which the
@@ -45,6 +50,7 @@ impl fmt::Display for GlfwSurfaceError {
       GlfwSurfaceError::WindowCreationFailed => f.write_str("failed to create window"),
       GlfwSurfaceError::NoPrimaryMonitor => f.write_str("no primary monitor"),
       GlfwSurfaceError::NoVideoMode => f.write_str("no video mode"),
+      GlfwSurfaceError::UnknownMonitor(index) => write!(f, "unknown monitor: {}", index),
       GlfwSurfaceError::GraphicsStateError(ref e) => write!(f, "failed to get graphics state: {}", e),
     }
   }
@@ -55,10 +61,13 @@ impl fmt::Display for GlfwSurfaceError {
 /// This type implements `GraphicsContext` so that you can use it to perform render with
 /// **luminance**.
 pub struct GlfwSurface {
+  // fields are dropped in order: the OpenGL objects of the graphics state and of the latency
+  // limiter go before the window, which holds the context
+  gfx_state: Rc<RefCell<GraphicsState>>,
+  latency_limiter: Option<FrameLatencyLimiter>,
   window: Window,
   events_rx: Receiver<(f64, WindowEvent)>,
-  gfx_state: Rc<RefCell<GraphicsState>>,
-  opts: WindowOpt
+  opts: WindowOpt,
 }
 
 unsafe impl GraphicsContext for GlfwSurface {
@@ -77,13 +86,27 @@ impl Surface for GlfwSurface {
     #[cfg(not(feature = "log-errors"))]
     let error_cbk = glfw::FAIL_ON_ERRORS;
 
+    // the driver is picked when GLFW loads OpenGL, which happens at initialization
+    if win_opt.gl_driver() == GlDriver::Software {
+      std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+    }
+
     let mut glfw = glfw::init(error_cbk).map_err(GlfwSurfaceError::InitError)?;
 
     // OpenGL hints
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(glfw::WindowHint::ContextVersionMajor(3));
-    glfw.window_hint(glfw::WindowHint::ContextVersionMinor(3));
+    if win_opt.gl_driver() == GlDriver::Angle {
+      // ANGLE exposes OpenGL ES through EGL
+      glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::OpenGlEs));
+      glfw.window_hint(glfw::WindowHint::ContextCreationApi(glfw::ContextCreationApi::Egl));
+      glfw.window_hint(glfw::WindowHint::ContextVersionMajor(3));
+      glfw.window_hint(glfw::WindowHint::ContextVersionMinor(0));
+    } else {
+      glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+      glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+      glfw.window_hint(glfw::WindowHint::ContextVersionMajor(3));
+      glfw.window_hint(glfw::WindowHint::ContextVersionMinor(3));
+    }
+
     glfw.window_hint(glfw::WindowHint::Samples(win_opt.num_samples()));
 
     // open a window in windowed or fullscreen mode
@@ -111,6 +134,15 @@ impl Surface for GlfwSurface {
             .ok_or(GlfwSurfaceError::WindowCreationFailed)?,
         )
       })?,
+      WindowDim::FullscreenExclusive(index, vmode) => glfw.with_connected_monitors(|glfw, monitors| {
+        let monitor = monitors.get(index).ok_or(GlfwSurfaceError::UnknownMonitor(index))?;
+
+        glfw.window_hint(glfw::WindowHint::RefreshRate(vmode.refresh_rate));
+
+        glfw
+          .create_window(vmode.width, vmode.height, title, WindowMode::FullScreen(monitor))
+          .ok_or(GlfwSurfaceError::WindowCreationFailed)
+      })?,
     };
 
     window.make_current();
@@ -126,13 +158,16 @@ impl Surface for GlfwSurface {
 
     // init OpenGL
     gl::load_with(|s| window.get_proc_address(s) as *const c_void);
+    luminance::shader::stage::load_spirv_with(|s| window.get_proc_address(s) as *const c_void);
 
+    let latency_limiter = win_opt.max_frames_in_flight().map(|n| FrameLatencyLimiter::new(n as usize));
     let gfx_state = GraphicsState::new().map_err(GlfwSurfaceError::GraphicsStateError)?;
     let surface = GlfwSurface {
       window,
       events_rx,
       gfx_state: Rc::new(RefCell::new(gfx_state)),
-      opts: win_opt
+      opts: win_opt,
+      latency_limiter
     };
 
     Ok(surface)
@@ -165,6 +200,17 @@ impl Surface for GlfwSurface {
     [x as u32, y as u32]
   }
 
+  fn window_size(&self) -> [f64; 2] {
+    let (w, h) = self.window.get_size();
+    [w as f64, h as f64]
+  }
+
+  fn monitors(&self) -> Vec<Monitor> {
+    self.window.glfw.clone().with_connected_monitors(|_, monitors| {
+      monitors.iter().map(from_glfw_monitor).collect()
+    })
+  }
+
   fn wait_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a> {
     self.window.glfw.wait_events();
     Box::new(self.events_rx.iter().map(|(_, e)| e))
@@ -177,5 +223,31 @@ impl Surface for GlfwSurface {
 
   fn swap_buffers(&mut self) {
     self.window.swap_buffers();
+
+    if let Some(ref mut latency_limiter) = self.latency_limiter {
+      latency_limiter.end_frame();
+    }
+    self.gfx_state.borrow_mut().end_frame();
+  }
+}
+
+fn from_glfw_vid_mode(vmode: &glfw::VidMode) -> VideoMode {
+  VideoMode::new(vmode.width, vmode.height, vmode.refresh_rate)
+}
+
+fn from_glfw_monitor(monitor: &glfw::Monitor) -> Monitor {
+  let (x, y) = monitor.get_pos();
+  let (w, h) = monitor.get_physical_size();
+  let physical_size = if w > 0 && h > 0 { Some([w as u32, h as u32]) } else { None };
+  // GLFW reports a scale per axis; the horizontal one is used as the monitor’s scale factor
+  let (scale, _) = monitor.get_content_scale();
+
+  Monitor {
+    name: monitor.get_name(),
+    position: [x, y],
+    physical_size,
+    scale_factor: scale as f64,
+    current_video_mode: monitor.get_video_mode().as_ref().map(from_glfw_vid_mode),
+    video_modes: monitor.get_video_modes().iter().map(from_glfw_vid_mode).collect(),
   }
 }
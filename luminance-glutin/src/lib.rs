@@ -1,5 +1,11 @@
 //! The [glutin] windowing implementation for [luminance-windowing].
 //!
+//! [`GlutinSurface`] is built on top of [winit] and [glutin]. It’s HiDPI-aware: its size – and
+//! then the size of its back buffer – is expressed in physical pixels and the OpenGL surface is
+//! resized whenever the window is resized or moved to a monitor with a different HiDPI factor, as
+//! long as you keep polling or waiting for events.
+//!
+//! [winit]: https://crates.io/crates/winit
 //! [glutin]: https://crates.io/crates/glutin
 //! [luminance-windowing]: https://crates.io/crates/luminance-windowing
 
@@ -12,15 +18,18 @@ pub use glutin::{
   MouseButton
 };
 pub use glutin::dpi::{LogicalPosition, LogicalSize};
-pub use luminance_windowing::{CursorMode, Surface, WindowDim, WindowOpt};
+pub use luminance_windowing::{
+  CursorMode, GlDriver, Monitor, Surface, VideoMode, WindowDim, WindowOpt
+};
 
 use glutin::{
-  Api, ContextBuilder, EventsLoop, GlProfile, GlRequest, PossiblyCurrent,
+  Api, ContextBuilder, ControlFlow, EventsLoop, GlProfile, GlRequest, MonitorId, PossiblyCurrent,
   WindowBuilder, WindowedContext
 };
 use glutin::dpi::PhysicalSize;
 use luminance::context::GraphicsContext;
 use luminance::state::{GraphicsState, StateQueryError};
+use luminance::sync::FrameLatencyLimiter;
 use std::cell::RefCell;
 use std::os::raw::c_void;
 use std::rc::Rc;
@@ -34,7 +43,9 @@ pub enum Error {
   /// OpenGL context error.
   ContextError(ContextError),
   /// Graphics state error that might occur when querying the initial state.
-  GraphicsStateError(StateQueryError)
+  GraphicsStateError(StateQueryError),
+  /// No monitor at the given index.
+  UnknownMonitor(usize),
 }
 
 impl From<CreationError> for Error {
@@ -55,9 +66,12 @@ impl From<ContextError> for Error {
 ///
 /// [luminance]: https://crates.io/crates/luminance
 pub struct GlutinSurface {
+  // fields are dropped in order: the OpenGL objects of the graphics state and of the latency
+  // limiter go before the context
+  gfx_state: Rc<RefCell<GraphicsState>>,
+  latency_limiter: Option<FrameLatencyLimiter>,
   ctx: WindowedContext<PossiblyCurrent>,
   event_loop: EventsLoop,
-  gfx_state: Rc<RefCell<GraphicsState>>,
   opts: WindowOpt,
   // a list of event that has happened
   event_queue: Vec<Event>
@@ -74,6 +88,17 @@ impl Surface for GlutinSurface {
   type Event = Event;
 
   fn new(dim: WindowDim, title: &str, win_opt: WindowOpt) -> Result<Self, Self::Error> {
+    // the driver is picked when the context is created
+    let (gl_request, hardware_acceleration) = match win_opt.gl_driver() {
+      GlDriver::Native => (GlRequest::Specific(Api::OpenGl, (3, 3)), Some(true)),
+      GlDriver::Software => {
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        (GlRequest::Specific(Api::OpenGl, (3, 3)), None)
+      }
+      // ANGLE exposes OpenGL ES through EGL
+      GlDriver::Angle => (GlRequest::Specific(Api::OpenGlEs, (3, 0)), Some(true)),
+    };
+
     let event_loop = EventsLoop::new();
 
     let window_builder = WindowBuilder::new().with_title(title);
@@ -83,20 +108,35 @@ impl Surface for GlutinSurface {
       WindowDim::FullscreenRestricted(w, h) =>
         window_builder
           .with_dimensions((w, h).into())
-          .with_fullscreen(Some(event_loop.get_primary_monitor()))
+          .with_fullscreen(Some(event_loop.get_primary_monitor())),
+      WindowDim::FullscreenExclusive(index, vmode) => {
+        // glutin cannot switch video modes; go fullscreen on the monitor with the mode dimensions
+        let monitor = event_loop.get_available_monitors().nth(index).ok_or(Error::UnknownMonitor(index))?;
+        let logical = PhysicalSize::new(vmode.width as f64, vmode.height as f64).to_logical(monitor.get_hidpi_factor());
+
+        window_builder
+          .with_dimensions(logical)
+          .with_fullscreen(Some(monitor))
+      }
     };
 
-    let windowed_ctx = ContextBuilder::new()
-      .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)))
-      .with_gl_profile(GlProfile::Core)
+    let ctx_builder = ContextBuilder::new().with_gl(gl_request);
+    let ctx_builder = match win_opt.gl_driver() {
+      GlDriver::Angle => ctx_builder,
+      _ => ctx_builder.with_gl_profile(GlProfile::Core),
+    };
+
+    let windowed_ctx = ctx_builder
       .with_multisampling(win_opt.num_samples().unwrap_or(0) as u16)
       .with_double_buffer(Some(true))
+      .with_hardware_acceleration(hardware_acceleration)
       .build_windowed(window_builder, &event_loop)?;
 
     let ctx = unsafe { windowed_ctx.make_current().map_err(|(_, e)| e)? };
 
     // init OpenGL
     gl::load_with(|s| ctx.get_proc_address(s) as *const c_void);
+    luminance::shader::stage::load_spirv_with(|s| ctx.get_proc_address(s) as *const c_void);
 
     match win_opt.cursor_mode() {
       CursorMode::Visible => ctx.window().hide_cursor(false),
@@ -106,12 +146,14 @@ impl Surface for GlutinSurface {
 
     ctx.window().show();
 
+    let latency_limiter = win_opt.max_frames_in_flight().map(|n| FrameLatencyLimiter::new(n as usize));
     let gfx_state = GraphicsState::new().map_err(Error::GraphicsStateError)?;
     let surface = GlutinSurface {
       ctx,
       event_loop,
       gfx_state: Rc::new(RefCell::new(gfx_state)),
       opts: win_opt,
+      latency_limiter,
       event_queue: Vec::new()
     };
 
@@ -137,13 +179,35 @@ impl Surface for GlutinSurface {
   }
 
   fn size(&self) -> [u32; 2] {
-    let logical = self.ctx.window().get_inner_size().unwrap();
-    let (w, h) = PhysicalSize::from_logical(logical, self.ctx.window().get_hidpi_factor()).into();
+    let (w, h) = self.physical_size().into();
+    [w, h]
+  }
+
+  fn window_size(&self) -> [f64; 2] {
+    let (w, h) = self.logical_size().into();
     [w, h]
   }
 
+  fn monitors(&self) -> Vec<Monitor> {
+    self.event_loop.get_available_monitors().map(|monitor| from_glutin_monitor(&monitor)).collect()
+  }
+
   fn wait_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a> {
-    panic!("not implemented yet")
+    self.event_queue.clear();
+
+    // block until the first event and then grab the ones already queued
+    let queue = &mut self.event_queue;
+    self.event_loop.run_forever(|event| {
+      queue.push(event);
+      ControlFlow::Break
+    });
+    self.event_loop.poll_events(|event| {
+      queue.push(event);
+    });
+
+    self.handle_resize_events();
+
+    Box::new(self.event_queue.iter().cloned())
   }
 
   fn poll_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a> {
@@ -154,10 +218,62 @@ impl Surface for GlutinSurface {
       queue.push(event);
     });
 
+    self.handle_resize_events();
+
     Box::new(self.event_queue.iter().cloned())
   }
 
   fn swap_buffers(&mut self) {
     self.ctx.swap_buffers().unwrap();
+
+    if let Some(ref mut latency_limiter) = self.latency_limiter {
+      latency_limiter.end_frame();
+    }
+    self.gfx_state.borrow_mut().end_frame();
+  }
+}
+
+impl GlutinSurface {
+  /// HiDPI factor of the window, i.e. the number of physical pixels per logical pixel.
+  pub fn hidpi_factor(&self) -> f64 {
+    self.ctx.window().get_hidpi_factor()
+  }
+
+  /// Size of the window, in logical pixels.
+  pub fn logical_size(&self) -> LogicalSize {
+    self.ctx.window().get_inner_size().unwrap_or_else(|| LogicalSize::new(0., 0.))
+  }
+
+  fn physical_size(&self) -> PhysicalSize {
+    self.logical_size().to_physical(self.hidpi_factor())
+  }
+
+  // Resize the OpenGL surface if the window was resized or its HiDPI factor changed.
+  fn handle_resize_events(&mut self) {
+    let resized = self.event_queue.iter().any(|event| matches!(
+      *event,
+      Event::WindowEvent { event: WindowEvent::Resized(_), .. } |
+      Event::WindowEvent { event: WindowEvent::HiDpiFactorChanged(_), .. }
+    ));
+
+    if resized {
+      self.ctx.resize(self.physical_size());
+    }
+  }
+}
+
+fn from_glutin_monitor(monitor: &MonitorId) -> Monitor {
+  let (x, y) = monitor.get_position().into();
+  let (w, h) = monitor.get_dimensions().into();
+  // glutin only knows about the current video mode, and not its refresh rate
+  let vmode = VideoMode::new(w, h, None);
+
+  Monitor {
+    name: monitor.get_name(),
+    position: [x, y],
+    physical_size: None,
+    scale_factor: monitor.get_hidpi_factor(),
+    current_video_mode: Some(vmode),
+    video_modes: vec![vmode],
   }
 }
@@ -0,0 +1,181 @@
+//! Headless graphics contexts for [luminance].
+//!
+//! A [`HeadlessContext`] is a [`GraphicsContext`] that is not bound to any window. It’s useful to
+//! create GPU resources and render without any display – for tests, server-side thumbnail
+//! renderers, etc. Because there is no window, you must render to [`Framebuffer`]s you create
+//! yourself and read their content back.
+//!
+//! Headless contexts are created with [glutin], via one of the [`Backend`]s.
+//!
+//! [luminance]: https://crates.io/crates/luminance
+//! [glutin]: https://crates.io/crates/glutin
+//! [`Framebuffer`]: luminance::framebuffer::Framebuffer
+
+#![deny(missing_docs)]
+
+pub use glutin::{ContextError, CreationError};
+
+use glutin::dpi::PhysicalSize;
+use glutin::{Api, Context, ContextBuilder, EventsLoop, GlProfile, GlRequest, PossiblyCurrent};
+use luminance::context::GraphicsContext;
+use luminance::state::{GraphicsState, StateQueryError};
+use std::cell::RefCell;
+use std::fmt;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+/// Way to create a headless context.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Backend {
+  /// Platform’s default headless context.
+  ///
+  /// This requires a connection to the windowing system (e.g. a X11 or Wayland display on Linux),
+  /// even though no window is opened.
+  Default,
+  /// EGL surfaceless context.
+  ///
+  /// This doesn’t render to any default framebuffer and requires a connection to the windowing
+  /// system.
+  #[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ))]
+  Surfaceless,
+  /// OSMesa context, rendering on the CPU.
+  ///
+  /// This doesn’t require any windowing system nor GPU, which makes it ideal for continuous
+  /// integration, but requires the OSMesa library to be installed.
+  #[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ))]
+  OsMesa,
+}
+
+/// Error that might occur when creating a headless context.
+#[derive(Debug)]
+pub enum Error {
+  /// Something went wrong when creating the context. The carried [`CreationError`] provides
+  /// more information.
+  CreationError(CreationError),
+  /// OpenGL context error.
+  ContextError(ContextError),
+  /// Graphics state error that might occur when querying the initial state.
+  GraphicsStateError(StateQueryError),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      Error::CreationError(ref e) => write!(f, "headless context creation error: {}", e),
+      Error::ContextError(ref e) => write!(f, "headless context error: {}", e),
+      Error::GraphicsStateError(ref e) => write!(f, "headless context graphics state error: {}", e),
+    }
+  }
+}
+
+impl From<CreationError> for Error {
+  fn from(e: CreationError) -> Self {
+    Error::CreationError(e)
+  }
+}
+
+impl From<ContextError> for Error {
+  fn from(e: ContextError) -> Self {
+    Error::ContextError(e)
+  }
+}
+
+/// A headless graphics context.
+///
+/// You want to create such an object in order to use any [luminance] construct without a window.
+///
+/// [luminance]: https://crates.io/crates/luminance
+pub struct HeadlessContext {
+  // fields are dropped in order: the OpenGL context and – for some backends – its events loop
+  // must outlive the GPU resources of the graphics state
+  gfx_state: Rc<RefCell<GraphicsState>>,
+  _ctx: Context<PossiblyCurrent>,
+  _event_loop: Option<EventsLoop>,
+  size: [u32; 2],
+}
+
+unsafe impl GraphicsContext for HeadlessContext {
+  fn state(&self) -> &Rc<RefCell<GraphicsState>> {
+    &self.gfx_state
+  }
+}
+
+impl HeadlessContext {
+  /// Create a new headless context.
+  ///
+  /// `size` is the size of the default framebuffer, for backends that have one.
+  pub fn new(size: [u32; 2], backend: Backend) -> Result<Self, Error> {
+    let builder = ContextBuilder::new()
+      .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)))
+      .with_gl_profile(GlProfile::Core);
+    let physical_size = PhysicalSize::new(size[0] as f64, size[1] as f64);
+
+    let (ctx, event_loop) = match backend {
+      Backend::Default => {
+        let event_loop = EventsLoop::new();
+        let ctx = builder.build_headless(&event_loop, physical_size)?;
+        (ctx, Some(event_loop))
+      }
+
+      #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+      ))]
+      Backend::Surfaceless => {
+        use glutin::os::unix::HeadlessContextExt;
+
+        let event_loop = EventsLoop::new();
+        let ctx = builder.build_surfaceless(&event_loop)?;
+        (ctx, Some(event_loop))
+      }
+
+      #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+      ))]
+      Backend::OsMesa => {
+        use glutin::os::unix::HeadlessContextExt;
+
+        (builder.build_osmesa(physical_size)?, None)
+      }
+    };
+
+    let ctx = unsafe { ctx.make_current().map_err(|(_, e)| e)? };
+
+    // init OpenGL
+    gl::load_with(|s| ctx.get_proc_address(s) as *const c_void);
+    luminance::shader::stage::load_spirv_with(|s| ctx.get_proc_address(s) as *const c_void);
+
+    let gfx_state = GraphicsState::new().map_err(Error::GraphicsStateError)?;
+
+    Ok(HeadlessContext {
+      _ctx: ctx,
+      _event_loop: event_loop,
+      gfx_state: Rc::new(RefCell::new(gfx_state)),
+      size,
+    })
+  }
+
+  /// Size of the default framebuffer, for backends that have one.
+  pub fn size(&self) -> [u32; 2] {
+    self.size
+  }
+}
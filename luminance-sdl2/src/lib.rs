@@ -0,0 +1,340 @@
+//! [SDL2](https://crates.io/crates/sdl2) backend for
+//! [luminance](https://crates.io/crates/luminance) and
+//! [luminance-windowing](https://crates.io/crates/luminance-windowing).
+//!
+//! [`Sdl2Surface`] links against the native SDL2 library. Enable the `bundled` feature to build
+//! SDL2 from source instead of using the one installed on the system.
+
+#![deny(missing_docs)]
+
+use luminance::context::GraphicsContext;
+use luminance::state::GraphicsState;
+use luminance::sync::FrameLatencyLimiter;
+pub use luminance::state::StateQueryError;
+pub use luminance_windowing::{
+  CursorMode, GlDriver, Monitor, Surface, VideoMode, WindowDim, WindowOpt
+};
+use sdl2::{EventPump, Sdl, VideoSubsystem};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::video::{DisplayMode, GLContext, GLProfile, SwapInterval, Window};
+use std::cell::RefCell;
+use std::fmt;
+use std::iter;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+pub use sdl2::event::{Event, WindowEvent};
+pub use sdl2::keyboard::{Keycode, Mod, Scancode};
+pub use sdl2::mouse::MouseButton;
+pub use sdl2::video::WindowBuildError;
+
+/// Error that can be risen while creating a surface.
+#[derive(Debug)]
+pub enum Sdl2SurfaceError {
+  /// Initialization of SDL2, its video subsystem or its event pump went wrong.
+  InitError(String),
+  /// Window creation failed.
+  WindowCreationFailed(WindowBuildError),
+  /// The OpenGL context couldn’t be created or made current.
+  GlContextError(String),
+  /// No video mode available for the requested display.
+  NoVideoMode,
+  /// No monitor at the given index.
+  UnknownMonitor(usize),
+  /// The graphics state is not available.
+  ///
+  /// This error is generated when the initialization code is called on a thread on which the
+  /// graphics state has already been acquired.
+  GraphicsStateError(StateQueryError),
+}
+
+impl fmt::Display for Sdl2SurfaceError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      Sdl2SurfaceError::InitError(ref e) => write!(f, "initialization error: {}", e),
+      Sdl2SurfaceError::WindowCreationFailed(ref e) => write!(f, "failed to create window: {}", e),
+      Sdl2SurfaceError::GlContextError(ref e) => write!(f, "OpenGL context error: {}", e),
+      Sdl2SurfaceError::NoVideoMode => f.write_str("no video mode"),
+      Sdl2SurfaceError::UnknownMonitor(index) => write!(f, "unknown monitor: {}", index),
+      Sdl2SurfaceError::GraphicsStateError(ref e) => {
+        write!(f, "failed to get graphics state: {}", e)
+      }
+    }
+  }
+}
+
+impl From<WindowBuildError> for Sdl2SurfaceError {
+  fn from(e: WindowBuildError) -> Self {
+    Sdl2SurfaceError::WindowCreationFailed(e)
+  }
+}
+
+/// SDL2 surface.
+///
+/// This type implements `GraphicsContext` so that you can use it to perform render with
+/// **luminance**.
+pub struct Sdl2Surface {
+  // fields are dropped in order: the OpenGL objects of the graphics state and of the latency
+  // limiter go before the context, which goes before its window and SDL2 itself
+  gfx_state: Rc<RefCell<GraphicsState>>,
+  latency_limiter: Option<FrameLatencyLimiter>,
+  opts: WindowOpt,
+  event_pump: EventPump,
+  _gl_ctx: GLContext,
+  window: Window,
+  video: VideoSubsystem,
+  sdl: Sdl,
+}
+
+impl Sdl2Surface {
+  /// The underlying SDL2 window.
+  pub fn window(&self) -> &Window {
+    &self.window
+  }
+
+  /// The SDL2 context, to initialize other subsystems – audio, game controllers, etc.
+  pub fn sdl(&self) -> &Sdl {
+    &self.sdl
+  }
+}
+
+unsafe impl GraphicsContext for Sdl2Surface {
+  fn state(&self) -> &Rc<RefCell<GraphicsState>> {
+    &self.gfx_state
+  }
+}
+
+impl Surface for Sdl2Surface {
+  type Error = Sdl2SurfaceError;
+  type Event = Event;
+
+  fn new(dim: WindowDim, title: &str, win_opt: WindowOpt) -> Result<Self, Self::Error> {
+    // the driver is picked when SDL2 loads OpenGL, which happens when creating the window
+    if win_opt.gl_driver() == GlDriver::Software {
+      std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+    }
+
+    let sdl = sdl2::init().map_err(Sdl2SurfaceError::InitError)?;
+    let video = sdl.video().map_err(Sdl2SurfaceError::InitError)?;
+
+    // OpenGL attributes; SDL2 requires them to be set before creating the window
+    let gl_attr = video.gl_attr();
+
+    if win_opt.gl_driver() == GlDriver::Angle {
+      gl_attr.set_context_profile(GLProfile::GLES);
+      gl_attr.set_context_version(3, 0);
+    } else {
+      gl_attr.set_context_profile(GLProfile::Core);
+      gl_attr.set_context_flags().forward_compatible().set();
+      gl_attr.set_context_version(3, 3);
+    }
+
+    // SDL2 defaults to a 16-bit depth buffer and no stencil buffer
+    gl_attr.set_double_buffer(true);
+    gl_attr.set_depth_size(24);
+    gl_attr.set_stencil_size(8);
+    set_multisample_attrs(&video, win_opt.num_samples());
+
+    // open a window in windowed or fullscreen mode
+    let window = match dim {
+      WindowDim::Windowed(w, h) => video
+        .window(title, w, h)
+        .opengl()
+        .allow_highdpi()
+        .resizable()
+        .build()?,
+      WindowDim::Fullscreen => {
+        let mode = video.current_display_mode(0).map_err(|_| Sdl2SurfaceError::NoVideoMode)?;
+
+        video
+          .window(title, mode.w as u32, mode.h as u32)
+          .opengl()
+          .allow_highdpi()
+          .fullscreen_desktop()
+          .build()?
+      }
+      WindowDim::FullscreenRestricted(w, h) => video
+        .window(title, w, h)
+        .opengl()
+        .allow_highdpi()
+        .fullscreen()
+        .build()?,
+      WindowDim::FullscreenExclusive(index, vmode) => {
+        let display =
+          display_index(&video, index).ok_or(Sdl2SurfaceError::UnknownMonitor(index))?;
+        let bounds = video.display_bounds(display).map_err(Sdl2SurfaceError::InitError)?;
+        let (w, h) = (vmode.width as i32, vmode.height as i32);
+        let refresh_rate = vmode.refresh_rate.unwrap_or(0) as i32;
+        let wanted = DisplayMode::new(PixelFormatEnum::Unknown, w, h, refresh_rate);
+        let mode = video
+          .closest_display_mode(display, &wanted)
+          .map_err(|_| Sdl2SurfaceError::NoVideoMode)?;
+
+        // the window is fullscreen on the display it’s created on
+        let mut window = video
+          .window(title, vmode.width, vmode.height)
+          .opengl()
+          .allow_highdpi()
+          .position(bounds.x(), bounds.y())
+          .fullscreen()
+          .build()?;
+
+        window.set_display_mode(mode).map_err(|_| Sdl2SurfaceError::NoVideoMode)?;
+        window
+      }
+    };
+
+    let gl_ctx = window.gl_create_context().map_err(Sdl2SurfaceError::GlContextError)?;
+    window.gl_make_current(&gl_ctx).map_err(Sdl2SurfaceError::GlContextError)?;
+
+    // not all drivers allow to change the swap interval; keep theirs when they don’t
+    let _ = video.gl_set_swap_interval(SwapInterval::VSync);
+
+    match win_opt.cursor_mode() {
+      CursorMode::Visible => sdl.mouse().show_cursor(true),
+      CursorMode::Invisible => sdl.mouse().show_cursor(false),
+      CursorMode::Disabled => sdl.mouse().set_relative_mouse_mode(true),
+    }
+
+    // init OpenGL
+    gl::load_with(|s| video.gl_get_proc_address(s) as *const c_void);
+    luminance::shader::stage::load_spirv_with(|s| video.gl_get_proc_address(s) as *const c_void);
+
+    let event_pump = sdl.event_pump().map_err(Sdl2SurfaceError::InitError)?;
+    let latency_limiter =
+      win_opt.max_frames_in_flight().map(|n| FrameLatencyLimiter::new(n as usize));
+    let gfx_state = GraphicsState::new().map_err(Sdl2SurfaceError::GraphicsStateError)?;
+    let surface = Sdl2Surface {
+      gfx_state: Rc::new(RefCell::new(gfx_state)),
+      latency_limiter,
+      opts: win_opt,
+      event_pump,
+      _gl_ctx: gl_ctx,
+      window,
+      video,
+      sdl,
+    };
+
+    Ok(surface)
+  }
+
+  fn opts(&self) -> &WindowOpt {
+    &self.opts
+  }
+
+  fn set_cursor_mode(&mut self, mode: CursorMode) -> &mut Self {
+    let mouse = self.sdl.mouse();
+
+    match mode {
+      CursorMode::Visible => {
+        mouse.set_relative_mouse_mode(false);
+        mouse.show_cursor(true);
+      }
+      CursorMode::Invisible => {
+        mouse.set_relative_mouse_mode(false);
+        mouse.show_cursor(false);
+      }
+      // relative mouse mode grabs and hides the cursor
+      CursorMode::Disabled => mouse.set_relative_mouse_mode(true),
+    }
+
+    self.opts = self.opts.set_cursor_mode(mode);
+    self
+  }
+
+  fn set_num_samples<S>(&mut self, samples: S) -> &mut Self where S: Into<Option<u32>> {
+    // SDL2 cannot change the samples of an existing context: this only applies to contexts
+    // created afterwards, as the window hint of GLFW
+    let samples = samples.into();
+    set_multisample_attrs(&self.video, samples);
+    self.opts = self.opts.set_num_samples(samples);
+    self
+  }
+
+  fn size(&self) -> [u32; 2] {
+    let (w, h) = self.window.drawable_size();
+    [w, h]
+  }
+
+  fn window_size(&self) -> [f64; 2] {
+    let (w, h) = self.window.size();
+    [w as f64, h as f64]
+  }
+
+  fn monitors(&self) -> Vec<Monitor> {
+    let count = self.video.num_video_displays().unwrap_or(0);
+    (0 .. count).map(|display| from_sdl2_display(&self.video, display)).collect()
+  }
+
+  fn wait_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a> {
+    let first = self.event_pump.wait_event();
+    Box::new(iter::once(first).chain(self.event_pump.poll_iter()))
+  }
+
+  fn poll_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a> {
+    Box::new(self.event_pump.poll_iter())
+  }
+
+  fn swap_buffers(&mut self) {
+    self.window.gl_swap_window();
+
+    if let Some(ref mut latency_limiter) = self.latency_limiter {
+      latency_limiter.end_frame();
+    }
+    self.gfx_state.borrow_mut().end_frame();
+  }
+}
+
+fn set_multisample_attrs(video: &VideoSubsystem, samples: Option<u32>) {
+  let gl_attr = video.gl_attr();
+
+  match samples {
+    Some(n) if n > 0 => {
+      gl_attr.set_multisample_buffers(1);
+      gl_attr.set_multisample_samples(n.min(255) as u8);
+    }
+    _ => {
+      gl_attr.set_multisample_buffers(0);
+      gl_attr.set_multisample_samples(0);
+    }
+  }
+}
+
+// SDL2 calls monitors displays and indexes them with an i32.
+fn display_index(video: &VideoSubsystem, index: usize) -> Option<i32> {
+  let count = video.num_video_displays().ok()?;
+
+  if index < count as usize {
+    Some(index as i32)
+  } else {
+    None
+  }
+}
+
+fn from_sdl2_display_mode(mode: &DisplayMode) -> VideoMode {
+  // SDL2 reports an unknown refresh rate as 0
+  let refresh_rate = if mode.refresh_rate > 0 { Some(mode.refresh_rate as u32) } else { None };
+  VideoMode::new(mode.w as u32, mode.h as u32, refresh_rate)
+}
+
+fn from_sdl2_display(video: &VideoSubsystem, display: i32) -> Monitor {
+  let position = video.display_bounds(display).map(|r| [r.x(), r.y()]).unwrap_or([0, 0]);
+  // SDL2 doesn’t expose the physical size; the horizontal DPI, relative to the reference 96 DPI,
+  // is used as the monitor’s scale factor
+  let scale_factor = video.display_dpi(display).map(|(_, h, _)| h as f64 / 96.).unwrap_or(1.);
+  let mode_count = video.num_display_modes(display).unwrap_or(0);
+  let video_modes = (0 .. mode_count)
+    .filter_map(|i| video.display_mode(display, i).ok())
+    .map(|mode| from_sdl2_display_mode(&mode))
+    .collect();
+  let current_video_mode = video.current_display_mode(display).ok();
+
+  Monitor {
+    name: video.display_name(display).ok(),
+    position,
+    physical_size: None,
+    scale_factor,
+    current_video_mode: current_video_mode.as_ref().map(from_sdl2_display_mode),
+    video_modes,
+  }
+}
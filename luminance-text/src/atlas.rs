@@ -0,0 +1,281 @@
+//! Glyph atlases.
+
+use luminance::context::GraphicsContext;
+use luminance::pixel::NormR8UI;
+use luminance::texture::{
+  Dim2, Flat, GenMipmaps, MagFilter, MinFilter, Sampler, Texture, TextureError,
+};
+use rusttype::{point, Font, GlyphId, Scale};
+use std::collections::HashMap;
+use std::fmt;
+
+// Empty texels around every glyph, so that linear filtering doesn’t bleed into its neighbors.
+const PADDING: u32 = 1;
+
+/// Identifier of a font added to a [`GlyphAtlas`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FontId(usize);
+
+/// Errors that might happen when rasterizing glyphs into a [`GlyphAtlas`].
+#[derive(Debug)]
+pub enum TextError {
+  /// The font is not part of the atlas.
+  UnknownFont(FontId),
+  /// There’s no room left in the atlas for a glyph.
+  ///
+  /// [`GlyphAtlas::clear`] makes room by removing all the glyphs.
+  AtlasFull,
+  /// The atlas texture couldn’t be created or updated.
+  Texture(TextureError),
+}
+
+impl fmt::Display for TextError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      TextError::UnknownFont(id) => write!(f, "unknown font {:?}", id),
+      TextError::AtlasFull => f.write_str("no room left in the glyph atlas"),
+      TextError::Texture(ref e) => write!(f, "glyph atlas texture error: {}", e),
+    }
+  }
+}
+
+impl From<TextureError> for TextError {
+  fn from(e: TextureError) -> Self {
+    TextError::Texture(e)
+  }
+}
+
+// Key of a rasterized glyph: font, glyph and size in pixels – as bits, to be hashable.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct GlyphKey {
+  font: FontId,
+  glyph: GlyphId,
+  size: u32,
+}
+
+// A glyph rasterized into the atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AtlasGlyph {
+  // offset of the top-left corner of the glyph from the pen position, on the baseline
+  pub(crate) offset: [i32; 2],
+  // size in pixels
+  pub(crate) size: [u32; 2],
+  // texture coordinates of the top-left and bottom-right corners
+  pub(crate) uv: [[f32; 2]; 2],
+}
+
+// A row of the atlas, glyphs being packed from left to right.
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+  y: u32,
+  height: u32,
+  x: u32,
+}
+
+/// A texture holding the glyphs of fonts, rasterized on demand.
+///
+/// Glyphs are rasterized the first time a [`TextMesh`] uses them, into a CPU copy of the atlas.
+/// [`GlyphAtlas::flush`] then uploads the part of the copy that changed since the last flush, so
+/// that adding a few glyphs every frame only costs the upload of those.
+///
+/// The atlas texture has a single, normalized channel of coverage: `1.` inside the glyphs and `0.`
+/// outside.
+///
+/// [`TextMesh`]: crate::TextMesh
+pub struct GlyphAtlas {
+  fonts: Vec<Font<'static>>,
+  texture: Texture<Flat, Dim2, NormR8UI>,
+  size: u32,
+  texels: Vec<u8>,
+  shelves: Vec<Shelf>,
+  // None for glyphs without any texel – spaces, for instance
+  glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+  // [x0, y0, x1, y1], exclusive
+  dirty: Option<[u32; 4]>,
+}
+
+impl GlyphAtlas {
+  /// Create an empty atlas of `size` × `size` texels.
+  pub fn new<C>(ctx: &mut C, size: u32) -> Result<Self, TextError> where C: GraphicsContext {
+    let sampler = Sampler {
+      min_filter: MinFilter::Linear,
+      mag_filter: MagFilter::Linear,
+      ..Sampler::default()
+    };
+
+    let texture = Texture::new(ctx, [size, size], 0, sampler)?;
+    texture.clear(GenMipmaps::No, 0)?;
+
+    Ok(GlyphAtlas {
+      fonts: Vec::new(),
+      texture,
+      size,
+      texels: vec![0; size as usize * size as usize],
+      shelves: Vec::new(),
+      glyphs: HashMap::new(),
+      dirty: None,
+    })
+  }
+
+  /// Add a font to the atlas.
+  pub fn add_font(&mut self, font: Font<'static>) -> FontId {
+    self.fonts.push(font);
+    FontId(self.fonts.len() - 1)
+  }
+
+  /// A font of the atlas.
+  pub fn font(&self, id: FontId) -> Option<&Font<'static>> {
+    self.fonts.get(id.0)
+  }
+
+  /// Size of the atlas, in texels.
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  /// The atlas texture.
+  ///
+  /// Glyphs rasterized since the last call to [`GlyphAtlas::flush`] are not in it yet.
+  pub fn texture(&self) -> &Texture<Flat, Dim2, NormR8UI> {
+    &self.texture
+  }
+
+  /// Number of rasterized glyphs.
+  pub fn len(&self) -> usize {
+    self.glyphs.len()
+  }
+
+  /// Whether no glyph is rasterized.
+  pub fn is_empty(&self) -> bool {
+    self.glyphs.is_empty()
+  }
+
+  /// Upload the glyphs rasterized since the last flush to the atlas texture.
+  ///
+  /// Only the rectangle enclosing them is uploaded.
+  pub fn flush(&mut self) -> Result<(), TextError> {
+    if let Some([x0, y0, x1, y1]) = self.dirty.take() {
+      let width = (x1 - x0) as usize;
+      let mut texels = Vec::with_capacity(width * (y1 - y0) as usize);
+
+      for y in y0..y1 {
+        let start = y as usize * self.size as usize + x0 as usize;
+        texels.extend_from_slice(&self.texels[start..start + width]);
+      }
+
+      self.texture.upload_part_raw(GenMipmaps::No, [x0, y0], [x1 - x0, y1 - y0], &texels)?;
+    }
+
+    Ok(())
+  }
+
+  /// Remove all the glyphs, to make room for others.
+  ///
+  /// Meshes using the removed glyphs must be laid out again. The fonts are kept.
+  pub fn clear(&mut self) {
+    self.texels.fill(0);
+    self.shelves.clear();
+    self.glyphs.clear();
+    self.dirty = Some([0, 0, self.size, self.size]);
+  }
+
+  // Get a glyph of a font at a given size in pixels, rasterizing it if it’s not in the atlas yet.
+  pub(crate) fn glyph(
+    &mut self,
+    font: FontId,
+    glyph: GlyphId,
+    size: f32,
+  ) -> Result<Option<AtlasGlyph>, TextError> {
+    let key = GlyphKey {
+      font,
+      glyph,
+      size: size.to_bits(),
+    };
+
+    if let Some(cached) = self.glyphs.get(&key) {
+      return Ok(*cached);
+    }
+
+    let positioned = self
+      .fonts
+      .get(font.0)
+      .ok_or(TextError::UnknownFont(font))?
+      .glyph(glyph)
+      .scaled(Scale::uniform(size))
+      .positioned(point(0., 0.));
+
+    let bb = match positioned.pixel_bounding_box() {
+      Some(bb) => bb,
+
+      None => {
+        self.glyphs.insert(key, None);
+        return Ok(None);
+      }
+    };
+
+    let (w, h) = (bb.width() as u32, bb.height() as u32);
+    let [x, y] = self.allocate(w, h).ok_or(TextError::AtlasFull)?;
+
+    let size = self.size as usize;
+    let texels = &mut self.texels;
+    positioned.draw(|gx, gy, coverage| {
+      let index = (y + gy) as usize * size + (x + gx) as usize;
+      texels[index] = (coverage * 255.).round() as u8;
+    });
+
+    self.mark_dirty([x, y, x + w, y + h]);
+
+    let atlas_size = self.size as f32;
+    let cached = Some(AtlasGlyph {
+      offset: [bb.min.x, bb.min.y],
+      size: [w, h],
+      uv: [
+        [x as f32 / atlas_size, y as f32 / atlas_size],
+        [(x + w) as f32 / atlas_size, (y + h) as f32 / atlas_size],
+      ],
+    });
+
+    self.glyphs.insert(key, cached);
+    Ok(cached)
+  }
+
+  // Find room for a w × h glyph, on the shortest shelf it fits in or on a new one.
+  fn allocate(&mut self, w: u32, h: u32) -> Option<[u32; 2]> {
+    let (w, h) = (w + PADDING, h + PADDING);
+    let size = self.size;
+    let mut best: Option<&mut Shelf> = None;
+
+    for shelf in &mut self.shelves {
+      if shelf.height < h || size - shelf.x < w {
+        continue;
+      }
+
+      match best {
+        Some(ref b) if b.height <= shelf.height => (),
+        _ => best = Some(shelf),
+      }
+    }
+
+    if let Some(shelf) = best {
+      let position = [shelf.x, shelf.y];
+      shelf.x += w;
+      return Some(position);
+    }
+
+    let y = self.shelves.last().map(|shelf| shelf.y + shelf.height).unwrap_or(0);
+
+    if w > size || y + h > size {
+      return None;
+    }
+
+    self.shelves.push(Shelf { y, height: h, x: w });
+    Some([0, y])
+  }
+
+  fn mark_dirty(&mut self, [x0, y0, x1, y1]: [u32; 4]) {
+    self.dirty = Some(match self.dirty {
+      Some([dx0, dy0, dx1, dy1]) => [dx0.min(x0), dy0.min(y0), dx1.max(x1), dy1.max(y1)],
+      None => [x0, y0, x1, y1],
+    });
+  }
+}
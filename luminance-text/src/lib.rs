@@ -0,0 +1,105 @@
+//! Text rendering for [luminance].
+//!
+//! This crate lays out text with the fonts of a [`GlyphAtlas`] – loaded with [rusttype] – into
+//! [`TextMesh`]es: a quad per glyph, with its position in pixels, its texture coordinates in the
+//! atlas and its color. Glyphs are rasterized the first time they’re used and uploaded to the
+//! atlas texture with [`GlyphAtlas::flush`], which only uploads the new ones.
+//!
+//! [`TEXT_VS`] and [`TEXT_FS`] render the meshes with the [`TextInterface`] uniform interface. The
+//! fragment shader outputs the color of the glyphs with their coverage as alpha, to blend over the
+//! framebuffer:
+//!
+//! ```ignore
+//! let mut atlas = GlyphAtlas::new(&mut surface, 1024)?;
+//! let font = atlas.add_font(Font::from_bytes(FONT_BYTES as &[u8])?);
+//! let style = TextStyle { font, size: 16., color: [255, 255, 255, 255] };
+//!
+//! let mut mesh = TextMesh::new();
+//! mesh.push_text(&mut atlas, &style, [8., 8.], &format!("{:.1} FPS", fps))?;
+//! atlas.flush()?;
+//! mesh.update_tess(&mut tess)?;
+//!
+//! // in a pipeline
+//! let bound_atlas = pipeline.bind_texture(atlas.texture())?;
+//!
+//! shd_gate.shade(&text_program, |iface, mut rdr_gate| {
+//!   iface.viewport.update([width as f32, height as f32]);
+//!   iface.atlas.update(&bound_atlas);
+//!
+//!   rdr_gate.render(text_render_state(), |mut tess_gate| tess_gate.render(&tess));
+//! });
+//! ```
+//!
+//! [luminance]: https://crates.io/crates/luminance
+//! [rusttype]: https://crates.io/crates/rusttype
+
+#![deny(missing_docs)]
+
+mod atlas;
+mod mesh;
+mod vertex;
+
+pub use rusttype::Font;
+
+pub use crate::atlas::{FontId, GlyphAtlas, TextError};
+pub use crate::mesh::{TextMesh, TextStyle};
+pub use crate::vertex::{TextColor, TextPosition, TextSemantics, TextUv, TextVertex};
+
+use luminance::blending::{Equation, Factor};
+use luminance::pipeline::BoundTexture;
+use luminance::pixel::NormUnsigned;
+use luminance::render_state::RenderState;
+use luminance::shader::program::Uniform;
+use luminance::texture::{Dim2, Flat};
+use luminance_derive::UniformInterface;
+
+/// Vertex shader of text meshes.
+///
+/// It converts the positions, in pixels from the top-left corner, into clip space.
+pub const TEXT_VS: &str = "\
+in vec2 position;
+in vec2 uv;
+in vec4 color;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+uniform vec2 viewport;
+
+void main() {
+  vec2 p = position / viewport * 2. - 1.;
+  gl_Position = vec4(p.x, -p.y, 0., 1.);
+  v_uv = uv;
+  v_color = color;
+}
+";
+
+/// Fragment shader of text meshes.
+pub const TEXT_FS: &str = "\
+in vec2 v_uv;
+in vec4 v_color;
+
+out vec4 frag;
+
+uniform sampler2D atlas;
+
+void main() {
+  frag = vec4(v_color.rgb, v_color.a * texture(atlas, v_uv).r);
+}
+";
+
+/// Uniform interface of [`TEXT_VS`] and [`TEXT_FS`].
+#[derive(UniformInterface)]
+pub struct TextInterface {
+  /// Size of the framebuffer, in pixels.
+  pub viewport: Uniform<[f32; 2]>,
+  /// The atlas texture the meshes were laid out with.
+  pub atlas: Uniform<&'static BoundTexture<'static, Flat, Dim2, NormUnsigned>>,
+}
+
+/// Render state blending text over the framebuffer, without depth test.
+pub fn text_render_state() -> RenderState {
+  RenderState::default()
+    .set_blending((Equation::Additive, Factor::SrcAlpha, Factor::SrcAlphaComplement))
+    .set_depth_test(None)
+}
@@ -0,0 +1,163 @@
+//! Text meshes.
+
+use luminance::context::GraphicsContext;
+use luminance::tess::{Mode, Tess, TessBuilder, TessError, TessMapError};
+use rusttype::Scale;
+
+use crate::atlas::{FontId, GlyphAtlas, TextError};
+use crate::vertex::{TextColor, TextPosition, TextUv, TextVertex};
+
+/// Font, size and color of a piece of text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+  /// Font, from the atlas the text is laid out with.
+  pub font: FontId,
+  /// Size, in pixels: the height of a line without its gap.
+  pub size: f32,
+  /// Color, as sRGBA.
+  pub color: [u8; 4],
+}
+
+/// Vertices and indices of pieces of text, to render with a [`Tess`].
+///
+/// Text is laid out with the glyphs of a [`GlyphAtlas`], which rasterizes the ones it doesn’t have
+/// yet: flush the atlas before rendering the mesh. A mesh is typically cleared and laid out again
+/// when its text changes, then sent to its tessellation with [`TextMesh::update_tess`], which
+/// doesn’t create a new one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextMesh {
+  vertices: Vec<TextVertex>,
+  indices: Vec<u32>,
+}
+
+impl TextMesh {
+  /// Create an empty mesh.
+  pub fn new() -> Self {
+    TextMesh {
+      vertices: Vec::new(),
+      indices: Vec::new(),
+    }
+  }
+
+  /// Lay out `text` with `style`, starting with the top-left corner of its first line at
+  /// `origin`, in pixels.
+  ///
+  /// Lines are broken at `\n`. The position of the pen after the last glyph – on the top of its
+  /// line – is returned, to lay out more text after it.
+  pub fn push_text(
+    &mut self,
+    atlas: &mut GlyphAtlas,
+    style: &TextStyle,
+    origin: [f32; 2],
+    text: &str,
+  ) -> Result<[f32; 2], TextError> {
+    let scale = Scale::uniform(style.size);
+    let (v_metrics, line_height) = {
+      let font = atlas.font(style.font).ok_or(TextError::UnknownFont(style.font))?;
+      let v_metrics = font.v_metrics(scale);
+      (v_metrics, v_metrics.ascent - v_metrics.descent + v_metrics.line_gap)
+    };
+
+    let [mut x, mut y] = origin;
+    let mut previous = None;
+
+    for c in text.chars() {
+      if c == '\n' {
+        x = origin[0];
+        y += line_height;
+        previous = None;
+        continue;
+      }
+
+      // the font is known to be in the atlas
+      let (glyph, advance, kerning) = {
+        let font = atlas.font(style.font).unwrap();
+        let glyph = font.glyph(c);
+        let id = glyph.id();
+        let advance = glyph.scaled(scale).h_metrics().advance_width;
+        let kerning = previous.map(|p| font.pair_kerning(scale, p, id)).unwrap_or(0.);
+
+        (id, advance, kerning)
+      };
+
+      x += kerning;
+
+      if let Some(atlas_glyph) = atlas.glyph(style.font, glyph, style.size)? {
+        let x0 = x.round() + atlas_glyph.offset[0] as f32;
+        let y0 = (y + v_metrics.ascent).round() + atlas_glyph.offset[1] as f32;
+        let x1 = x0 + atlas_glyph.size[0] as f32;
+        let y1 = y0 + atlas_glyph.size[1] as f32;
+
+        self.push_quad([[x0, y0], [x1, y1]], atlas_glyph.uv, style.color);
+      }
+
+      x += advance;
+      previous = Some(glyph);
+    }
+
+    Ok([x, y])
+  }
+
+  /// Remove all the text, keeping the allocated memory.
+  pub fn clear(&mut self) {
+    self.vertices.clear();
+    self.indices.clear();
+  }
+
+  /// Vertices of the mesh, four per glyph.
+  pub fn vertices(&self) -> &[TextVertex] {
+    &self.vertices
+  }
+
+  /// Indices of the mesh, six per glyph.
+  pub fn indices(&self) -> &[u32] {
+    &self.indices
+  }
+
+  /// Whether the mesh has no glyph.
+  ///
+  /// Spaces and other glyphs without texels are not part of the mesh.
+  pub fn is_empty(&self) -> bool {
+    self.indices.is_empty()
+  }
+
+  /// Build a tessellation rendering the mesh.
+  ///
+  /// This fails if the mesh is empty.
+  pub fn build_tess<C>(&self, ctx: &mut C) -> Result<Tess, TessError> where C: GraphicsContext {
+    TessBuilder::new(ctx)
+      .add_vertices(&self.vertices)
+      .set_indices(&self.indices)
+      .set_mode(Mode::Triangle)
+      .build()
+  }
+
+  /// Replace the vertices and indices of a tessellation built with [`TextMesh::build_tess`].
+  pub fn update_tess(&self, tess: &mut Tess) -> Result<(), TessMapError> {
+    tess.set_vertices(&self.vertices)?;
+    tess.set_indices(&self.indices)
+  }
+
+  fn push_quad(&mut self, [[x0, y0], [x1, y1]]: [[f32; 2]; 2], uv: [[f32; 2]; 2], color: [u8; 4]) {
+    let [[u0, v0], [u1, v1]] = uv;
+    let first = self.vertices.len() as u32;
+    let color = TextColor::new(color);
+
+    let corners = [
+      ([x0, y0], [u0, v0]),
+      ([x1, y0], [u1, v0]),
+      ([x1, y1], [u1, v1]),
+      ([x0, y1], [u0, v1]),
+    ];
+
+    for &(position, uv) in &corners {
+      self.vertices.push(TextVertex {
+        position: TextPosition::new(position),
+        uv: TextUv::new(uv),
+        color,
+      });
+    }
+
+    self.indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+  }
+}
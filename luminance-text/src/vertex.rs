@@ -0,0 +1,38 @@
+//! Vertices of text meshes.
+
+// the vertex attribute types generated by the derives have no documentation
+#![allow(missing_docs)]
+
+use luminance_derive::{Semantics, Vertex};
+
+/// Vertex semantics of text meshes.
+///
+/// They’re the inputs of [`TEXT_VS`].
+///
+/// [`TEXT_VS`]: crate::TEXT_VS
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Semantics)]
+pub enum TextSemantics {
+  /// Position of a glyph corner, in pixels from the top-left corner of the framebuffer.
+  #[sem(name = "position", repr = "[f32; 2]", wrapper = "TextPosition")]
+  Position,
+  /// Texture coordinates of a glyph corner in the atlas.
+  #[sem(name = "uv", repr = "[f32; 2]", wrapper = "TextUv")]
+  Uv,
+  /// Color of the glyph.
+  #[sem(name = "color", repr = "[u8; 4]", wrapper = "TextColor")]
+  Color,
+}
+
+/// Vertex of a text mesh: a corner of a glyph quad.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Vertex)]
+#[vertex(sem = "TextSemantics")]
+pub struct TextVertex {
+  /// Position, in pixels.
+  pub position: TextPosition,
+  /// Texture coordinates in the atlas.
+  pub uv: TextUv,
+  /// Color, as sRGBA.
+  #[vertex(normalized = "true")]
+  pub color: TextColor,
+}
@@ -18,6 +18,12 @@
 //!   restricted).
 //! - `WindowOpt`: an opaque type giving access to hints to customize the window integration, such as whether
 //!   the cursor should be hidden or not.
+//! - `GlDriver`: the OpenGL implementation to create the context with, to fall back to a software
+//!   renderer when the native driver is broken.
+//! - `Monitor` and `VideoMode`: description of the monitors connected to the system and the video
+//!   modes they support, used to build display settings and to select an exclusive fullscreen mode.
+//! - `ViewportMapping`: conversion of cursor positions, as reported by window events, into
+//!   framebuffer pixel coordinates and normalized device coordinates.
 //!
 //! The `Device` trait must be implemented by a backend so that an application is completely
 //! agnostic of the backend. This trait defines several basic methods that will help you to:
@@ -38,6 +44,8 @@ use luminance::texture::{Dim2, Flat};
 ///   - `Fullscreen` opens in fullscreen mode by using the primary monitor resolution.
 ///   - `FullscreenRestricted(width, height)` is a mix between `Windowed(width, height)` and `Fullscreen`. It
 ///     opens in fullscreen mode by using the wished resolution.
+///   - `FullscreenExclusive(monitor, video_mode)` opens in exclusive fullscreen mode on the monitor
+///     at index `monitor` – as returned by `Surface::monitors` – by switching it to `video_mode`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum WindowDim {
   /// Windowed mode.
@@ -46,6 +54,77 @@ pub enum WindowDim {
   Fullscreen,
   /// Fullscreen mode with restricted viewport dimension.
   FullscreenRestricted(u32, u32),
+  /// Exclusive fullscreen mode on a given monitor with a given video mode.
+  FullscreenExclusive(usize, VideoMode),
+}
+
+/// A video mode a monitor can be switched to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct VideoMode {
+  /// Width of the video mode, in pixels.
+  pub width: u32,
+  /// Height of the video mode, in pixels.
+  pub height: u32,
+  /// Refresh rate of the video mode, in Hz.
+  ///
+  /// `None` if the backend cannot know the refresh rate.
+  pub refresh_rate: Option<u32>,
+}
+
+impl VideoMode {
+  /// Create a new video mode.
+  pub fn new<R>(width: u32, height: u32, refresh_rate: R) -> Self where R: Into<Option<u32>> {
+    VideoMode {
+      width,
+      height,
+      refresh_rate: refresh_rate.into(),
+    }
+  }
+}
+
+/// A monitor connected to the system.
+///
+/// Monitors are enumerated with `Surface::monitors`. The index of a monitor in that list is what
+/// you pass to `WindowDim::FullscreenExclusive`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monitor {
+  /// Human-readable name of the monitor, if any.
+  pub name: Option<String>,
+  /// Position of the top-left corner of the monitor in the virtual desktop, in pixels.
+  pub position: [i32; 2],
+  /// Physical size of the monitor, in millimeters, if known.
+  pub physical_size: Option<[u32; 2]>,
+  /// Scale factor (HiDPI factor) of the monitor.
+  pub scale_factor: f64,
+  /// Video mode the monitor is currently using, if known.
+  pub current_video_mode: Option<VideoMode>,
+  /// All video modes supported by the monitor.
+  pub video_modes: Vec<VideoMode>,
+}
+
+impl Monitor {
+  /// Dots per inch of the monitor, on both axis.
+  ///
+  /// This is computed from the current video mode and the physical size of the monitor and then
+  /// is `None` if any of those is unknown.
+  pub fn dpi(&self) -> Option<[f64; 2]> {
+    let mode = self.current_video_mode?;
+    let [w, h] = self.physical_size?;
+
+    if w == 0 || h == 0 {
+      return None;
+    }
+
+    Some([
+      mode.width as f64 * 25.4 / w as f64,
+      mode.height as f64 * 25.4 / h as f64,
+    ])
+  }
+
+  /// Highest refresh rate supported by the monitor, in Hz, if known.
+  pub fn max_refresh_rate(&self) -> Option<u32> {
+    self.video_modes.iter().filter_map(|mode| mode.refresh_rate).max()
+  }
 }
 
 /// Cursor mode.
@@ -59,6 +138,26 @@ pub enum CursorMode {
   Disabled
 }
 
+/// OpenGL implementation to create the context with.
+///
+/// Some desktops have broken native drivers; falling back to another implementation keeps the
+/// application running there, usually slower.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GlDriver {
+  /// The native driver of the system.
+  Native,
+  /// A software renderer, such as Mesa’s llvmpipe.
+  ///
+  /// With Mesa, surfaces set `LIBGL_ALWAYS_SOFTWARE` before creating the context. On Windows, a
+  /// software `opengl32.dll` – from Mesa, for instance – must be shipped with the application.
+  Software,
+  /// ANGLE, implementing OpenGL ES on top of Direct3D on Windows.
+  ///
+  /// Surfaces ask for an OpenGL ES 3.0 context with that driver, which luminance runs in its ES
+  /// profile.
+  Angle,
+}
+
 /// Different window options.
 ///
 /// Feel free to look at the different methods available to tweak the options. You may want to start
@@ -67,6 +166,8 @@ pub enum CursorMode {
 pub struct WindowOpt {
   cursor_mode: CursorMode,
   num_samples: Option<u32>,
+  max_frames_in_flight: Option<u32>,
+  gl_driver: GlDriver,
 }
 
 impl Default for WindowOpt {
@@ -74,10 +175,14 @@ impl Default for WindowOpt {
   ///
   /// - `cursor_mode` set to `CursorMode::Visible`.
   /// - `num_samples` set to `None`.
+  /// - `max_frames_in_flight` set to `None`.
+  /// - `gl_driver` set to `GlDriver::Native`.
   fn default() -> Self {
     WindowOpt {
       cursor_mode: CursorMode::Visible,
       num_samples: None,
+      max_frames_in_flight: None,
+      gl_driver: GlDriver::Native,
     }
   }
 }
@@ -114,6 +219,124 @@ impl WindowOpt {
   pub fn num_samples(&self) -> Option<u32> {
     self.num_samples
   }
+
+  /// Set the maximum number of frames that can be queued on the GPU.
+  ///
+  /// When set, swapping buffers blocks if more than that number of frames are still being
+  /// processed by the GPU, reducing input latency under GPU-bound loads. Pass `None` to let the
+  /// driver decide.
+  #[inline]
+  pub fn set_max_frames_in_flight<N>(self, max: N) -> Self where N: Into<Option<u32>> {
+    WindowOpt {
+      max_frames_in_flight: max.into(),
+      ..self
+    }
+  }
+
+  /// Get the maximum number of frames that can be queued on the GPU, if any.
+  #[inline]
+  pub fn max_frames_in_flight(&self) -> Option<u32> {
+    self.max_frames_in_flight
+  }
+
+  /// Set the OpenGL implementation to create the context with. Default to `GlDriver::Native`.
+  #[inline]
+  pub fn set_gl_driver(self, gl_driver: GlDriver) -> Self {
+    WindowOpt {
+      gl_driver,
+      ..self
+    }
+  }
+
+  /// Get the OpenGL implementation to create the context with.
+  #[inline]
+  pub fn gl_driver(&self) -> GlDriver {
+    self.gl_driver
+  }
+}
+
+/// Mapping from window coordinates to framebuffer coordinates.
+///
+/// Cursor positions are reported by the windowing systems in window coordinates: the origin is at
+/// the top-left corner of the window and the unit is not necessarily a framebuffer pixel – on
+/// HiDPI displays, a window coordinate spans several pixels. OpenGL, on the other side, puts the
+/// origin at the bottom-left corner of the framebuffer. A `ViewportMapping` takes care of both the
+/// scaling and the y-flip, and optionally restricts the mapping to a viewport of the framebuffer.
+///
+/// Get one for a surface with `Surface::viewport_mapping`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportMapping {
+  window_size: [f64; 2],
+  framebuffer_size: [u32; 2],
+  viewport_origin: [u32; 2],
+  viewport_size: [u32; 2],
+}
+
+impl ViewportMapping {
+  /// Create a mapping between a window of size `window_size`, in window coordinates, and its
+  /// framebuffer of size `framebuffer_size`, in pixels.
+  ///
+  /// The viewport covers the whole framebuffer.
+  pub fn new(window_size: [f64; 2], framebuffer_size: [u32; 2]) -> Self {
+    ViewportMapping {
+      window_size,
+      framebuffer_size,
+      viewport_origin: [0, 0],
+      viewport_size: framebuffer_size,
+    }
+  }
+
+  /// Restrict the mapping to a viewport, given by its lower-left corner and its size in
+  /// framebuffer pixels.
+  pub fn set_viewport(self, origin: [u32; 2], size: [u32; 2]) -> Self {
+    ViewportMapping {
+      viewport_origin: origin,
+      viewport_size: size,
+      ..self
+    }
+  }
+
+  /// Number of framebuffer pixels per window coordinate, on both axis.
+  pub fn scale_factor(&self) -> [f64; 2] {
+    let scale = |fb: u32, win: f64| if win > 0. { fb as f64 / win } else { 1. };
+
+    [
+      scale(self.framebuffer_size[0], self.window_size[0]),
+      scale(self.framebuffer_size[1], self.window_size[1]),
+    ]
+  }
+
+  /// Convert a cursor position into framebuffer pixel coordinates.
+  ///
+  /// The result has its origin at the lower-left corner of the framebuffer, as `gl_FragCoord`.
+  pub fn to_framebuffer(&self, cursor: [f64; 2]) -> [f64; 2] {
+    let [sx, sy] = self.scale_factor();
+    [cursor[0] * sx, self.framebuffer_size[1] as f64 - cursor[1] * sy]
+  }
+
+  /// Convert a cursor position into pixel coordinates relative to the lower-left corner of the
+  /// viewport.
+  pub fn to_pixel(&self, cursor: [f64; 2]) -> [f64; 2] {
+    let [x, y] = self.to_framebuffer(cursor);
+    [x - self.viewport_origin[0] as f64, y - self.viewport_origin[1] as f64]
+  }
+
+  /// Convert a cursor position into normalized device coordinates of the viewport.
+  ///
+  /// The viewport spans `[-1; 1]` on both axis, `y` pointing up. Positions outside of the viewport
+  /// map outside of that range.
+  pub fn to_ndc(&self, cursor: [f64; 2]) -> [f64; 2] {
+    let [x, y] = self.to_pixel(cursor);
+    let ndc = |p: f64, size: u32| if size > 0 { p / size as f64 * 2. - 1. } else { 0. };
+
+    [ndc(x, self.viewport_size[0]), ndc(y, self.viewport_size[1])]
+  }
+
+  /// Check whether a cursor position lies in the viewport.
+  pub fn contains(&self, cursor: [f64; 2]) -> bool {
+    let [x, y] = self.to_pixel(cursor);
+    x >= 0. && y >= 0. && x < self.viewport_size[0] as f64 && y < self.viewport_size[1] as f64
+  }
 }
 
 /// Rendering surface.
@@ -143,6 +366,11 @@ pub trait Surface: GraphicsContext + Sized {
   /// Size of the surface’s framebuffer.
   fn size(&self) -> [u32; 2];
 
+  /// Enumerate the monitors connected to the system.
+  ///
+  /// The first monitor is not necessarily the primary one.
+  fn monitors(&self) -> Vec<Monitor>;
+
   /// Width of the surface’s framebuffer.
   ///
   /// # Defaults
@@ -161,6 +389,25 @@ pub trait Surface: GraphicsContext + Sized {
     self.size()[1]
   }
 
+  /// Size of the window, in the coordinates cursor positions are reported in.
+  ///
+  /// # Defaults
+  ///
+  /// Defaults to `.size()`, i.e. window coordinates are framebuffer pixels.
+  fn window_size(&self) -> [f64; 2] {
+    let [w, h] = self.size();
+    [w as f64, h as f64]
+  }
+
+  /// Mapping from cursor positions to the framebuffer of the surface.
+  ///
+  /// # Defaults
+  ///
+  /// Defaults to `ViewportMapping::new(self.window_size(), self.size())`.
+  fn viewport_mapping(&self) -> ViewportMapping {
+    ViewportMapping::new(self.window_size(), self.size())
+  }
+
   // FIXME: existential impl trait
   /// Get an iterator over events by blocking until the first event happens.
   fn wait_events<'a>(&'a mut self) -> Box<dyn Iterator<Item = Self::Event> + 'a>;
@@ -98,7 +98,7 @@ fn run(texture_path: &Path) {
       .pipeline_builder()
       .pipeline(&back_buffer, &PipelineState::default(), |pipeline, mut shd_gate| {
         // bind our fancy texture to the GPU: it gives us a bound texture we can use with the shader
-        let bound_tex = pipeline.bind_texture(&tex);
+        let bound_tex = pipeline.bind_texture(&tex).expect("texture binding");
 
         shd_gate.shade(&program, |iface, mut rdr_gate| {
           // update the texture; strictly speaking, this update doesn’t do much: it just tells the GPU
@@ -9,7 +9,7 @@ mod common;
 
 use crate::common::{Semantics, Vertex, VertexPosition, VertexColor};
 use luminance::context::GraphicsContext as _;
-use luminance::framebuffer::Framebuffer;
+use luminance::framebuffer::RenderTarget;
 use luminance::pipeline::{BoundTexture, PipelineState};
 use luminance::pixel::{RGBA32F, Floating};
 use luminance::render_state::RenderState;
@@ -77,10 +77,11 @@ fn main() {
 
   // “screen“ we want to render into our offscreen render
   let mut back_buffer = surface.back_buffer().unwrap();
-  // offscreen buffer that we will render in the first place
+  // offscreen buffer that we will render in the first place; a render target is a framebuffer
+  // that can be resized
   let size = surface.size();
   let mut offscreen_buffer =
-    Framebuffer::<Flat, Dim2, RGBA32F, ()>::new(&mut surface, size, 0).expect("framebuffer creation");
+    RenderTarget::<Flat, Dim2, RGBA32F, ()>::new(&mut surface, size, 0).expect("framebuffer creation");
 
   // hack to update the offscreen buffer if needed; this is needed because we cannot update the
   // offscreen buffer from within the event loop
@@ -104,9 +105,9 @@ fn main() {
     if resize {
       // simply ask another backbuffer at the right dimension (no allocation / reallocation)
       back_buffer = surface.back_buffer().unwrap();
-      // ditto for the offscreen framebuffer
+      // resize the offscreen framebuffer, re-creating its color slot
       let size = surface.size();
-      offscreen_buffer = Framebuffer::new(&mut surface, size, 0).expect("framebuffer recreation");
+      offscreen_buffer.resize(&mut surface, size).expect("framebuffer recreation");
 
       resize = false;
     }
@@ -127,7 +128,9 @@ fn main() {
     // read from the offscreen framebuffer and output it into the back buffer
     builder.pipeline(&back_buffer, &PipelineState::default(), |pipeline, mut shd_gate| {
       // we must bind the offscreen framebuffer color content so that we can pass it to a shader
-      let bound_texture = pipeline.bind_texture(offscreen_buffer.color_slot());
+      let bound_texture = pipeline
+        .bind_texture(offscreen_buffer.color_slot())
+        .expect("texture binding");
 
       shd_gate.shade(&copy_program, |iface, mut rdr_gate| {
         // we update the texture with the bound texture
@@ -145,9 +145,15 @@ fn main() {
             &back_buffer,
             &PipelineState::default(),
             |pipeline, mut shading_gate| {
-                let bound_texture = pipeline.bind_texture(&tex);
-                let bound_displacement_1 = pipeline.bind_texture(&displacement_tex_1);
-                let bound_displacement_2 = pipeline.bind_texture(&displacement_tex_2);
+                let bound_texture = pipeline
+                    .bind_texture(&tex)
+                    .expect("texture binding");
+                let bound_displacement_1 = pipeline
+                    .bind_texture(&displacement_tex_1)
+                    .expect("texture binding");
+                let bound_displacement_2 = pipeline
+                    .bind_texture(&displacement_tex_2)
+                    .expect("texture binding");
 
                 shading_gate.shade(&program, |interface, mut render_gate| {
                     interface.image.update(&bound_texture);
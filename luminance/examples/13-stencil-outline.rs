@@ -0,0 +1,116 @@
+//! This program shows how to use the stencil buffer to render outlines around objects, a very
+//! common feature of editors and games to highlight selected objects.
+//!
+//! The triangle is rendered twice: the first time normally, marking its fragments in the stencil
+//! buffer, and the second time scaled up with a flat color, only where the stencil buffer was not
+//! marked. What’s left is an outline around the triangle.
+//!
+//! Press <space> to toggle the outline.
+//! Press <escape> to quit or close the window.
+//!
+//! https://docs.rs/luminance
+
+mod common;
+
+use crate::common::{Semantics, Vertex, VertexPosition, VertexColor};
+use luminance::context::GraphicsContext as _;
+use luminance::pipeline::PipelineState;
+use luminance::render_state::RenderState;
+use luminance::shader::program::{Program, Uniform};
+use luminance::stencil::StencilOutline;
+use luminance::tess::{Mode, TessBuilder};
+use luminance_derive::UniformInterface;
+use luminance_glfw::{Action, GlfwSurface, Key, Surface, WindowEvent, WindowDim, WindowOpt};
+
+const VS: &'static str = include_str!("outline-vs.glsl");
+const FS: &'static str = include_str!("outline-fs.glsl");
+
+const TRI_VERTICES: [Vertex; 3] = [
+  Vertex { pos: VertexPosition::new([0.5, -0.5]), rgb: VertexColor::new([1., 0., 0.]) },
+  Vertex { pos: VertexPosition::new([0.0, 0.5]), rgb: VertexColor::new([0., 1., 0.]) },
+  Vertex { pos: VertexPosition::new([-0.5, -0.5]), rgb: VertexColor::new([0., 0., 1.]) },
+];
+
+#[derive(Debug, UniformInterface)]
+struct ShaderInterface {
+  scale: Uniform<f32>,
+  outline_color: Uniform<[f32; 3]>,
+  flat_color: Uniform<bool>
+}
+
+fn main() {
+  let mut surface = GlfwSurface::new(
+    WindowDim::Windowed(960, 540),
+    "Hello, world!",
+    WindowOpt::default(),
+  )
+  .expect("GLFW surface creation");
+
+  let program = Program::<Semantics, (), ShaderInterface>::from_strings(None, VS, None, FS)
+    .expect("program creation")
+    .ignore_warnings();
+
+  let triangle = TessBuilder::new(&mut surface)
+    .add_vertices(TRI_VERTICES)
+    .set_mode(Mode::Triangle)
+    .build()
+    .unwrap();
+
+  let mut back_buffer = surface.back_buffer().unwrap();
+
+  // the stencil buffer must be cleared every frame for the outline to work
+  let pipeline_state = PipelineState::default().enable_clear_stencil(true);
+  let outline = StencilOutline::default();
+  let mut outline_enabled = true;
+  let mut resize = false;
+
+  'app: loop {
+    for event in surface.poll_events() {
+      match event {
+        WindowEvent::Close | WindowEvent::Key(Key::Escape, _, Action::Release, _) => break 'app,
+
+        WindowEvent::Key(Key::Space, _, Action::Release, _) => {
+          outline_enabled = !outline_enabled;
+        }
+
+        WindowEvent::FramebufferSize(..) => {
+          resize = true;
+        }
+
+        _ => (),
+      }
+    }
+
+    if resize {
+      back_buffer = surface.back_buffer().unwrap();
+      resize = false;
+    }
+
+    surface
+      .pipeline_builder()
+      .pipeline(&back_buffer, &pipeline_state, |_, mut shd_gate| {
+        shd_gate.shade(&program, |iface, mut rdr_gate| {
+          // first pass: render the triangle and mark it in the stencil buffer
+          iface.scale.update(1.);
+          iface.flat_color.update(false);
+
+          rdr_gate.render(outline.mask_render_state(RenderState::default()), |mut tess_gate| {
+            tess_gate.render(&triangle);
+          });
+
+          if outline_enabled {
+            // second pass: render the scaled triangle everywhere but where it was marked
+            iface.scale.update(1.1);
+            iface.outline_color.update([1., 0.5, 0.]);
+            iface.flat_color.update(true);
+
+            rdr_gate.render(outline.outline_render_state(RenderState::default()), |mut tess_gate| {
+              tess_gate.render(&triangle);
+            });
+          }
+        });
+      });
+
+    surface.swap_buffers();
+  }
+}
@@ -0,0 +1,10 @@
+in vec3 v_color;
+
+out vec4 frag;
+
+uniform vec3 outline_color;
+uniform bool flat_color;
+
+void main() {
+  frag = vec4(flat_color ? outline_color : v_color, 1.);
+}
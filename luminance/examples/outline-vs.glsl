@@ -0,0 +1,11 @@
+in vec2 co;
+in vec3 color;
+
+out vec3 v_color;
+
+uniform float scale;
+
+void main() {
+  gl_Position = vec4(co * scale, 0., 1.);
+  v_color = color;
+}
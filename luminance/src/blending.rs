@@ -9,8 +9,22 @@
 //!
 //! The factors are encoded with [`Factor`].
 //!
+//! By default, the same equation and factors apply to the RGB and alpha channels. They can also be
+//! set separately – see [`RenderState::set_alpha_blending`] –, which is typically needed when
+//! rendering premultiplied alpha over a transparent framebuffer. The `Constant*` factors use the
+//! constant color set with [`RenderState::set_blend_color`].
+//!
+//! Blending can finally be configured per draw buffer with [`SeparateBlending`] overrides – see
+//! [`RenderState::set_draw_buffer_blending`].
+//!
+//! > Note: per draw buffer blending requires *OpenGL 4.0* or `GL_ARB_draw_buffers_blend`. Without
+//! > them, overrides are ignored.
+//!
 //! [`Equation`]: crate::blending::Equation
 //! [`Factor`]: crate::blending::Factor
+//! [`RenderState::set_alpha_blending`]: crate::render_state::RenderState::set_alpha_blending
+//! [`RenderState::set_blend_color`]: crate::render_state::RenderState::set_blend_color
+//! [`RenderState::set_draw_buffer_blending`]: crate::render_state::RenderState::set_draw_buffer_blending
 
 /// Whether or not enable blending.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -73,4 +87,35 @@ pub enum Factor {
   DstAlphaComplement,
   /// This behavior is still not well understood. Dammit.
   SrcAlphaSaturate,
+  /// `constant * color`
+  ConstantColor,
+  /// `(1 - constant) * color`
+  ConstantColorComplement,
+  /// `constantA * color`
+  ConstantAlpha,
+  /// `(1 - constantA) * color`
+  ConstantAlphaComplement,
+}
+
+/// Blending configuration with separate RGB and alpha equations and factors.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SeparateBlending {
+  /// Equation, source and destination factors of the RGB channels.
+  pub rgb: (Equation, Factor, Factor),
+  /// Equation, source and destination factors of the alpha channel.
+  pub alpha: (Equation, Factor, Factor),
+}
+
+impl SeparateBlending {
+  /// Create a blending configuration from the RGB and alpha ones.
+  pub fn new(rgb: (Equation, Factor, Factor), alpha: (Equation, Factor, Factor)) -> Self {
+    SeparateBlending { rgb, alpha }
+  }
+}
+
+impl From<(Equation, Factor, Factor)> for SeparateBlending {
+  /// Use the same configuration for the RGB and alpha channels.
+  fn from(blending: (Equation, Factor, Factor)) -> Self {
+    SeparateBlending::new(blending, blending)
+  }
 }
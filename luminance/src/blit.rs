@@ -0,0 +1,219 @@
+//! GPU texture blitting, scaling and format conversion.
+//!
+//! [`gpu_convert`] copies the texels of a 2D texture into another one, scaling them if the sizes
+//! differ and converting them if the pixel formats differ. Asset pipelines can then resize or
+//! convert textures on the GPU instead of round-tripping texels through the CPU.
+//!
+//! When both textures hold the same kind of data – floating-point or normalized, signed integral
+//! or unsigned integral – the copy is a framebuffer blit. Otherwise, a shader pass converts the
+//! texels with *GLSL* constructors (i.e. `1.5` becomes `1` in an integral texture, and `255` in an
+//! unsigned integral texture becomes `255.` – not `1.` – in a normalized one). The program of the
+//! shader pass is built on each call, so cross-kind conversions are not meant to be done every
+//! frame.
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{BlitBuffers, Framebuffer, FramebufferError};
+use crate::pipeline::{BindingError, BoundTexture, PipelineState};
+use crate::pixel::{ColorPixel, RenderablePixel, SamplerType, Type};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Tess, TessError};
+use crate::texture::{Dim2, Flat, MagFilter, Texture};
+
+/// Copy the texels of `src` to `dst`, scaling and converting them if needed.
+///
+/// `filter` is used when the sizes differ. Integral textures are always scaled with
+/// [`MagFilter::Nearest`]; their sampler must use nearest filters too, otherwise they cannot be
+/// read (see [`Texture::set_sampler`]).
+pub fn gpu_convert<C, P, Q>(
+  ctx: &mut C,
+  src: &Texture<Flat, Dim2, P>,
+  dst: &Texture<Flat, Dim2, Q>,
+  filter: MagFilter,
+) -> Result<(), BlitError>
+where C: GraphicsContext,
+      P: ColorPixel,
+      P::SamplerType: 'static,
+      Q: ColorPixel + RenderablePixel {
+  let src_kind = Kind::from_type(P::pixel_format().encoding);
+  let dst_kind = Kind::from_type(Q::pixel_format().encoding);
+  let filter = match src_kind {
+    Kind::Floating => filter,
+    _ => MagFilter::Nearest,
+  };
+  let dst_fb = unsafe { Framebuffer::from_raw_texture(ctx, dst, dst.size())? };
+
+  if src_kind == dst_kind {
+    let src_fb = unsafe { Framebuffer::from_raw_texture(ctx, src, src.size())? };
+    src_fb.blit(&dst_fb, src_fb.rect(), dst_fb.rect(), BlitBuffers::COLOR, filter)?;
+
+    return Ok(());
+  }
+
+  let fs = convert_fs(src_kind, dst_kind, filter);
+  let program: Program<(), (), ConvertInterface<P::SamplerType>> =
+    Program::from_strings(None, FULLSCREEN_VS, None, &fs)?.ignore_warnings();
+  let triangle = Tess::fullscreen_triangle(ctx)?;
+  let [dst_w, dst_h] = dst.size();
+  let pipeline_state = PipelineState::default().enable_clear(false);
+  let render_state = RenderState::default().set_depth_test(None);
+  let mut result = Ok(());
+
+  ctx.pipeline_builder().pipeline(&dst_fb, &pipeline_state, |pipeline, mut shd_gate| {
+    let source = match pipeline.bind_texture(src) {
+      Ok(source) => source,
+      Err(e) => {
+        result = Err(e);
+        return;
+      }
+    };
+
+    shd_gate.shade(&program, |iface, mut rdr_gate| {
+      iface.source.update(&source);
+      iface.dst_size.update([dst_w as f32, dst_h as f32]);
+      rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(&triangle));
+    });
+  });
+
+  result.map_err(BlitError::BindingError)
+}
+
+/// Errors that can happen while blitting textures.
+#[derive(Debug)]
+pub enum BlitError {
+  /// A texture cannot be attached to a framebuffer.
+  FramebufferError(FramebufferError),
+  /// The conversion shader program failed to build.
+  ProgramError(ProgramError),
+  /// The triangle used by the conversion shader pass couldn’t be created.
+  TessError(TessError),
+  /// No texture unit was left to bind the source texture of the conversion shader pass.
+  BindingError(BindingError),
+}
+
+impl fmt::Display for BlitError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      BlitError::FramebufferError(ref e) => write!(f, "blit framebuffer error: {}", e),
+      BlitError::ProgramError(ref e) => write!(f, "blit program error: {}", e),
+      BlitError::TessError(ref e) => write!(f, "blit tessellation error: {:?}", e),
+      BlitError::BindingError(ref e) => write!(f, "blit binding error: {}", e),
+    }
+  }
+}
+
+impl From<FramebufferError> for BlitError {
+  fn from(e: FramebufferError) -> Self {
+    BlitError::FramebufferError(e)
+  }
+}
+
+impl From<ProgramError> for BlitError {
+  fn from(e: ProgramError) -> Self {
+    BlitError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for BlitError {
+  fn from(e: TessError) -> Self {
+    BlitError::TessError(e)
+  }
+}
+
+impl From<BindingError> for BlitError {
+  fn from(e: BindingError) -> Self {
+    BlitError::BindingError(e)
+  }
+}
+
+// Kind of data held by a texture; blits only work between textures of the same kind.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+  Floating,
+  Integral,
+  Unsigned,
+}
+
+impl Kind {
+  fn from_type(ty: Type) -> Self {
+    match ty {
+      Type::Floating | Type::NormIntegral | Type::NormUnsigned => Kind::Floating,
+      Type::Integral => Kind::Integral,
+      Type::Unsigned => Kind::Unsigned,
+    }
+  }
+
+  // GLSL prefix of the sampler and vector types of that kind.
+  fn glsl_prefix(self) -> &'static str {
+    match self {
+      Kind::Floating => "",
+      Kind::Integral => "i",
+      Kind::Unsigned => "u",
+    }
+  }
+}
+
+struct ConvertInterface<S> where S: 'static + SamplerType {
+  source: Uniform<&'static BoundTexture<'static, Flat, Dim2, S>>,
+  dst_size: Uniform<[f32; 2]>,
+}
+
+impl<S> UniformInterface for ConvertInterface<S> where S: 'static + SamplerType {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(ConvertInterface {
+      source: builder.ask("source").map_err(ProgramError::UniformWarning)?,
+      dst_size: builder.ask("dst_size").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+// Fragment shader converting from a kind of texture to another; linear filtering is done by hand
+// so that the sampler of the source texture doesn’t matter.
+fn convert_fs(src: Kind, dst: Kind, filter: MagFilter) -> String {
+  let linear = match filter {
+    MagFilter::Nearest => "",
+    MagFilter::Linear => "#define LINEAR\n",
+  };
+
+  format!(
+    "{linear}
+uniform {src}sampler2D source;
+uniform vec2 dst_size;
+
+out {dst}vec4 frag;
+
+{src}vec4 fetch(ivec2 p) {{
+  return texelFetch(source, clamp(p, ivec2(0), textureSize(source, 0) - 1), 0);
+}}
+
+void main() {{
+  vec2 p = gl_FragCoord.xy / dst_size * vec2(textureSize(source, 0));
+
+#ifdef LINEAR
+  p -= .5;
+  ivec2 i = ivec2(floor(p));
+  vec2 f = fract(p);
+  vec4 bottom = mix(vec4(fetch(i)), vec4(fetch(i + ivec2(1, 0))), f.x);
+  vec4 top = mix(vec4(fetch(i + ivec2(0, 1))), vec4(fetch(i + ivec2(1, 1))), f.x);
+  frag = {dst}vec4(mix(bottom, top, f.y));
+#else
+  frag = {dst}vec4(fetch(ivec2(p)));
+#endif
+}}
+",
+    linear = linear,
+    src = src.glsl_prefix(),
+    dst = dst.glsl_prefix()
+  )
+}
@@ -67,14 +67,50 @@
 //! assert_eq!(buffer.at(2), Some(42));
 //! ```
 //!
+//! # Mapping a buffer
+//!
+//! [`Buffer::as_slice`] and [`Buffer::as_slice_mut`] map the whole buffer as a Rust slice.
+//! [`Buffer::as_slice_range`] and [`Buffer::as_slice_range_mut`] only map a range of items, which
+//! is cheaper when a few items of a large buffer must be read or updated:
+//!
+//! ```ignore
+//! // update items 100 to 109 only
+//! let mut slice = buffer.as_slice_range_mut(100, 10)?;
+//! slice[0] = 42;
+//! ```
+//!
+//! # Streaming
+//!
+//! Buffers are created with a [`BufferUsage`] hint – [`Buffer::from_slice_with_usage`], for
+//! instance –, telling the driver how often their content changes. Buffers rewritten every frame
+//! should also be invalidated with [`Buffer::invalidate`] before being written to, so that the
+//! driver doesn’t wait for the GPU to be done with their previous content:
+//!
+//! ```ignore
+//! let mut particles = Buffer::from_slice_with_usage(&mut ctx, &initial, BufferUsage::Stream);
+//!
+//! // every frame
+//! particles.invalidate()?;
+//! particles.fill(&simulated)?;
+//! ```
+//!
 //! # Uniform buffer
 //!
 //! It’s possible to use buffers as *uniform buffers*. That is, buffers that will be in bound at
 //! rendering time and which content will be available for a shader to read (no write).
 //!
 //! In order to use your buffers in a uniform context, the inner type has to implement
-//! [`UniformBlock`]. Keep in mind alignment must be respected and is a bit peculiar. TODO: explain
-//! std140 here.
+//! [`UniformBlock`]. Keep in mind alignment must be respected and is a bit peculiar: uniform
+//! blocks declared with `layout (std140)` align `vec3` and `vec4` members to 16 bytes, as well as
+//! structures, arrays items and matrix columns. A Rust `[f32; 3]` followed by a `f32` fits that
+//! layout, but a `f32` followed by a `[f32; 3]` doesn’t: the vector is expected 12 bytes further.
+//!
+//! `#[derive(UniformBlock)]` (from luminance-derive) checks that at compile time, for types
+//! implementing [`Std140`].
+//!
+//! A [`UniformBuffer`] holds a single uniform block along with a CPU copy of it, so that it can be
+//! modified field by field and uploaded at once. A [`UniformBufferCache`] also tracks the bytes
+//! that changed, so that only them are uploaded – which matters for large blocks.
 //!
 //! [`Buffer`]: crate::buffer::Buffer
 //! [`Buffer::new`]: crate::buffer::Buffer::new
@@ -83,8 +119,18 @@
 //! [`Buffer::clear`]: crate::buffer::Buffer::clear
 //! [`Buffer::fill`]: crate::buffer::Buffer::fill
 //! [`Buffer::set`]: crate::buffer::Buffer::set
+//! [`Buffer::from_slice_with_usage`]: crate::buffer::Buffer::from_slice_with_usage
+//! [`Buffer::invalidate`]: crate::buffer::Buffer::invalidate
+//! [`BufferUsage`]: crate::buffer::BufferUsage
+//! [`Buffer::as_slice`]: crate::buffer::Buffer::as_slice
+//! [`Buffer::as_slice_mut`]: crate::buffer::Buffer::as_slice_mut
+//! [`Buffer::as_slice_range`]: crate::buffer::Buffer::as_slice_range
+//! [`Buffer::as_slice_range_mut`]: crate::buffer::Buffer::as_slice_range_mut
 //! [`GraphicsContext`]: crate::context::GraphicsContext
 //! [`UniformBlock`]: crate::buffer::UniformBlock
+//! [`Std140`]: crate::buffer::Std140
+//! [`UniformBuffer`]: crate::buffer::UniformBuffer
+//! [`UniformBufferCache`]: crate::buffer::UniformBufferCache
 
 #[cfg(feature = "std")]
 use std::cell::RefCell;
@@ -97,7 +143,7 @@ use std::marker::PhantomData;
 #[cfg(feature = "std")]
 use std::mem;
 #[cfg(feature = "std")]
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 #[cfg(feature = "std")]
 use std::os::raw::c_void;
 #[cfg(feature = "std")]
@@ -122,16 +168,17 @@ use core::marker::PhantomData;
 #[cfg(not(feature = "std"))]
 use core::mem;
 #[cfg(not(feature = "std"))]
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range};
 #[cfg(not(feature = "std"))]
 use core::ptr;
 #[cfg(not(feature = "std"))]
 use core::slice;
 
 use crate::context::GraphicsContext;
+use crate::debug;
 use crate::linear::{M22, M33, M44};
 use crate::metagl::*;
-use crate::state::{Bind, GraphicsState};
+use crate::state::{Bind, GraphicsState, PoisonCause};
 
 /// Buffer errors.
 #[derive(Debug, Eq, PartialEq)]
@@ -150,6 +197,8 @@ pub enum BufferError {
   TooManyValues(usize, usize),
   /// Mapping the buffer failed.
   MapFailed,
+  /// The buffer cannot be used anymore.
+  Poisoned(PoisonCause),
 }
 
 impl fmt::Display for BufferError {
@@ -174,6 +223,39 @@ impl fmt::Display for BufferError {
       }
 
       BufferError::MapFailed => write!(f, "buffer mapping failed"),
+
+      BufferError::Poisoned(ref cause) => write!(f, "poisoned buffer: {}", cause),
+    }
+  }
+}
+
+impl From<PoisonCause> for BufferError {
+  fn from(cause: PoisonCause) -> Self {
+    BufferError::Poisoned(cause)
+  }
+}
+
+/// Usage hint of a buffer, telling the driver how its content is going to be updated.
+///
+/// Hints don’t restrict what a buffer can be used for, but drivers place buffers in memory
+/// accordingly.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BufferUsage {
+  /// The content is set once and used many times.
+  Static,
+  /// The content is updated repeatedly and used many times.
+  Dynamic,
+  /// The content is updated – typically every frame – and used a few times only.
+  #[default]
+  Stream,
+}
+
+impl BufferUsage {
+  fn to_glenum(self) -> GLenum {
+    match self {
+      BufferUsage::Static => gl::STATIC_DRAW,
+      BufferUsage::Dynamic => gl::DYNAMIC_DRAW,
+      BufferUsage::Stream => gl::STREAM_DRAW,
     }
   }
 }
@@ -182,6 +264,9 @@ impl fmt::Display for BufferError {
 ///
 /// You’re strongly advised to use either [`Buffer::from_slice`] or [`Buffer::repeat`] to create a
 /// [`Buffer`]. The [`Buffer::new`] should only be used if you know what you’re doing.
+///
+/// Buffers share the state of their context and are not [`Send`]: they statically stay on the
+/// thread of their context.
 pub struct Buffer<T> {
   raw: RawBuffer,
   _t: PhantomData<T>,
@@ -193,20 +278,37 @@ impl<T> Buffer<T> {
   /// That function leaves the buffer _uninitialized_, which is `unsafe`. If you prefer not to use
   /// any `unsafe` function, feel free to use [`Buffer::from_slice`] or [`Buffer::repeat`] instead.
   pub unsafe fn new<C>(ctx: &mut C, len: usize) -> Buffer<T> where C: GraphicsContext {
+    Self::new_with_usage(ctx, len, BufferUsage::default())
+  }
+
+  /// Create a new [`Buffer`] with a given number of elements and a usage hint.
+  ///
+  /// # Safety
+  ///
+  /// The buffer is left _uninitialized_, as with [`Buffer::new`].
+  pub unsafe fn new_with_usage<C>(
+    ctx: &mut C,
+    len: usize,
+    usage: BufferUsage,
+  ) -> Buffer<T>
+  where C: GraphicsContext {
     let mut buffer: GLuint = 0;
     let bytes = mem::size_of::<T>() * len;
 
     // generate a buffer and force binding the handle; this prevent side-effects from previous bound
     // resources to prevent binding the buffer
     gl::GenBuffers(1, &mut buffer);
+    log_event!(created(Buffer, buffer));
     ctx.state().borrow_mut().bind_array_buffer(buffer, Bind::Forced);
-    gl::BufferData(gl::ARRAY_BUFFER, bytes as isize, ptr::null(), gl::STREAM_DRAW);
+    gl::BufferData(gl::ARRAY_BUFFER, bytes as isize, ptr::null(), usage.to_glenum());
+    gl_check!("BufferData", buffer);
 
     Buffer {
       raw: RawBuffer {
         handle: buffer,
         bytes,
         len,
+        usage,
         state: ctx.state().clone(),
       },
       _t: PhantomData,
@@ -218,6 +320,17 @@ impl<T> Buffer<T> {
     ctx: &mut C,
     slice: S
   ) -> Buffer<T>
+  where C: GraphicsContext,
+        S: AsRef<[T]> {
+    Self::from_slice_with_usage(ctx, slice, BufferUsage::default())
+  }
+
+  /// Create a buffer out of a slice, with a usage hint.
+  pub fn from_slice_with_usage<C, S>(
+    ctx: &mut C,
+    slice: S,
+    usage: BufferUsage,
+  ) -> Buffer<T>
   where C: GraphicsContext,
         S: AsRef<[T]> {
     let mut buffer: GLuint = 0;
@@ -227,13 +340,17 @@ impl<T> Buffer<T> {
 
     unsafe {
       gl::GenBuffers(1, &mut buffer);
-      ctx.state().borrow_mut().bind_array_buffer(buffer, Bind::Cached);
+      log_event!(created(Buffer, buffer));
+      let mut state = ctx.state().borrow_mut();
+      state.bind_array_buffer(buffer, Bind::Cached);
+      state.count_buffer_upload();
       gl::BufferData(
         gl::ARRAY_BUFFER,
         bytes as isize,
         slice.as_ptr() as *const c_void,
-        gl::STREAM_DRAW,
+        usage.to_glenum(),
       );
+      gl_check!("BufferData", buffer);
     }
 
     Buffer {
@@ -241,6 +358,7 @@ impl<T> Buffer<T> {
         handle: buffer,
         bytes,
         len,
+        usage,
         state: ctx.state().clone(),
       },
       _t: PhantomData,
@@ -257,17 +375,17 @@ impl<T> Buffer<T> {
 
   /// Retrieve an element from the [`Buffer`].
   ///
-  /// This version checks boundaries.
+  /// This version checks boundaries. `None` is also returned if the buffer is poisoned.
   pub fn at(&self, i: usize) -> Option<T> where T: Copy {
-    if i >= self.len {
+    if i >= self.len || self.raw.check_usable().is_err() {
       return None;
     }
 
     unsafe {
       self.raw.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::READ_ONLY) as *const T;
+      let ptr = map_array_buffer::<T>(i, 1, gl::MAP_READ_BIT) as *const T;
 
-      let x = *ptr.add(i);
+      let x = *ptr;
 
       let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER);
 
@@ -278,10 +396,15 @@ impl<T> Buffer<T> {
   /// Retrieve the whole content of the [`Buffer`].
   pub fn whole(&self) -> Vec<T> where T: Copy {
     unsafe {
+      if self.len == 0 {
+        return Vec::new();
+      }
+
       self.raw.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::READ_ONLY) as *mut T;
+      let ptr = map_array_buffer::<T>(0, self.len, gl::MAP_READ_BIT) as *const T;
 
-      let values = Vec::from_raw_parts(ptr, self.len, self.len);
+      // the mapped memory belongs to the driver; copy it
+      let values = slice::from_raw_parts(ptr, self.len).to_vec();
 
       let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER);
 
@@ -297,11 +420,16 @@ impl<T> Buffer<T> {
       return Err(BufferError::Overflow(i, self.len));
     }
 
+    self.raw.check_usable()?;
+
     unsafe {
-      self.raw.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::WRITE_ONLY) as *mut T;
+      let mut state = self.raw.state.borrow_mut();
+      state.bind_array_buffer(self.handle, Bind::Cached);
+      state.count_buffer_upload();
+      let access = gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_RANGE_BIT;
+      let ptr = map_array_buffer::<T>(i, 1, access) as *mut T;
 
-      *ptr.add(i) = x;
+      *ptr = x;
 
       let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER);
     }
@@ -327,9 +455,13 @@ impl<T> Buffer<T> {
       _ => in_bytes,
     };
 
+    self.raw.check_usable()?;
+
     unsafe {
-      self.raw.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::WRITE_ONLY);
+      let mut state = self.raw.state.borrow_mut();
+      state.bind_array_buffer(self.handle, Bind::Cached);
+      state.count_buffer_upload();
+      let ptr = map_array_buffer::<T>(0, len, gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT);
 
       ptr::copy_nonoverlapping(values.as_ptr() as *const c_void, ptr, real_bytes);
 
@@ -339,6 +471,79 @@ impl<T> Buffer<T> {
     Ok(())
   }
 
+  /// Write a slice into a buffer, starting at item `offset`.
+  ///
+  /// If the slice doesn’t fit in the buffer, you’ll get a [`BufferError::Overflow`] error, holding
+  /// the index past the last written item, and nothing is written.
+  pub fn write_part(&mut self, offset: usize, values: &[T]) -> Result<(), BufferError> {
+    let end = offset + values.len();
+
+    if end > self.len {
+      return Err(BufferError::Overflow(end, self.len));
+    }
+
+    self.raw.check_usable()?;
+
+    unsafe {
+      let mut state = self.raw.state.borrow_mut();
+      state.bind_array_buffer(self.handle, Bind::Cached);
+      state.count_buffer_upload();
+      gl::BufferSubData(
+        gl::ARRAY_BUFFER,
+        (offset * mem::size_of::<T>()) as GLintptr,
+        mem::size_of_val(values) as GLsizeiptr,
+        values.as_ptr() as *const c_void,
+      );
+      gl_check!("BufferSubData", self.handle);
+    }
+
+    Ok(())
+  }
+
+  /// Copy `len` items of this buffer, starting at item `src_offset`, to `other`, starting at item
+  /// `dst_offset`.
+  ///
+  /// The copy happens on the GPU, without mapping any of the buffers. If the items don’t fit in
+  /// either buffer, you’ll get a [`BufferError::Overflow`] error, holding the index past the last
+  /// item and the length of that buffer, and nothing is copied.
+  pub fn copy_to(
+    &self,
+    other: &mut Buffer<T>,
+    src_offset: usize,
+    dst_offset: usize,
+    len: usize,
+  ) -> Result<(), BufferError> {
+    let src_end = src_offset + len;
+    let dst_end = dst_offset + len;
+
+    if src_end > self.len {
+      return Err(BufferError::Overflow(src_end, self.len));
+    }
+
+    if dst_end > other.len {
+      return Err(BufferError::Overflow(dst_end, other.len));
+    }
+
+    self.raw.check_usable()?;
+    other.raw.check_usable()?;
+
+    // the copy targets aren’t used anywhere else, so they’re not cached
+    unsafe {
+      gl::BindBuffer(gl::COPY_READ_BUFFER, self.handle);
+      gl::BindBuffer(gl::COPY_WRITE_BUFFER, other.handle);
+      gl::CopyBufferSubData(
+        gl::COPY_READ_BUFFER,
+        gl::COPY_WRITE_BUFFER,
+        (src_offset * mem::size_of::<T>()) as GLintptr,
+        (dst_offset * mem::size_of::<T>()) as GLintptr,
+        (len * mem::size_of::<T>()) as GLsizeiptr,
+      );
+      gl_check!("CopyBufferSubData", other.handle);
+    }
+
+    Ok(())
+  }
+
   /// Fill the [`Buffer`] with a single value.
   pub fn clear(&mut self, x: T) -> Result<(), BufferError> where T: Copy {
     self.write_whole(&vec![x; self.len])
@@ -349,6 +554,34 @@ impl<T> Buffer<T> {
     self.write_whole(values.as_ref())
   }
 
+  /// Invalidate the content of the buffer, leaving it _uninitialized_.
+  ///
+  /// Streaming workloads rewriting a buffer the GPU might still be reading from should invalidate
+  /// it first: the driver then gives the buffer fresh storage instead of waiting for the GPU to be
+  /// done with the previous content. `glInvalidateBufferData` is used when available; otherwise,
+  /// the storage is _orphaned_ by reallocating it, with the same size and usage hint.
+  pub fn invalidate(&mut self) -> Result<(), BufferError> {
+    self.raw.check_usable()?;
+
+    unsafe {
+      if gl_post33!(InvalidateBufferData) {
+        gl::InvalidateBufferData(self.handle);
+      } else {
+        self.raw.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
+        gl::BufferData(
+          gl::ARRAY_BUFFER,
+          self.bytes as GLsizeiptr,
+          ptr::null(),
+          self.usage.to_glenum(),
+        );
+      }
+
+      gl_check!("InvalidateBufferData", self.handle);
+    }
+
+    Ok(())
+  }
+
   /// Convert a buffer to its raw representation.
   ///
   /// Becareful: once you have called this function, it is not possible to go back to a [`Buffer`].
@@ -357,6 +590,7 @@ impl<T> Buffer<T> {
       handle: self.raw.handle,
       bytes: self.raw.bytes,
       len: self.raw.len,
+      usage: self.raw.usage,
       state: self.raw.state.clone(),
     };
 
@@ -374,6 +608,31 @@ impl<T> Buffer<T> {
   pub fn as_slice_mut(&mut self) -> Result<BufferSliceMut<T>, BufferError> {
     self.raw.as_slice_mut()
   }
+
+  /// Obtain an immutable slice view into `len` items of the buffer, starting at item `offset`.
+  ///
+  /// Only that range is mapped, which is cheaper than mapping a large buffer as a whole. Fails
+  /// with [`BufferError::Overflow`] if the range goes past the end of the buffer.
+  pub fn as_slice_range(
+    &mut self,
+    offset: usize,
+    len: usize,
+  ) -> Result<BufferSlice<'_, T>, BufferError> {
+    self.raw.as_slice_range(offset, len)
+  }
+
+  /// Obtain a mutable slice view into `len` items of the buffer, starting at item `offset`.
+  ///
+  /// Only that range is mapped – and uploaded back when the slice is dropped –, which is cheaper
+  /// than mapping a large buffer as a whole to update a few items. Fails with
+  /// [`BufferError::Overflow`] if the range goes past the end of the buffer.
+  pub fn as_slice_range_mut(
+    &mut self,
+    offset: usize,
+    len: usize,
+  ) -> Result<BufferSliceMut<'_, T>, BufferError> {
+    self.raw.as_slice_range_mut(offset, len)
+  }
 }
 
 impl<T> Deref for Buffer<T> {
@@ -396,38 +655,98 @@ pub struct RawBuffer {
   handle: GLuint,
   bytes: usize,
   len: usize,
+  usage: BufferUsage,
   state: Rc<RefCell<GraphicsState>>,
 }
 
 impl RawBuffer {
   /// Obtain an immutable slice view into the buffer.
   pub(crate) fn as_slice<T>(&mut self) -> Result<BufferSlice<T>, BufferError> {
+    let len = self.len;
+    self.as_slice_range(0, len)
+  }
+
+  /// Obtain a mutable slice view into the buffer.
+  pub(crate) fn as_slice_mut<T>(&mut self) -> Result<BufferSliceMut<T>, BufferError> {
+    let len = self.len;
+    self.as_slice_range_mut(0, len)
+  }
+
+  /// Obtain an immutable slice view into `len` items of the buffer, starting at item `offset`.
+  pub(crate) fn as_slice_range<T>(
+    &mut self,
+    offset: usize,
+    len: usize,
+  ) -> Result<BufferSlice<'_, T>, BufferError> {
+    let ptr = self.map_range::<T>(offset, len, gl::MAP_READ_BIT)? as *const T;
+    Ok(BufferSlice { raw: self, ptr, len })
+  }
+
+  /// Obtain a mutable slice view into `len` items of the buffer, starting at item `offset`.
+  pub(crate) fn as_slice_range_mut<T>(
+    &mut self,
+    offset: usize,
+    len: usize,
+  ) -> Result<BufferSliceMut<'_, T>, BufferError> {
+    let ptr = self.map_range::<T>(offset, len, gl::MAP_READ_BIT | gl::MAP_WRITE_BIT)? as *mut T;
+    Ok(BufferSliceMut { raw: self, ptr, len })
+  }
+
+  // Map a range of items, checking it fits in the buffer.
+  fn map_range<T>(
+    &mut self,
+    offset: usize,
+    len: usize,
+    access: GLbitfield,
+  ) -> Result<*mut c_void, BufferError> {
+    self.check_usable()?;
+
+    let end = offset.saturating_add(len);
+
+    if end > self.len {
+      return Err(BufferError::Overflow(end, self.len));
+    }
+
     unsafe {
       self.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
 
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::READ_ONLY) as *const T;
+      let ptr = map_array_buffer::<T>(offset, len, access);
 
       if ptr.is_null() {
         return Err(BufferError::MapFailed);
       }
 
-      Ok(BufferSlice { raw: self, ptr })
+      Ok(ptr)
     }
   }
 
-  /// Obtain a mutable slice view into the buffer.
-  pub(crate) fn as_slice_mut<T>(&mut self) -> Result<BufferSliceMut<T>, BufferError> {
-    unsafe {
-      self.state.borrow_mut().bind_array_buffer(self.handle, Bind::Cached);
-
-      let ptr = gl::MapBuffer(gl::ARRAY_BUFFER, gl::READ_WRITE) as *mut T;
+  // Replace the content of the buffer with `values`, reallocating its storage if their size
+  // differs. The handle is kept, so that vertex arrays referencing the buffer stay valid.
+  pub(crate) fn reallocate<T>(&mut self, values: &[T]) {
+    let len = values.len();
+    let bytes = mem::size_of_val(values);
 
-      if ptr.is_null() {
-        return Err(BufferError::MapFailed);
+    unsafe {
+      let mut state = self.state.borrow_mut();
+      state.bind_array_buffer(self.handle, Bind::Cached);
+      state.count_buffer_upload();
+
+      if bytes == self.bytes {
+        gl::BufferSubData(gl::ARRAY_BUFFER, 0, bytes as GLsizeiptr, values.as_ptr() as *const c_void);
+      } else {
+        gl::BufferData(
+          gl::ARRAY_BUFFER,
+          bytes as GLsizeiptr,
+          values.as_ptr() as *const c_void,
+          self.usage.to_glenum(),
+        );
       }
 
-      Ok(BufferSliceMut { raw: self, ptr })
+      gl_check!("BufferData", self.handle);
     }
+
+    self.bytes = bytes;
+    self.len = len;
   }
 
   // Get the underlying GPU handle.
@@ -436,6 +755,16 @@ impl RawBuffer {
     self.handle
   }
 
+  // Check whether the buffer can be used: its context must not be lost.
+  pub(crate) fn check_usable(&self) -> Result<(), PoisonCause> {
+    self.state.borrow().check_usable()
+  }
+
+  /// Label the buffer, so that graphics debuggers show it by that name.
+  pub fn set_label(&self, label: &str) {
+    debug::label(gl::BUFFER, self.handle, label);
+  }
+
   /// Get the length of the buffer.
   #[inline(always)]
   pub fn len(&self) -> usize {
@@ -447,6 +776,23 @@ impl RawBuffer {
   pub fn is_empty(&self) -> bool {
     self.len == 0
   }
+
+  /// Get the usage hint the buffer was created with.
+  #[inline(always)]
+  pub fn usage(&self) -> BufferUsage {
+    self.usage
+  }
+}
+
+// Map `len` items of type `T` of the buffer bound to the array buffer target, starting at item
+// `offset`. glMapBufferRange is used rather than glMapBuffer, which OpenGL ES lacks.
+unsafe fn map_array_buffer<T>(offset: usize, len: usize, access: GLbitfield) -> *mut c_void {
+  gl::MapBufferRange(
+    gl::ARRAY_BUFFER,
+    (offset * mem::size_of::<T>()) as GLintptr,
+    (len * mem::size_of::<T>()) as GLsizeiptr,
+    access,
+  )
 }
 
 impl Drop for RawBuffer {
@@ -455,6 +801,8 @@ impl Drop for RawBuffer {
       self.state.borrow_mut().unbind_buffer(self.handle);
       gl::DeleteBuffers(1, &self.handle);
     }
+
+    log_event!(destroyed(Buffer, self.handle));
   }
 }
 
@@ -474,6 +822,8 @@ pub struct BufferSlice<'a, T> where T: 'a {
   raw: &'a RawBuffer,
   // Raw pointer into the GPU memory.
   ptr: *const T,
+  // Number of mapped items.
+  len: usize,
 }
 
 impl<'a, T> Drop for BufferSlice<'a, T> where T: 'a {
@@ -489,7 +839,7 @@ impl<'a, T> Deref for BufferSlice<'a, T> where T: 'a {
   type Target = [T];
 
   fn deref(&self) -> &Self::Target {
-    unsafe { slice::from_raw_parts(self.ptr, self.raw.len) }
+    unsafe { slice::from_raw_parts(self.ptr, self.len) }
   }
 }
 
@@ -512,6 +862,8 @@ pub struct BufferSliceMut<'a, T> where T: 'a {
   raw: &'a RawBuffer,
   // Raw pointer into the GPU memory.
   ptr: *mut T,
+  // Number of mapped items.
+  len: usize,
 }
 
 impl<'a, T> Drop for BufferSliceMut<'a, T> where T: 'a {
@@ -545,13 +897,222 @@ impl<'a, T> Deref for BufferSliceMut<'a, T> where T: 'a {
   type Target = [T];
 
   fn deref(&self) -> &Self::Target {
-    unsafe { slice::from_raw_parts(self.ptr, self.raw.len) }
+    unsafe { slice::from_raw_parts(self.ptr, self.len) }
   }
 }
 
 impl<'a, T> DerefMut for BufferSliceMut<'a, T> where T: 'a {
   fn deref_mut(&mut self) -> &mut Self::Target {
-    unsafe { slice::from_raw_parts_mut(self.ptr, self.raw.len) }
+    unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+  }
+}
+
+/// A uniform buffer holding a single [`UniformBlock`] value, along with a CPU copy of it.
+///
+/// Setting many individual uniforms can be slow on some drivers. Gathering them in a uniform
+/// block instead, the block can be modified field by field with [`UniformBuffer::get_mut`]; all
+/// the changes are uploaded at once by [`UniformBuffer::sync`], only if anything changed.
+///
+/// ```ignore
+/// uniforms.get_mut().time = t;
+/// uniforms.get_mut().resolution = [w, h];
+/// uniforms.sync()?;
+///
+/// let bound = pipeline.bind_buffer(&uniforms);
+/// iface.params.update(&bound);
+/// ```
+pub struct UniformBuffer<T> where T: UniformBlock + Copy {
+  buffer: Buffer<T>,
+  value: T,
+  dirty: bool,
+}
+
+impl<T> UniformBuffer<T> where T: UniformBlock + Copy {
+  /// Create a uniform buffer holding `value`.
+  pub fn new<C>(ctx: &mut C, value: T) -> Self where C: GraphicsContext {
+    UniformBuffer {
+      buffer: Buffer::from_slice(ctx, [value]),
+      value,
+      dirty: false,
+    }
+  }
+
+  /// CPU copy of the value, including the changes not uploaded yet.
+  pub fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Modify the value. The changes are uploaded by the next call to [`UniformBuffer::sync`].
+  pub fn get_mut(&mut self) -> &mut T {
+    self.dirty = true;
+    &mut self.value
+  }
+
+  /// Replace the value. The change is uploaded by the next call to [`UniformBuffer::sync`].
+  pub fn set(&mut self, value: T) {
+    self.value = value;
+    self.dirty = true;
+  }
+
+  /// Check whether the value has changes not uploaded yet.
+  pub fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+
+  /// Upload the value if it has changed since the last upload.
+  pub fn sync(&mut self) -> Result<(), BufferError> {
+    if self.dirty {
+      self.buffer.set(0, self.value)?;
+      self.dirty = false;
+    }
+
+    Ok(())
+  }
+
+  /// Buffer holding the uploaded value.
+  pub fn buffer(&self) -> &Buffer<T> {
+    &self.buffer
+  }
+}
+
+impl<T> Deref for UniformBuffer<T> where T: UniformBlock + Copy {
+  type Target = RawBuffer;
+
+  fn deref(&self) -> &Self::Target {
+    &self.buffer
+  }
+}
+
+/// A uniform buffer holding a single [`UniformBlock`] value, along with a CPU copy of it, uploading
+/// only the bytes that changed.
+///
+/// Fields set with [`UniformBufferCache::set_field`] mark their bytes dirty, and
+/// [`UniformBufferCache::sync`] then uploads the dirty byte ranges – merged when they overlap or touch – with one
+/// `glBufferSubData` each. For large per-scene blocks of which only a few fields change per frame,
+/// that’s much less data sent to the GPU than with [`UniformBuffer`].
+///
+/// ```ignore
+/// scene.set_field(|s| &mut s.time, t);
+/// scene.set_field(|s| &mut s.lights[3], light);
+/// scene.sync()?; // uploads two ranges
+/// ```
+pub struct UniformBufferCache<T> where T: UniformBlock + Copy {
+  buffer: Buffer<T>,
+  value: T,
+  dirty: Vec<Range<usize>>,
+}
+
+impl<T> UniformBufferCache<T> where T: UniformBlock + Copy {
+  /// Create a uniform buffer holding `value`.
+  pub fn new<C>(ctx: &mut C, value: T) -> Self where C: GraphicsContext {
+    UniformBufferCache {
+      buffer: Buffer::from_slice(ctx, [value]),
+      value,
+      dirty: Vec::new(),
+    }
+  }
+
+  /// CPU copy of the value, including the changes not uploaded yet.
+  pub fn get(&self) -> &T {
+    &self.value
+  }
+
+  /// Modify the whole value, marking all of it dirty.
+  ///
+  /// Prefer [`UniformBufferCache::set_field`] to upload only the fields that change.
+  pub fn get_mut(&mut self) -> &mut T {
+    self.mark_dirty(0 .. mem::size_of::<T>());
+    &mut self.value
+  }
+
+  /// Replace the whole value, marking all of it dirty.
+  pub fn set(&mut self, value: T) {
+    self.value = value;
+    self.mark_dirty(0 .. mem::size_of::<T>());
+  }
+
+  /// Set a field of the value, marking only its bytes dirty.
+  ///
+  /// `field` selects the field to set – e.g. `|block| &mut block.time`. If it returns a reference
+  /// that is not in the value, the value is left untouched and nothing is marked dirty.
+  pub fn set_field<F, A>(&mut self, field: A, value: F) where A: FnOnce(&mut T) -> &mut F {
+    let start = &self.value as *const T as usize;
+    let field = field(&mut self.value);
+    let offset = (field as *mut F as usize).wrapping_sub(start);
+    let end = offset + mem::size_of::<F>();
+
+    if end <= mem::size_of::<T>() {
+      *field = value;
+      self.mark_dirty(offset .. end);
+    }
+  }
+
+  // Mark a byte range of the value dirty.
+  fn mark_dirty(&mut self, range: Range<usize>) {
+    if range.start >= range.end {
+      return;
+    }
+
+    // keep the ranges sorted and disjoint, merging the ones overlapping or touching the new one
+    let first = self.dirty.iter().position(|r| r.end >= range.start).unwrap_or(self.dirty.len());
+    let last = self.dirty.iter().rposition(|r| r.start <= range.end).map_or(first, |i| i + 1);
+
+    let merged = if first < last {
+      self.dirty[first].start.min(range.start) .. self.dirty[last - 1].end.max(range.end)
+    } else {
+      range
+    };
+
+    self.dirty.splice(first .. last.max(first), Some(merged));
+  }
+
+  /// Dirty byte ranges, not uploaded yet.
+  pub fn dirty_ranges(&self) -> &[Range<usize>] {
+    &self.dirty
+  }
+
+  /// Check whether the value has changes not uploaded yet.
+  pub fn is_dirty(&self) -> bool {
+    !self.dirty.is_empty()
+  }
+
+  /// Upload the dirty byte ranges of the value.
+  pub fn sync(&mut self) {
+    if self.dirty.is_empty() {
+      return;
+    }
+
+    let bytes = &self.value as *const T as *const u8;
+
+    unsafe {
+      let mut state = self.buffer.raw.state.borrow_mut();
+      state.bind_array_buffer(self.buffer.handle, Bind::Cached);
+
+      for range in self.dirty.drain(..) {
+        state.count_buffer_upload();
+        gl::BufferSubData(
+          gl::ARRAY_BUFFER,
+          range.start as GLintptr,
+          range.len() as GLsizeiptr,
+          bytes.add(range.start) as *const c_void,
+        );
+      }
+
+      gl_check!("BufferSubData", self.buffer.handle);
+    }
+  }
+
+  /// Buffer holding the uploaded value.
+  pub fn buffer(&self) -> &Buffer<T> {
+    &self.buffer
+  }
+}
+
+impl<T> Deref for UniformBufferCache<T> where T: UniformBlock + Copy {
+  type Target = RawBuffer;
+
+  fn deref(&self) -> &Self::Target {
+    &self.buffer
   }
 }
 
@@ -633,3 +1194,49 @@ impl_uniform_block_tuple!(A, B, C, D, E, F, G);
 impl_uniform_block_tuple!(A, B, C, D, E, F, G, H);
 impl_uniform_block_tuple!(A, B, C, D, E, F, G, H, I);
 impl_uniform_block_tuple!(A, B, C, D, E, F, G, H, I, J);
+
+/// Types with a known *std140* layout, that can be checked against their Rust layout.
+///
+/// `STD140_ALIGNMENT` and `STD140_SIZE` are the base alignment and the size of the type in a
+/// *std140* uniform block. Only types which Rust layout matches that size implement this trait:
+/// `bool` – 4 bytes in *std140* –, `M22` and `M33` – which columns are padded to 16 bytes – don’t.
+///
+/// `#[derive(UniformBlock)]` (from luminance-derive) implements this trait and [`UniformBlock`]
+/// for `#[repr(C)]` structs, failing to compile if a field is not at its *std140* offset.
+///
+/// # Safety
+///
+/// The Rust layout of the type must be its *std140* layout, up to `STD140_SIZE` bytes.
+pub unsafe trait Std140: UniformBlock {
+  /// Base alignment of the type, in bytes.
+  const STD140_ALIGNMENT: usize;
+  /// Size of the type, in bytes.
+  const STD140_SIZE: usize;
+}
+
+macro_rules! impl_std140 {
+  ($($t:ty => $alignment:expr, $size:expr);* $(;)?) => {
+    $(
+      unsafe impl Std140 for $t {
+        const STD140_ALIGNMENT: usize = $alignment;
+        const STD140_SIZE: usize = $size;
+      }
+    )*
+  }
+}
+
+impl_std140! {
+  u32 => 4, 4;
+  i32 => 4, 4;
+  f32 => 4, 4;
+  [u32; 2] => 8, 8;
+  [i32; 2] => 8, 8;
+  [f32; 2] => 8, 8;
+  [u32; 3] => 16, 12;
+  [i32; 3] => 16, 12;
+  [f32; 3] => 16, 12;
+  [u32; 4] => 16, 16;
+  [i32; 4] => 16, 16;
+  [f32; 4] => 16, 16;
+  M44 => 16, 64;
+}
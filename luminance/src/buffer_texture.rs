@@ -0,0 +1,121 @@
+//! Buffer textures.
+//!
+//! A buffer texture exposes the content of a [`Buffer`] to shaders as a one-dimensional array of
+//! texels, read with `texelFetch` on a `samplerBuffer` (or `isamplerBuffer` / `usamplerBuffer`)
+//! uniform. Buffer textures can be way larger than uniform buffers, which makes them a good fit
+//! for large random-access data – bone matrices, light lists, etc. – on hardware without shader
+//! storage buffers.
+//!
+//! The texels are not copied: the buffer texture is a view over the buffer, so updating the buffer
+//! – via [`BufferTexture::buffer_mut`] – updates what shaders read.
+//!
+//! Only a subset of the pixel formats can be used with buffer textures: one-, two- and
+//! four-channel formats of 8, 16 or 32 bits per channel, except normalized signed ones.
+//! Three-channel formats are not supported. Matrices or structures are fetched as several texels;
+//! for instance, a 4×4 floating-point matrix is four [`RGBA32F`] texels.
+//!
+//! > Note: *OpenGL 3.3* doesn’t have image load / store, so buffer textures are read-only in
+//! > shaders (`imageBuffer` is not supported).
+//!
+//! [`RGBA32F`]: crate::pixel::RGBA32F
+
+#[cfg(feature = "std")]
+use std::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::mem;
+
+#[cfg(not(feature = "std"))]
+use core::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use core::mem;
+
+use crate::buffer::Buffer;
+use crate::context::GraphicsContext;
+use crate::metagl::*;
+use crate::pixel::{opengl_pixel_format, Pixel, PixelFormat};
+use crate::texture::TextureError;
+
+/// A buffer texture, viewing a [`Buffer`] of `T` as texels of pixel format `P`.
+pub struct BufferTexture<T, P> where P: Pixel {
+  handle: GLuint,
+  buffer: Buffer<T>,
+  _p: PhantomData<P>,
+}
+
+impl<T, P> BufferTexture<T, P> where P: Pixel {
+  /// Create a buffer texture viewing a buffer.
+  ///
+  /// The size of `T` must be a multiple of the size of a texel of pixel format `P` and `P` must be
+  /// supported by buffer textures, otherwise this function fails.
+  pub fn new<C>(ctx: &mut C, buffer: Buffer<T>) -> Result<Self, TextureError>
+  where C: GraphicsContext {
+    let pf = P::pixel_format();
+    let format = opengl_buffer_texture_format(pf).ok_or(TextureError::UnsupportedPixelFormat(pf))?;
+    let texel_bytes = pf.format.size();
+
+    if !mem::size_of::<T>().is_multiple_of(texel_bytes) {
+      return Err(TextureError::UnalignedBufferTexels(mem::size_of::<T>(), texel_bytes));
+    }
+
+    let mut handle = 0;
+
+    unsafe {
+      gl::GenTextures(1, &mut handle);
+      log_event!(created(Texture, handle));
+      ctx.state().borrow_mut().bind_texture(gl::TEXTURE_BUFFER, handle);
+      gl::TexBuffer(gl::TEXTURE_BUFFER, format, buffer.handle());
+    }
+
+    Ok(BufferTexture {
+      handle,
+      buffer,
+      _p: PhantomData,
+    })
+  }
+
+  /// Buffer viewed by the buffer texture.
+  pub fn buffer(&self) -> &Buffer<T> {
+    &self.buffer
+  }
+
+  /// Buffer viewed by the buffer texture, to update its content.
+  pub fn buffer_mut(&mut self) -> &mut Buffer<T> {
+    &mut self.buffer
+  }
+
+  /// Number of texels of the buffer texture.
+  pub fn len(&self) -> usize {
+    self.buffer.len() * mem::size_of::<T>() / P::pixel_format().format.size()
+  }
+
+  /// Check whether the buffer texture has no texel.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  #[inline]
+  pub(crate) fn handle(&self) -> GLuint {
+    self.handle
+  }
+}
+
+impl<T, P> Drop for BufferTexture<T, P> where P: Pixel {
+  fn drop(&mut self) {
+    unsafe { gl::DeleteTextures(1, &self.handle) }
+    log_event!(destroyed(Texture, self.handle));
+  }
+}
+
+// Internal format of a buffer texture, if the pixel format is supported.
+fn opengl_buffer_texture_format(pf: PixelFormat) -> Option<GLenum> {
+  let (_, format, _) = opengl_pixel_format(pf)?;
+
+  match format {
+    gl::R8 | gl::R16 | gl::R16F | gl::R32F | gl::R8I | gl::R16I | gl::R32I | gl::R8UI |
+    gl::R16UI | gl::R32UI | gl::RG8 | gl::RG16 | gl::RG16F | gl::RG32F | gl::RG8I | gl::RG16I |
+    gl::RG32I | gl::RG8UI | gl::RG16UI | gl::RG32UI | gl::RGBA8 | gl::RGBA16 | gl::RGBA16F |
+    gl::RGBA32F | gl::RGBA8I | gl::RGBA16I | gl::RGBA32I | gl::RGBA8UI | gl::RGBA16UI |
+    gl::RGBA32UI => Some(format),
+    _ => None,
+  }
+}
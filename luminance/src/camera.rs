@@ -0,0 +1,309 @@
+//! Cameras.
+//!
+//! A [`Camera`] gathers a point of view – position and orientation – and a [`Projection`], and
+//! computes the matrices shaders need to transform vertices. It also computes its view
+//! [`Frustum`], used to cull objects that cannot be seen.
+//!
+//! The matrices can be exported as a [`CameraBlock`], laid out with the *std140* rules so that it
+//! can be stored in a uniform buffer and read by shaders with the block declared in
+//! [`CAMERA_GLSL`]:
+//!
+//! ```ignore
+//! let mut camera_buffer = Buffer::from_slice(&mut surface, &[camera.block()]);
+//!
+//! // every frame
+//! camera_buffer.set(0, camera.block())?;
+//! ```
+//!
+//! Matrices are column-major, as OpenGL expects them: `m[col][row]`. The camera looks towards
+//! `-Z` in view space and projections map depths to the `[-1; 1]` range.
+//!
+//! > This module is only available with the `camera` feature.
+
+use crate::buffer::{Std140, UniformBlock};
+use crate::linear::M44;
+
+/// *GLSL* declaration of the uniform block holding a [`CameraBlock`].
+///
+/// Bind the uniform buffer holding the block to the `Camera` block of the program.
+pub const CAMERA_GLSL: &str = "
+layout (std140) uniform Camera {
+  mat4 camera_view;
+  mat4 camera_projection;
+  mat4 camera_view_projection;
+  vec4 camera_position;
+};
+";
+
+/// Projection of a [`Camera`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+  /// Perspective projection.
+  Perspective {
+    /// Vertical field of view, in radians.
+    fovy: f32,
+    /// Ratio of the width over the height of the viewport.
+    aspect: f32,
+    /// Distance to the near clipping plane.
+    near: f32,
+    /// Distance to the far clipping plane.
+    far: f32,
+  },
+  /// Orthographic projection.
+  Orthographic {
+    /// Left clipping plane.
+    left: f32,
+    /// Right clipping plane.
+    right: f32,
+    /// Bottom clipping plane.
+    bottom: f32,
+    /// Top clipping plane.
+    top: f32,
+    /// Distance to the near clipping plane.
+    near: f32,
+    /// Distance to the far clipping plane.
+    far: f32,
+  },
+}
+
+impl Projection {
+  /// Projection matrix.
+  pub fn matrix(&self) -> M44 {
+    match *self {
+      Projection::Perspective { fovy, aspect, near, far } => {
+        let f = 1. / (fovy * 0.5).tan();
+        let depth = near - far;
+
+        [
+          [f / aspect, 0., 0., 0.],
+          [0., f, 0., 0.],
+          [0., 0., (far + near) / depth, -1.],
+          [0., 0., 2. * far * near / depth, 0.],
+        ]
+      }
+
+      Projection::Orthographic { left, right, bottom, top, near, far } => {
+        let w = right - left;
+        let h = top - bottom;
+        let d = far - near;
+
+        [
+          [2. / w, 0., 0., 0.],
+          [0., 2. / h, 0., 0.],
+          [0., 0., -2. / d, 0.],
+          [-(right + left) / w, -(top + bottom) / h, -(far + near) / d, 1.],
+        ]
+      }
+    }
+  }
+}
+
+/// A camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+  position: [f32; 3],
+  view: M44,
+  projection: Projection,
+}
+
+impl Camera {
+  /// Create a camera at the origin, looking towards `-Z`.
+  pub fn new(projection: Projection) -> Self {
+    Camera {
+      position: [0., 0., 0.],
+      view: IDENTITY,
+      projection,
+    }
+  }
+
+  /// Place the camera at `eye`, looking at `target`. `up` is the rough direction of the top of the
+  /// viewport.
+  pub fn look_at(self, eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Self {
+    let f = normalize(sub(target, eye));
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+
+    Camera {
+      position: eye,
+      view: [
+        [s[0], u[0], -f[0], 0.],
+        [s[1], u[1], -f[1], 0.],
+        [s[2], u[2], -f[2], 0.],
+        [-dot(s, eye), -dot(u, eye), dot(f, eye), 1.],
+      ],
+      ..self
+    }
+  }
+
+  /// Change the projection, e.g. when the viewport is resized.
+  pub fn set_projection(self, projection: Projection) -> Self {
+    Camera { projection, ..self }
+  }
+
+  /// Position of the camera, in world space.
+  pub fn position(&self) -> [f32; 3] {
+    self.position
+  }
+
+  /// Projection of the camera.
+  pub fn projection(&self) -> Projection {
+    self.projection
+  }
+
+  /// View matrix, transforming world space into view space.
+  pub fn view(&self) -> M44 {
+    self.view
+  }
+
+  /// Projection matrix, transforming view space into clip space.
+  pub fn projection_matrix(&self) -> M44 {
+    self.projection.matrix()
+  }
+
+  /// Product of the projection and view matrices, transforming world space into clip space.
+  pub fn view_projection(&self) -> M44 {
+    mul(&self.projection.matrix(), &self.view)
+  }
+
+  /// View frustum of the camera, in world space.
+  pub fn frustum(&self) -> Frustum {
+    Frustum::from_matrix(&self.view_projection())
+  }
+
+  /// Matrices and position of the camera, ready to be uploaded to a uniform buffer.
+  pub fn block(&self) -> CameraBlock {
+    let [x, y, z] = self.position;
+    let projection = self.projection.matrix();
+
+    CameraBlock {
+      view: self.view,
+      projection,
+      view_projection: mul(&projection, &self.view),
+      position: [x, y, z, 1.],
+    }
+  }
+}
+
+/// Camera data as laid out in a *std140* uniform block.
+///
+/// See [`CAMERA_GLSL`] for the matching *GLSL* declaration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct CameraBlock {
+  /// View matrix.
+  pub view: M44,
+  /// Projection matrix.
+  pub projection: M44,
+  /// Product of the projection and view matrices.
+  pub view_projection: M44,
+  /// Position of the camera in world space; the last component is always `1`.
+  pub position: [f32; 4],
+}
+
+unsafe impl UniformBlock for CameraBlock {}
+
+unsafe impl Std140 for CameraBlock {
+  const STD140_ALIGNMENT: usize = 16;
+  const STD140_SIZE: usize = 208;
+}
+
+/// View frustum, as six planes pointing inwards.
+///
+/// Planes are `[a, b, c, d]` so that a point `p` is on the inner side of a plane if
+/// `a * p.x + b * p.y + c * p.z + d >= 0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+  /// Left, right, bottom, top, near and far planes, in that order.
+  pub planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+  /// Extract the frustum of a view-projection matrix.
+  pub fn from_matrix(m: &M44) -> Self {
+    let row = |i: usize| [m[0][i], m[1][i], m[2][i], m[3][i]];
+    let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+    let plane = |a: [f32; 4], sign: f32| {
+      let p = [
+        w[0] + sign * a[0],
+        w[1] + sign * a[1],
+        w[2] + sign * a[2],
+        w[3] + sign * a[3],
+      ];
+      let len = dot([p[0], p[1], p[2]], [p[0], p[1], p[2]]).sqrt();
+
+      if len > 0. {
+        [p[0] / len, p[1] / len, p[2] / len, p[3] / len]
+      } else {
+        p
+      }
+    };
+
+    Frustum {
+      planes: [
+        plane(x, 1.),
+        plane(x, -1.),
+        plane(y, 1.),
+        plane(y, -1.),
+        plane(z, 1.),
+        plane(z, -1.),
+      ],
+    }
+  }
+
+  /// Check whether a point lies in the frustum.
+  pub fn contains_point(&self, p: [f32; 3]) -> bool {
+    self.contains_sphere(p, 0.)
+  }
+
+  /// Check whether a sphere intersects the frustum.
+  ///
+  /// This is conservative: spheres near the corners of the frustum might be reported as visible.
+  pub fn contains_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+    self.planes.iter().all(|p| dot([p[0], p[1], p[2]], center) + p[3] >= -radius)
+  }
+}
+
+const IDENTITY: M44 = [
+  [1., 0., 0., 0.],
+  [0., 1., 0., 0.],
+  [0., 0., 1., 0.],
+  [0., 0., 0., 1.],
+];
+
+fn mul(a: &M44, b: &M44) -> M44 {
+  let mut m = [[0.; 4]; 4];
+
+  for (col, m) in m.iter_mut().enumerate() {
+    for (row, m) in m.iter_mut().enumerate() {
+      *m = (0 .. 4).map(|k| a[k][row] * b[col][k]).sum();
+    }
+  }
+
+  m
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+  [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+  a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+  [
+    a[1] * b[2] - a[2] * b[1],
+    a[2] * b[0] - a[0] * b[2],
+    a[0] * b[1] - a[1] * b[0],
+  ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+  let len = dot(a, a).sqrt();
+
+  if len > 0. {
+    [a[0] / len, a[1] / len, a[2] / len]
+  } else {
+    a
+  }
+}
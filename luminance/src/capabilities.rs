@@ -0,0 +1,435 @@
+//! Capabilities of the graphics context.
+//!
+//! Not all graphics contexts support the same set of features. This module exposes what the
+//! current context is able to do, so that applications can branch on optional features – such as
+//! anisotropic filtering, direct state access, bindless or sparse textures.
+//!
+//! Capabilities are queried once with [`Capabilities::new`] and don’t change afterwards, so you
+//! should query them once and keep them around.
+//!
+//! Optional features – listed in [`Feature`] – are available either since a given OpenGL version
+//! or with an extension. [`GraphicsState::supports`] checks both, and the functions depending on
+//! such a feature fail with [`NotSupported`] on contexts lacking it.
+//!
+//! The numeric limits of the context – maximum texture size, number of color attachments, etc. –
+//! are gathered in [`Limits`], available with [`GraphicsContext::limits`]. Checking resources
+//! against them up-front gives better errors than a failing OpenGL call.
+//!
+//! [`GraphicsContext::limits`]: crate::context::GraphicsContext::limits
+//! [`GraphicsState::supports`]: crate::state::GraphicsState::supports
+
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::str::FromStr;
+
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::context::GraphicsContext;
+use crate::metagl::*;
+
+/// Capabilities of a graphics context.
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+  extensions: Extensions,
+}
+
+impl Capabilities {
+  /// Query the capabilities of a graphics context.
+  pub fn new<C>(ctx: &mut C) -> Self where C: ?Sized + GraphicsContext {
+    let extensions = ctx.state().borrow().extensions().clone();
+
+    Capabilities { extensions }
+  }
+
+  /// Extensions supported by the graphics context.
+  pub fn extensions(&self) -> &Extensions {
+    &self.extensions
+  }
+}
+
+/// Extensions supported by a graphics context.
+///
+/// Extensions are available both as raw strings – as reported by the driver – and as
+/// [`Extension`] for the ones luminance knows about.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Extensions {
+  raw: BTreeSet<String>,
+  known: BTreeSet<Extension>,
+}
+
+impl Extensions {
+  /// Build a set of extensions out of their raw names.
+  pub fn from_names<I, S>(names: I) -> Self where I: IntoIterator<Item = S>, S: Into<String> {
+    let raw: BTreeSet<String> = names.into_iter().map(Into::into).collect();
+    let known = raw.iter().filter_map(|name| name.parse().ok()).collect();
+
+    Extensions { raw, known }
+  }
+
+  /// Raw names of all the supported extensions.
+  pub fn raw(&self) -> &BTreeSet<String> {
+    &self.raw
+  }
+
+  /// Supported extensions luminance knows about.
+  pub fn known(&self) -> &BTreeSet<Extension> {
+    &self.known
+  }
+
+  /// Check whether an extension is supported.
+  pub fn has(&self, ext: Extension) -> bool {
+    self.known.contains(&ext)
+  }
+
+  /// Check whether an extension is supported by its raw name (e.g. `"GL_ARB_bindless_texture"`).
+  pub fn has_raw(&self, name: &str) -> bool {
+    self.raw.contains(name)
+  }
+}
+
+/// Extensions luminance knows about.
+///
+/// Some extensions exist under several names (typically, an `EXT` and an `ARB` version); they are
+/// merged into a single variant.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Extension {
+  /// Anisotropic texture filtering (`GL_EXT_texture_filter_anisotropic` or
+  /// `GL_ARB_texture_filter_anisotropic`).
+  TextureFilterAnisotropic,
+  /// Direct state access (`GL_ARB_direct_state_access`).
+  DirectStateAccess,
+  /// Bindless textures (`GL_ARB_bindless_texture`).
+  BindlessTexture,
+  /// Sparse textures (`GL_ARB_sparse_texture`).
+  SparseTexture,
+  /// Immutable buffer storage (`GL_ARB_buffer_storage`).
+  BufferStorage,
+  /// Texture clearing (`GL_ARB_clear_texture`).
+  ClearTexture,
+  /// Debug output (`GL_KHR_debug`).
+  Debug,
+  /// Indirect and multi-indirect draws (`GL_ARB_multi_draw_indirect`).
+  MultiDrawIndirect,
+  /// Base instance in draw commands (`GL_ARB_base_instance`).
+  BaseInstance,
+  /// Compute shaders (`GL_ARB_compute_shader`).
+  ComputeShader,
+  /// SPIR-V shader modules (`GL_ARB_gl_spirv`).
+  GlSpirv,
+  /// Seamless cubemap filtering per texture (`GL_ARB_seamless_cubemap_per_texture`).
+  SeamlessCubemapPerTexture,
+  /// Indirect draws (`GL_ARB_draw_indirect`).
+  DrawIndirect,
+  /// Shader storage buffers (`GL_ARB_shader_storage_buffer_object`).
+  ShaderStorageBufferObject,
+  /// Mirrored clamping of texture coordinates (`GL_ARB_texture_mirror_clamp_to_edge` or
+  /// `GL_EXT_texture_mirror_clamp_to_edge`).
+  TextureMirrorClampToEdge,
+}
+
+impl Extension {
+  /// Name of the extension, as reported by the driver.
+  ///
+  /// For extensions that exist under several names, the first one listed in the variant’s
+  /// documentation is returned.
+  pub fn name(self) -> &'static str {
+    match self {
+      Extension::TextureFilterAnisotropic => "GL_EXT_texture_filter_anisotropic",
+      Extension::DirectStateAccess => "GL_ARB_direct_state_access",
+      Extension::BindlessTexture => "GL_ARB_bindless_texture",
+      Extension::SparseTexture => "GL_ARB_sparse_texture",
+      Extension::BufferStorage => "GL_ARB_buffer_storage",
+      Extension::ClearTexture => "GL_ARB_clear_texture",
+      Extension::Debug => "GL_KHR_debug",
+      Extension::MultiDrawIndirect => "GL_ARB_multi_draw_indirect",
+      Extension::BaseInstance => "GL_ARB_base_instance",
+      Extension::ComputeShader => "GL_ARB_compute_shader",
+      Extension::GlSpirv => "GL_ARB_gl_spirv",
+      Extension::SeamlessCubemapPerTexture => "GL_ARB_seamless_cubemap_per_texture",
+      Extension::DrawIndirect => "GL_ARB_draw_indirect",
+      Extension::ShaderStorageBufferObject => "GL_ARB_shader_storage_buffer_object",
+      Extension::TextureMirrorClampToEdge => "GL_ARB_texture_mirror_clamp_to_edge",
+    }
+  }
+}
+
+impl fmt::Display for Extension {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    f.write_str(self.name())
+  }
+}
+
+impl FromStr for Extension {
+  type Err = ();
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name {
+      "GL_EXT_texture_filter_anisotropic" | "GL_ARB_texture_filter_anisotropic" => {
+        Ok(Extension::TextureFilterAnisotropic)
+      }
+      "GL_ARB_direct_state_access" => Ok(Extension::DirectStateAccess),
+      "GL_ARB_bindless_texture" => Ok(Extension::BindlessTexture),
+      "GL_ARB_sparse_texture" => Ok(Extension::SparseTexture),
+      "GL_ARB_buffer_storage" => Ok(Extension::BufferStorage),
+      "GL_ARB_clear_texture" => Ok(Extension::ClearTexture),
+      "GL_KHR_debug" => Ok(Extension::Debug),
+      "GL_ARB_multi_draw_indirect" => Ok(Extension::MultiDrawIndirect),
+      "GL_ARB_base_instance" => Ok(Extension::BaseInstance),
+      "GL_ARB_compute_shader" => Ok(Extension::ComputeShader),
+      "GL_ARB_gl_spirv" => Ok(Extension::GlSpirv),
+      "GL_ARB_seamless_cubemap_per_texture" => Ok(Extension::SeamlessCubemapPerTexture),
+      "GL_ARB_draw_indirect" => Ok(Extension::DrawIndirect),
+      "GL_ARB_shader_storage_buffer_object" => Ok(Extension::ShaderStorageBufferObject),
+      "GL_ARB_texture_mirror_clamp_to_edge" | "GL_EXT_texture_mirror_clamp_to_edge" => {
+        Ok(Extension::TextureMirrorClampToEdge)
+      }
+      _ => Err(()),
+    }
+  }
+}
+
+/// Flavor of OpenGL of a graphics context.
+///
+/// luminance targets desktop OpenGL 3.3 core and OpenGL ES 3.0, which lacks a few features: 1D
+/// textures, polygon modes, depth clamping, tessellation and geometry shaders, among others. On
+/// OpenGL ES contexts, the render state settings depending on them are ignored, shader stages get
+/// a `#version 300 es` header and creating unsupported objects fails.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Profile {
+  /// Desktop OpenGL, core profile.
+  Core,
+  /// OpenGL ES.
+  Es,
+}
+
+// Whether the last created graphics state is an OpenGL ES one; shader stages are created without
+// a context and read it to pick their GLSL header.
+static ES_PROFILE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn current_profile() -> Profile {
+  if ES_PROFILE.load(Ordering::Relaxed) {
+    Profile::Es
+  } else {
+    Profile::Core
+  }
+}
+
+pub(crate) unsafe fn get_ctx_profile() -> Profile {
+  let ptr = gl::GetString(gl::VERSION);
+  let es = !ptr.is_null() && CStr::from_ptr(ptr as *const _).to_bytes().starts_with(b"OpenGL ES");
+
+  ES_PROFILE.store(es, Ordering::Relaxed);
+
+  if es {
+    Profile::Es
+  } else {
+    Profile::Core
+  }
+}
+
+/// Optional features of a graphics context.
+///
+/// A feature is supported if the context has at least the version of OpenGL it went core in, or if
+/// it has the matching extension.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Feature {
+  /// Debug output, core in OpenGL 4.3.
+  DebugOutput,
+  /// Indirect draws, core in OpenGL 4.0.
+  IndirectDraw,
+  /// Several indirect draws in a single call, core in OpenGL 4.3.
+  MultiDrawIndirect,
+  /// Shader storage buffers, core in OpenGL 4.3.
+  ShaderStorageBuffer,
+  /// Compute shaders, core in OpenGL 4.3.
+  ComputeShader,
+  /// SPIR-V shader modules, core in OpenGL 4.6.
+  Spirv,
+  /// Texture clearing, core in OpenGL 4.4.
+  ClearTexture,
+  /// Anisotropic texture filtering, core in OpenGL 4.6.
+  AnisotropicFiltering,
+  /// [`Wrap::MirrorClampToEdge`], core in OpenGL 4.4.
+  ///
+  /// [`Wrap::MirrorClampToEdge`]: crate::texture::Wrap::MirrorClampToEdge
+  MirrorClampToEdge,
+}
+
+impl Feature {
+  /// OpenGL version the feature went core in, as `(major, minor)`.
+  pub fn core_version(self) -> (u32, u32) {
+    match self {
+      Feature::IndirectDraw => (4, 0),
+      Feature::DebugOutput
+      | Feature::MultiDrawIndirect
+      | Feature::ShaderStorageBuffer
+      | Feature::ComputeShader => (4, 3),
+      Feature::ClearTexture | Feature::MirrorClampToEdge => (4, 4),
+      Feature::Spirv | Feature::AnisotropicFiltering => (4, 6),
+    }
+  }
+
+  /// Extension providing the feature on older contexts.
+  pub fn extension(self) -> Extension {
+    match self {
+      Feature::DebugOutput => Extension::Debug,
+      Feature::IndirectDraw => Extension::DrawIndirect,
+      Feature::MultiDrawIndirect => Extension::MultiDrawIndirect,
+      Feature::ShaderStorageBuffer => Extension::ShaderStorageBufferObject,
+      Feature::ComputeShader => Extension::ComputeShader,
+      Feature::Spirv => Extension::GlSpirv,
+      Feature::ClearTexture => Extension::ClearTexture,
+      Feature::AnisotropicFiltering => Extension::TextureFilterAnisotropic,
+      Feature::MirrorClampToEdge => Extension::TextureMirrorClampToEdge,
+    }
+  }
+}
+
+impl fmt::Display for Feature {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      Feature::DebugOutput => f.write_str("debug output"),
+      Feature::IndirectDraw => f.write_str("indirect draws"),
+      Feature::MultiDrawIndirect => f.write_str("multi indirect draws"),
+      Feature::ShaderStorageBuffer => f.write_str("shader storage buffers"),
+      Feature::ComputeShader => f.write_str("compute shaders"),
+      Feature::Spirv => f.write_str("SPIR-V shader modules"),
+      Feature::ClearTexture => f.write_str("texture clearing"),
+      Feature::AnisotropicFiltering => f.write_str("anisotropic filtering"),
+      Feature::MirrorClampToEdge => f.write_str("mirrored clamping to edges"),
+    }
+  }
+}
+
+/// Error returned when using a [`Feature`] the context doesn’t support.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct NotSupported(pub Feature);
+
+impl fmt::Display for NotSupported {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    let (major, minor) = self.0.core_version();
+    write!(
+      f,
+      "{} not supported: requires OpenGL {}.{} or {}",
+      self.0,
+      major,
+      minor,
+      self.0.extension()
+    )
+  }
+}
+
+/// Numeric limits of a graphics context.
+///
+/// Limits are queried when the [`GraphicsState`] is created; see [`GraphicsState::limits`].
+///
+/// [`GraphicsState`]: crate::state::GraphicsState
+/// [`GraphicsState::limits`]: crate::state::GraphicsState::limits
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Limits {
+  /// Maximum width and height of 1D and 2D textures, in texels.
+  pub max_texture_size: u32,
+  /// Maximum width, height and depth of 3D textures, in texels.
+  pub max_3d_texture_size: u32,
+  /// Maximum width and height of cubemap faces, in texels.
+  pub max_cubemap_size: u32,
+  /// Maximum number of layers of layered textures.
+  pub max_texture_layers: u32,
+  /// Maximum number of color attachments of a framebuffer.
+  pub max_color_attachments: u32,
+  /// Maximum number of draw buffers a fragment shader can write to.
+  pub max_draw_buffers: u32,
+  /// Maximum size of a uniform block, in bytes.
+  pub max_uniform_block_size: usize,
+  /// Maximum number of uniform buffers bound at once.
+  pub max_uniform_buffer_bindings: u32,
+  /// Maximum number of textures bound at once, all shader stages combined.
+  pub max_texture_units: u32,
+  /// Maximum number of samples of multisample textures and renderbuffers.
+  pub max_samples: u32,
+  /// Maximum number of vertex attributes.
+  pub max_vertex_attribs: u32,
+  /// Highest supported GLSL version, as written in `#version` directives (e.g. `330`).
+  pub glsl_version: u32,
+}
+
+pub(crate) unsafe fn get_ctx_limits() -> Limits {
+  Limits {
+    max_texture_size: get_ctx_integer(gl::MAX_TEXTURE_SIZE),
+    max_3d_texture_size: get_ctx_integer(gl::MAX_3D_TEXTURE_SIZE),
+    max_cubemap_size: get_ctx_integer(gl::MAX_CUBE_MAP_TEXTURE_SIZE),
+    max_texture_layers: get_ctx_integer(gl::MAX_ARRAY_TEXTURE_LAYERS),
+    max_color_attachments: get_ctx_integer(gl::MAX_COLOR_ATTACHMENTS),
+    max_draw_buffers: get_ctx_integer(gl::MAX_DRAW_BUFFERS),
+    max_uniform_block_size: get_ctx_integer(gl::MAX_UNIFORM_BLOCK_SIZE) as usize,
+    max_uniform_buffer_bindings: get_ctx_integer(gl::MAX_UNIFORM_BUFFER_BINDINGS),
+    max_texture_units: get_ctx_integer(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS),
+    max_samples: get_ctx_integer(gl::MAX_SAMPLES),
+    max_vertex_attribs: get_ctx_integer(gl::MAX_VERTEX_ATTRIBS),
+    glsl_version: get_ctx_glsl_version(),
+  }
+}
+
+pub(crate) unsafe fn get_ctx_gl_version() -> (u32, u32) {
+  (get_ctx_integer(gl::MAJOR_VERSION), get_ctx_integer(gl::MINOR_VERSION))
+}
+
+unsafe fn get_ctx_integer(pname: GLenum) -> u32 {
+  let mut value = 0 as GLint;
+  gl::GetIntegerv(pname, &mut value);
+  value.max(0) as u32
+}
+
+unsafe fn get_ctx_glsl_version() -> u32 {
+  let ptr = gl::GetString(gl::SHADING_LANGUAGE_VERSION);
+
+  if ptr.is_null() {
+    return 0;
+  }
+
+  parse_glsl_version(&CStr::from_ptr(ptr as *const _).to_string_lossy()).unwrap_or(0)
+}
+
+// Parse the version out of a GL_SHADING_LANGUAGE_VERSION string, such as “4.60 NVIDIA” or
+// “OpenGL ES GLSL ES 3.00”; the minor number has two digits.
+fn parse_glsl_version(version: &str) -> Option<u32> {
+  let number = version
+    .split_whitespace()
+    .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+  let mut parts = number.split('.');
+  let major: u32 = parts.next()?.parse().ok()?;
+  let mut digits = parts.next().unwrap_or("").chars().map_while(|c| c.to_digit(10));
+  let minor = (0 .. 2).fold(0, |minor, _| minor * 10 + digits.next().unwrap_or(0));
+
+  Some(major * 100 + minor)
+}
+
+pub(crate) unsafe fn get_ctx_extensions() -> Extensions {
+  let mut count = 0;
+  gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+
+  let names = (0 .. count.max(0) as GLuint).filter_map(|i| {
+    let ptr = gl::GetStringi(gl::EXTENSIONS, i);
+
+    if ptr.is_null() {
+      None
+    } else {
+      Some(CStr::from_ptr(ptr as *const _).to_string_lossy().into_owned())
+    }
+  });
+
+  Extensions::from_names(names)
+}
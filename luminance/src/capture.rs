@@ -0,0 +1,197 @@
+//! Frame capture.
+//!
+//! A [`FrameCapture`] grabs the content of a framebuffer every few frames – for gameplay capture
+//! or to record frames for visual regression tests – without stalling the pipeline: the texels are
+//! read back into pixel buffers asynchronously, and handed to a callback a few frames later, once
+//! the GPU is done copying them.
+//!
+//! ```ignore
+//! let mut capture = FrameCapture::new(2, 3, |frame: CapturedFrame| {
+//!   // save frame.texels, pipe them to an encoder, etc.
+//! });
+//!
+//! loop {
+//!   // render the frame…
+//!
+//!   capture.capture(&mut surface, &back_buffer)?;
+//!   surface.swap_buffers();
+//! }
+//! ```
+//!
+//! The number of readbacks in flight is bounded: if the GPU lags behind, frames that should have
+//! been captured are dropped – and counted – rather than blocking the CPU.
+//!
+//! To encode a video, the callback can write the texels to the standard input of an encoder
+//! process – e.g. `ffmpeg -f rawvideo -pix_fmt rgba -s WxH -i - -vf vflip out.mp4`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::buffer::{Buffer, BufferError};
+use crate::context::GraphicsContext;
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::metagl::*;
+use crate::state::Bind;
+use crate::sync::Fence;
+use crate::texture::{Dim2, Flat};
+
+/// A captured frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedFrame {
+  /// Index of the frame, counting every frame passed to [`FrameCapture::capture`].
+  pub index: u64,
+  /// Size of the frame, in pixels.
+  pub size: [u32; 2],
+  /// RGBA texels, 8 bits per channel, row by row starting at the bottom of the frame.
+  pub texels: Vec<u8>,
+}
+
+/// Asynchronous frame capture.
+pub struct FrameCapture<F> where F: FnMut(CapturedFrame) {
+  every: u64,
+  max_in_flight: usize,
+  frame: u64,
+  dropped: u64,
+  free: Vec<Buffer<u8>>,
+  pending: VecDeque<PendingFrame>,
+  callback: F,
+}
+
+impl<F> FrameCapture<F> where F: FnMut(CapturedFrame) {
+  /// Create a capture grabbing a frame every `every` frames, with at most `max_in_flight`
+  /// readbacks in flight, handing captured frames to `callback`.
+  ///
+  /// `every` and `max_in_flight` are clamped to `1`.
+  pub fn new(every: u64, max_in_flight: usize, callback: F) -> Self {
+    FrameCapture {
+      every: every.max(1),
+      max_in_flight: max_in_flight.max(1),
+      frame: 0,
+      dropped: 0,
+      free: Vec::new(),
+      pending: VecDeque::new(),
+      callback,
+    }
+  }
+
+  /// Number of frames that should have been captured but were dropped because too many readbacks
+  /// were in flight.
+  pub fn dropped_frames(&self) -> u64 {
+    self.dropped
+  }
+
+  /// Count a frame, starting a readback of `framebuffer` if it has to be captured, and hand the
+  /// frames done reading back to the callback.
+  ///
+  /// Call this once per frame, after rendering and before swapping buffers.
+  pub fn capture<C, CS, DS>(
+    &mut self,
+    ctx: &mut C,
+    framebuffer: &Framebuffer<Flat, Dim2, CS, DS>,
+  ) -> Result<(), BufferError>
+  where C: GraphicsContext,
+        CS: ColorSlot<Flat, Dim2>,
+        DS: DepthSlot<Flat, Dim2> {
+    self.poll(false)?;
+
+    let index = self.frame;
+    self.frame += 1;
+
+    if !index.is_multiple_of(self.every) {
+      return Ok(());
+    }
+
+    if self.pending.len() >= self.max_in_flight {
+      self.dropped += 1;
+      return Ok(());
+    }
+
+    let size = [framebuffer.width(), framebuffer.height()];
+    let bytes = size[0] as usize * size[1] as usize * 4;
+
+    // buffers of another size are left from before a resize
+    self.free.retain(|buffer| buffer.len() == bytes);
+
+    let buffer = match self.free.pop() {
+      Some(buffer) => buffer,
+      // the buffer is entirely written by the readback before being read
+      None => unsafe { Buffer::new(ctx, bytes) },
+    };
+
+    unsafe {
+      let mut state = ctx.state().borrow_mut();
+
+      state.bind_read_framebuffer(framebuffer.handle());
+      state.bind_pixel_pack_buffer(buffer.handle(), Bind::Cached);
+
+      gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+      gl::ReadPixels(
+        0,
+        0,
+        size[0] as GLsizei,
+        size[1] as GLsizei,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        ptr::null_mut::<c_void>(),
+      );
+
+      // other readbacks go to the client memory
+      state.bind_pixel_pack_buffer(0, Bind::Cached);
+    }
+
+    self.pending.push_back(PendingFrame {
+      index,
+      size,
+      buffer,
+      fence: Fence::insert(ctx),
+    });
+
+    Ok(())
+  }
+
+  /// Wait for all the readbacks in flight and hand their frames to the callback.
+  pub fn flush(&mut self) -> Result<(), BufferError> {
+    self.poll(true)
+  }
+
+  // Hand the frames done reading back to the callback, waiting for them if asked.
+  fn poll(&mut self, wait: bool) -> Result<(), BufferError> {
+    while let Some(pending) = self.pending.front() {
+      if wait {
+        pending.fence.wait();
+      } else if !pending.fence.is_signaled() {
+        break;
+      }
+
+      let PendingFrame { index, size, mut buffer, .. } = self.pending.pop_front().unwrap();
+      let texels = buffer.as_slice()?.to_vec();
+
+      self.free.push(buffer);
+      (self.callback)(CapturedFrame { index, size, texels });
+    }
+
+    Ok(())
+  }
+}
+
+impl<F> fmt::Debug for FrameCapture<F> where F: FnMut(CapturedFrame) {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    f.debug_struct("FrameCapture")
+      .field("every", &self.every)
+      .field("max_in_flight", &self.max_in_flight)
+      .field("frame", &self.frame)
+      .field("dropped", &self.dropped)
+      .field("in_flight", &self.pending.len())
+      .finish()
+  }
+}
+
+// A readback in flight.
+struct PendingFrame {
+  index: u64,
+  size: [u32; 2],
+  buffer: Buffer<u8>,
+  fence: Fence,
+}
@@ -0,0 +1,535 @@
+//! Color grading with 3D lookup tables.
+//!
+//! A 3D lookup table (LUT) maps colors to graded colors: each axis of the table is a color
+//! channel – red, green and blue – and each texel holds the graded color of the input color at its
+//! coordinates. Colors between texels are graded by trilinear filtering.
+//!
+//! LUTs are authored by grading a screenshot in an image or video editor – DaVinci Resolve,
+//! Photoshop, etc. – and exporting the grade as a `.cube` file. [`Lut::from_cube`] parses such
+//! files, [`Lut::to_texture`] uploads them to a 3D texture and [`ColorGrading`] grades a texture –
+//! typically the rendered scene – with it:
+//!
+//! ```ignore
+//! let lut = Lut::from_cube(&fs::read_to_string("sunset.cube")?)?.to_texture(&mut surface)?;
+//! let grading = ColorGrading::new(&mut surface)?;
+//!
+//! grading.render(&mut surface, scene.color_slot(), &lut, 1., &back_buffer)?;
+//! ```
+//!
+//! Colors out of the domain of the table are clamped to it. Within an [`EffectChain`], use
+//! [`ColorGrading::shade`] in the render function of a pass instead.
+//!
+//! [`EffectChain`]: crate::effect::EffectChain
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::pipeline::{BindingError, BoundTexture, Pipeline, PipelineState, ShadingGate};
+use crate::pixel::{Floating, Pixel, RGB32F};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Tess, TessError};
+use crate::texture::{
+  Dim2, Dim3, Dimensionable, Flat, GenMipmaps, Layerable, MagFilter, MinFilter, Sampler, Texture,
+  TextureError
+};
+
+/// A 3D lookup table uploaded to the GPU.
+pub struct LutTexture {
+  texture: Texture<Flat, Dim3, RGB32F>,
+  domain_min: [f32; 3],
+  domain_max: [f32; 3],
+}
+
+impl LutTexture {
+  /// Texture holding the graded colors.
+  pub fn texture(&self) -> &Texture<Flat, Dim3, RGB32F> {
+    &self.texture
+  }
+
+  /// Input color mapped to the first texel of each axis.
+  pub fn domain_min(&self) -> [f32; 3] {
+    self.domain_min
+  }
+
+  /// Input color mapped to the last texel of each axis.
+  pub fn domain_max(&self) -> [f32; 3] {
+    self.domain_max
+  }
+}
+
+/// A 3D lookup table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+  size: u32,
+  domain_min: [f32; 3],
+  domain_max: [f32; 3],
+  title: Option<String>,
+  // red varies the fastest, then green, then blue
+  texels: Vec<f32>,
+}
+
+impl Lut {
+  /// Create an identity lookup table – mapping every color to itself – with `size` texels per
+  /// axis.
+  ///
+  /// Panics if `size` is lower than `2`.
+  pub fn identity(size: u32) -> Self {
+    assert!(size >= 2, "a lookup table has at least two texels per axis");
+
+    let max = (size - 1) as f32;
+    let mut texels = Vec::with_capacity(3 * (size * size * size) as usize);
+
+    for b in 0 .. size {
+      for g in 0 .. size {
+        for r in 0 .. size {
+          texels.extend_from_slice(&[r as f32 / max, g as f32 / max, b as f32 / max]);
+        }
+      }
+    }
+
+    Lut {
+      size,
+      domain_min: [0., 0., 0.],
+      domain_max: [1., 1., 1.],
+      title: None,
+      texels,
+    }
+  }
+
+  /// Parse a lookup table from the content of a `.cube` file.
+  ///
+  /// The `TITLE`, `LUT_3D_SIZE`, `DOMAIN_MIN` and `DOMAIN_MAX` keywords are supported; 1D lookup
+  /// tables (`LUT_1D_SIZE`) are not. Other keywords are ignored.
+  pub fn from_cube(src: &str) -> Result<Self, CubeError> {
+    let mut size = None;
+    let mut domain_min = [0., 0., 0.];
+    let mut domain_max = [1., 1., 1.];
+    let mut title = None;
+    let mut texels = Vec::new();
+
+    for (i, line) in src.lines().enumerate() {
+      let line_nb = i + 1;
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let mut words = line.split_whitespace();
+      let keyword = words.next().unwrap_or("");
+
+      match keyword {
+        "TITLE" => {
+          title = Some(line["TITLE".len() ..].trim().trim_matches('"').into());
+        }
+
+        "LUT_3D_SIZE" => {
+          let n = words
+            .next()
+            .and_then(|n| n.parse::<u32>().ok())
+            .ok_or(CubeError::InvalidLine(line_nb))?;
+
+          if !(2 ..= 256).contains(&n) {
+            return Err(CubeError::InvalidSize(n));
+          }
+
+          size = Some(n);
+        }
+
+        "LUT_1D_SIZE" => return Err(CubeError::Unsupported1D),
+
+        "DOMAIN_MIN" => domain_min = parse_rgb(words, line_nb)?,
+
+        "DOMAIN_MAX" => domain_max = parse_rgb(words, line_nb)?,
+
+        _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => (),
+
+        _ => {
+          let rgb = parse_rgb(line.split_whitespace(), line_nb)?;
+          texels.extend_from_slice(&rgb);
+        }
+      }
+    }
+
+    let size = size.ok_or(CubeError::MissingSize)?;
+    let expected = (size * size * size) as usize;
+
+    if texels.len() != 3 * expected {
+      return Err(CubeError::WrongTexelCount(expected, texels.len() / 3));
+    }
+
+    let ordered = |i: usize| domain_min[i] < domain_max[i];
+
+    // NaN bounds are not ordered either
+    if !(0 .. 3).all(ordered) {
+      return Err(CubeError::InvalidDomain(domain_min, domain_max));
+    }
+
+    Ok(Lut {
+      size,
+      domain_min,
+      domain_max,
+      title,
+      texels,
+    })
+  }
+
+  /// Number of texels per axis.
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  /// Input color mapped to the first texel of each axis.
+  pub fn domain_min(&self) -> [f32; 3] {
+    self.domain_min
+  }
+
+  /// Input color mapped to the last texel of each axis.
+  pub fn domain_max(&self) -> [f32; 3] {
+    self.domain_max
+  }
+
+  /// Title of the lookup table, if any.
+  pub fn title(&self) -> Option<&str> {
+    self.title.as_deref()
+  }
+
+  /// Graded colors, as RGB triplets; red varies the fastest, then green, then blue.
+  pub fn texels(&self) -> &[f32] {
+    &self.texels
+  }
+
+  /// Upload the lookup table to a 3D texture, sampled with trilinear filtering.
+  pub fn to_texture<C>(&self, ctx: &mut C) -> Result<LutTexture, TextureError>
+  where C: GraphicsContext {
+    let sampler = Sampler {
+      min_filter: MinFilter::Linear,
+      mag_filter: MagFilter::Linear,
+      ..Sampler::default()
+    };
+    let texture = Texture::new(ctx, [self.size; 3], 0, sampler)?;
+    texture.upload_raw(GenMipmaps::No, &self.texels)?;
+
+    Ok(LutTexture {
+      texture,
+      domain_min: self.domain_min,
+      domain_max: self.domain_max,
+    })
+  }
+}
+
+// Parse three floating-point numbers.
+fn parse_rgb<'a, I>(mut words: I, line_nb: usize) -> Result<[f32; 3], CubeError>
+where I: Iterator<Item = &'a str> {
+  let mut rgb = [0.; 3];
+
+  for c in &mut rgb {
+    *c = words
+      .next()
+      .and_then(|w| w.parse().ok())
+      .ok_or(CubeError::InvalidLine(line_nb))?;
+  }
+
+  if words.next().is_some() {
+    return Err(CubeError::InvalidLine(line_nb));
+  }
+
+  Ok(rgb)
+}
+
+/// Errors that can happen while parsing a `.cube` file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CubeError {
+  /// A line cannot be parsed; the [`usize`] is its number, starting at `1`.
+  InvalidLine(usize),
+  /// The `LUT_3D_SIZE` keyword is missing.
+  MissingSize,
+  /// The size of the table is out of the `[2; 256]` range.
+  InvalidSize(u32),
+  /// The file holds a 1D lookup table.
+  Unsupported1D,
+  /// The domain minimum isn’t lower than the domain maximum on every axis, or one of them isn’t a
+  /// number.
+  InvalidDomain([f32; 3], [f32; 3]),
+  /// The number of texels doesn’t match the size of the table.
+  ///
+  /// The first [`usize`] is the expected number of texels and the second one is the number found.
+  WrongTexelCount(usize, usize),
+}
+
+impl fmt::Display for CubeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      CubeError::InvalidLine(ref line) => write!(f, "invalid .cube line {}", line),
+      CubeError::MissingSize => f.write_str("missing LUT_3D_SIZE"),
+      CubeError::InvalidSize(ref size) => write!(f, "invalid lookup table size: {}", size),
+      CubeError::Unsupported1D => f.write_str("1D lookup tables are not supported"),
+      CubeError::InvalidDomain(ref min, ref max) => {
+        write!(f, "invalid lookup table domain: {:?} to {:?}", min, max)
+      }
+      CubeError::WrongTexelCount(ref expected, ref found) => {
+        write!(f, "wrong lookup table texel count: expected {}, found {}", expected, found)
+      }
+    }
+  }
+}
+
+/// Color grading pass.
+pub struct ColorGrading {
+  program: Program<(), (), GradingInterface>,
+  triangle: Tess,
+}
+
+impl ColorGrading {
+  /// Create a color grading pass, compiling its shader program.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, ColorGradingError> where C: GraphicsContext {
+    let program = Program::from_strings(None, FULLSCREEN_VS, None, GRADING_FS)?.ignore_warnings();
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+
+    Ok(ColorGrading { program, triangle })
+  }
+
+  /// Grade `source` with `lut` and write the result to `framebuffer`.
+  ///
+  /// `strength` blends between the source colors (`0`) and the graded ones (`1`).
+  pub fn render<C, P, L, D, CS, DS>(
+    &self,
+    ctx: &mut C,
+    source: &Texture<Flat, Dim2, P>,
+    lut: &LutTexture,
+    strength: f32,
+    framebuffer: &Framebuffer<L, D, CS, DS>,
+  ) -> Result<(), BindingError>
+  where C: GraphicsContext,
+        P: Pixel<SamplerType = Floating>,
+        L: Layerable,
+        D: Dimensionable,
+        D::Size: Copy,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D> {
+    let pipeline_state = PipelineState::default().enable_clear(false);
+    let mut result = Ok(());
+
+    ctx.pipeline_builder().pipeline(framebuffer, &pipeline_state, |pipeline, mut shd_gate| {
+      result = self.shade(&pipeline, &mut shd_gate, source, lut, strength);
+    });
+
+    result
+  }
+
+  /// Grade `source` with `lut` in an existing pipeline.
+  ///
+  /// This binds two textures – failing if no texture unit is left for them – and renders a
+  /// fullscreen triangle; see [`ColorGrading::render`].
+  pub fn shade<C, P>(
+    &self,
+    pipeline: &Pipeline,
+    shd_gate: &mut ShadingGate<C>,
+    source: &Texture<Flat, Dim2, P>,
+    lut: &LutTexture,
+    strength: f32,
+  ) -> Result<(), BindingError>
+  where C: ?Sized + GraphicsContext,
+        P: Pixel<SamplerType = Floating> {
+    let source = pipeline.bind_texture(source)?;
+    let lut_texture = pipeline.bind_texture(&lut.texture)?;
+    let lut_size = lut.texture.size()[0] as f32;
+    let render_state = RenderState::default().set_depth_test(None);
+    let triangle = &self.triangle;
+
+    shd_gate.shade(&self.program, |iface, mut rdr_gate| {
+      iface.source.update(&source);
+      iface.lut.update(&lut_texture);
+      iface.lut_size.update(lut_size);
+      iface.domain_min.update(lut.domain_min);
+      iface.domain_max.update(lut.domain_max);
+      iface.strength.update(strength);
+      rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(triangle));
+    });
+
+    Ok(())
+  }
+}
+
+/// Errors that can happen while creating a [`ColorGrading`] pass.
+#[derive(Debug)]
+pub enum ColorGradingError {
+  /// The grading shader program failed to build.
+  ProgramError(ProgramError),
+  /// The triangle used by the grading pass couldn’t be created.
+  TessError(TessError),
+}
+
+impl fmt::Display for ColorGradingError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      ColorGradingError::ProgramError(ref e) => write!(f, "color grading program error: {}", e),
+      ColorGradingError::TessError(ref e) => write!(f, "color grading tessellation error: {:?}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for ColorGradingError {
+  fn from(e: ProgramError) -> Self {
+    ColorGradingError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for ColorGradingError {
+  fn from(e: TessError) -> Self {
+    ColorGradingError::TessError(e)
+  }
+}
+
+struct GradingInterface {
+  source: Uniform<&'static BoundTexture<'static, Flat, Dim2, Floating>>,
+  lut: Uniform<&'static BoundTexture<'static, Flat, Dim3, Floating>>,
+  lut_size: Uniform<f32>,
+  domain_min: Uniform<[f32; 3]>,
+  domain_max: Uniform<[f32; 3]>,
+  strength: Uniform<f32>,
+}
+
+impl UniformInterface for GradingInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(GradingInterface {
+      source: builder.ask("source").map_err(ProgramError::UniformWarning)?,
+      lut: builder.ask("lut").map_err(ProgramError::UniformWarning)?,
+      lut_size: builder.ask("lut_size").map_err(ProgramError::UniformWarning)?,
+      domain_min: builder.ask("domain_min").map_err(ProgramError::UniformWarning)?,
+      domain_max: builder.ask("domain_max").map_err(ProgramError::UniformWarning)?,
+      strength: builder.ask("strength").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+// the domain is remapped to the centers of the first and last texels, so that filtering stays
+// within the table
+const GRADING_FS: &str = "
+in vec2 v_uv;
+
+uniform sampler2D source;
+uniform sampler3D lut;
+uniform float lut_size;
+uniform vec3 domain_min;
+uniform vec3 domain_max;
+uniform float strength;
+
+out vec4 frag;
+
+void main() {
+  vec4 color = texture(source, v_uv);
+  vec3 t = clamp((color.rgb - domain_min) / (domain_max - domain_min), 0., 1.);
+  vec3 uvw = t * ((lut_size - 1.) / lut_size) + .5 / lut_size;
+  vec3 graded = texture(lut, uvw).rgb;
+
+  frag = vec4(mix(color.rgb, graded, strength), color.a);
+}
+";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // .cube file of a table with each axis going from black to white
+  fn cube(size: u32, texels: &[f32]) -> String {
+    let mut src = format!("LUT_3D_SIZE {}\n", size);
+
+    for rgb in texels.chunks(3) {
+      src += &format!("{} {} {}\n", rgb[0], rgb[1], rgb[2]);
+    }
+
+    src
+  }
+
+  #[test]
+  fn parse_identity() {
+    let identity = Lut::identity(2);
+    let src = format!("# comment\n\nLUT_3D_INPUT_RANGE 0 1\n{}", cube(2, identity.texels()));
+
+    assert_eq!(Lut::from_cube(&src), Ok(identity));
+  }
+
+  #[test]
+  fn parse_title_and_domain() {
+    let src = format!(
+      "TITLE \"sunset\"\nDOMAIN_MIN 0 -1 0\nDOMAIN_MAX 1 2 4\n{}",
+      cube(2, Lut::identity(2).texels())
+    );
+    let lut = Lut::from_cube(&src).unwrap();
+
+    assert_eq!(lut.title(), Some("sunset"));
+    assert_eq!(lut.domain_min(), [0., -1., 0.]);
+    assert_eq!(lut.domain_max(), [1., 2., 4.]);
+  }
+
+  #[test]
+  fn size_bounds() {
+    assert_eq!(Lut::from_cube("LUT_3D_SIZE 1\n0 0 0\n"), Err(CubeError::InvalidSize(1)));
+    assert_eq!(Lut::from_cube("LUT_3D_SIZE 257\n"), Err(CubeError::InvalidSize(257)));
+    assert_eq!(Lut::from_cube("LUT_3D_SIZE two\n"), Err(CubeError::InvalidLine(1)));
+  }
+
+  #[test]
+  fn missing_size() {
+    assert_eq!(Lut::from_cube("0 0 0\n1 1 1\n"), Err(CubeError::MissingSize));
+  }
+
+  #[test]
+  fn wrong_texel_count() {
+    let texels = Lut::identity(2).texels().to_vec();
+
+    assert_eq!(
+      Lut::from_cube(&cube(2, &texels[3 ..])),
+      Err(CubeError::WrongTexelCount(8, 7))
+    );
+    assert_eq!(
+      Lut::from_cube(&cube(3, &texels)),
+      Err(CubeError::WrongTexelCount(27, 8))
+    );
+  }
+
+  #[test]
+  fn unsupported_1d() {
+    assert_eq!(Lut::from_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n"), Err(CubeError::Unsupported1D));
+  }
+
+  #[test]
+  fn invalid_lines() {
+    assert_eq!(Lut::from_cube("LUT_3D_SIZE 2\n0 0\n"), Err(CubeError::InvalidLine(2)));
+    assert_eq!(Lut::from_cube("LUT_3D_SIZE 2\n0 0 0 0\n"), Err(CubeError::InvalidLine(2)));
+    assert_eq!(Lut::from_cube("DOMAIN_MIN 0 x 0\n"), Err(CubeError::InvalidLine(1)));
+  }
+
+  #[test]
+  fn invalid_domain() {
+    let texels = cube(2, Lut::identity(2).texels());
+
+    let domains = [
+      ("0 0 0", "1 0 1"),
+      ("0 2 0", "1 1 1"),
+      ("0 NaN 0", "1 1 1"),
+      ("0 0 0", "1 1 NaN"),
+    ];
+
+    for &(min, max) in &domains {
+      let src = format!("DOMAIN_MIN {}\nDOMAIN_MAX {}\n{}", min, max, texels);
+
+      match Lut::from_cube(&src) {
+        Err(CubeError::InvalidDomain(..)) => (),
+        result => panic!("{} to {}: {:?}", min, max, result),
+      }
+    }
+  }
+}
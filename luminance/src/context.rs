@@ -20,10 +20,25 @@
 //! That last property might seem to be a drawback to you but is required to remove a lot of
 //! dynamic branches in the implementation and reduce the number of required safety
 //! checks – enforced at compile time instead.
+//!
+//! # Bring your own context
+//!
+//! If the OpenGL context is created elsewhere – by a GUI toolkit, SDL, a host application,
+//! etc. – [`GlContext`] wraps it into a [`GraphicsContext`], given a function to load OpenGL
+//! symbols.
+//!
+//! When the host application keeps rendering in that context, luminance and the host step on each
+//! other’s states. Wrap luminance rendering between [`GraphicsState::save_host_state`] and
+//! [`GraphicsState::restore_host_state`] so that the host finds its context as it left it.
+//!
+//! [`GraphicsState::save_host_state`]: crate::state::GraphicsState::save_host_state
+//! [`GraphicsState::restore_host_state`]: crate::state::GraphicsState::restore_host_state
 
 #[cfg(feature = "std")]
 use std::cell::RefCell;
 #[cfg(feature = "std")]
+use std::os::raw::c_void;
+#[cfg(feature = "std")]
 use std::rc::Rc;
 
 #[cfg(not(feature = "std"))]
@@ -31,8 +46,21 @@ use alloc::rc::Rc;
 #[cfg(not(feature = "std"))]
 use core::cell::RefCell;
 
+use crate::capabilities::Limits;
+use crate::debug;
+#[cfg(feature = "std")]
+use crate::framebuffer::Framebuffer;
+use crate::metagl::*;
 use crate::pipeline::Builder;
-use crate::state::GraphicsState;
+use crate::raw_gl::RawGl;
+#[cfg(feature = "std")]
+use crate::shader::stage;
+use crate::state::{FrameCounters, GraphicsState};
+#[cfg(feature = "std")]
+use crate::state::StateQueryError;
+#[cfg(feature = "std")]
+use crate::texture::{Dim2, Flat};
+use crate::sync::MemoryBarrier;
 
 /// Class of graphics context.
 ///
@@ -52,4 +80,113 @@ pub unsafe trait GraphicsContext {
   fn pipeline_builder(&mut self) -> Builder<Self> {
     Builder::new(self)
   }
+
+  /// Gather the commands issued by `f` in a debug group named `name`.
+  ///
+  /// See the [`debug`](crate::debug) module for further details.
+  fn debug_group<F>(&mut self, name: &str, f: F) where F: FnOnce(&mut Self) {
+    let _group = debug::Group::push(name);
+    f(self);
+  }
+
+  /// Run raw OpenGL code.
+  ///
+  /// `f` declares the states it changes on the given [`RawGl`]; their cached values are issued
+  /// again once it returns – or panics –, so that the cache matches the context again. See the
+  /// [`raw_gl`](crate::raw_gl) module for further details.
+  fn with_raw_gl<F, A>(&mut self, f: F) -> A where F: FnOnce(&mut RawGl) -> A {
+    let mut raw = RawGl::new(self.state().clone());
+    f(&mut raw)
+  }
+
+  /// Numeric limits of the context, such as the maximum texture size.
+  ///
+  /// See [`Limits`] for the list.
+  fn limits(&self) -> Limits {
+    self.state().borrow().limits()
+  }
+
+  /// Draw calls, program switches, texture binds and buffer uploads of the last frame.
+  ///
+  /// See [`GraphicsState::frame_counters`] for further details.
+  fn frame_counters(&self) -> FrameCounters {
+    self.state().borrow().frame_counters()
+  }
+
+  /// Send all the queued commands to the GPU, without waiting for them to complete.
+  fn flush(&mut self) {
+    let _state = self.state().borrow();
+    unsafe { gl::Flush() }
+  }
+
+  /// Block until the GPU has executed all the queued commands.
+  ///
+  /// This stalls the CPU: prefer a [`Fence`](crate::sync::Fence) to wait for a specific piece of
+  /// work – e.g. before reading back a buffer.
+  fn finish(&mut self) {
+    let _state = self.state().borrow();
+    unsafe { gl::Finish() }
+  }
+
+  /// Order the incoherent memory accesses of previous commands with later commands.
+  ///
+  /// This is needed after image stores or shader storage writes, before reading the written data.
+  /// Memory barriers require *OpenGL 4.2* or `GL_ARB_shader_image_load_store`; `false` is
+  /// returned – and nothing happens – if the context doesn’t support them.
+  fn memory_barrier(&mut self, barriers: MemoryBarrier) -> bool {
+    let _state = self.state().borrow();
+
+    if gl_post33!(MemoryBarrier) {
+      unsafe { gl::MemoryBarrier(barriers.bits()) };
+      true
+    } else {
+      false
+    }
+  }
+}
+
+/// A graphics context around an OpenGL context created elsewhere.
+///
+/// The windowing – swapping buffers, handling events, etc. – is left to whatever created the
+/// OpenGL context.
+#[cfg(feature = "std")]
+pub struct GlContext {
+  gfx_state: Rc<RefCell<GraphicsState>>,
+}
+
+#[cfg(feature = "std")]
+impl GlContext {
+  /// Create a graphics context around the OpenGL context current on this thread.
+  ///
+  /// `loader` returns the address of the OpenGL function it’s given the name of – e.g. the
+  /// `get_proc_address` function of the windowing library. Like any other graphics context, only
+  /// one can be created per thread; see the [module documentation](crate::context).
+  ///
+  /// # Safety
+  ///
+  /// An OpenGL 3.3 core context must be current on this thread, and must stay current as long as
+  /// the returned context and the objects created with it live.
+  pub unsafe fn from_loader<F>(mut loader: F) -> Result<Self, StateQueryError>
+  where F: FnMut(&str) -> *const c_void {
+    gl::load_with(&mut loader);
+    stage::load_spirv_with(&mut loader);
+
+    let gfx_state = GraphicsState::new()?;
+
+    Ok(GlContext {
+      gfx_state: Rc::new(RefCell::new(gfx_state)),
+    })
+  }
+
+  /// Back buffer of the OpenGL context, which size – in pixels – is `size`.
+  pub fn back_buffer(&mut self, size: [u32; 2]) -> Framebuffer<Flat, Dim2, (), ()> {
+    Framebuffer::back_buffer(self, size)
+  }
+}
+
+#[cfg(feature = "std")]
+unsafe impl GraphicsContext for GlContext {
+  fn state(&self) -> &Rc<RefCell<GraphicsState>> {
+    &self.gfx_state
+  }
 }
@@ -0,0 +1,201 @@
+//! Cubemaps out of equirectangular images.
+//!
+//! Skyboxes and image-based lighting environments are often distributed as equirectangular
+//! images – typically HDR panoramas – rather than as six faces. [`EquirectToCubemap`] converts
+//! them to cubemaps on the GPU, rendering each face with a fragment shader sampling the panorama
+//! in the direction of its texels:
+//!
+//! ```ignore
+//! let panorama: Texture<Flat, Dim2, RGBA32F> = load_hdr(&mut surface, "studio.hdr")?;
+//! let converter = EquirectToCubemap::new(&mut surface)?;
+//! let sky = converter.convert(&mut surface, &panorama, 512, 0, Sampler::default())?;
+//! ```
+//!
+//! Cubemaps made of six face images are created with [`Texture::from_faces`] instead.
+//!
+//! [`Texture::from_faces`]: crate::texture::Texture::from_faces
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{Framebuffer, FramebufferError};
+use crate::pipeline::{BindingError, BoundTexture, PipelineState};
+use crate::pixel::{ColorPixel, Floating, Pixel, RenderablePixel};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Tess, TessError};
+use crate::texture::{CubeFace, Cubemap, Dim2, Flat, Sampler, Texture, TextureError};
+
+/// Converter of equirectangular images to cubemaps.
+pub struct EquirectToCubemap {
+  program: Program<(), (), EquirectInterface>,
+  triangle: Tess,
+}
+
+impl EquirectToCubemap {
+  /// Create a converter, compiling its shader program.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, CubemapError> where C: GraphicsContext {
+    let program = Program::from_strings(None, FULLSCREEN_VS, None, EQUIRECT_FS)?.ignore_warnings();
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+
+    Ok(EquirectToCubemap { program, triangle })
+  }
+
+  /// Convert `equirect` to a new cubemap which faces have `size` × `size` texels.
+  ///
+  /// `equirect` covers 360° horizontally and 180° vertically, its top row first – as image
+  /// decoders output them. Its center is in the +Z direction, facing the front face of the
+  /// cubemap. If `mipmaps` is not `0`, the mipmaps of the cubemap are generated from its faces.
+  pub fn convert<C, P>(
+    &self,
+    ctx: &mut C,
+    equirect: &Texture<Flat, Dim2, P>,
+    size: u32,
+    mipmaps: usize,
+    sampler: Sampler,
+  ) -> Result<Texture<Flat, Cubemap, P>, CubemapError>
+  where C: GraphicsContext,
+        P: ColorPixel + RenderablePixel + Pixel<SamplerType = Floating> {
+    let cubemap = Texture::new(ctx, size, mipmaps, sampler)?;
+    let face_buffer = Framebuffer::<Flat, Dim2, P, ()>::new(ctx, [size, size], 0)?;
+    let pipeline_state = PipelineState::default().enable_clear(false);
+    let render_state = RenderState::default().set_depth_test(None);
+    let triangle = &self.triangle;
+
+    for (layer, &face) in CubeFace::ALL.iter().enumerate() {
+      let mut result = Ok(());
+
+      ctx.pipeline_builder().pipeline(&face_buffer, &pipeline_state, |pipeline, mut shd_gate| {
+        let equirect = match pipeline.bind_texture(equirect) {
+          Ok(equirect) => equirect,
+          Err(e) => {
+            result = Err(e);
+            return;
+          }
+        };
+
+        shd_gate.shade(&self.program, |iface, mut rdr_gate| {
+          iface.equirect.update(&equirect);
+          iface.face.update(layer as i32);
+          rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(triangle));
+        });
+      });
+
+      result?;
+      cubemap.copy_face_from(face, face_buffer.color_slot())?;
+    }
+
+    if mipmaps > 0 {
+      cubemap.generate_mipmaps();
+    }
+
+    Ok(cubemap)
+  }
+}
+
+/// Errors that can happen while converting equirectangular images to cubemaps.
+#[derive(Debug)]
+pub enum CubemapError {
+  /// The conversion shader program failed to build.
+  ProgramError(ProgramError),
+  /// The triangle used by the conversion couldn’t be created.
+  TessError(TessError),
+  /// The framebuffer faces are rendered to couldn’t be created.
+  FramebufferError(FramebufferError),
+  /// The cubemap couldn’t be created or its faces couldn’t be copied.
+  TextureError(TextureError),
+  /// The equirectangular image couldn’t be bound.
+  BindingError(BindingError),
+}
+
+impl fmt::Display for CubemapError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      CubemapError::ProgramError(ref e) => write!(f, "cubemap program error: {}", e),
+      CubemapError::TessError(ref e) => write!(f, "cubemap tessellation error: {:?}", e),
+      CubemapError::FramebufferError(ref e) => write!(f, "cubemap framebuffer error: {}", e),
+      CubemapError::TextureError(ref e) => write!(f, "cubemap texture error: {}", e),
+      CubemapError::BindingError(ref e) => write!(f, "cubemap binding error: {}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for CubemapError {
+  fn from(e: ProgramError) -> Self {
+    CubemapError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for CubemapError {
+  fn from(e: TessError) -> Self {
+    CubemapError::TessError(e)
+  }
+}
+
+impl From<FramebufferError> for CubemapError {
+  fn from(e: FramebufferError) -> Self {
+    CubemapError::FramebufferError(e)
+  }
+}
+
+impl From<TextureError> for CubemapError {
+  fn from(e: TextureError) -> Self {
+    CubemapError::TextureError(e)
+  }
+}
+
+impl From<BindingError> for CubemapError {
+  fn from(e: BindingError) -> Self {
+    CubemapError::BindingError(e)
+  }
+}
+
+struct EquirectInterface {
+  equirect: Uniform<&'static BoundTexture<'static, Flat, Dim2, Floating>>,
+  face: Uniform<i32>,
+}
+
+impl UniformInterface for EquirectInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(EquirectInterface {
+      equirect: builder.ask("equirect").map_err(ProgramError::UniformWarning)?,
+      face: builder.ask("face").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+// the direction of each texel follows the face orientations of the OpenGL specification – the rows
+// of the framebuffer being copied as is into the face – and the panorama is sampled at its base
+// level, as the discontinuity of atan would otherwise select a tiny mipmap along a seam
+const EQUIRECT_FS: &str = "
+in vec2 v_uv;
+
+uniform sampler2D equirect;
+uniform int face;
+
+out vec4 frag;
+
+const float PI = 3.141592653589793;
+
+vec3 direction(vec2 st) {
+  switch (face) {
+    case 0: return vec3(1., -st.y, -st.x);
+    case 1: return vec3(-1., -st.y, st.x);
+    case 2: return vec3(st.x, 1., st.y);
+    case 3: return vec3(st.x, -1., -st.y);
+    case 4: return vec3(st.x, -st.y, 1.);
+    default: return vec3(-st.x, -st.y, -1.);
+  }
+}
+
+void main() {
+  vec3 d = normalize(direction(v_uv * 2. - 1.));
+  vec2 uv = vec2(atan(d.x, d.z) / (2. * PI) + .5, .5 - asin(clamp(d.y, -1., 1.)) / PI);
+  frag = textureLod(equirect, uv, 0.);
+}
+";
@@ -0,0 +1,286 @@
+//! Debug annotations.
+//!
+//! Graphics debuggers – RenderDoc, apitrace, etc. – show the commands sent to the GPU and the
+//! objects they use. Annotations make their captures readable:
+//!
+//!   - _Debug groups_ gather commands under a name – e.g. `"shadow pass"`. Pipelines can be
+//!     grouped with [`Builder::pipeline_named`], and any commands with
+//!     [`GraphicsContext::debug_group`].
+//!   - _Labels_ name objects. Textures, buffers, framebuffers, shader programs and tessellations
+//!     all have a `set_label` method.
+//!
+//! The driver can also report errors, performance warnings, etc. as _debug messages_, forwarded to
+//! a handler installed with [`GraphicsState::enable_debug_output`] – or to the [log] crate with
+//! [`GraphicsState::enable_debug_log`] and the `log` feature.
+//!
+//! > Note: annotations and debug messages require *OpenGL 4.3* or `GL_KHR_debug`. Without them,
+//! > annotating does nothing, so annotations can be left in release builds.
+//!
+//! [`Builder::pipeline_named`]: crate::pipeline::Builder::pipeline_named
+//! [`GraphicsContext::debug_group`]: crate::context::GraphicsContext::debug_group
+//! [`GraphicsState::enable_debug_output`]: crate::state::GraphicsState::enable_debug_output
+//! [`GraphicsState::enable_debug_log`]: crate::state::GraphicsState::enable_debug_log
+//! [log]: https://crates.io/crates/log
+
+#[cfg(feature = "std")]
+use std::os::raw::c_void;
+#[cfg(feature = "std")]
+use std::ptr;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use core::ptr;
+
+use crate::metagl::*;
+
+// Open debug group, closed when dropped – even when unwinding from a panic.
+pub(crate) struct Group;
+
+impl Group {
+  pub(crate) fn push(name: &str) -> Self {
+    if gl_post33!(PushDebugGroup) {
+      unsafe {
+        gl::PushDebugGroup(
+          gl::DEBUG_SOURCE_APPLICATION,
+          0,
+          name.len() as GLsizei,
+          name.as_ptr() as *const GLchar,
+        );
+      }
+    }
+
+    Group
+  }
+}
+
+impl Drop for Group {
+  fn drop(&mut self) {
+    if gl_post33!(PopDebugGroup) {
+      unsafe { gl::PopDebugGroup() }
+    }
+  }
+}
+
+// Label an object; identifier is the namespace of the handle (GL_TEXTURE, GL_BUFFER, etc.).
+pub(crate) fn label(identifier: GLenum, handle: GLuint, label: &str) {
+  if handle != 0 && gl_post33!(ObjectLabel) {
+    unsafe {
+      gl::ObjectLabel(
+        identifier,
+        handle,
+        label.len() as GLsizei,
+        label.as_ptr() as *const GLchar,
+      );
+    }
+  }
+}
+
+// Label of an object, if it has one.
+pub(crate) fn object_label(identifier: GLenum, handle: GLuint) -> Option<String> {
+  if handle == 0 || !gl_post33!(GetObjectLabel) {
+    return None;
+  }
+
+  unsafe {
+    let mut len: GLsizei = 0;
+    gl::GetObjectLabel(identifier, handle, 0, &mut len, ptr::null_mut());
+
+    if len <= 0 {
+      return None;
+    }
+
+    // room for the terminating nul byte
+    let mut label = vec![0u8; len as usize + 1];
+    gl::GetObjectLabel(
+      identifier,
+      handle,
+      label.len() as GLsizei,
+      &mut len,
+      label.as_mut_ptr() as *mut GLchar,
+    );
+    label.truncate(len as usize);
+
+    Some(String::from_utf8_lossy(&label).into_owned())
+  }
+}
+
+/// Severity of a debug message.
+///
+/// Severities are ordered, from the least to the most severe.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DebugSeverity {
+  /// Anything that isn’t an error or a performance issue, like debug group markers.
+  Notification,
+  /// Redundant state changes, unimportant undefined behavior, etc.
+  Low,
+  /// Performance warnings, deprecated functionality, etc.
+  Medium,
+  /// Errors and dangerous undefined behavior.
+  High,
+}
+
+impl DebugSeverity {
+  fn from_glenum(severity: GLenum) -> Self {
+    match severity {
+      gl::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+      gl::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+      gl::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+      _ => DebugSeverity::Notification,
+    }
+  }
+
+  pub(crate) fn to_glenum(self) -> GLenum {
+    match self {
+      DebugSeverity::Notification => gl::DEBUG_SEVERITY_NOTIFICATION,
+      DebugSeverity::Low => gl::DEBUG_SEVERITY_LOW,
+      DebugSeverity::Medium => gl::DEBUG_SEVERITY_MEDIUM,
+      DebugSeverity::High => gl::DEBUG_SEVERITY_HIGH,
+    }
+  }
+}
+
+/// Source of a debug message.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DebugSource {
+  /// The graphics API.
+  Api,
+  /// The window system.
+  WindowSystem,
+  /// The shader compiler.
+  ShaderCompiler,
+  /// A third-party tool, like a graphics debugger.
+  ThirdParty,
+  /// The application – e.g. debug groups.
+  Application,
+  /// Any other source.
+  Other,
+}
+
+impl DebugSource {
+  fn from_glenum(source: GLenum) -> Self {
+    match source {
+      gl::DEBUG_SOURCE_API => DebugSource::Api,
+      gl::DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::WindowSystem,
+      gl::DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+      gl::DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+      gl::DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+      _ => DebugSource::Other,
+    }
+  }
+}
+
+/// Type of a debug message.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DebugType {
+  /// An error, typically from the graphics API.
+  Error,
+  /// Use of deprecated functionality.
+  DeprecatedBehavior,
+  /// Use of functionality which behavior is undefined.
+  UndefinedBehavior,
+  /// Use of functionality that isn’t portable.
+  Portability,
+  /// Performance issue.
+  Performance,
+  /// Annotation of the command stream.
+  Marker,
+  /// A debug group was opened.
+  PushGroup,
+  /// A debug group was closed.
+  PopGroup,
+  /// Any other type.
+  Other,
+}
+
+impl DebugType {
+  fn from_glenum(ty: GLenum) -> Self {
+    match ty {
+      gl::DEBUG_TYPE_ERROR => DebugType::Error,
+      gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugType::DeprecatedBehavior,
+      gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugType::UndefinedBehavior,
+      gl::DEBUG_TYPE_PORTABILITY => DebugType::Portability,
+      gl::DEBUG_TYPE_PERFORMANCE => DebugType::Performance,
+      gl::DEBUG_TYPE_MARKER => DebugType::Marker,
+      gl::DEBUG_TYPE_PUSH_GROUP => DebugType::PushGroup,
+      gl::DEBUG_TYPE_POP_GROUP => DebugType::PopGroup,
+      _ => DebugType::Other,
+    }
+  }
+}
+
+/// A debug message, sent by the driver.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DebugMessage<'a> {
+  /// Source of the message.
+  pub source: DebugSource,
+  /// Type of the message.
+  pub ty: DebugType,
+  /// Identifier of the message, specific to the driver.
+  pub id: u32,
+  /// Severity of the message.
+  pub severity: DebugSeverity,
+  /// Content of the message.
+  pub message: &'a str,
+}
+
+// Handler of debug messages.
+#[cfg(feature = "std")]
+pub(crate) type DebugHandler = Box<dyn FnMut(&DebugMessage)>;
+
+// Callback installed with glDebugMessageCallback; user_param points to the DebugHandler, or is null
+// once the debug output is disabled.
+#[cfg(feature = "std")]
+pub(crate) extern "system" fn debug_callback(
+  source: GLenum,
+  ty: GLenum,
+  id: GLuint,
+  severity: GLenum,
+  length: GLsizei,
+  message: *const GLchar,
+  user_param: *mut c_void,
+) {
+  if user_param.is_null() || message.is_null() {
+    return;
+  }
+
+  let message = unsafe {
+    if length < 0 {
+      std::ffi::CStr::from_ptr(message).to_string_lossy()
+    } else {
+      String::from_utf8_lossy(std::slice::from_raw_parts(message as *const u8, length as usize))
+    }
+  };
+
+  let message = DebugMessage {
+    source: DebugSource::from_glenum(source),
+    ty: DebugType::from_glenum(ty),
+    id,
+    severity: DebugSeverity::from_glenum(severity),
+    message: &message,
+  };
+
+  let handler = unsafe { &mut *(user_param as *mut DebugHandler) };
+  handler(&message);
+}
+
+// Forward debug messages to the log crate.
+#[cfg(feature = "log")]
+pub(crate) fn log_message(message: &DebugMessage) {
+  let level = match message.severity {
+    DebugSeverity::High => log::Level::Error,
+    DebugSeverity::Medium => log::Level::Warn,
+    DebugSeverity::Low => log::Level::Info,
+    DebugSeverity::Notification => log::Level::Debug,
+  };
+
+  log::log!(
+    target: "luminance::debug",
+    level,
+    "{:?} {:?} ({}): {}",
+    message.source,
+    message.ty,
+    message.id,
+    message.message
+  );
+}
@@ -1,4 +1,13 @@
 //! Depth test related features.
+//!
+//! Besides the depth test itself, the render state can alter the depth of fragments before they’re
+//! tested:
+//!
+//!   - _Depth clamping_ clamps depths to the depth range instead of clipping primitives against the
+//!     near and far planes – useful to render shadow casters located behind the light’s near
+//!     plane.
+//!   - _Polygon offset_ biases the depth of polygons by a [`PolygonOffset`], typically to avoid
+//!     shadow acne when rendering shadow maps, or z-fighting when rendering decals.
 
 use gl::types::*;
 
@@ -47,3 +56,22 @@ impl DepthComparison {
     }
   }
 }
+
+/// Depth offset applied to polygons.
+///
+/// The depth of each fragment is offset by `factor * dz + units * r`, where `dz` is the depth slope
+/// of the polygon and `r` the smallest depth difference the depth buffer can represent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PolygonOffset {
+  /// Factor scaling the depth slope of polygons.
+  pub factor: f32,
+  /// Constant offset, in units of the depth buffer resolution.
+  pub units: f32,
+}
+
+impl PolygonOffset {
+  /// Create a new polygon offset.
+  pub fn new(factor: f32, units: f32) -> Self {
+    PolygonOffset { factor, units }
+  }
+}
@@ -0,0 +1,359 @@
+//! Blue noise dithering.
+//!
+//! Smooth gradients – skies, fog, vignettes, dark scenes in general – quantized to 8-bit color
+//! channels show visible bands. Adding a small amount of noise before quantization trades those
+//! bands for a fine grain the eye doesn’t notice. Blue noise – noise without low frequencies –
+//! gives the least visible grain.
+//!
+//! [`Dither`] is a final pass copying a texture – typically the tone-mapped scene – to a
+//! framebuffer – typically the back buffer –, adding blue noise of the amplitude of one
+//! quantization step of the framebuffer. The noise is a tileable texture generated once, when the
+//! pass is created, with the *void-and-cluster* algorithm.
+//!
+//! Within an [`EffectChain`], use [`Dither::shade`] in the render function of the last pass
+//! instead of [`Dither::render`].
+//!
+//! > Note: dithering must happen after any operation changing colors – tone mapping, color grading,
+//! > etc. Noise added before them is amplified or attenuated, and doesn’t match the quantization
+//! > step of the framebuffer anymore.
+//!
+//! [`EffectChain`]: crate::effect::EffectChain
+
+use std::fmt;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::pipeline::{BindingError, BoundTexture, Pipeline, PipelineState, ShadingGate};
+use crate::pixel::{Floating, Pixel, R32F};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Tess, TessError};
+use crate::texture::{
+  Dim2, Dimensionable, Flat, GenMipmaps, Layerable, MagFilter, MinFilter, Sampler, Texture,
+  TextureError, Wrap
+};
+
+// size of the noise tile, in texels
+const NOISE_SIZE: usize = 64;
+
+// radius, in texels, and standard deviation of the energy kernel of the void-and-cluster algorithm
+const KERNEL_RADIUS: isize = 6;
+const KERNEL_SIGMA: f32 = 1.5;
+
+/// Blue noise dithering pass.
+pub struct Dither {
+  program: Program<(), (), DitherInterface>,
+  triangle: Tess,
+  noise: Texture<Flat, Dim2, R32F>,
+  bit_depth: u32,
+}
+
+impl Dither {
+  /// Create a dithering pass for 8-bit framebuffers, compiling its shader program and generating
+  /// its noise texture.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, DitherError> where C: GraphicsContext {
+    let program = Program::from_strings(None, FULLSCREEN_VS, None, DITHER_FS)?.ignore_warnings();
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+
+    let sampler = Sampler {
+      wrap_s: Wrap::Repeat,
+      wrap_t: Wrap::Repeat,
+      min_filter: MinFilter::Nearest,
+      mag_filter: MagFilter::Nearest,
+      ..Sampler::default()
+    };
+    let size = NOISE_SIZE as u32;
+    let noise = Texture::new(ctx, [size, size], 0, sampler)?;
+    noise.upload_raw(GenMipmaps::No, &blue_noise(NOISE_SIZE))?;
+
+    Ok(Dither {
+      program,
+      triangle,
+      noise,
+      bit_depth: 8,
+    })
+  }
+
+  /// Number of bits per color channel of the framebuffers the pass writes to.
+  pub fn bit_depth(&self) -> u32 {
+    self.bit_depth
+  }
+
+  /// Set the number of bits per color channel of the framebuffers the pass writes to – e.g. `10`
+  /// for `RGB10A2` framebuffers.
+  pub fn set_bit_depth(&mut self, bit_depth: u32) {
+    self.bit_depth = bit_depth;
+  }
+
+  /// Dither `source` and write the result to `framebuffer`.
+  pub fn render<C, P, L, D, CS, DS>(
+    &self,
+    ctx: &mut C,
+    source: &Texture<Flat, Dim2, P>,
+    framebuffer: &Framebuffer<L, D, CS, DS>,
+  ) -> Result<(), BindingError>
+  where C: GraphicsContext,
+        P: Pixel<SamplerType = Floating>,
+        L: Layerable,
+        D: Dimensionable,
+        D::Size: Copy,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D> {
+    let pipeline_state = PipelineState::default().enable_clear(false);
+    let mut result = Ok(());
+
+    ctx.pipeline_builder().pipeline(framebuffer, &pipeline_state, |pipeline, mut shd_gate| {
+      result = self.shade(&pipeline, &mut shd_gate, source);
+    });
+
+    result
+  }
+
+  /// Dither `source` in an existing pipeline.
+  ///
+  /// This binds two textures – failing if no texture unit is left for them – and renders a
+  /// fullscreen triangle; see [`Dither::render`].
+  pub fn shade<C, P>(
+    &self,
+    pipeline: &Pipeline,
+    shd_gate: &mut ShadingGate<C>,
+    source: &Texture<Flat, Dim2, P>,
+  ) -> Result<(), BindingError>
+  where C: ?Sized + GraphicsContext,
+        P: Pixel<SamplerType = Floating> {
+    let source = pipeline.bind_texture(source)?;
+    let noise = pipeline.bind_texture(&self.noise)?;
+    let levels = (2f32).powi(self.bit_depth as i32) - 1.;
+    // the fixed-function dithering would add noise on top of ours
+    let render_state = RenderState::default()
+      .set_depth_test(None)
+      .set_dithering(false);
+    let triangle = &self.triangle;
+
+    shd_gate.shade(&self.program, |iface, mut rdr_gate| {
+      iface.source.update(&source);
+      iface.noise.update(&noise);
+      iface.levels.update(levels);
+      rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(triangle));
+    });
+
+    Ok(())
+  }
+}
+
+/// Errors that can happen while creating a [`Dither`] pass.
+#[derive(Debug)]
+pub enum DitherError {
+  /// The dithering shader program failed to build.
+  ProgramError(ProgramError),
+  /// The triangle used by the dithering pass couldn’t be created.
+  TessError(TessError),
+  /// The noise texture couldn’t be created.
+  TextureError(TextureError),
+}
+
+impl fmt::Display for DitherError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      DitherError::ProgramError(ref e) => write!(f, "dithering program error: {}", e),
+      DitherError::TessError(ref e) => write!(f, "dithering tessellation error: {:?}", e),
+      DitherError::TextureError(ref e) => write!(f, "dithering texture error: {}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for DitherError {
+  fn from(e: ProgramError) -> Self {
+    DitherError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for DitherError {
+  fn from(e: TessError) -> Self {
+    DitherError::TessError(e)
+  }
+}
+
+impl From<TextureError> for DitherError {
+  fn from(e: TextureError) -> Self {
+    DitherError::TextureError(e)
+  }
+}
+
+// Tileable blue noise of size × size texels, with values evenly spread in [0; 1), generated with
+// the void-and-cluster algorithm.
+fn blue_noise(size: usize) -> Vec<f32> {
+  let n = size * size;
+  let kernel = energy_kernel();
+  let mut pattern = vec![false; n];
+  let mut energy = vec![0.; n];
+  let mut rank = vec![0; n];
+
+  // initial binary pattern: a tenth of the texels, at random
+  let initial = n / 10;
+  let mut seed = 0x9e37_79b9_u32;
+  let mut count = 0;
+
+  while count < initial {
+    let i = xorshift(&mut seed) as usize % n;
+
+    if !pattern[i] {
+      pattern[i] = true;
+      splat(&mut energy, &kernel, size, i, 1.);
+      count += 1;
+    }
+  }
+
+  // spread the initial pattern evenly, moving the tightest cluster to the largest void until the
+  // two are the same texel; this converges quickly, so the cap only guards against cycles
+  for _ in 0 .. n {
+    let cluster = argmax(&energy, &pattern, true);
+    pattern[cluster] = false;
+    splat(&mut energy, &kernel, size, cluster, -1.);
+
+    let void = argmin(&energy, &pattern, false);
+    pattern[void] = true;
+    splat(&mut energy, &kernel, size, void, 1.);
+
+    if void == cluster {
+      break;
+    }
+  }
+
+  // rank the initial pattern, removing its tightest clusters first
+  let mut removed = pattern.clone();
+  let mut removed_energy = energy.clone();
+
+  for r in (0 .. initial).rev() {
+    let cluster = argmax(&removed_energy, &removed, true);
+    removed[cluster] = false;
+    splat(&mut removed_energy, &kernel, size, cluster, -1.);
+    rank[cluster] = r;
+  }
+
+  // fill the largest voids up to half of the texels
+  for r in initial .. n / 2 {
+    let void = argmin(&energy, &pattern, false);
+    pattern[void] = true;
+    splat(&mut energy, &kernel, size, void, 1.);
+    rank[void] = r;
+  }
+
+  // past half of the texels, texels left out are the minority: fill their tightest clusters
+  energy.iter_mut().for_each(|e| *e = 0.);
+
+  for i in (0 .. n).filter(|&i| !pattern[i]) {
+    splat(&mut energy, &kernel, size, i, 1.);
+  }
+
+  for r in n / 2 .. n {
+    let cluster = argmax(&energy, &pattern, false);
+    pattern[cluster] = true;
+    splat(&mut energy, &kernel, size, cluster, -1.);
+    rank[cluster] = r;
+  }
+
+  rank.into_iter().map(|r| (r as f32 + 0.5) / n as f32).collect()
+}
+
+// Gaussian kernel of the energy each texel spreads to its neighbors.
+fn energy_kernel() -> Vec<f32> {
+  let mut kernel = Vec::new();
+
+  for dy in -KERNEL_RADIUS ..= KERNEL_RADIUS {
+    for dx in -KERNEL_RADIUS ..= KERNEL_RADIUS {
+      let d2 = (dx * dx + dy * dy) as f32;
+      kernel.push((-d2 / (2. * KERNEL_SIGMA * KERNEL_SIGMA)).exp());
+    }
+  }
+
+  kernel
+}
+
+// Add (sign = 1) or remove (sign = -1) the energy of texel i, wrapping around the edges.
+fn splat(energy: &mut [f32], kernel: &[f32], size: usize, i: usize, sign: f32) {
+  let (x, y) = ((i % size) as isize, (i / size) as isize);
+  let size = size as isize;
+  let width = 2 * KERNEL_RADIUS + 1;
+
+  for dy in -KERNEL_RADIUS ..= KERNEL_RADIUS {
+    let ny = (y + dy).rem_euclid(size);
+
+    for dx in -KERNEL_RADIUS ..= KERNEL_RADIUS {
+      let nx = (x + dx).rem_euclid(size);
+      let k = kernel[((dy + KERNEL_RADIUS) * width + dx + KERNEL_RADIUS) as usize];
+      energy[(ny * size + nx) as usize] += sign * k;
+    }
+  }
+}
+
+// Texel of highest energy among the ones which pattern value is value.
+fn argmax(energy: &[f32], pattern: &[bool], value: bool) -> usize {
+  select(energy, pattern, value, |a, b| a > b)
+}
+
+// Texel of lowest energy among the ones which pattern value is value.
+fn argmin(energy: &[f32], pattern: &[bool], value: bool) -> usize {
+  select(energy, pattern, value, |a, b| a < b)
+}
+
+fn select<F>(energy: &[f32], pattern: &[bool], value: bool, better: F) -> usize
+where F: Fn(f32, f32) -> bool {
+  let mut best: Option<usize> = None;
+
+  for (i, (&e, &p)) in energy.iter().zip(pattern).enumerate() {
+    if p != value {
+      continue;
+    }
+
+    match best {
+      Some(b) if !better(e, energy[b]) => (),
+      _ => best = Some(i),
+    }
+  }
+
+  best.expect("no texel to select")
+}
+
+fn xorshift(state: &mut u32) -> u32 {
+  *state ^= *state << 13;
+  *state ^= *state >> 17;
+  *state ^= *state << 5;
+  *state
+}
+
+struct DitherInterface {
+  source: Uniform<&'static BoundTexture<'static, Flat, Dim2, Floating>>,
+  noise: Uniform<&'static BoundTexture<'static, Flat, Dim2, Floating>>,
+  levels: Uniform<f32>,
+}
+
+impl UniformInterface for DitherInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(DitherInterface {
+      source: builder.ask("source").map_err(ProgramError::UniformWarning)?,
+      noise: builder.ask("noise").map_err(ProgramError::UniformWarning)?,
+      levels: builder.ask("levels").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+// the noise is centered around zero and spans one quantization step; alpha is left untouched
+const DITHER_FS: &str = "
+in vec2 v_uv;
+
+uniform sampler2D source;
+uniform sampler2D noise;
+uniform float levels;
+
+out vec4 frag;
+
+void main() {
+  vec4 color = texture(source, v_uv);
+  ivec2 p = ivec2(gl_FragCoord.xy) % textureSize(noise, 0);
+  float t = texelFetch(noise, p, 0).r - .5;
+
+  frag = vec4(color.rgb + t / levels, color.a);
+}
+";
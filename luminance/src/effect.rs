@@ -0,0 +1,503 @@
+//! Post-processing effect chains.
+//!
+//! Post-processing effects – bloom, god rays, tone mapping, etc. – are made of several passes,
+//! each reading the output of previous passes and writing to an intermediate framebuffer, the last
+//! one writing to the final framebuffer (typically the back buffer). Managing those intermediate
+//! framebuffers by hand is tedious: they must be allocated at the right size, re-allocated when
+//! the window gets resized and, ideally, reused between passes to save memory.
+//!
+//! An [`EffectChain`] takes a list of [`EffectPass`] declarations and does all of that for you.
+//! Intermediate framebuffers are allocated once per size and reused as soon as no later pass reads
+//! their content anymore.
+//!
+//! Chains rendered one after the other – e.g. one per camera, or a bloom chain followed by a depth
+//! of field one – never use their intermediate framebuffers at the same time either. Creating them
+//! with [`EffectChain::with_pool`] and the same [`TargetPool`] makes them share those framebuffers:
+//! the pool holds, for each size, as many framebuffers as the most demanding chain needs.
+
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::rc::Rc;
+
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer, FramebufferError};
+use crate::pipeline::{Builder, Pipeline, PipelineState, ShadingGate};
+use crate::pixel::{ColorPixel, RenderablePixel};
+use crate::texture::{Dim2, Dimensionable, Flat, Layerable, Texture};
+
+/// Input of an [`EffectPass`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EffectInput {
+  /// The source texture of the chain, typically the rendered scene.
+  Source,
+  /// The output of the previous pass, or the source texture for the first pass.
+  Previous,
+  /// The output of a given pass, which must come before the pass reading it.
+  Pass(usize),
+}
+
+/// Declaration of a pass of an [`EffectChain`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EffectPass {
+  inputs: Vec<EffectInput>,
+  scale: f32,
+}
+
+impl EffectPass {
+  /// Create a new pass reading the output of the previous pass, at full resolution.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Inputs of the pass, in the order they’re handed to the render function.
+  pub fn inputs(&self) -> &[EffectInput] {
+    &self.inputs
+  }
+
+  /// Set the inputs of the pass.
+  pub fn set_inputs<I>(self, inputs: I) -> Self where I: IntoIterator<Item = EffectInput> {
+    EffectPass {
+      inputs: inputs.into_iter().collect(),
+      ..self
+    }
+  }
+
+  /// Resolution of the pass output, relative to the size of the chain.
+  pub fn scale(&self) -> f32 {
+    self.scale
+  }
+
+  /// Set the resolution of the pass output, relative to the size of the chain.
+  ///
+  /// Use `0.5` for a half-resolution pass, as typically done for blur passes. The scale is ignored
+  /// for the last pass, which renders to the framebuffer passed to [`EffectChain::render`].
+  pub fn set_scale(self, scale: f32) -> Self {
+    EffectPass { scale, ..self }
+  }
+}
+
+impl Default for EffectPass {
+  /// The default pass has the following configuration:
+  ///
+  ///   - `inputs`: `[EffectInput::Previous]`
+  ///   - `scale`: `1.`
+  fn default() -> Self {
+    EffectPass {
+      inputs: vec![EffectInput::Previous],
+      scale: 1.,
+    }
+  }
+}
+
+/// A pool of intermediate framebuffers, shared by effect chains rendered one after the other.
+///
+/// Cloning a pool yields another handle to the same framebuffers.
+pub struct TargetPool<P> where P: ColorPixel + RenderablePixel {
+  inner: Rc<RefCell<PoolInner<P>>>,
+}
+
+// Intermediate framebuffer.
+type Target<P> = Framebuffer<Flat, Dim2, P, ()>;
+
+// Number of framebuffers needed per size.
+type Needs = Vec<([u32; 2], usize)>;
+
+// Framebuffers of a pool, and what the chains using it need.
+struct PoolInner<P> where P: ColorPixel + RenderablePixel {
+  // framebuffers, grouped by size
+  targets: Vec<([u32; 2], Vec<Target<P>>)>,
+  // number of framebuffers of each size needed by each chain, by chain identifier
+  needs: Vec<(u64, Needs)>,
+  next_id: u64,
+}
+
+impl<P> TargetPool<P> where P: ColorPixel + RenderablePixel {
+  /// Create an empty pool.
+  pub fn new() -> Self {
+    TargetPool {
+      inner: Rc::new(RefCell::new(PoolInner {
+        targets: Vec::new(),
+        needs: Vec::new(),
+        next_id: 0,
+      })),
+    }
+  }
+
+  /// Number of framebuffers in the pool.
+  pub fn target_count(&self) -> usize {
+    self.inner.borrow().targets.iter().map(|(_, targets)| targets.len()).sum()
+  }
+
+  // Identifier of a new chain using the pool.
+  fn register(&self) -> u64 {
+    let mut inner = self.inner.borrow_mut();
+    inner.next_id += 1;
+    inner.next_id
+  }
+
+  // Set the framebuffers needed by chain id, allocating the missing ones and releasing the ones no
+  // chain needs anymore.
+  //
+  // The missing framebuffers are allocated first: if that fails, the needs of the chain and its
+  // framebuffers are left untouched.
+  fn set_needs<C>(
+    &self,
+    ctx: &mut C,
+    id: u64,
+    needs: Needs,
+  ) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    let mut inner = self.inner.borrow_mut();
+    let mut required: Needs = Vec::new();
+    let others = inner.needs.iter().filter(|&&(chain, _)| chain != id).flat_map(|(_, needs)| needs);
+
+    for &(size, count) in others.chain(&needs) {
+      match required.iter_mut().find(|(s, _)| *s == size) {
+        Some((_, max)) => *max = (*max).max(count),
+        None => required.push((size, count)),
+      }
+    }
+
+    if let Err(e) = inner.allocate(ctx, required) {
+      inner.release_unneeded();
+      return Err(e);
+    }
+
+    inner.needs.retain(|&(chain, _)| chain != id);
+    inner.needs.push((id, needs));
+    inner.release_unneeded();
+
+    Ok(())
+  }
+
+  // Forget about chain id, releasing the framebuffers no other chain needs.
+  fn unregister(&self, id: u64) {
+    let mut inner = self.inner.borrow_mut();
+    inner.needs.retain(|&(chain, _)| chain != id);
+    inner.release_unneeded();
+  }
+}
+
+impl<P> PoolInner<P> where P: ColorPixel + RenderablePixel {
+  // Allocate the framebuffers missing to have the required number of each size.
+  fn allocate<C>(&mut self, ctx: &mut C, required: Needs) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    for (size, count) in required {
+      let index = match self.targets.iter().position(|(s, _)| *s == size) {
+        Some(index) => index,
+        None => {
+          self.targets.push((size, Vec::new()));
+          self.targets.len() - 1
+        }
+      };
+
+      let targets = &mut self.targets[index].1;
+
+      while targets.len() < count {
+        targets.push(Framebuffer::new(ctx, size, 0)?);
+      }
+    }
+
+    Ok(())
+  }
+
+  fn release_unneeded(&mut self) {
+    let needs = &self.needs;
+    let required = |size: [u32; 2]| {
+      needs
+        .iter()
+        .flat_map(|(_, needs)| needs)
+        .filter(|(s, _)| *s == size)
+        .map(|&(_, count)| count)
+        .max()
+        .unwrap_or(0)
+    };
+
+    for (size, targets) in &mut self.targets {
+      targets.truncate(required(*size));
+    }
+
+    self.targets.retain(|(_, targets)| !targets.is_empty());
+  }
+
+  fn target(&self, location: ([u32; 2], usize)) -> &Target<P> {
+    let (size, index) = location;
+    let targets = &self.targets.iter().find(|(s, _)| *s == size).unwrap().1;
+    &targets[index]
+  }
+}
+
+impl<P> Clone for TargetPool<P> where P: ColorPixel + RenderablePixel {
+  fn clone(&self) -> Self {
+    TargetPool {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<P> Default for TargetPool<P> where P: ColorPixel + RenderablePixel {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A chain of post-processing passes.
+///
+/// `P` is the pixel format of the source texture and of all the intermediate framebuffers.
+pub struct EffectChain<P> where P: ColorPixel + RenderablePixel {
+  size: [u32; 2],
+  passes: Vec<EffectPass>,
+  pool: TargetPool<P>,
+  id: u64,
+  target_count: usize,
+  // size and index among the pool framebuffers of that size of the output of each pass but the
+  // last one
+  outputs: Vec<([u32; 2], usize)>,
+}
+
+impl<P> EffectChain<P> where P: ColorPixel + RenderablePixel {
+  /// Create a new effect chain for the given size – typically the size of the framebuffer the
+  /// source texture is rendered to.
+  pub fn new<C>(ctx: &mut C, size: [u32; 2], passes: Vec<EffectPass>) -> Result<Self, EffectError>
+  where C: GraphicsContext {
+    Self::with_pool(ctx, &TargetPool::new(), size, passes)
+  }
+
+  /// Create a new effect chain for the given size, taking its intermediate framebuffers from
+  /// `pool`.
+  ///
+  /// The chain shares the framebuffers of the pool with the other chains using it, so it must not
+  /// be rendered while another one is – e.g. from the render function of another chain.
+  pub fn with_pool<C>(
+    ctx: &mut C,
+    pool: &TargetPool<P>,
+    size: [u32; 2],
+    passes: Vec<EffectPass>,
+  ) -> Result<Self, EffectError>
+  where C: GraphicsContext {
+    validate_passes(&passes)?;
+
+    let mut chain = EffectChain {
+      size,
+      passes,
+      pool: pool.clone(),
+      id: pool.register(),
+      target_count: 0,
+      outputs: Vec::new(),
+    };
+
+    chain.allocate(ctx, size)?;
+
+    Ok(chain)
+  }
+
+  /// Size of the chain.
+  pub fn size(&self) -> [u32; 2] {
+    self.size
+  }
+
+  /// Passes of the chain.
+  pub fn passes(&self) -> &[EffectPass] {
+    &self.passes
+  }
+
+  /// Number of intermediate framebuffers used by the chain.
+  pub fn target_count(&self) -> usize {
+    self.target_count
+  }
+
+  /// Pool the intermediate framebuffers are taken from.
+  pub fn pool(&self) -> &TargetPool<P> {
+    &self.pool
+  }
+
+  /// Change the size of the chain, re-allocating the intermediate framebuffers.
+  ///
+  /// If the framebuffers can’t be allocated, the chain keeps its previous size and framebuffers.
+  pub fn resize<C>(&mut self, ctx: &mut C, size: [u32; 2]) -> Result<(), EffectError>
+  where C: GraphicsContext {
+    if size != self.size {
+      self.allocate(ctx, size)?;
+    }
+
+    Ok(())
+  }
+
+  /// Render all the passes.
+  ///
+  /// `f` is called once per pass with the index of the pass, its input textures – in the order
+  /// they were declared – and the pipeline and shading gate to render with. The last pass renders
+  /// to `output`; all passes use `pipeline_state`, the viewport being set to their output.
+  pub fn render<C, L, D, CS, DS, F>(
+    &self,
+    builder: &mut Builder<C>,
+    source: &Texture<Flat, Dim2, P>,
+    output: &Framebuffer<L, D, CS, DS>,
+    pipeline_state: &PipelineState,
+    mut f: F,
+  )
+  where C: ?Sized + GraphicsContext,
+        L: Layerable,
+        D: Dimensionable,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D>,
+        F: for<'b> FnMut(usize, &[&Texture<Flat, Dim2, P>], Pipeline<'b>, ShadingGate<'b, C>) {
+    let pool = self.pool.inner.borrow();
+    let mut inputs = Vec::new();
+
+    for (i, pass) in self.passes.iter().enumerate() {
+      inputs.clear();
+      inputs.extend(pass.inputs.iter().map(|input| match resolve_input(i, *input) {
+        None => source,
+        Some(j) => pool.target(self.outputs[j]).color_slot(),
+      }));
+
+      if i + 1 == self.passes.len() {
+        builder.pipeline(output, pipeline_state, |pipeline, shd_gate| {
+          f(i, &inputs, pipeline, shd_gate)
+        });
+      } else {
+        let target = pool.target(self.outputs[i]);
+
+        builder.pipeline(target, pipeline_state, |pipeline, shd_gate| {
+          f(i, &inputs, pipeline, shd_gate)
+        });
+      }
+    }
+  }
+
+  // Allocate the intermediate framebuffers for the given size, reusing them across passes when
+  // possible. The chain is left untouched on error.
+  fn allocate<C>(&mut self, ctx: &mut C, size: [u32; 2]) -> Result<(), EffectError>
+  where C: GraphicsContext {
+    let intermediate = self.passes.len().saturating_sub(1);
+
+    // index of the last pass reading the output of each pass
+    let mut last_reads = vec![0; intermediate];
+
+    for (i, pass) in self.passes.iter().enumerate() {
+      for input in &pass.inputs {
+        if let Some(j) = resolve_input(i, *input) {
+          last_reads[j] = i;
+        }
+      }
+    }
+
+    // size and index among the targets of that size of each target
+    let mut targets: Vec<([u32; 2], usize)> = Vec::new();
+    let mut busy_until: Vec<usize> = Vec::new();
+    let mut outputs = Vec::new();
+
+    for (i, pass) in self.passes[.. intermediate].iter().enumerate() {
+      let pass_size = scaled_size(size, pass.scale);
+
+      // a target is free if its content was read for the last time by a previous pass
+      let free = (0 .. targets.len()).find(|&t| targets[t].0 == pass_size && busy_until[t] < i);
+
+      let target = match free {
+        Some(target) => target,
+        None => {
+          let same_size = targets.iter().filter(|(s, _)| *s == pass_size).count();
+          targets.push((pass_size, same_size));
+          busy_until.push(0);
+          targets.len() - 1
+        }
+      };
+
+      busy_until[target] = last_reads[i].max(i);
+      outputs.push(targets[target]);
+    }
+
+    let mut needs: Needs = Vec::new();
+
+    for &(size, index) in &targets {
+      match needs.iter_mut().find(|(s, _)| *s == size) {
+        Some((_, count)) => *count = (*count).max(index + 1),
+        None => needs.push((size, index + 1)),
+      }
+    }
+
+    self.pool.set_needs(ctx, self.id, needs)?;
+    self.size = size;
+    self.target_count = targets.len();
+    self.outputs = outputs;
+
+    Ok(())
+  }
+}
+
+impl<P> Drop for EffectChain<P> where P: ColorPixel + RenderablePixel {
+  fn drop(&mut self) {
+    self.pool.unregister(self.id);
+  }
+}
+
+/// Errors that can happen with effect chains.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EffectError {
+  /// A pass reads the output of itself or of a pass coming after it.
+  ///
+  /// The first [`usize`] is the index of the pass and the second one is the index of the pass it
+  /// tried to read.
+  InvalidInput(usize, usize),
+  /// An intermediate framebuffer couldn’t be created.
+  FramebufferError(FramebufferError),
+}
+
+impl fmt::Display for EffectError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      EffectError::InvalidInput(pass, input) => {
+        write!(f, "effect pass {} cannot read the output of pass {}", pass, input)
+      }
+
+      EffectError::FramebufferError(ref e) => write!(f, "effect framebuffer error: {}", e),
+    }
+  }
+}
+
+impl From<FramebufferError> for EffectError {
+  fn from(e: FramebufferError) -> Self {
+    EffectError::FramebufferError(e)
+  }
+}
+
+fn validate_passes(passes: &[EffectPass]) -> Result<(), EffectError> {
+  for (i, pass) in passes.iter().enumerate() {
+    for input in &pass.inputs {
+      if let EffectInput::Pass(j) = *input {
+        if j >= i {
+          return Err(EffectError::InvalidInput(i, j));
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+// Index of the pass an input of pass `pass` refers to; `None` is the source texture.
+fn resolve_input(pass: usize, input: EffectInput) -> Option<usize> {
+  match input {
+    EffectInput::Source => None,
+    EffectInput::Previous => pass.checked_sub(1),
+    EffectInput::Pass(j) => Some(j),
+  }
+}
+
+fn scaled_size(size: <Dim2 as Dimensionable>::Size, scale: f32) -> [u32; 2] {
+  let scale = |x: u32| ((x as f32 * scale) as u32).max(1);
+  [scale(size[0]), scale(size[1])]
+}
@@ -0,0 +1,287 @@
+//! Deterministic resource IDs and frame event logs.
+//!
+//! This module is only available with the `event-log` feature. It’s a debugging aid: once
+//! enabled with [`enable`], luminance records every GPU resource creation and destruction as well
+//! as every pipeline, shading and render command in an event log you can dump at the end of each
+//! frame with [`end_frame`]. Comparing the logs of two runs – or attaching one to a bug report –
+//! tells exactly what luminance asked the GPU to do.
+//!
+//! *OpenGL* object names are allocated by the driver and can change from a run to another, so logs
+//! never show them. Instead, every resource is given a [`ResourceId`], made of the kind of the
+//! resource and of its creation rank among the resources of the same kind since [`enable`] was
+//! called. luminance creates the *OpenGL* objects backing a resource in a fixed order, so as long as
+//! your application creates its resources in the same order, IDs are the same from a run to
+//! another – which makes logs diffable.
+//!
+//! Because *OpenGL* contexts are bound to a thread, event logs are per thread.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+
+use crate::metagl::*;
+
+/// Kind of GPU resource.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ResourceKind {
+  /// A [`Buffer`](crate::buffer::Buffer).
+  Buffer,
+  /// A [`Texture`](crate::texture::Texture), including framebuffer color and depth slots.
+  Texture,
+  /// A [`Framebuffer`](crate::framebuffer::Framebuffer).
+  Framebuffer,
+  /// The depth renderbuffer of a [`Framebuffer`](crate::framebuffer::Framebuffer) without depth
+  /// slot.
+  Renderbuffer,
+  /// A [`Tess`](crate::tess::Tess).
+  Tess,
+  /// A shader [`Stage`](crate::shader::stage::Stage).
+  Stage,
+  /// A shader [`Program`](crate::shader::program::Program).
+  Program,
+}
+
+impl fmt::Display for ResourceKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      ResourceKind::Buffer => f.write_str("buffer"),
+      ResourceKind::Texture => f.write_str("texture"),
+      ResourceKind::Framebuffer => f.write_str("framebuffer"),
+      ResourceKind::Renderbuffer => f.write_str("renderbuffer"),
+      ResourceKind::Tess => f.write_str("tess"),
+      ResourceKind::Stage => f.write_str("stage"),
+      ResourceKind::Program => f.write_str("program"),
+    }
+  }
+}
+
+/// Deterministic ID of a GPU resource.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ResourceId {
+  kind: ResourceKind,
+  index: u64,
+}
+
+impl ResourceId {
+  /// Kind of the resource.
+  pub fn kind(&self) -> ResourceKind {
+    self.kind
+  }
+
+  /// Creation rank of the resource among the resources of the same kind.
+  pub fn index(&self) -> u64 {
+    self.index
+  }
+}
+
+impl fmt::Display for ResourceId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    write!(f, "{}#{}", self.kind, self.index)
+  }
+}
+
+/// An event of the event log.
+///
+/// Resources created before the event log was enabled have no ID and are referred to with `None`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+  /// A resource was created.
+  Created(ResourceId),
+  /// A resource was destroyed.
+  Destroyed(Option<ResourceId>),
+  /// A pipeline started rendering to a framebuffer.
+  Pipeline(Option<ResourceId>),
+  /// A pipeline started rendering to the back buffer.
+  BackBufferPipeline,
+  /// A shader program was used.
+  Shade(Option<ResourceId>),
+  /// A render state was applied.
+  Render,
+  /// A tessellation was drawn.
+  Draw {
+    /// The drawn tessellation.
+    tess: Option<ResourceId>,
+    /// Index of the first vertex (or index, for indexed tessellations).
+    start_index: usize,
+    /// Number of vertices (or indices, for indexed tessellations).
+    vert_nb: usize,
+    /// Number of instances.
+    inst_nb: usize,
+  },
+}
+
+impl fmt::Display for Event {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      Event::Created(id) => write!(f, "create {}", id),
+      Event::Destroyed(id) => write!(f, "destroy {}", OptionalId(id)),
+      Event::Pipeline(id) => write!(f, "pipeline {}", OptionalId(id)),
+      Event::BackBufferPipeline => f.write_str("pipeline back buffer"),
+      Event::Shade(id) => write!(f, "shade {}", OptionalId(id)),
+      Event::Render => f.write_str("render"),
+      Event::Draw { tess, start_index, vert_nb, inst_nb } => write!(
+        f,
+        "draw {} start={} vertices={} instances={}",
+        OptionalId(tess),
+        start_index,
+        vert_nb,
+        inst_nb
+      ),
+    }
+  }
+}
+
+// Display an optional ID, showing unknown resources as `?`.
+struct OptionalId(Option<ResourceId>);
+
+impl fmt::Display for OptionalId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match self.0 {
+      Some(id) => id.fmt(f),
+      None => f.write_str("?"),
+    }
+  }
+}
+
+/// Events recorded during a frame.
+///
+/// The [`Display`](fmt::Display) implementation dumps the log with one event per line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrameLog {
+  frame: u64,
+  events: Vec<Event>,
+}
+
+impl FrameLog {
+  /// Index of the frame, starting from `0` when the event log was enabled.
+  pub fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  /// Events of the frame, in recording order.
+  pub fn events(&self) -> &[Event] {
+    &self.events
+  }
+}
+
+impl fmt::Display for FrameLog {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    writeln!(f, "frame {}", self.frame)?;
+
+    for event in &self.events {
+      writeln!(f, "  {}", event)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Enable the event log for the current thread.
+///
+/// Resource IDs start from `0` again and the current frame becomes the frame `0`. Enable the event
+/// log before creating any resource to get IDs for all of them.
+pub fn enable() {
+  LOG.with(|log| *log.borrow_mut() = Some(Log::default()));
+}
+
+/// Disable the event log for the current thread, dropping the events of the current frame.
+pub fn disable() {
+  LOG.with(|log| *log.borrow_mut() = None);
+}
+
+/// Whether the event log is enabled for the current thread.
+pub fn is_enabled() -> bool {
+  LOG.with(|log| log.borrow().is_some())
+}
+
+/// End the current frame, returning its events.
+///
+/// Call this function once per frame – typically right after swapping buffers. Returns `None` if
+/// the event log is disabled.
+pub fn end_frame() -> Option<FrameLog> {
+  LOG.with(|log| {
+    log.borrow_mut().as_mut().map(|log| {
+      let frame = log.frame;
+      log.frame += 1;
+
+      FrameLog { frame, events: mem::take(&mut log.events) }
+    })
+  })
+}
+
+thread_local! {
+  static LOG: RefCell<Option<Log>> = RefCell::new(None);
+}
+
+#[derive(Default)]
+struct Log {
+  frame: u64,
+  events: Vec<Event>,
+  // next index of each resource kind
+  next_indices: HashMap<ResourceKind, u64>,
+  // IDs of the alive resources, by OpenGL name
+  ids: HashMap<(ResourceKind, GLuint), ResourceId>,
+}
+
+impl Log {
+  fn id(&self, kind: ResourceKind, handle: GLuint) -> Option<ResourceId> {
+    self.ids.get(&(kind, handle)).cloned()
+  }
+}
+
+fn with_log<F>(f: F) where F: FnOnce(&mut Log) {
+  LOG.with(|log| {
+    if let Some(ref mut log) = *log.borrow_mut() {
+      f(log);
+    }
+  })
+}
+
+pub(crate) fn created(kind: ResourceKind, handle: GLuint) {
+  with_log(|log| {
+    let next_index = log.next_indices.entry(kind).or_insert(0);
+    let id = ResourceId { kind, index: *next_index };
+    *next_index += 1;
+
+    log.ids.insert((kind, handle), id);
+    log.events.push(Event::Created(id));
+  })
+}
+
+pub(crate) fn destroyed(kind: ResourceKind, handle: GLuint) {
+  with_log(|log| {
+    let id = log.ids.remove(&(kind, handle));
+    log.events.push(Event::Destroyed(id));
+  })
+}
+
+pub(crate) fn pipeline(framebuffer: GLuint) {
+  with_log(|log| {
+    let event = if framebuffer == 0 {
+      Event::BackBufferPipeline
+    } else {
+      Event::Pipeline(log.id(ResourceKind::Framebuffer, framebuffer))
+    };
+
+    log.events.push(event);
+  })
+}
+
+pub(crate) fn shade(program: GLuint) {
+  with_log(|log| {
+    let id = log.id(ResourceKind::Program, program);
+    log.events.push(Event::Shade(id));
+  })
+}
+
+pub(crate) fn render() {
+  with_log(|log| log.events.push(Event::Render))
+}
+
+pub(crate) fn draw(vao: GLuint, start_index: usize, vert_nb: usize, inst_nb: usize) {
+  with_log(|log| {
+    let tess = log.id(ResourceKind::Tess, vao);
+    log.events.push(Event::Draw { tess, start_index, vert_nb, inst_nb });
+  })
+}
@@ -1,5 +1,13 @@
 //! Face culling is the operation of removing triangles if they’re facing the screen in a specific
 //! direction with a specific mode.
+//!
+//! A [`FaceCulling`] is made of a winding order – telling which side of triangles is the front one
+//! – and a mode – telling which sides are culled. Both can be changed independently, which comes in
+//! handy to alter the face culling of a render state without rebuilding it – see
+//! [`RenderState::override_face_culling`]. For instance, geometry mirrored by a negative scale has
+//! its winding order reversed, and must be rendered with [`FaceCulling::flipped`].
+//!
+//! [`RenderState::override_face_culling`]: crate::render_state::RenderState::override_face_culling
 
 /// Face culling setup.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -15,6 +23,31 @@ impl FaceCulling {
   pub fn new(order: FaceCullingOrder, mode: FaceCullingMode) -> Self {
     FaceCulling { order, mode }
   }
+
+  /// Face culling order.
+  pub fn order(self) -> FaceCullingOrder {
+    self.order
+  }
+
+  /// Override the face culling order, keeping the mode.
+  pub fn set_order(self, order: FaceCullingOrder) -> Self {
+    FaceCulling { order, ..self }
+  }
+
+  /// Face culling mode.
+  pub fn mode(self) -> FaceCullingMode {
+    self.mode
+  }
+
+  /// Override the face culling mode, keeping the order.
+  pub fn set_mode(self, mode: FaceCullingMode) -> Self {
+    FaceCulling { mode, ..self }
+  }
+
+  /// Reverse the face culling order, keeping the mode.
+  pub fn flipped(self) -> Self {
+    self.set_order(self.order.flipped())
+  }
 }
 
 impl Default for FaceCulling {
@@ -45,6 +78,16 @@ pub enum FaceCullingOrder {
   CCW,
 }
 
+impl FaceCullingOrder {
+  /// The reverse order.
+  pub fn flipped(self) -> Self {
+    match self {
+      FaceCullingOrder::CW => FaceCullingOrder::CCW,
+      FaceCullingOrder::CCW => FaceCullingOrder::CW,
+    }
+  }
+}
+
 /// Side to show and side to cull.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum FaceCullingMode {
@@ -52,6 +95,8 @@ pub enum FaceCullingMode {
   Front,
   /// Cull the back side only.
   Back,
-  /// Always cull any triangle.
+  /// Always cull any triangle (`GL_FRONT_AND_BACK`).
+  ///
+  /// Points and lines are still rendered.
   Both,
 }
@@ -27,6 +27,38 @@
 //! their respective texture representation so that you can handle the corresponding texels.
 //!
 //! Color buffers are abstracted by `ColorSlot` and the depth buffer by `DepthSlot`.
+//!
+//! # Draw buffers
+//!
+//! With several color slots, a pipeline can select the color slots it renders to – and clears –
+//! with [`PipelineState::set_draw_buffers`], and a render state the ones it blends into with
+//! [`RenderState::set_blended_draw_buffers`]. Both use a [`DrawBuffers`] set. A deferred renderer
+//! can, for instance, render decals into the albedo and normal slots of its G-buffer only.
+//!
+//! [`PipelineState::set_draw_buffers`]: crate::pipeline::PipelineState::set_draw_buffers
+//! [`RenderState::set_blended_draw_buffers`]: crate::render_state::RenderState::set_blended_draw_buffers
+//!
+//! # Blitting
+//!
+//! [`Framebuffer::blit`] copies a region of a framebuffer to another – possibly the back buffer –
+//! scaling it if needed. The source framebuffer is bound for reading and the destination one for
+//! drawing, so that blits don’t disturb a framebuffer in use by a pipeline.
+//!
+//! # Layered rendering
+//!
+//! The textures of framebuffers with several layers – `Dim2Array`, `Cubemap` or `Dim3` – are
+//! attached as a whole. A geometry shader then selects the layer – or face – each primitive is
+//! rendered to by writing `gl_Layer`, rendering all the cascades of a cascaded shadow map or all
+//! the faces of a cube shadow map in a single pass:
+//!
+//! ```ignore
+//! let size = ([1024, 1024], 4);
+//! let cascades = Framebuffer::<Flat, Dim2Array, (), Depth32F>::new(&mut surface, size, 0)?;
+//! ```
+//!
+//! All the attachments of a layered framebuffer must be layered, so such framebuffers don’t get
+//! the depth renderbuffer other framebuffers without a depth slot have: give them a depth slot to
+//! depth test. OpenGL ES 3.0 has no layered rendering; only the first layer is attached there.
 
 #[cfg(feature = "std")]
 use std::cell::RefCell;
@@ -34,21 +66,32 @@ use std::fmt;
 use std::rc::Rc;
 #[cfg(feature = "std")]
 use std::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::ops::{BitOr, Deref};
+#[cfg(feature = "std")]
+use std::os::raw::c_void;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use core::fmt;
 #[cfg(not(feature = "std"))]
+use core::ffi::c_void;
+#[cfg(not(feature = "std"))]
 use core::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use core::ops::{BitOr, Deref};
 
+use crate::capabilities::{current_profile, Profile};
 use crate::context::GraphicsContext;
+use crate::debug;
+use crate::linear::M44;
 use crate::metagl::*;
-use crate::pixel::{ColorPixel, DepthPixel, PixelFormat, RenderablePixel};
+use crate::pixel::{opengl_pixel_format, ColorPixel, DepthPixel, PixelFormat, RenderablePixel};
 use crate::state::{Bind, GraphicsState};
 use crate::texture::{
-  create_texture, opengl_target, Dim2, Dimensionable, Flat, Layerable, RawTexture, Texture,
-  TextureError,
+  check_target, create_texture, opengl_target, Dim2, Dimensionable, Flat, Layerable, MagFilter,
+  RawTexture, Texture, TextureError,
 };
 
 /// Framebuffer error.
@@ -62,6 +105,15 @@ pub enum FramebufferError {
   ///
   /// This happens when finalizing the construction of the framebuffer.
   Incomplete(IncompleteReason),
+  /// A region doesn’t fit in the framebuffer.
+  OutOfBounds(Rect),
+  /// Depth and stencil buffers can only be blitted with [`MagFilter::Nearest`].
+  InvalidBlitFilter,
+  /// The framebuffer has no color slot at the given index with the requested pixel format.
+  ColorSlotMismatch(usize, PixelFormat),
+  /// Layered framebuffers cannot have a [`DepthRenderbuffer`] depth slot, as renderbuffers have
+  /// a single layer.
+  LayeredRenderbuffer,
 }
 
 impl fmt::Display for FramebufferError {
@@ -70,6 +122,24 @@ impl fmt::Display for FramebufferError {
       FramebufferError::TextureError(ref e) => write!(f, "framebuffer texture error: {}", e),
 
       FramebufferError::Incomplete(ref e) => write!(f, "incomplete framebuffer: {}", e),
+
+      FramebufferError::OutOfBounds(ref r) => write!(
+        f,
+        "region {}×{} at ({}, {}) is out of the framebuffer bounds",
+        r.width, r.height, r.x, r.y
+      ),
+
+      FramebufferError::InvalidBlitFilter => {
+        f.write_str("depth and stencil buffers can only be blitted with nearest filtering")
+      }
+
+      FramebufferError::ColorSlotMismatch(index, ref pf) => {
+        write!(f, "no color slot {} with pixel format {:?}", index, pf)
+      }
+
+      FramebufferError::LayeredRenderbuffer => {
+        f.write_str("layered framebuffers cannot have a depth renderbuffer")
+      }
     }
   }
 }
@@ -124,6 +194,10 @@ impl fmt::Display for IncompleteReason {
 /// A framebuffer can have zero or several color slots and it can have zero or one depth slot. If
 /// you use several color slots, you’ll be performing what’s called *MRT* (*M* ultiple *R* ender
 /// *T* argets), enabling to render to several textures at once.
+///
+/// If you don’t use a depth slot, the framebuffer still gets a depth and stencil buffer, which
+/// you cannot read from but that enables the depth and stencil tests. Use a [`DepthRenderbuffer`]
+/// depth slot to choose the format of that buffer instead.
 pub struct Framebuffer<L, D, CS, DS>
 where L: Layerable,
       D: Dimensionable,
@@ -162,6 +236,44 @@ impl Framebuffer<Flat, Dim2, (), ()> {
   }
 }
 
+impl Framebuffer<Flat, Dim2, (), ()> {
+  // Create a framebuffer rendering to an existing 2D texture.
+  //
+  // The texture is attached to the first color attachment but not owned: it must outlive the
+  // framebuffer. No depth buffer is attached.
+  pub(crate) unsafe fn from_raw_texture<C>(
+    ctx: &mut C,
+    texture: &RawTexture,
+    size: <Dim2 as Dimensionable>::Size,
+  ) -> Result<Self, FramebufferError>
+  where C: GraphicsContext {
+    let mut handle: GLuint = 0;
+
+    gl::GenFramebuffers(1, &mut handle);
+    log_event!(created(Framebuffer, handle));
+
+    ctx.state().borrow_mut().bind_draw_framebuffer(handle);
+    attach_texture(gl::COLOR_ATTACHMENT0, texture.target(), texture.handle());
+
+    let framebuffer = Framebuffer {
+      handle,
+      renderbuffer: None,
+      w: size[0],
+      h: size[1],
+      color_slot: (),
+      depth_slot: (),
+      state: ctx.state().clone(),
+      _l: PhantomData,
+      _d: PhantomData,
+    };
+
+    let status = get_status();
+    ctx.state().borrow_mut().bind_draw_framebuffer(0);
+
+    status.map(|_| framebuffer).map_err(FramebufferError::Incomplete)
+  }
+}
+
 impl<L, D, CS, DS> Drop for Framebuffer<L, D, CS, DS>
 where L: Layerable,
       D: Dimensionable,
@@ -198,8 +310,15 @@ where L: Layerable,
     let mut depth_texture: Option<GLuint> = None;
     let mut depth_renderbuffer: Option<GLuint> = None;
 
+    check_target(L::layering(), D::dim()).map_err(FramebufferError::TextureError)?;
+
+    if DS::depth_renderbuffer_format().is_some() && is_layered_attachment(target) {
+      return Err(FramebufferError::LayeredRenderbuffer);
+    }
+
     unsafe {
       gl::GenFramebuffers(1, &mut handle);
+      log_event!(created(Framebuffer, handle));
 
       ctx.state().borrow_mut().bind_draw_framebuffer(handle);
 
@@ -207,15 +326,21 @@ where L: Layerable,
       // into other containers afterwards (in ColorSlot::reify_textures)
       gl::GenTextures((textures.len()) as GLint, textures.as_mut_ptr());
 
+      #[cfg(feature = "event-log")]
+      for &texture in &textures {
+        crate::event_log::created(crate::event_log::ResourceKind::Texture, texture);
+      }
+
       // color textures
       if color_formats.is_empty() {
-        gl::DrawBuffer(gl::NONE);
+        let none = gl::NONE;
+        gl::DrawBuffers(1, &none);
       } else {
         for (i, (format, texture)) in color_formats.iter().zip(&textures).enumerate() {
           ctx.state().borrow_mut().bind_texture(target, *texture);
           create_texture::<L, D>(target, size, mipmaps, *format, Default::default())
             .map_err(FramebufferError::TextureError)?;
-          gl::FramebufferTexture(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0 + i as GLenum, *texture, 0);
+          attach_texture(gl::COLOR_ATTACHMENT0 + i as GLenum, target, *texture);
         }
 
         // specify the list of color buffers to draw to
@@ -233,17 +358,24 @@ where L: Layerable,
         ctx.state().borrow_mut().bind_texture(target, texture);
         create_texture::<L, D>(target, size, mipmaps, format, Default::default())
           .map_err(FramebufferError::TextureError)?;
-        gl::FramebufferTexture(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, texture, 0);
+        attach_texture(gl::DEPTH_ATTACHMENT, target, texture);
 
         depth_texture = Some(texture);
-      } else {
+      } else if !is_layered_attachment(target) {
+        // renderbuffers without an explicit format also hold the stencil buffer
+        let renderbuffer_format = DS::depth_renderbuffer_format();
+        let (iformat, attachment) = match renderbuffer_format.and_then(opengl_pixel_format) {
+          Some((_, iformat, _)) => (iformat, gl::DEPTH_ATTACHMENT),
+          None => (gl::DEPTH32F_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT),
+        };
         let mut renderbuffer: GLuint = 0;
 
         gl::GenRenderbuffers(1, &mut renderbuffer);
+        log_event!(created(Renderbuffer, renderbuffer));
         gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
         gl::RenderbufferStorage(
           gl::RENDERBUFFER,
-          gl::DEPTH_COMPONENT32F,
+          iformat,
           D::width(size) as GLsizei,
           D::height(size) as GLsizei,
         );
@@ -251,7 +383,7 @@ where L: Layerable,
 
         gl::FramebufferRenderbuffer(
           gl::FRAMEBUFFER,
-          gl::DEPTH_ATTACHMENT,
+          attachment,
           gl::RENDERBUFFER,
           renderbuffer,
         );
@@ -297,11 +429,16 @@ where L: Layerable,
       if let Some(renderbuffer) = self.renderbuffer {
         gl::DeleteRenderbuffers(1, &renderbuffer);
         gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        log_event!(destroyed(Renderbuffer, renderbuffer));
       }
 
       if self.handle != 0 {
         gl::DeleteFramebuffers(1, &self.handle);
-        self.state.borrow_mut().bind_vertex_array(0, Bind::Cached);
+        log_event!(destroyed(Framebuffer, self.handle));
+
+        let mut state = self.state.borrow_mut();
+        state.forget_framebuffer(self.handle);
+        state.bind_vertex_array(0, Bind::Cached);
       }
     }
   }
@@ -312,6 +449,13 @@ where L: Layerable,
     self.handle
   }
 
+  /// Label the framebuffer, so that graphics debuggers show it by that name.
+  ///
+  /// The back buffer cannot be labelled.
+  pub fn set_label(&self, label: &str) {
+    debug::label(gl::FRAMEBUFFER, self.handle, label);
+  }
+
   /// Width of the framebuffer.
   #[inline]
   pub fn width(&self) -> u32 {
@@ -335,6 +479,342 @@ where L: Layerable,
   pub fn depth_slot(&self) -> &DS::DepthTexture {
     &self.depth_slot
   }
+
+  /// Rectangle covering the whole framebuffer.
+  #[inline]
+  pub fn rect(&self) -> Rect {
+    Rect::new(0, 0, self.w, self.h)
+  }
+
+  /// Read back the depth values of a region of the framebuffer.
+  ///
+  /// Depth values are in the `[0; 1]` range and are returned row by row, starting at the lower-left
+  /// corner of `rect`. Use [`linearize_depth`] to convert them back to view-space distances.
+  ///
+  /// This works whether the framebuffer has a depth slot or not, as long as it has a depth buffer.
+  /// Reading back forces the CPU to wait for the GPU to be done rendering to the framebuffer, so
+  /// avoid doing it every frame.
+  pub fn read_depth(&self, rect: Rect) -> Result<Vec<f32>, FramebufferError> {
+    if !self.contains(rect) {
+      return Err(FramebufferError::OutOfBounds(rect));
+    }
+
+    let mut depths = vec![0.; rect.width as usize * rect.height as usize];
+
+    unsafe {
+      self.state.borrow_mut().bind_read_framebuffer(self.handle);
+
+      gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+      gl::ReadPixels(
+        rect.x as GLint,
+        rect.y as GLint,
+        rect.width as GLsizei,
+        rect.height as GLsizei,
+        gl::DEPTH_COMPONENT,
+        gl::FLOAT,
+        depths.as_mut_ptr() as *mut c_void,
+      );
+    }
+
+    Ok(depths)
+  }
+
+  /// Read back the texels of a region of the color slot at index `index`.
+  ///
+  /// `P` must be the pixel format of the color slot; the texels are returned row by row, starting at
+  /// the lower-left corner of `rect`. This is what screenshots and GPU picking – reading back an
+  /// object ID slot under the cursor – are made of.
+  ///
+  /// The back buffer has no color slot but can be read back too, with index `0` and a normalized
+  /// pixel format – typically [`NormRGBA8UI`](crate::pixel::NormRGBA8UI).
+  ///
+  /// Reading back forces the CPU to wait for the GPU to be done rendering to the framebuffer; see
+  /// the [`capture`](crate::capture) module to read back asynchronously.
+  pub fn read_color_slot<P>(&self, index: usize, rect: Rect) -> Result<Vec<P::Encoding>, FramebufferError>
+  where P: ColorPixel,
+        P::Encoding: Copy + Default {
+    let pf = P::pixel_format();
+    let is_slot = if self.handle == 0 {
+      index == 0
+    } else {
+      CS::color_formats().get(index) == Some(&pf)
+    };
+
+    let (format, ty) = match opengl_pixel_format(pf) {
+      Some((format, _, ty)) if is_slot => (format, ty),
+      _ => return Err(FramebufferError::ColorSlotMismatch(index, pf)),
+    };
+
+    if !self.contains(rect) {
+      return Err(FramebufferError::OutOfBounds(rect));
+    }
+
+    let mut texels = vec![P::Encoding::default(); rect.width as usize * rect.height as usize];
+
+    unsafe {
+      self.state.borrow_mut().bind_read_framebuffer(self.handle);
+
+      if self.handle == 0 {
+        gl::ReadBuffer(gl::BACK);
+      } else {
+        gl::ReadBuffer(gl::COLOR_ATTACHMENT0 + index as GLenum);
+      }
+
+      gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+      gl::ReadPixels(
+        rect.x as GLint,
+        rect.y as GLint,
+        rect.width as GLsizei,
+        rect.height as GLsizei,
+        format,
+        ty,
+        texels.as_mut_ptr() as *mut c_void,
+      );
+      gl_check!("ReadPixels", self.handle);
+    }
+
+    Ok(texels)
+  }
+
+  /// Copy the `src` region of this framebuffer to the `dst` region of another framebuffer.
+  ///
+  /// `buffers` selects which buffers are copied. If the regions have different sizes, the copied
+  /// region is scaled with `filter`, which must be [`MagFilter::Nearest`] if depth or stencil
+  /// buffers are copied. The framebuffers must have compatible formats: integral color buffers
+  /// can only be copied to integral color buffers, for instance.
+  ///
+  /// Since the framebuffers are respectively bound for reading and drawing, this framebuffer can
+  /// be the one holding the previous frame while the next one is drawn.
+  pub fn blit<L2, D2, CS2, DS2>(
+    &self,
+    dst_framebuffer: &Framebuffer<L2, D2, CS2, DS2>,
+    src: Rect,
+    dst: Rect,
+    buffers: BlitBuffers,
+    filter: MagFilter,
+  ) -> Result<(), FramebufferError>
+  where L2: Layerable,
+        D2: Dimensionable,
+        D2::Size: Copy,
+        CS2: ColorSlot<L2, D2>,
+        DS2: DepthSlot<L2, D2> {
+    if !self.contains(src) {
+      return Err(FramebufferError::OutOfBounds(src));
+    }
+
+    if !dst_framebuffer.contains(dst) {
+      return Err(FramebufferError::OutOfBounds(dst));
+    }
+
+    let filter = match filter {
+      MagFilter::Nearest => gl::NEAREST,
+      MagFilter::Linear if buffers.0 & !gl::COLOR_BUFFER_BIT != 0 => {
+        return Err(FramebufferError::InvalidBlitFilter);
+      }
+      MagFilter::Linear => gl::LINEAR,
+    };
+
+    unsafe {
+      let mut state = self.state.borrow_mut();
+
+      state.bind_read_framebuffer(self.handle);
+      state.bind_draw_framebuffer(dst_framebuffer.handle);
+
+      gl::BlitFramebuffer(
+        src.x as GLint,
+        src.y as GLint,
+        (src.x + src.width) as GLint,
+        (src.y + src.height) as GLint,
+        dst.x as GLint,
+        dst.y as GLint,
+        (dst.x + dst.width) as GLint,
+        (dst.y + dst.height) as GLint,
+        buffers.0,
+        filter,
+      );
+      gl_check!("BlitFramebuffer", self.handle);
+    }
+
+    Ok(())
+  }
+
+  // Whether a region fits in the framebuffer.
+  fn contains(&self, rect: Rect) -> bool {
+    rect.x.saturating_add(rect.width) <= self.w && rect.y.saturating_add(rect.height) <= self.h
+  }
+}
+
+/// A resizable framebuffer.
+///
+/// A render target owns a [`Framebuffer`] and re-creates it – along with its color and depth slot
+/// textures – when resized, which is typically needed for offscreen framebuffers following the
+/// size of the window. It dereferences to its framebuffer, so it can be used wherever a
+/// framebuffer is expected.
+pub struct RenderTarget<L, D, CS, DS>
+where L: Layerable,
+      D: Dimensionable,
+      D::Size: Copy,
+      CS: ColorSlot<L, D>,
+      DS: DepthSlot<L, D> {
+  framebuffer: Framebuffer<L, D, CS, DS>,
+  size: D::Size,
+  mipmaps: usize,
+}
+
+impl<L, D, CS, DS> RenderTarget<L, D, CS, DS>
+where L: Layerable,
+      D: Dimensionable,
+      D::Size: Copy + PartialEq,
+      CS: ColorSlot<L, D>,
+      DS: DepthSlot<L, D> {
+  /// Create a new render target.
+  ///
+  /// `mipmaps` is the number of additional mipmap levels of the slot textures, as with
+  /// [`Framebuffer::new`]; it’s kept across resizes.
+  pub fn new<C>(ctx: &mut C, size: D::Size, mipmaps: usize) -> Result<Self, FramebufferError>
+  where C: GraphicsContext {
+    let framebuffer = Framebuffer::new(ctx, size, mipmaps)?;
+
+    Ok(RenderTarget {
+      framebuffer,
+      size,
+      mipmaps,
+    })
+  }
+
+  /// Resize the render target.
+  ///
+  /// The framebuffer and its slot textures are re-created, losing their content, unless `size` is
+  /// the current size. If re-creating the framebuffer fails, the render target keeps its previous
+  /// framebuffer.
+  pub fn resize<C>(&mut self, ctx: &mut C, size: D::Size) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    if size != self.size {
+      self.framebuffer = Framebuffer::new(ctx, size, self.mipmaps)?;
+      self.size = size;
+    }
+
+    Ok(())
+  }
+
+  /// Size of the render target.
+  #[inline]
+  pub fn size(&self) -> D::Size {
+    self.size
+  }
+
+  /// Number of additional mipmap levels of the slot textures.
+  #[inline]
+  pub fn mipmaps(&self) -> usize {
+    self.mipmaps
+  }
+
+  /// Access the underlying framebuffer.
+  #[inline]
+  pub fn framebuffer(&self) -> &Framebuffer<L, D, CS, DS> {
+    &self.framebuffer
+  }
+}
+
+impl<L, D, CS, DS> Deref for RenderTarget<L, D, CS, DS>
+where L: Layerable,
+      D: Dimensionable,
+      D::Size: Copy,
+      CS: ColorSlot<L, D>,
+      DS: DepthSlot<L, D> {
+  type Target = Framebuffer<L, D, CS, DS>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.framebuffer
+  }
+}
+
+/// A rectangular region of a framebuffer, in pixels.
+///
+/// The origin is at the lower-left corner of the framebuffer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rect {
+  /// Lower position on the X axis.
+  pub x: u32,
+  /// Lower position on the Y axis.
+  pub y: u32,
+  /// Width of the rectangle.
+  pub width: u32,
+  /// Height of the rectangle.
+  pub height: u32,
+}
+
+impl Rect {
+  /// Create a new [`Rect`].
+  pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+    Rect { x, y, width, height }
+  }
+}
+
+/// Buffers copied by [`Framebuffer::blit`].
+///
+/// Buffers are combined with `|`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BlitBuffers(GLbitfield);
+
+impl BlitBuffers {
+  /// Color buffers.
+  pub const COLOR: Self = BlitBuffers(gl::COLOR_BUFFER_BIT);
+  /// Depth buffer.
+  pub const DEPTH: Self = BlitBuffers(gl::DEPTH_BUFFER_BIT);
+  /// Stencil buffer.
+  pub const STENCIL: Self = BlitBuffers(gl::STENCIL_BUFFER_BIT);
+}
+
+impl BitOr for BlitBuffers {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    BlitBuffers(self.0 | rhs.0)
+  }
+}
+
+/// Convert a depth value read from a depth buffer back to a view-space distance.
+///
+/// `depth` is in the `[0; 1]` range and `near` and `far` are the clipping planes of the
+/// perspective projection the depth was rendered with. The returned value is in `[near; far]`.
+pub fn linearize_depth(depth: f32, near: f32, far: f32) -> f32 {
+  let z_ndc = depth * 2. - 1.;
+  2. * near * far / (far + near - z_ndc * (far - near))
+}
+
+/// Convert a position in a framebuffer, in pixels, to normalized device coordinates.
+///
+/// `pixel` has its origin at the lower-left corner of the framebuffer – flip the Y axis of cursor
+/// positions given by windowing systems, usually relative to the upper-left corner. Pass the
+/// center of a pixel (e.g. `x + 0.5`) to target it exactly. `depth` is a depth buffer value, in the
+/// `[0; 1]` range.
+pub fn pixel_to_ndc(pixel: [f32; 2], depth: f32, fb_dim: [u32; 2]) -> [f32; 3] {
+  [
+    pixel[0] / fb_dim[0] as f32 * 2. - 1.,
+    pixel[1] / fb_dim[1] as f32 * 2. - 1.,
+    depth * 2. - 1.,
+  ]
+}
+
+/// Convert a position in a framebuffer, in pixels, back to world space.
+///
+/// `pixel`, `depth` and `fb_dim` are the same as with [`pixel_to_ndc`]. `inv_view_proj` is the
+/// inverse of the `projection * view` matrix the scene was rendered with, in column-major order;
+/// pass an inverse projection only to get view-space positions instead.
+///
+/// Typical picking code reads the depth under the cursor with [`Framebuffer::read_depth`] and
+/// unprojects it; unprojecting a depth of `0` and `1` gives a ray through the pixel instead.
+pub fn unproject(pixel: [f32; 2], depth: f32, inv_view_proj: M44, fb_dim: [u32; 2]) -> [f32; 3] {
+  let [x, y, z] = pixel_to_ndc(pixel, depth, fb_dim);
+  let v = [x, y, z, 1.];
+  let mut p = [0.; 4];
+
+  for (row, p) in p.iter_mut().enumerate() {
+    *p = (0 .. 4).map(|col| inv_view_proj[col][row] * v[col]).sum();
+  }
+
+  [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
 }
 
 fn get_status() -> Result<(), IncompleteReason> {
@@ -354,6 +834,108 @@ fn get_status() -> Result<(), IncompleteReason> {
   }
 }
 
+// Whether textures of the given target are attached with all their layers.
+//
+// Renderbuffers have a single layer and can’t be attached along with such textures.
+fn is_layered_attachment(target: GLenum) -> bool {
+  current_profile() == Profile::Core && target != gl::TEXTURE_1D && target != gl::TEXTURE_2D
+}
+
+// Attach the base level of a texture to the bound framebuffer.
+//
+// OpenGL ES 3.0 has no glFramebufferTexture, so only the first layer – or face – of layered
+// textures gets attached there.
+unsafe fn attach_texture(attachment: GLenum, target: GLenum, texture: GLuint) {
+  if current_profile() == Profile::Core {
+    gl::FramebufferTexture(gl::FRAMEBUFFER, attachment, texture, 0);
+    return;
+  }
+
+  match target {
+    gl::TEXTURE_2D => {
+      gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0)
+    }
+
+    gl::TEXTURE_CUBE_MAP => gl::FramebufferTexture2D(
+      gl::FRAMEBUFFER,
+      attachment,
+      gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+      texture,
+      0,
+    ),
+
+    _ => gl::FramebufferTextureLayer(gl::FRAMEBUFFER, attachment, texture, 0, 0),
+  }
+}
+
+/// A set of color slots – _draw buffers_ – of a framebuffer, by index.
+///
+/// Indices follow the order of the color slot: `0` is the first color slot, `1` the second, etc.
+/// Sets hold at most 32 draw buffers; GPUs rarely support more than 8.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DrawBuffers(u32);
+
+impl DrawBuffers {
+  /// Every draw buffer.
+  pub fn all() -> Self {
+    DrawBuffers(!0)
+  }
+
+  /// No draw buffer.
+  pub fn none() -> Self {
+    DrawBuffers(0)
+  }
+
+  /// The draw buffers with the given indices.
+  ///
+  /// Indices above `31` are ignored.
+  pub fn from_indices<I>(indices: I) -> Self where I: IntoIterator<Item = usize> {
+    indices.into_iter().fold(Self::none(), DrawBuffers::with)
+  }
+
+  /// Add the draw buffer at index `index`.
+  pub fn with(self, index: usize) -> Self {
+    if index < 32 {
+      DrawBuffers(self.0 | 1 << index)
+    } else {
+      self
+    }
+  }
+
+  /// Remove the draw buffer at index `index`.
+  pub fn without(self, index: usize) -> Self {
+    if index < 32 {
+      DrawBuffers(self.0 & !(1 << index))
+    } else {
+      self
+    }
+  }
+
+  /// Check whether the draw buffer at index `index` is in the set.
+  pub fn contains(self, index: usize) -> bool {
+    index < 32 && self.0 & 1 << index != 0
+  }
+
+  /// Check whether every draw buffer is in the set.
+  pub fn is_all(self) -> bool {
+    self.0 == !0
+  }
+
+  // Attachments to pass to glDrawBuffers for a framebuffer with color_nb color slots.
+  pub(crate) fn to_attachments(self, color_nb: usize) -> Vec<GLenum> {
+    (0 .. color_nb)
+      .map(|i| if self.contains(i) { gl::COLOR_ATTACHMENT0 + i as GLenum } else { gl::NONE })
+      .collect()
+  }
+}
+
+impl Default for DrawBuffers {
+  /// Every draw buffer.
+  fn default() -> Self {
+    Self::all()
+  }
+}
+
 /// A framebuffer has a color slot. A color slot can either be empty (the *unit* type is used,`()`)
 /// or several color formats.
 pub unsafe trait ColorSlot<L, D>
@@ -479,6 +1061,14 @@ where L: Layerable,
   /// Turn a depth slot into a pixel format.
   fn depth_format() -> Option<PixelFormat>;
 
+  /// Pixel format of the renderbuffer backing the depth slot, if it has no depth texture.
+  ///
+  /// Slots with neither a depth texture nor a renderbuffer format get a depth and stencil
+  /// renderbuffer.
+  fn depth_renderbuffer_format() -> Option<PixelFormat> {
+    None
+  }
+
   /// Reify a raw textures into a depth slot.
   fn reify_texture<C, T>(ctx: &mut C, size: D::Size, mipmaps: usize, texture: T) -> Self::DepthTexture
   where C: GraphicsContext,
@@ -525,3 +1115,43 @@ where L: Layerable,
     }
   }
 }
+
+/// A depth slot backed by a renderbuffer instead of a texture.
+///
+/// The depth buffer of such framebuffers cannot be sampled – they have no depth texture – but it
+/// costs less memory and bandwidth than a depth texture and can still be read back with
+/// [`Framebuffer::read_depth`]. Use it when the depth buffer is only there for the depth test:
+///
+/// ```ignore
+/// type HdrFramebuffer = Framebuffer<Flat, Dim2, RGBA32F, DepthRenderbuffer<Depth32F>>;
+///
+/// let fb = HdrFramebuffer::new(&mut surface, size, 0)?;
+/// ```
+///
+/// Renderbuffers have a single layer, so layered framebuffers cannot use such depth slots: they
+/// fail with [`FramebufferError::LayeredRenderbuffer`].
+#[derive(Clone, Copy, Debug)]
+pub struct DepthRenderbuffer<P> {
+  _p: PhantomData<P>,
+}
+
+unsafe impl<L, D, P> DepthSlot<L, D> for DepthRenderbuffer<P>
+where L: Layerable,
+      D: Dimensionable,
+      D::Size: Copy,
+      P: DepthPixel {
+  type DepthTexture = ();
+
+  fn depth_format() -> Option<PixelFormat> {
+    None
+  }
+
+  fn depth_renderbuffer_format() -> Option<PixelFormat> {
+    Some(P::pixel_format())
+  }
+
+  fn reify_texture<C, T>(_: &mut C, _: D::Size, _: usize, _: T) -> Self::DepthTexture
+  where C: GraphicsContext,
+        T: Into<Option<GLuint>> {
+  }
+}
@@ -0,0 +1,144 @@
+//! Indirect draws.
+//!
+//! An indirect draw reads its parameters – number of vertices, number of instances, etc. – from a
+//! GPU buffer instead of getting them from the CPU. Those parameters are stored as _draw commands_
+//! in a [`DrawIndirectBuffer`], which can be written by the GPU itself – for instance by a culling
+//! pass – so that the CPU doesn’t have to know what gets rendered.
+//!
+//! Draw commands come in two flavors:
+//!
+//!   - [`DrawArraysCommand`], for tessellations without indices.
+//!   - [`DrawElementsCommand`], for indexed tessellations.
+//!
+//! Indirect draws are issued with [`TessGate::render_indirect`].
+//!
+//! > Note: indirect draws require *OpenGL 4.0* or `GL_ARB_draw_indirect`. Issuing several draw
+//! > commands with a single call requires *OpenGL 4.3* or `GL_ARB_multi_draw_indirect`; without
+//! > it, commands are issued one at a time.
+//!
+//! [`TessGate::render_indirect`]: crate::pipeline::TessGate::render_indirect
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::buffer::Buffer;
+use crate::context::GraphicsContext;
+
+/// Draw command of non-indexed tessellations.
+///
+/// This maps to the `DrawArraysIndirectCommand` structure of OpenGL.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[repr(C)]
+pub struct DrawArraysCommand {
+  /// Number of vertices to render.
+  pub vert_nb: u32,
+  /// Number of instances to render.
+  pub inst_nb: u32,
+  /// Index of the first vertex to render.
+  pub first_vertex: u32,
+  /// Index of the first instance; must be `0` without `GL_ARB_base_instance`.
+  pub base_instance: u32,
+}
+
+/// Draw command of indexed tessellations.
+///
+/// This maps to the `DrawElementsIndirectCommand` structure of OpenGL.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[repr(C)]
+pub struct DrawElementsCommand {
+  /// Number of indices to render.
+  pub index_nb: u32,
+  /// Number of instances to render.
+  pub inst_nb: u32,
+  /// Position of the first index to render in the index buffer.
+  pub first_index: u32,
+  /// Value added to every index before fetching vertices.
+  pub base_vertex: i32,
+  /// Index of the first instance; must be `0` without `GL_ARB_base_instance`.
+  pub base_instance: u32,
+}
+
+/// Class of draw commands.
+///
+/// # Safety
+///
+/// The type must have the exact memory layout OpenGL expects for the kind of command.
+pub unsafe trait DrawCommand: Copy {
+  /// Whether the command is meant to render indexed tessellations.
+  const INDEXED: bool;
+}
+
+unsafe impl DrawCommand for DrawArraysCommand {
+  const INDEXED: bool = false;
+}
+
+unsafe impl DrawCommand for DrawElementsCommand {
+  const INDEXED: bool = true;
+}
+
+/// A buffer of draw commands, used by indirect draws.
+pub struct DrawIndirectBuffer<T> where T: DrawCommand {
+  buffer: Buffer<T>,
+}
+
+impl<T> DrawIndirectBuffer<T> where T: DrawCommand {
+  /// Create a buffer of draw commands.
+  pub fn new<C>(ctx: &mut C, commands: &[T]) -> Self where C: GraphicsContext {
+    DrawIndirectBuffer {
+      buffer: Buffer::from_slice(ctx, commands),
+    }
+  }
+
+  /// Buffer holding the draw commands.
+  pub fn buffer(&self) -> &Buffer<T> {
+    &self.buffer
+  }
+
+  /// Buffer holding the draw commands, to update them.
+  pub fn buffer_mut(&mut self) -> &mut Buffer<T> {
+    &mut self.buffer
+  }
+
+  /// Number of draw commands.
+  pub fn len(&self) -> usize {
+    self.buffer.len()
+  }
+
+  /// Check whether the buffer holds no draw command.
+  pub fn is_empty(&self) -> bool {
+    self.buffer.is_empty()
+  }
+}
+
+/// Errors that can happen while issuing indirect draws.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IndirectDrawError {
+  /// Indirect draws are not supported by the context.
+  Unsupported,
+  /// Indexed draw commands were used with a non-indexed tessellation, or conversely.
+  CommandMismatch,
+  /// More draw commands were asked than the buffer holds. The first value is the number of
+  /// commands asked and the second one is the number of commands in the buffer.
+  Overflow(usize, usize),
+}
+
+impl fmt::Display for IndirectDrawError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      IndirectDrawError::Unsupported => f.write_str("indirect draws are not supported"),
+
+      IndirectDrawError::CommandMismatch => {
+        f.write_str("draw commands don’t match the tessellation (indexed or not)")
+      }
+
+      IndirectDrawError::Overflow(count, len) => write!(
+        f,
+        "{} draw commands asked but the buffer holds only {}",
+        count, len
+      ),
+    }
+  }
+}
@@ -94,6 +94,12 @@
 //!     executable but you’re responsible for lots of stuff. **Currently, that feature is not well
 //!     tested and very experimental; use with care and caution and please provide feedback on
 //!     the issue tracker if you try it out!**
+//!   - `gl33-strict`: Refuse to use any OpenGL function introduced after 3.3, even when the context
+//!     has it. Optional features take their 3.3 code paths and the ones without such a path fail –
+//!     or panic, for patch primitives. Enable it while developing on a recent driver to catch what
+//!     would break on a capped context, such as macOS’ OpenGL 4.1.
+//!   - `wgsl`: Author shader stages in *WGSL*, translated to *GLSL* by
+//!     [naga](https://crates.io/crates/naga). See the `shader::stage` module.
 //!
 //! # Windowing
 //!
@@ -372,8 +378,12 @@
 //!     own `new` function for your backend type because of the restriction of having only one context per
 //!     thread in [luminance].
 //!
+//! If the OpenGL context is created by another library, you don’t have to implement
+//! [`GraphicsContext`]: [`GlContext::from_loader`] wraps the current OpenGL context.
+//!
 //! [gfx-hal]: https://crates.io/crates/gfx-hal
 //! [`GraphicsContext`]: crate::context::GraphicsContext
+//! [`GlContext::from_loader`]: crate::context::GlContext::from_loader
 //! [`GraphicsState`]: crate::state::GraphicsState
 //! [`GraphicsState::new`]: crate::state::GraphicsState::new
 //! [`Tess`]: crate::tess::Tess
@@ -398,20 +408,112 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(not(feature = "std"), feature(alloc))]
 
+// Open a profiling zone lasting until the end of the enclosing scope, if profiling is enabled.
+macro_rules! profile_zone {
+  ($name:expr) => {
+    #[cfg(feature = "profiling")]
+    let _zone = crate::profiling::Zone::new($name);
+  };
+}
+
+// Panic if the previous OpenGL call failed, if error checking is enabled. The call is named without
+// its gl prefix and the object it was made on can be given.
+macro_rules! gl_check {
+  ($call:expr) => {
+    #[cfg(feature = "error-check")]
+    crate::state::check_gl_call($call, None);
+  };
+
+  ($call:expr, $object:expr) => {
+    #[cfg(feature = "error-check")]
+    crate::state::check_gl_call($call, Some($object as u32));
+  };
+}
+
+// Whether an OpenGL function introduced after 3.3 can be used: it must be loaded and the
+// gl33-strict feature must be disabled, so that applications take their 3.3 code paths.
+macro_rules! gl_post33 {
+  ($f:ident) => {
+    (!cfg!(feature = "gl33-strict") && gl::$f::is_loaded())
+  };
+}
+
+// Panic if an OpenGL function introduced after 3.3 – available since `$version` – is about to be
+// called while the gl33-strict feature is enabled; used where no 3.3 code path exists.
+macro_rules! gl33_strict_check {
+  ($call:expr, $version:expr) => {
+    if cfg!(feature = "gl33-strict") {
+      panic!("gl{} requires OpenGL {}, which gl33-strict forbids", $call, $version);
+    }
+  };
+}
+
+// Record an event in the event log, if the event log is enabled.
+macro_rules! log_event {
+  (created($kind:ident, $handle:expr)) => {
+    #[cfg(feature = "event-log")]
+    crate::event_log::created(crate::event_log::ResourceKind::$kind, $handle);
+  };
+
+  (destroyed($kind:ident, $handle:expr)) => {
+    #[cfg(feature = "event-log")]
+    crate::event_log::destroyed(crate::event_log::ResourceKind::$kind, $handle);
+  };
+
+  ($f:ident($($arg:expr),*)) => {
+    #[cfg(feature = "event-log")]
+    crate::event_log::$f($($arg),*);
+  };
+}
+
 pub mod blending;
+pub mod blit;
 pub mod buffer;
+pub mod buffer_texture;
+#[cfg(feature = "std")]
+pub mod capture;
+#[cfg(feature = "camera")]
+pub mod camera;
+pub mod capabilities;
+pub mod color_grading;
 pub mod context;
+pub mod cubemap;
+pub mod debug;
 pub mod depth_test;
+#[cfg(feature = "std")]
+pub mod dither;
+pub mod effect;
+#[cfg(feature = "event-log")]
+pub mod event_log;
 pub mod face_culling;
 pub mod framebuffer;
+pub mod indirect;
+pub mod light_cluster;
 pub mod linear;
 mod metagl;
 pub mod pipeline;
 pub mod pixel;
+pub mod point_size;
+pub mod polygon_mode;
+pub mod post_process;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod raw_gl;
+pub mod render_list;
 pub mod render_state;
+pub mod sampler_presets;
+pub mod sdf;
 pub mod shader;
+pub mod sprite_batch;
 pub mod state;
+#[cfg(feature = "std")]
+pub mod stats;
+pub mod stencil;
+#[cfg(feature = "std")]
+pub mod streaming;
+pub mod sync;
 pub mod tess;
 pub mod texture;
 pub mod vertex;
 pub mod vertex_restart;
+pub mod virtual_back_buffer;
@@ -0,0 +1,374 @@
+//! Clustered light culling.
+//!
+//! Clustered forward rendering splits the view frustum in a 3D grid of *clusters* – screen-space
+//! tiles, sliced along the depth axis – and assigns to each cluster the list of lights that might
+//! affect it. Fragment shaders then only iterate over the lights of the cluster they belong to
+//! instead of the whole light list, which allows for hundreds of lights per frame.
+//!
+//! [`LightClusters`] builds the clusters for a perspective projection, assigns point lights to
+//! them on the CPU and uploads the result to two [`BufferTexture`]s:
+//!
+//!   - The *grid*, holding for each cluster the offset of its first light index and its number of
+//!     lights, as two consecutive [`R32UI`] texels.
+//!   - The *light indices*, holding the indices of the lights – in the slice passed to
+//!     [`LightClusters::assign`] – of every cluster, as [`R32UI`] texels.
+//!
+//! The light data themselves (position, color, etc.) stay yours: store them in a buffer texture
+//! or a uniform buffer and index them with the light indices.
+//!
+//! Depth slices are distributed exponentially between the near and far planes, so that the
+//! clusters stay roughly cubic. [`CLUSTER_GLSL`] provides the GLSL functions to find the cluster of
+//! a fragment and iterate over its lights; prepend it to your fragment shader sources and set the
+//! uniforms it declares.
+//!
+//! [`R32UI`]: crate::pixel::R32UI
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::buffer::{Buffer, BufferError};
+use crate::buffer_texture::BufferTexture;
+use crate::context::GraphicsContext;
+use crate::pixel::R32UI;
+use crate::texture::TextureError;
+
+/// GLSL functions to look up the lights of a fragment’s cluster.
+///
+/// The following uniforms must be set:
+///
+///   - `luminance_cluster_grid` and `luminance_cluster_lights`: the bound
+///     [`LightClusters::grid`] and [`LightClusters::light_indices`] buffer textures.
+///   - `luminance_cluster_dim`: [`LightClusters::dim`].
+///   - `luminance_cluster_viewport`: the size of the framebuffer, in pixels.
+///   - `luminance_cluster_depth`: the near and far planes of the projection.
+///
+/// Iterating over the lights of a fragment looks like:
+///
+/// ```glsl
+/// uvec2 range = luminance_cluster_range(luminance_cluster(gl_FragCoord.xy, -view_pos.z));
+///
+/// for (uint i = range.x; i < range.x + range.y; ++i) {
+///   uint light = luminance_cluster_light(i);
+///   // …
+/// }
+/// ```
+pub const CLUSTER_GLSL: &str = "
+uniform usamplerBuffer luminance_cluster_grid;
+uniform usamplerBuffer luminance_cluster_lights;
+uniform uvec3 luminance_cluster_dim;
+uniform vec2 luminance_cluster_viewport;
+uniform vec2 luminance_cluster_depth;
+
+// Index of the cluster of a fragment, from its window coordinates and view-space depth.
+uint luminance_cluster(vec2 frag_coord, float view_depth) {
+  vec2 tile = frag_coord / luminance_cluster_viewport * vec2(luminance_cluster_dim.xy);
+  float near = luminance_cluster_depth.x;
+  float far = luminance_cluster_depth.y;
+  float slice = log(view_depth / near) / log(far / near) * float(luminance_cluster_dim.z);
+  uvec3 cluster = uvec3(clamp(vec3(tile, slice), vec3(0.), vec3(luminance_cluster_dim - 1u)));
+
+  return cluster.x + luminance_cluster_dim.x * (cluster.y + luminance_cluster_dim.y * cluster.z);
+}
+
+// Offset of the first light index and number of lights of a cluster.
+uvec2 luminance_cluster_range(uint cluster) {
+  int i = int(cluster) * 2;
+  return uvec2(texelFetch(luminance_cluster_grid, i).x, texelFetch(luminance_cluster_grid, i + 1).x);
+}
+
+// Light index at a given offset.
+uint luminance_cluster_light(uint offset) {
+  return texelFetch(luminance_cluster_lights, int(offset)).x;
+}
+";
+
+/// Perspective projection the clusters are built for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClusterProjection {
+  /// Vertical field of view, in radians.
+  pub fovy: f32,
+  /// Aspect ratio (width / height).
+  pub aspect: f32,
+  /// Near plane distance.
+  pub near: f32,
+  /// Far plane distance.
+  pub far: f32,
+}
+
+impl ClusterProjection {
+  /// Create a new cluster projection.
+  pub fn new(fovy: f32, aspect: f32, near: f32, far: f32) -> Self {
+    ClusterProjection { fovy, aspect, near, far }
+  }
+}
+
+/// A point light, in view space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+  /// Position of the light, in view space (the camera looks towards -Z).
+  pub position: [f32; 3],
+  /// Distance after which the light has no effect.
+  pub radius: f32,
+}
+
+impl PointLight {
+  /// Create a new point light.
+  pub fn new(position: [f32; 3], radius: f32) -> Self {
+    PointLight { position, radius }
+  }
+}
+
+/// Statistics of a light assignment.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ClusterStats {
+  /// Number of light indices written.
+  pub light_indices: usize,
+  /// Number of light indices that didn’t fit in the light index buffer and were dropped.
+  pub dropped_light_indices: usize,
+}
+
+/// Clusters and their assigned lights.
+pub struct LightClusters {
+  dim: [u32; 3],
+  projection: Option<ClusterProjection>,
+  // view-space bounding boxes of the clusters
+  aabbs: Vec<Aabb>,
+  grid: BufferTexture<[u32; 2], R32UI>,
+  light_indices: BufferTexture<u32, R32UI>,
+  // CPU copies, re-used across assignments
+  grid_data: Vec<[u32; 2]>,
+  light_indices_data: Vec<u32>,
+  cluster_lights: Vec<Vec<u32>>,
+}
+
+impl LightClusters {
+  /// Create clusters.
+  ///
+  /// `dim` is the number of clusters along the X, Y and depth axis – e.g. `[16, 9, 24]`.
+  /// `max_light_indices` is the capacity of the light index buffer, i.e. the maximum total number
+  /// of lights over all the clusters.
+  pub fn new<C>(ctx: &mut C, dim: [u32; 3], max_light_indices: usize) -> Result<Self, LightClusterError>
+  where C: GraphicsContext {
+    let cluster_nb = dim[0] as usize * dim[1] as usize * dim[2] as usize;
+    let max_light_indices = max_light_indices.max(1);
+    let grid_buffer = Buffer::repeat(ctx, cluster_nb, [0, 0]);
+    let grid = BufferTexture::new(ctx, grid_buffer)?;
+    let light_indices_buffer = Buffer::repeat(ctx, max_light_indices, 0);
+    let light_indices = BufferTexture::new(ctx, light_indices_buffer)?;
+
+    Ok(LightClusters {
+      dim,
+      projection: None,
+      aabbs: Vec::new(),
+      grid,
+      light_indices,
+      grid_data: vec![[0, 0]; cluster_nb],
+      light_indices_data: vec![0; max_light_indices],
+      cluster_lights: vec![Vec::new(); cluster_nb],
+    })
+  }
+
+  /// Number of clusters along the X, Y and depth axis.
+  pub fn dim(&self) -> [u32; 3] {
+    self.dim
+  }
+
+  /// Total number of clusters.
+  pub fn cluster_nb(&self) -> usize {
+    self.cluster_lights.len()
+  }
+
+  /// Buffer texture holding the light index offset and light count of every cluster.
+  pub fn grid(&self) -> &BufferTexture<[u32; 2], R32UI> {
+    &self.grid
+  }
+
+  /// Buffer texture holding the light indices of all the clusters.
+  pub fn light_indices(&self) -> &BufferTexture<u32, R32UI> {
+    &self.light_indices
+  }
+
+  /// Assign lights to the clusters and upload the result.
+  ///
+  /// `lights` are in view space. Clusters are re-built only if `projection` changed since the last
+  /// assignment. If the light index buffer is too small, the last lights of the last clusters are
+  /// dropped; check the returned [`ClusterStats`].
+  pub fn assign(
+    &mut self,
+    projection: ClusterProjection,
+    lights: &[PointLight],
+  ) -> Result<ClusterStats, LightClusterError> {
+    if self.projection != Some(projection) {
+      self.aabbs = cluster_aabbs(self.dim, projection);
+      self.projection = Some(projection);
+    }
+
+    for cluster in &mut self.cluster_lights {
+      cluster.clear();
+    }
+
+    let [dim_x, dim_y, dim_z] = self.dim;
+
+    for (index, light) in lights.iter().enumerate() {
+      let depth = -light.position[2];
+
+      // depth slices the light might overlap
+      let (first, last) = match (
+        depth_slice(depth - light.radius, self.dim, projection),
+        depth_slice(depth + light.radius, self.dim, projection),
+      ) {
+        (_, None) => continue, // the light is behind the near plane
+        (first, Some(last)) => (first.unwrap_or(0), last),
+      };
+
+      for z in first ..= last.min(dim_z - 1) {
+        for y in 0 .. dim_y {
+          for x in 0 .. dim_x {
+            let cluster = (x + dim_x * (y + dim_y * z)) as usize;
+
+            if self.aabbs[cluster].intersects_sphere(light.position, light.radius) {
+              self.cluster_lights[cluster].push(index as u32);
+            }
+          }
+        }
+      }
+    }
+
+    // flatten the per-cluster lists
+    let capacity = self.light_indices_data.len();
+    let mut offset = 0;
+    let mut dropped = 0;
+
+    for (cluster, lights) in self.cluster_lights.iter().enumerate() {
+      let count = lights.len().min(capacity - offset);
+
+      self.light_indices_data[offset .. offset + count].copy_from_slice(&lights[.. count]);
+      self.grid_data[cluster] = [offset as u32, count as u32];
+
+      offset += count;
+      dropped += lights.len() - count;
+    }
+
+    self.grid.buffer_mut().write_whole(&self.grid_data)?;
+    self.light_indices.buffer_mut().write_whole(&self.light_indices_data)?;
+
+    Ok(ClusterStats {
+      light_indices: offset,
+      dropped_light_indices: dropped,
+    })
+  }
+}
+
+/// Errors that can happen with light clusters.
+#[derive(Debug, Eq, PartialEq)]
+pub enum LightClusterError {
+  /// A buffer texture couldn’t be created.
+  TextureError(TextureError),
+  /// A buffer couldn’t be updated.
+  BufferError(BufferError),
+}
+
+impl fmt::Display for LightClusterError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      LightClusterError::TextureError(ref e) => write!(f, "light cluster texture error: {}", e),
+      LightClusterError::BufferError(ref e) => write!(f, "light cluster buffer error: {}", e),
+    }
+  }
+}
+
+impl From<TextureError> for LightClusterError {
+  fn from(e: TextureError) -> Self {
+    LightClusterError::TextureError(e)
+  }
+}
+
+impl From<BufferError> for LightClusterError {
+  fn from(e: BufferError) -> Self {
+    LightClusterError::BufferError(e)
+  }
+}
+
+/// View-space axis-aligned bounding box.
+#[derive(Clone, Copy, Debug)]
+struct Aabb {
+  min: [f32; 3],
+  max: [f32; 3],
+}
+
+impl Aabb {
+  fn intersects_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+    let mut dist2 = 0.;
+
+    for ((&c, &min), &max) in center.iter().zip(&self.min).zip(&self.max) {
+      let d = c - c.max(min).min(max);
+      dist2 += d * d;
+    }
+
+    dist2 <= radius * radius
+  }
+}
+
+// Depth of the near plane of a depth slice.
+fn slice_depth(slice: u32, dim: [u32; 3], projection: ClusterProjection) -> f32 {
+  let ClusterProjection { near, far, .. } = projection;
+  near * (far / near).powf(slice as f32 / dim[2] as f32)
+}
+
+// Depth slice a view-space depth belongs to; `None` if in front of the near plane.
+fn depth_slice(depth: f32, dim: [u32; 3], projection: ClusterProjection) -> Option<u32> {
+  let ClusterProjection { near, far, .. } = projection;
+
+  if depth < near {
+    None
+  } else {
+    Some(((depth / near).ln() / (far / near).ln() * dim[2] as f32) as u32)
+  }
+}
+
+// Compute the view-space bounding boxes of all the clusters.
+fn cluster_aabbs(dim: [u32; 3], projection: ClusterProjection) -> Vec<Aabb> {
+  let [dim_x, dim_y, dim_z] = dim;
+  // half extents of the frustum at a depth of 1
+  let half_h = (projection.fovy * 0.5).tan();
+  let half_w = half_h * projection.aspect;
+  let mut aabbs = Vec::with_capacity(dim_x as usize * dim_y as usize * dim_z as usize);
+
+  for z in 0 .. dim_z {
+    let depths = [slice_depth(z, dim, projection), slice_depth(z + 1, dim, projection)];
+
+    for y in 0 .. dim_y {
+      let ndc_y = [y as f32 / dim_y as f32 * 2. - 1., (y + 1) as f32 / dim_y as f32 * 2. - 1.];
+
+      for x in 0 .. dim_x {
+        let ndc_x = [x as f32 / dim_x as f32 * 2. - 1., (x + 1) as f32 / dim_x as f32 * 2. - 1.];
+        let mut aabb = Aabb {
+          min: [f32::INFINITY; 3],
+          max: [f32::NEG_INFINITY; 3],
+        };
+
+        for &depth in &depths {
+          for &nx in &ndc_x {
+            for &ny in &ndc_y {
+              let corner = [nx * half_w * depth, ny * half_h * depth, -depth];
+
+              for ((min, max), &c) in aabb.min.iter_mut().zip(&mut aabb.max).zip(&corner) {
+                *min = min.min(c);
+                *max = max.max(c);
+              }
+            }
+          }
+        }
+
+        aabbs.push(aabb);
+      }
+    }
+  }
+
+  aabbs
+}
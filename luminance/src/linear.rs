@@ -8,3 +8,13 @@ pub type M33 = [[f32; 3]; 3];
 
 /// 4x4 floating matrix.
 pub type M44 = [[f32; 4]; 4];
+
+/// A row-major matrix.
+///
+/// Matrices – [`M22`], [`M33`] and [`M44`] – are column-major: each inner array is a column. Some
+/// math crates and file formats store matrices row by row instead; wrapping them in `RowMajor`
+/// lets the GPU transpose them when they’re sent as uniforms, rather than transposing them on the
+/// CPU.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(transparent)]
+pub struct RowMajor<M>(pub M);
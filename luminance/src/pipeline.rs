@@ -80,6 +80,10 @@
 //! spread information of root nodes down. For instance, if you have a shader node, every nested
 //! child of that shader node will have the possibility to use its features via a shading gate.
 //!
+//! Gates are panic-safe: if one of their closures panics, the bound resources are released and the
+//! state luminance tracks is issued to OpenGL again as the panic unwinds. The application can then
+//! catch the panic – with [`std::panic::catch_unwind`] – and keep rendering.
+//!
 //! # Render commands
 //!
 //! A set of values that tag a collection of tessellations. Typical information is whether we should
@@ -98,6 +102,8 @@
 #[cfg(feature = "std")]
 use std::cell::RefCell;
 #[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
 use std::marker::PhantomData;
 #[cfg(feature = "std")]
 use std::ops::Deref;
@@ -107,26 +113,38 @@ use std::rc::Rc;
 #[cfg(not(feature = "std"))]
 use alloc::rc::Rc;
 #[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use core::cell::RefCell;
 #[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
 use core::marker::PhantomData;
 #[cfg(not(feature = "std"))]
 use core::ops::Deref;
 
-use crate::blending::BlendingState;
+use crate::blending::{BlendingState, SeparateBlending};
 use crate::buffer::{Buffer, RawBuffer};
+use crate::buffer_texture::BufferTexture;
 use crate::context::GraphicsContext;
+use crate::debug;
 use crate::depth_test::DepthTest;
 use crate::face_culling::FaceCullingState;
-use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::stencil::StencilTestState;
+use crate::framebuffer::{ColorSlot, DepthSlot, DrawBuffers, Framebuffer};
+use crate::indirect::{DrawCommand, DrawIndirectBuffer, IndirectDrawError};
 use crate::metagl::*;
-use crate::pixel::{Pixel, SamplerType, Type as PxType};
+use crate::pixel::{DepthPixel, Pixel, PixelFormat, SamplerType, Shadow, Type as PxType};
+use crate::point_size::PointSize;
+use crate::render_list::{
+  BatchedShadingGate, Command, RenderList, RenderListError, RenderResources,
+};
 use crate::render_state::RenderState;
 use crate::shader::program::{Program, ProgramInterface, Type, Uniform, UniformInterface, Uniformable};
-use crate::state::GraphicsState;
-use crate::tess::TessSlice;
+use crate::state::{GraphicsState, PoisonCause};
+use crate::tess::{Tess, TessSlice};
 use crate::texture::{Dim, Dimensionable, Layerable, Texture};
 use crate::vertex::Semantics;
 
@@ -135,12 +153,17 @@ use crate::vertex::Semantics;
 // This type implements a stacking system for effective resource bindings by allocating new
 // bindings points only when no recycled resource is available. It helps have a better memory
 // footprint in the resource space.
+//
+// Some binding points can be reserved; they’re never allocated by the stack and can only be used
+// explicitly.
 struct BindingStack {
   state: Rc<RefCell<GraphicsState>>,
   next_texture_unit: u32,
   free_texture_units: Vec<u32>,
+  reserved_texture_units: Vec<u32>,
   next_buffer_binding: u32,
   free_buffer_bindings: Vec<u32>,
+  reserved_buffer_bindings: Vec<u32>,
 }
 
 impl BindingStack {
@@ -150,8 +173,82 @@ impl BindingStack {
       state,
       next_texture_unit: 0,
       free_texture_units: Vec::new(),
+      reserved_texture_units: Vec::new(),
       next_buffer_binding: 0,
       free_buffer_bindings: Vec::new(),
+      reserved_buffer_bindings: Vec::new(),
+    }
+  }
+
+  // Get a texture unit, either a recycled one or a new one that is not reserved.
+  fn alloc_texture_unit(&mut self) -> Result<u32, BindingError> {
+    if let Some(unit) = self.free_texture_units.pop() {
+      return Ok(unit);
+    }
+
+    // no more free units; reserve one
+    while self.reserved_texture_units.contains(&self.next_texture_unit) {
+      self.next_texture_unit += 1;
+    }
+
+    // binding past the last unit would fail in the middle of the frame; the last units might be
+    // dedicated to long-lived textures
+    let max_texture_units = {
+      let state = self.state.borrow();
+      state.max_texture_units() - state.cached_texture_units()
+    };
+
+    if self.next_texture_unit >= max_texture_units {
+      return Err(BindingError::TextureUnitsExhausted(max_texture_units));
+    }
+
+    let unit = self.next_texture_unit;
+    self.next_texture_unit += 1;
+    Ok(unit)
+  }
+
+  // Get a buffer binding, either a recycled one or a new one that is not reserved.
+  fn alloc_buffer_binding(&mut self) -> u32 {
+    if let Some(binding) = self.free_buffer_bindings.pop() {
+      return binding;
+    }
+
+    // no more free bindings; reserve one
+    while self.reserved_buffer_bindings.contains(&self.next_buffer_binding) {
+      self.next_buffer_binding += 1;
+    }
+
+    let binding = self.next_buffer_binding;
+    self.next_buffer_binding += 1;
+    binding
+  }
+}
+
+/// Errors that might happen when binding resources in a [`Pipeline`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BindingError {
+  /// The texture unit was not reserved with [`Builder::reserve_texture_units`].
+  UnreservedTextureUnit(u32),
+  /// The buffer binding was not reserved with [`Builder::reserve_buffer_bindings`].
+  UnreservedBufferBinding(u32),
+  /// All the texture units are in use. Contains the number of texture units of the context.
+  ///
+  /// Textures bound in a [`Pipeline`] stay bound until their [`BoundTexture`] is dropped: bind
+  /// fewer textures at once – e.g. by splitting draws – to free some units.
+  TextureUnitsExhausted(u32),
+}
+
+impl fmt::Display for BindingError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      BindingError::UnreservedTextureUnit(unit) => write!(f, "texture unit {} is not reserved", unit),
+      BindingError::UnreservedBufferBinding(binding) => {
+        write!(f, "buffer binding {} is not reserved", binding)
+      }
+
+      BindingError::TextureUnitsExhausted(max) => {
+        write!(f, "all the {} texture units are in use", max)
+      }
     }
   }
 }
@@ -178,6 +275,34 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
     }
   }
 
+  /// Reserve texture units.
+  ///
+  /// Reserved texture units are never used when binding textures with [`Pipeline::bind_texture`];
+  /// you can only bind textures to them explicitly with [`Pipeline::bind_texture_at`]. This is
+  /// useful to have stable binding layouts across frames – e.g. unit `0` always used for the shadow
+  /// map.
+  pub fn reserve_texture_units<U>(self, units: U) -> Self where U: IntoIterator<Item = u32> {
+    {
+      let mut bstack = self.binding_stack.borrow_mut();
+      bstack.reserved_texture_units.extend(units);
+    }
+
+    self
+  }
+
+  /// Reserve buffer bindings.
+  ///
+  /// Reserved buffer bindings are never used when binding buffers with [`Pipeline::bind_buffer`];
+  /// you can only bind buffers to them explicitly with [`Pipeline::bind_buffer_at`].
+  pub fn reserve_buffer_bindings<B>(self, bindings: B) -> Self where B: IntoIterator<Item = u32> {
+    {
+      let mut bstack = self.binding_stack.borrow_mut();
+      bstack.reserved_buffer_bindings.extend(bindings);
+    }
+
+    self
+  }
+
   /// Create a new [`Pipeline`] and consume it immediately.
   ///
   /// A dynamic rendering pipeline is responsible of rendering into a `Framebuffer`.
@@ -189,7 +314,8 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
   /// `CS` and `DS` are – respectively – the *color* and *depth* `Slot`(s) of the underlying
   /// `Framebuffer`.
   ///
-  /// Pipelines also have a *clear color*, used to clear the framebuffer.
+  /// Pipelines also have a *clear color*, a *clear depth* and a *clear stencil* value, used to
+  /// clear the framebuffer. Clearing can be skipped per buffer; see [`PipelineState`].
   pub fn pipeline<'b, L, D, CS, DS, F>(
     &'b mut self,
     framebuffer: &Framebuffer<L, D, CS, DS>,
@@ -201,6 +327,12 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
         CS: ColorSlot<L, D>,
         DS: DepthSlot<L, D>,
         F: FnOnce(Pipeline<'b>, ShadingGate<'b, C>) {
+    #[cfg(feature = "profiling")]
+    crate::profiling::collect_gpu_zones();
+
+    profile_zone!("luminance::pipeline");
+    log_event!(pipeline(framebuffer.handle()));
+
     unsafe {
       let mut state = self.ctx.state().borrow_mut();
 
@@ -209,11 +341,25 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
       let PipelineState {
         clear_color,
         clear_color_enabled,
+        clear_depth,
         clear_depth_enabled,
+        clear_stencil,
+        clear_stencil_enabled,
         viewport,
         srgb_enabled,
+        draw_buffers,
+        ref attachment_clear_colors,
       } = *pipeline_state;
 
+      // the draw buffers are part of the framebuffer, so a previous pipeline might have changed them
+      let color_nb = CS::color_formats().len();
+
+      if framebuffer.handle() != 0 && color_nb != 0 {
+        let attachments = draw_buffers.to_attachments(color_nb);
+        gl::DrawBuffers(color_nb as GLsizei, attachments.as_ptr());
+        gl_check!("DrawBuffers", framebuffer.handle());
+      }
+
       match viewport {
         Viewport::Whole => {
           state.set_viewport([0, 0, framebuffer.width() as GLint, framebuffer.height() as GLint]);
@@ -224,17 +370,54 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
         }
       }
 
-      state.set_clear_color([clear_color[0] as _, clear_color[1] as _, clear_color[2] as _, clear_color[3] as _]);
+      let mut clear_bits = 0;
+      let color_formats = CS::color_formats();
 
-      if clear_color_enabled || clear_depth_enabled {
-        let color_bit = if clear_color_enabled { gl::COLOR_BUFFER_BIT } else { 0 };
-        let depth_bit = if clear_depth_enabled { gl::DEPTH_BUFFER_BIT } else { 0 };
-        gl::Clear(color_bit | depth_bit);
+      // color slots with their own clear colors or with integral formats are cleared one by one
+      let clear_per_slot = framebuffer.handle() != 0
+        && (attachment_clear_colors.iter().any(Option::is_some)
+          || color_formats.iter().any(|f| is_integral(f.encoding)));
+
+      if clear_color_enabled && clear_per_slot {
+        for (i, format) in color_formats.iter().enumerate() {
+          let slot_clear_color = attachment_clear_colors
+            .get(i)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| ClearColor::for_encoding(clear_color, format.encoding));
+
+          slot_clear_color.clear_draw_buffer(i as GLint);
+        }
+
+        gl_check!("ClearBuffer", framebuffer.handle());
+      } else if clear_color_enabled {
+        state.set_clear_color([clear_color[0] as _, clear_color[1] as _, clear_color[2] as _, clear_color[3] as _]);
+        clear_bits |= gl::COLOR_BUFFER_BIT;
+      }
+
+      if clear_depth_enabled {
+        state.set_clear_depth(clear_depth as _);
+        clear_bits |= gl::DEPTH_BUFFER_BIT;
       }
 
-      state.enable_srgb_framebuffer(srgb_enabled);
+      if clear_stencil_enabled {
+        state.set_clear_stencil(clear_stencil as _);
+        clear_bits |= gl::STENCIL_BUFFER_BIT;
+      }
+
+      if clear_bits != 0 {
+        gl::Clear(clear_bits);
+        gl_check!("Clear", framebuffer.handle());
+      }
+
+      // sRGB color slots always encode the linear colors they’re given
+      let srgb_slot = color_formats.into_iter().any(PixelFormat::is_srgb);
+      state.enable_srgb_framebuffer(srgb_enabled || srgb_slot);
     }
 
+    #[cfg(feature = "std")]
+    let _guard = UnwindGuard(self.ctx.state().clone());
+
     let binding_stack = &self.binding_stack;
     let p = Pipeline { binding_stack };
     let shd_gt = ShadingGate {
@@ -244,6 +427,65 @@ impl<'a, C> Builder<'a, C> where C: ?Sized + GraphicsContext {
 
     f(p, shd_gt);
   }
+
+  /// Create a new dynamic pipeline, gathering its commands in a debug group named `name`.
+  ///
+  /// This is [`Builder::pipeline`] made readable in graphics debuggers. See the
+  /// [`debug`](crate::debug) module for further details.
+  pub fn pipeline_named<'b, L, D, CS, DS, F>(
+    &'b mut self,
+    name: &str,
+    framebuffer: &Framebuffer<L, D, CS, DS>,
+    pipeline_state: &PipelineState,
+    f: F,
+  )
+  where L: Layerable,
+        D: Dimensionable,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D>,
+        F: FnOnce(Pipeline<'b>, ShadingGate<'b, C>) {
+    let _group = debug::Group::push(name);
+    self.pipeline(framebuffer, pipeline_state, f);
+  }
+}
+
+/// Clear color of a color slot.
+///
+/// The variant must match the pixel format of the color slot: integral formats are cleared with
+/// integers and the other ones – normalized and floating – with floats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearColor {
+  /// Clear color of normalized and floating color slots.
+  Float([f32; 4]),
+  /// Clear color of signed integral color slots.
+  Int([i32; 4]),
+  /// Clear color of unsigned integral color slots.
+  UInt([u32; 4]),
+}
+
+impl ClearColor {
+  // Clear color of a slot with the given encoding, converted from a float clear color.
+  fn for_encoding(color: [f32; 4], encoding: PxType) -> Self {
+    match encoding {
+      PxType::Integral => ClearColor::Int([color[0] as _, color[1] as _, color[2] as _, color[3] as _]),
+      PxType::Unsigned => ClearColor::UInt([color[0] as _, color[1] as _, color[2] as _, color[3] as _]),
+      _ => ClearColor::Float(color),
+    }
+  }
+
+  // Clear the draw buffer at index draw_buffer of the bound draw framebuffer.
+  unsafe fn clear_draw_buffer(self, draw_buffer: GLint) {
+    match self {
+      ClearColor::Float(ref color) => gl::ClearBufferfv(gl::COLOR, draw_buffer, color.as_ptr()),
+      ClearColor::Int(ref color) => gl::ClearBufferiv(gl::COLOR, draw_buffer, color.as_ptr()),
+      ClearColor::UInt(ref color) => gl::ClearBufferuiv(gl::COLOR, draw_buffer, color.as_ptr()),
+    }
+  }
+}
+
+// Whether a pixel encoding is integral – i.e. not normalized nor floating.
+fn is_integral(encoding: PxType) -> bool {
+  encoding == PxType::Integral || encoding == PxType::Unsigned
 }
 
 /// The viewport being part of the [`PipelineState`].
@@ -270,26 +512,41 @@ pub enum Viewport {
 pub struct PipelineState {
   clear_color: [f32; 4],
   clear_color_enabled: bool,
+  clear_depth: f32,
   clear_depth_enabled: bool,
+  clear_stencil: i32,
+  clear_stencil_enabled: bool,
   viewport: Viewport,
   srgb_enabled: bool,
+  draw_buffers: DrawBuffers,
+  // clear colors overriding clear_color, per color slot
+  attachment_clear_colors: Vec<Option<ClearColor>>,
 }
 
 impl Default for PipelineState {
   /// Default [`PipelineState`]:
   ///
-  /// - Clear color: `[0, 0, 0, 1]`.
+  /// - Clear color: `[0, 0, 0, 1]`, for every color slot.
   /// - Color is always cleared.
+  /// - Clear depth: `1`.
   /// - Depth is always cleared.
+  /// - Clear stencil: `0`.
+  /// - Stencil is never cleared.
   /// - The viewport uses the whole framebuffer’s.
   /// - sRGB encoding is disabled.
+  /// - Every draw buffer is rendered to.
   fn default() -> Self {
     PipelineState {
       clear_color: [0., 0., 0., 1.],
       clear_color_enabled: true,
+      clear_depth: 1.,
       clear_depth_enabled: true,
+      clear_stencil: 0,
+      clear_stencil_enabled: false,
       viewport: Viewport::Whole,
       srgb_enabled: false,
+      draw_buffers: DrawBuffers::all(),
+      attachment_clear_colors: Vec::new(),
     }
   }
 }
@@ -312,6 +569,25 @@ impl PipelineState {
     Self { clear_color, ..self }
   }
 
+  /// Get the clear color of the color slot at index `index`, if it has its own.
+  pub fn attachment_clear_color(&self, index: usize) -> Option<ClearColor> {
+    self.attachment_clear_colors.get(index).cloned().flatten()
+  }
+
+  /// Set the clear color of the color slot at index `index`, overriding the clear color.
+  ///
+  /// Color slots with integral pixel formats – e.g. an object ID slot – must be cleared with
+  /// integers: use [`ClearColor::Int`] or [`ClearColor::UInt`] for them. Color slots without their
+  /// own clear color are cleared with the clear color, converted to integers if needed.
+  pub fn set_attachment_clear_color(mut self, index: usize, clear_color: ClearColor) -> Self {
+    if self.attachment_clear_colors.len() <= index {
+      self.attachment_clear_colors.resize(index + 1, None);
+    }
+
+    self.attachment_clear_colors[index] = Some(clear_color);
+    self
+  }
+
   /// Check whether the pipeline’s framebuffer’s color buffers will be cleared.
   pub fn is_clear_color_enabled(&self) -> bool {
     self.clear_color_enabled
@@ -322,6 +598,18 @@ impl PipelineState {
     Self { clear_color_enabled, ..self }
   }
 
+  /// Get the clear depth.
+  pub fn clear_depth(&self) -> f32 {
+    self.clear_depth
+  }
+
+  /// Set the clear depth.
+  ///
+  /// The value is clamped to `[0, 1]` by the GPU.
+  pub fn set_clear_depth(self, clear_depth: f32) -> Self {
+    Self { clear_depth, ..self }
+  }
+
   /// Check whether the pipeline’s framebuffer’s depth buffer will be cleared.
   pub fn is_clear_depth_enabled(&self) -> bool {
     self.clear_depth_enabled
@@ -332,6 +620,39 @@ impl PipelineState {
     Self { clear_depth_enabled, ..self }
   }
 
+  /// Get the clear stencil.
+  pub fn clear_stencil(&self) -> i32 {
+    self.clear_stencil
+  }
+
+  /// Set the clear stencil.
+  pub fn set_clear_stencil(self, clear_stencil: i32) -> Self {
+    Self { clear_stencil, ..self }
+  }
+
+  /// Check whether the pipeline’s framebuffer’s stencil buffer will be cleared.
+  pub fn is_clear_stencil_enabled(&self) -> bool {
+    self.clear_stencil_enabled
+  }
+
+  /// Enable clearing stencil buffers.
+  pub fn enable_clear_stencil(self, clear_stencil_enabled: bool) -> Self {
+    Self { clear_stencil_enabled, ..self }
+  }
+
+  /// Enable or disable clearing all buffers at once.
+  ///
+  /// Disabling clearing is useful for accumulative rendering – e.g. motion blur or progressive
+  /// path tracing – where the content of the framebuffer must be kept between pipelines.
+  pub fn enable_clear(self, enabled: bool) -> Self {
+    Self {
+      clear_color_enabled: enabled,
+      clear_depth_enabled: enabled,
+      clear_stencil_enabled: enabled,
+      ..self
+    }
+  }
+
   /// Get the viewport.
   pub fn viewport(&self) -> Viewport {
     self.viewport
@@ -348,9 +669,27 @@ impl PipelineState {
   }
 
   /// Enable sRGB linearization.
+  ///
+  /// Framebuffers with sRGB color slots – see [`Srgb`] – always have it enabled; this is mostly
+  /// useful for the back buffer.
+  ///
+  /// [`Srgb`]: crate::pixel::Srgb
   pub fn enable_srgb(self, srgb_enabled: bool) -> Self {
     Self { srgb_enabled, ..self }
   }
+
+  /// Get the draw buffers.
+  pub fn draw_buffers(&self) -> DrawBuffers {
+    self.draw_buffers
+  }
+
+  /// Set the color slots rendered to – and cleared – by the pipeline.
+  ///
+  /// The other color slots of the framebuffer are left untouched, which enables masking out
+  /// channels of a G-buffer per pass. This has no effect on the back buffer.
+  pub fn set_draw_buffers(self, draw_buffers: DrawBuffers) -> Self {
+    Self { draw_buffers, ..self }
+  }
 }
 
 /// A dynamic pipeline.
@@ -362,24 +701,106 @@ pub struct Pipeline<'a> {
 }
 
 impl<'a> Pipeline<'a> {
+  /// Texture units and buffer bindings currently in use by the pipeline, and the resources bound
+  /// to them.
+  ///
+  /// This is meant to debug wrong bindings: the result can be printed – e.g. every frame – with
+  /// its [`Display`](fmt::Display) implementation. Resources are listed with their labels – see
+  /// the [`debug`](crate::debug) module – when they have one.
+  pub fn debug_bindings(&self) -> DebugBindings {
+    let bstack = self.binding_stack.borrow();
+    let state = bstack.state.borrow();
+
+    let in_use = |next: u32, free: &[u32], reserved: &[u32]| {
+      let mut indices = (0 .. next)
+        .filter(|i| !free.contains(i) && !reserved.contains(i))
+        .chain(reserved.iter().cloned())
+        .collect::<Vec<_>>();
+      indices.sort_unstable();
+      indices.dedup();
+      indices
+    };
+
+    let mut texture_units = in_use(
+      bstack.next_texture_unit,
+      &bstack.free_texture_units,
+      &bstack.reserved_texture_units,
+    );
+    texture_units.extend(state.cached_texture_units_in_use());
+    texture_units.sort_unstable();
+
+    let textures = texture_units.into_iter().map(|unit| {
+      let handle = state.bound_texture(unit).map_or(0, |(_, handle)| handle);
+
+      DebugBinding {
+        index: unit,
+        reserved: bstack.reserved_texture_units.contains(&unit),
+        handle,
+        label: debug::object_label(gl::TEXTURE, handle),
+      }
+    }).collect();
+
+    let buffers = in_use(
+      bstack.next_buffer_binding,
+      &bstack.free_buffer_bindings,
+      &bstack.reserved_buffer_bindings,
+    )
+    .into_iter()
+    .map(|binding| {
+      let handle = state.bound_uniform_buffer(binding).unwrap_or(0);
+
+      DebugBinding {
+        index: binding,
+        reserved: bstack.reserved_buffer_bindings.contains(&binding),
+        handle,
+        label: debug::object_label(gl::BUFFER, handle),
+      }
+    })
+    .collect();
+
+    DebugBindings { textures, buffers }
+  }
+
   /// Bind a texture and return the bound texture.
   ///
-  /// The texture remains bound as long as the return value lives.
+  /// The texture remains bound as long as the return value lives. This function fails with
+  /// [`BindingError::TextureUnitsExhausted`] if all the texture units are already in use.
   pub fn bind_texture<L, D, P>(
     &'a self,
     texture: &'a Texture<L, D, P>,
-  ) -> BoundTexture<'a, L, D, P::SamplerType>
+  ) -> Result<BoundTexture<'a, L, D, P::SamplerType>, BindingError>
   where L: 'a + Layerable,
         D: 'a + Dimensionable,
         P: 'a + Pixel {
     let mut bstack = self.binding_stack.borrow_mut();
+    let unit = bstack.alloc_texture_unit()?;
 
-    let unit = bstack.free_texture_units.pop().unwrap_or_else(|| {
-      // no more free units; reserve one
-      let unit = bstack.next_texture_unit;
-      bstack.next_texture_unit += 1;
-      unit
-    });
+    unsafe {
+      let mut state = bstack.state.borrow_mut();
+      state.set_texture_unit(unit);
+      state.bind_texture(texture.target(), texture.handle());
+    }
+
+    Ok(BoundTexture::new(self.binding_stack, unit, UnitKind::Allocated))
+  }
+
+  /// Bind a depth texture to sample it with a shadow sampler and return the bound texture.
+  ///
+  /// The texture must have been created with a sampler comparing depths – e.g.
+  /// [`Sampler::shadow_pcf`] – or get one with [`Texture::set_sampler`]; see [`Shadow`]. It
+  /// remains bound as long as the return value lives.
+  ///
+  /// [`Sampler::shadow_pcf`]: crate::texture::Sampler::shadow_pcf
+  /// [`Texture::set_sampler`]: crate::texture::Texture::set_sampler
+  pub fn bind_shadow_texture<L, D, P>(
+    &'a self,
+    texture: &'a Texture<L, D, P>,
+  ) -> Result<BoundTexture<'a, L, D, Shadow>, BindingError>
+  where L: 'a + Layerable,
+        D: 'a + Dimensionable,
+        P: 'a + DepthPixel {
+    let mut bstack = self.binding_stack.borrow_mut();
+    let unit = bstack.alloc_texture_unit()?;
 
     unsafe {
       let mut state = bstack.state.borrow_mut();
@@ -387,7 +808,96 @@ impl<'a> Pipeline<'a> {
       state.bind_texture(texture.target(), texture.handle());
     }
 
-    BoundTexture::new(self.binding_stack, unit)
+    Ok(BoundTexture::new(self.binding_stack, unit, UnitKind::Allocated))
+  }
+
+  /// Bind a long-lived texture to a cached texture unit and return the bound texture.
+  ///
+  /// Cached texture units – see [`GraphicsState::set_cached_texture_units`] – keep their texture
+  /// bound across pipelines and frames. Textures bound with this function in every frame – the
+  /// textures of materials, for instance – then keep their unit and are not bound again, unless
+  /// more textures than cached units are used. The texture remains bound – and its unit in use –
+  /// as long as the return value lives.
+  ///
+  /// When there’s no cached texture unit or all of them are in use, this is the same as
+  /// [`Pipeline::bind_texture`].
+  pub fn bind_texture_cached<L, D, P>(
+    &'a self,
+    texture: &'a Texture<L, D, P>,
+  ) -> Result<BoundTexture<'a, L, D, P::SamplerType>, BindingError>
+  where L: 'a + Layerable,
+        D: 'a + Dimensionable,
+        P: 'a + Pixel {
+    let bstack = self.binding_stack.borrow();
+    let mut state = bstack.state.borrow_mut();
+
+    let unit = match state.texture_unit_cache().acquire(texture.handle()) {
+      Some(unit) => unit,
+
+      None => {
+        drop(state);
+        drop(bstack);
+        return self.bind_texture(texture);
+      }
+    };
+
+    // the unit is only made active if the texture is not still bound to it
+    if state.bound_texture(unit) != Some((texture.target(), texture.handle())) {
+      unsafe {
+        state.set_texture_unit(unit);
+        state.bind_texture(texture.target(), texture.handle());
+      }
+    }
+
+    Ok(BoundTexture::new(self.binding_stack, unit, UnitKind::Cached))
+  }
+
+  /// Bind a texture to a reserved texture unit and return the bound texture.
+  ///
+  /// The texture remains bound as long as the return value lives. The unit must have been
+  /// reserved with [`Builder::reserve_texture_units`].
+  pub fn bind_texture_at<L, D, P>(
+    &'a self,
+    texture: &'a Texture<L, D, P>,
+    unit: u32,
+  ) -> Result<BoundTexture<'a, L, D, P::SamplerType>, BindingError>
+  where L: 'a + Layerable,
+        D: 'a + Dimensionable,
+        P: 'a + Pixel {
+    let bstack = self.binding_stack.borrow();
+
+    if !bstack.reserved_texture_units.contains(&unit) {
+      return Err(BindingError::UnreservedTextureUnit(unit));
+    }
+
+    unsafe {
+      let mut state = bstack.state.borrow_mut();
+      state.set_texture_unit(unit);
+      state.bind_texture(texture.target(), texture.handle());
+    }
+
+    Ok(BoundTexture::new(self.binding_stack, unit, UnitKind::Reserved))
+  }
+
+  /// Bind a buffer texture and return the bound buffer texture.
+  ///
+  /// The buffer texture remains bound as long as the return value lives. This function fails with
+  /// [`BindingError::TextureUnitsExhausted`] if all the texture units are already in use.
+  pub fn bind_buffer_texture<T, P>(
+    &'a self,
+    texture: &'a BufferTexture<T, P>,
+  ) -> Result<BoundBufferTexture<'a, P::SamplerType>, BindingError>
+  where P: 'a + Pixel {
+    let mut bstack = self.binding_stack.borrow_mut();
+    let unit = bstack.alloc_texture_unit()?;
+
+    unsafe {
+      let mut state = bstack.state.borrow_mut();
+      state.set_texture_unit(unit);
+      state.bind_texture(gl::TEXTURE_BUFFER, texture.handle());
+    }
+
+    Ok(BoundBufferTexture::new(self.binding_stack, unit))
   }
 
   /// Bind a buffer and return the bound buffer.
@@ -396,13 +906,33 @@ impl<'a> Pipeline<'a> {
   pub fn bind_buffer<T>(&'a self, buffer: &'a T) -> BoundBuffer<'a, T>
   where T: Deref<Target = RawBuffer> {
     let mut bstack = self.binding_stack.borrow_mut();
+    let binding = bstack.alloc_buffer_binding();
 
-    let binding = bstack.free_buffer_bindings.pop().unwrap_or_else(|| {
-      // no more free bindings; reserve one
-      let binding = bstack.next_buffer_binding;
-      bstack.next_buffer_binding += 1;
-      binding
-    });
+    unsafe {
+      bstack
+        .state
+        .borrow_mut()
+        .bind_buffer_base(buffer.handle(), binding);
+    }
+
+    BoundBuffer::new(self.binding_stack, binding, false)
+  }
+
+  /// Bind a buffer to a reserved binding and return the bound buffer.
+  ///
+  /// The buffer remains bound as long as the return value lives. The binding must have been
+  /// reserved with [`Builder::reserve_buffer_bindings`].
+  pub fn bind_buffer_at<T>(
+    &'a self,
+    buffer: &'a T,
+    binding: u32,
+  ) -> Result<BoundBuffer<'a, T>, BindingError>
+  where T: Deref<Target = RawBuffer> {
+    let bstack = self.binding_stack.borrow();
+
+    if !bstack.reserved_buffer_bindings.contains(&binding) {
+      return Err(BindingError::UnreservedBufferBinding(binding));
+    }
 
     unsafe {
       bstack
@@ -411,7 +941,7 @@ impl<'a> Pipeline<'a> {
         .bind_buffer_base(buffer.handle(), binding);
     }
 
-    BoundBuffer::new(self.binding_stack, binding)
+    Ok(BoundBuffer::new(self.binding_stack, binding, true))
   }
 }
 
@@ -422,17 +952,30 @@ where L: 'a + Layerable,
       D: 'a + Dimensionable,
       S: 'a + SamplerType, {
   unit: u32,
+  kind: UnitKind,
   binding_stack: &'a Rc<RefCell<BindingStack>>,
   _t: PhantomData<&'a (L, D, S)>,
 }
 
+// How the texture unit of a bound texture was obtained.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum UnitKind {
+  // allocated by the binding stack
+  Allocated,
+  // reserved with Builder::reserve_texture_units
+  Reserved,
+  // assigned by the texture unit cache of the graphics state
+  Cached,
+}
+
 impl<'a, L, D, S> BoundTexture<'a, L, D, S>
 where L: 'a + Layerable,
       D: 'a + Dimensionable,
       S: 'a + SamplerType {
-  fn new(binding_stack: &'a Rc<RefCell<BindingStack>>, unit: u32) -> Self {
+  fn new(binding_stack: &'a Rc<RefCell<BindingStack>>, unit: u32, kind: UnitKind) -> Self {
     BoundTexture {
       unit,
+      kind,
       binding_stack,
       _t: PhantomData,
     }
@@ -445,8 +988,13 @@ where L: 'a + Layerable,
       S: 'a + SamplerType {
   fn drop(&mut self) {
     let mut bstack = self.binding_stack.borrow_mut();
-    // place the unit into the free list
-    bstack.free_texture_units.push(self.unit);
+
+    // reserved units are never placed into the free list, and cached ones go back to the cache
+    match self.kind {
+      UnitKind::Allocated => bstack.free_texture_units.push(self.unit),
+      UnitKind::Reserved => (),
+      UnitKind::Cached => bstack.state.borrow_mut().texture_unit_cache().release(self.unit),
+    }
   }
 }
 
@@ -459,6 +1007,17 @@ where L: 'a + Layerable,
   }
 
   fn ty() -> Type {
+    // there’s no 3D shadow sampler; binding 3D depth textures as shadow textures has no meaning
+    if S::is_shadow() {
+      match D::dim() {
+        Dim::Dim1 => return Type::Sampler1DShadow,
+        Dim::Dim2 => return Type::Sampler2DShadow,
+        Dim::Cubemap => return Type::CubemapShadow,
+        Dim::Dim2Array => return Type::Sampler2DArrayShadow,
+        Dim::Dim3 => (),
+      }
+    }
+
     match (S::sample_type(), D::dim()) {
       (PxType::NormIntegral, Dim::Dim1) => Type::Sampler1D,
       (PxType::NormUnsigned, Dim::Dim1) => Type::Sampler1D,
@@ -483,22 +1042,131 @@ where L: 'a + Layerable,
       (PxType::Integral, Dim::Cubemap) => Type::ICubemap,
       (PxType::Unsigned, Dim::Cubemap) => Type::UICubemap,
       (PxType::Floating, Dim::Cubemap) => Type::Cubemap,
+
+      (PxType::NormIntegral, Dim::Dim2Array) => Type::Sampler2DArray,
+      (PxType::NormUnsigned, Dim::Dim2Array) => Type::Sampler2DArray,
+      (PxType::Integral, Dim::Dim2Array) => Type::ISampler2DArray,
+      (PxType::Unsigned, Dim::Dim2Array) => Type::UISampler2DArray,
+      (PxType::Floating, Dim::Dim2Array) => Type::Sampler2DArray,
     }
   }
 }
 
+/// An opaque type representing a bound buffer texture in a `Builder`. You may want to pass such an
+/// object to a shader’s uniform’s update.
+pub struct BoundBufferTexture<'a, S> where S: 'a + SamplerType {
+  unit: u32,
+  binding_stack: &'a Rc<RefCell<BindingStack>>,
+  _t: PhantomData<&'a S>,
+}
+
+impl<'a, S> BoundBufferTexture<'a, S> where S: 'a + SamplerType {
+  fn new(binding_stack: &'a Rc<RefCell<BindingStack>>, unit: u32) -> Self {
+    BoundBufferTexture {
+      unit,
+      binding_stack,
+      _t: PhantomData,
+    }
+  }
+}
+
+impl<'a, S> Drop for BoundBufferTexture<'a, S> where S: 'a + SamplerType {
+  fn drop(&mut self) {
+    let mut bstack = self.binding_stack.borrow_mut();
+    // place the unit into the free list
+    bstack.free_texture_units.push(self.unit);
+  }
+}
+
+unsafe impl<'a, S> Uniformable for &BoundBufferTexture<'a, S> where S: 'a + SamplerType {
+  fn update(self, u: &Uniform<Self>) {
+    unsafe { gl::Uniform1i(u.index(), self.unit as GLint) }
+  }
+
+  fn ty() -> Type {
+    match S::sample_type() {
+      PxType::Integral => Type::IBufferSampler,
+      PxType::Unsigned => Type::UIBufferSampler,
+      PxType::NormIntegral | PxType::NormUnsigned | PxType::Floating => Type::BufferSampler,
+    }
+  }
+}
+
+/// Texture units and buffer bindings in use by a [`Pipeline`].
+///
+/// See [`Pipeline::debug_bindings`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DebugBindings {
+  /// Texture units in use, in increasing order.
+  pub textures: Vec<DebugBinding>,
+  /// Buffer bindings in use, in increasing order.
+  pub buffers: Vec<DebugBinding>,
+}
+
+impl fmt::Display for DebugBindings {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    writeln!(f, "texture units:")?;
+
+    for binding in &self.textures {
+      writeln!(f, "  {}", binding)?;
+    }
+
+    writeln!(f, "buffer bindings:")?;
+
+    for binding in &self.buffers {
+      writeln!(f, "  {}", binding)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// A texture unit or buffer binding in use, and the resource bound to it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DebugBinding {
+  /// Texture unit or buffer binding.
+  pub index: u32,
+  /// Whether the texture unit or buffer binding is reserved – see
+  /// [`Builder::reserve_texture_units`] and [`Builder::reserve_buffer_bindings`].
+  pub reserved: bool,
+  /// OpenGL handle of the bound resource; `0` if nothing is bound.
+  pub handle: u32,
+  /// Label of the bound resource, if it has one.
+  pub label: Option<String>,
+}
+
+impl fmt::Display for DebugBinding {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    write!(f, "{}", self.index)?;
+
+    if self.reserved {
+      f.write_str(" (reserved)")?;
+    }
+
+    write!(f, ": {}", self.handle)?;
+
+    if let Some(ref label) = self.label {
+      write!(f, " “{}”", label)?;
+    }
+
+    Ok(())
+  }
+}
+
 /// An opaque type representing a bound buffer in a `Builder`. You may want to pass such an object
 /// to a shader’s uniform’s update.
 pub struct BoundBuffer<'a, T> where T: 'a {
   binding: u32,
+  reserved: bool,
   binding_stack: &'a Rc<RefCell<BindingStack>>,
   _t: PhantomData<&'a Buffer<T>>,
 }
 
 impl<'a, T> BoundBuffer<'a, T> {
-  fn new(binding_stack: &'a Rc<RefCell<BindingStack>>, binding: u32) -> Self {
+  fn new(binding_stack: &'a Rc<RefCell<BindingStack>>, binding: u32, reserved: bool) -> Self {
     BoundBuffer {
       binding,
+      reserved,
       binding_stack,
       _t: PhantomData,
     }
@@ -507,9 +1175,12 @@ impl<'a, T> BoundBuffer<'a, T> {
 
 impl<'a, T> Drop for BoundBuffer<'a, T> {
   fn drop(&mut self) {
-    let mut bstack = self.binding_stack.borrow_mut();
-    // place the binding into the free list
-    bstack.free_buffer_bindings.push(self.binding);
+    // reserved bindings are never placed into the free list
+    if !self.reserved {
+      let mut bstack = self.binding_stack.borrow_mut();
+      // place the binding into the free list
+      bstack.free_buffer_bindings.push(self.binding);
+    }
   }
 }
 
@@ -523,6 +1194,25 @@ unsafe impl<'a, 'b, T> Uniformable for &'b BoundBuffer<'a, T> {
   }
 }
 
+// Guard restoring the cached state to OpenGL when a gate closure panics.
+//
+// The bound resources release their binding points when dropped, but the closure might have left
+// OpenGL in a state the cache doesn’t know about; restoring it lets the application catch the panic
+// and keep rendering.
+#[cfg(feature = "std")]
+struct UnwindGuard(Rc<RefCell<GraphicsState>>);
+
+#[cfg(feature = "std")]
+impl Drop for UnwindGuard {
+  fn drop(&mut self) {
+    if std::thread::panicking() {
+      if let Ok(mut state) = self.0.try_borrow_mut() {
+        unsafe { state.restore() };
+      }
+    }
+  }
+}
+
 /// A shading gate provides you with a way to run shaders on rendering commands.
 pub struct ShadingGate<'a, C> where C: ?Sized {
   ctx: &'a mut C,
@@ -531,14 +1221,42 @@ pub struct ShadingGate<'a, C> where C: ?Sized {
 
 impl<'a, C> ShadingGate<'a, C> where C: ?Sized + GraphicsContext {
   /// Run a shader on a set of rendering commands.
+  ///
+  /// Nothing is run if `program` cannot be used; see [`ShadingGate::try_shade`] to know why.
   pub fn shade<'b, In, Out, Uni, F>(&'b mut self, program: &Program<In, Out, Uni>, f: F)
   where In: Semantics,
         Uni: UniformInterface,
         F: FnOnce(ProgramInterface<Uni>, RenderGate<'b, C>) {
-    unsafe {
+    let _ = self.try_shade(program, f);
+  }
+
+  /// Run a shader on a set of rendering commands, if `program` can be used.
+  ///
+  /// `f` is not called if the context was lost or, with the `thread-check` feature, if `program`
+  /// was created on another thread than the one of the context.
+  pub fn try_shade<'b, In, Out, Uni, F>(
+    &'b mut self,
+    program: &Program<In, Out, Uni>,
+    f: F,
+  ) -> Result<(), PoisonCause>
+  where In: Semantics,
+        Uni: UniformInterface,
+        F: FnOnce(ProgramInterface<Uni>, RenderGate<'b, C>) {
+    profile_zone!("luminance::shade");
+    log_event!(shade(program.handle()));
+
+    program.check_usable()?;
+
+    {
       let bstack = self.binding_stack.borrow_mut();
-      bstack.state.borrow_mut().use_program(program.handle());
-    };
+      let mut state = bstack.state.borrow_mut();
+
+      state.check_usable()?;
+      unsafe { state.use_program(program.handle()) };
+    }
+
+    #[cfg(feature = "std")]
+    let _guard = UnwindGuard(self.binding_stack.borrow().state.clone());
 
     let render_gate = RenderGate {
       ctx: self.ctx,
@@ -547,6 +1265,130 @@ impl<'a, C> ShadingGate<'a, C> where C: ?Sized + GraphicsContext {
 
     let program_interface = program.interface();
     f(program_interface, render_gate);
+
+    Ok(())
+  }
+
+  /// Replay a [`RenderList`], resolving its identifiers with `resources`.
+  ///
+  /// Commands are issued in the order they were recorded, as the gates would issue them. Replaying
+  /// stops at the first invalid command: the commands before it are issued, the ones after it are
+  /// not. Nothing is replayed if the context was lost.
+  pub fn replay(
+    &mut self,
+    list: &RenderList,
+    resources: &RenderResources,
+  ) -> Result<(), RenderListError> {
+    profile_zone!("luminance::replay");
+
+    if self.binding_stack.borrow().state.borrow().is_context_lost() {
+      return Ok(());
+    }
+
+    // program in use, and the texture units allocated for its sampler uniforms, as (index, unit)
+    let mut program = None;
+    let mut units: Vec<(GLint, u32)> = Vec::new();
+
+    let result = list.commands().iter().try_for_each(|command| {
+      match *command {
+        Command::UseProgram(id) => {
+          let handle = resources.program(id)?;
+          let mut bstack = self.binding_stack.borrow_mut();
+
+          bstack.free_texture_units.extend(units.drain(..).map(|(_, unit)| unit));
+          unsafe { bstack.state.borrow_mut().use_program(handle) };
+          program = Some(handle);
+        }
+
+        Command::SetRenderState(ref rdr_st) => {
+          let bstack = self.binding_stack.borrow();
+          unsafe { apply_render_state(&mut bstack.state.borrow_mut(), rdr_st) };
+        }
+
+        Command::SetUniform(uni_program, index, ref value) => {
+          check_replayed_program(program, uni_program)?;
+          unsafe { value.upload(index) };
+        }
+
+        Command::BindTexture(uni_program, index, id) => {
+          check_replayed_program(program, uni_program)?;
+          let (target, handle) = resources.texture(id)?;
+          let mut bstack = self.binding_stack.borrow_mut();
+
+          let unit = match units.iter().find(|&&(i, _)| i == index) {
+            Some(&(_, unit)) => unit,
+
+            None => {
+              let unit = bstack.alloc_texture_unit()?;
+              units.push((index, unit));
+              unsafe { gl::Uniform1i(index, unit as GLint) };
+              unit
+            }
+          };
+
+          unsafe {
+            let mut state = bstack.state.borrow_mut();
+            state.set_texture_unit(unit);
+            state.bind_texture(target, handle);
+          }
+        }
+
+        Command::Draw(ref draw) => {
+          if program.is_none() {
+            return Err(RenderListError::NoProgram);
+          }
+
+          let tess = resources.tess(draw.tess)?;
+          let slice = match (draw.range, draw.inst_nb) {
+            (Some((start, nb)), inst_nb) => {
+              match start.checked_add(nb) {
+                Some(end) if end <= tess.vert_nb() => (),
+                _ => return Err(RenderListError::InvalidSlice(draw.tess, start, nb)),
+              }
+
+              TessSlice::inst_slice(tess, start, nb, inst_nb.unwrap_or(1))
+            }
+
+            (None, Some(inst_nb)) => TessSlice::inst_whole(tess, inst_nb),
+            (None, None) => TessSlice::one_whole(tess),
+          };
+
+          profile_zone!("luminance::tess");
+          slice.render(self.ctx);
+        }
+      }
+
+      Ok(())
+    });
+
+    let mut bstack = self.binding_stack.borrow_mut();
+    bstack.free_texture_units.extend(units.into_iter().map(|(_, unit)| unit));
+
+    result
+  }
+
+  /// Collect draws in a [`BatchedShadingGate`] and submit them sorted by program and texture
+  /// bindings once `f` returns.
+  ///
+  /// Errors are the ones of [`ShadingGate::replay`].
+  pub fn batched<F>(&mut self, resources: &RenderResources, f: F) -> Result<(), RenderListError>
+  where F: FnOnce(&mut BatchedShadingGate) {
+    let mut batch = BatchedShadingGate::new();
+    f(&mut batch);
+    self.submit_batch(&mut batch, resources)
+  }
+
+  /// Submit the draws of a [`BatchedShadingGate`] sorted by program and texture bindings.
+  ///
+  /// The draws are sorted in place and stay in `batch`, to be submitted again or cleared.
+  pub fn submit_batch(
+    &mut self,
+    batch: &mut BatchedShadingGate,
+    resources: &RenderResources,
+  ) -> Result<(), RenderListError> {
+    let mut list = RenderList::new();
+    batch.record_sorted(&mut list);
+    self.replay(&list, resources)
   }
 }
 
@@ -559,46 +1401,105 @@ pub struct RenderGate<'a, C> where C: ?Sized {
 impl<'a, C> RenderGate<'a, C> where C: ?Sized + GraphicsContext {
   /// Alter the render state and draw tessellations.
   pub fn render<'b, F>(&'b mut self, rdr_st: RenderState, f: F) where F: FnOnce(TessGate<'b, C>) {
+    profile_zone!("luminance::render");
+    log_event!(render());
+
     unsafe {
       let bstack = self.binding_stack.borrow_mut();
       let mut gfx_state = bstack.state.borrow_mut();
+      apply_render_state(&mut gfx_state, &rdr_st);
+    }
 
-      match rdr_st.blending {
-        Some((equation, src_factor, dst_factor)) => {
-          gfx_state.set_blending_state(BlendingState::On);
-          gfx_state.set_blending_equation(equation);
-          gfx_state.set_blending_func(src_factor, dst_factor);
-        }
-        None => {
-          gfx_state.set_blending_state(BlendingState::Off);
-        }
-      }
+    #[cfg(feature = "std")]
+    let _guard = UnwindGuard(self.binding_stack.borrow().state.clone());
 
-      if let Some(depth_comparison) = rdr_st.depth_test {
-        gfx_state.set_depth_test(DepthTest::On);
-        gfx_state.set_depth_test_comparison(depth_comparison);
+    let tess_gate = TessGate {
+      ctx: self.ctx,
+    };
+
+    f(tess_gate);
+  }
+}
+
+// Check that a uniform of a replayed render list belongs to the program in use.
+fn check_replayed_program(
+  program: Option<GLuint>,
+  uni_program: GLuint,
+) -> Result<(), RenderListError> {
+  match program {
+    Some(program) if program == uni_program => Ok(()),
+    Some(_) => Err(RenderListError::UniformMismatch(uni_program)),
+    None => Err(RenderListError::NoProgram),
+  }
+}
+
+// Issue a render state to OpenGL, through the cache of the graphics state.
+unsafe fn apply_render_state(gfx_state: &mut GraphicsState, rdr_st: &RenderState) {
+  match rdr_st.blending {
+    Some((equation, src_factor, dst_factor)) => {
+      if rdr_st.blended_draw_buffers.is_all() {
+        gfx_state.set_blending_state(BlendingState::On);
       } else {
-        gfx_state.set_depth_test(DepthTest::Off);
+        gfx_state.set_blended_draw_buffers(rdr_st.blended_draw_buffers);
       }
 
-      match rdr_st.face_culling {
-        Some(face_culling) => {
-          gfx_state.set_face_culling_state(FaceCullingState::On);
-          gfx_state.set_face_culling_order(face_culling.order);
-          gfx_state.set_face_culling_mode(face_culling.mode);
-        }
-        None => {
-          gfx_state.set_face_culling_state(FaceCullingState::Off);
-        }
-      }
+      let rgb = (equation, src_factor, dst_factor);
+      let alpha = rdr_st.alpha_blending.unwrap_or(rgb);
+
+      gfx_state.set_blending(SeparateBlending::new(rgb, alpha));
+      gfx_state.set_blend_color(rdr_st.blend_color);
+      gfx_state.set_draw_buffer_blending(&rdr_st.draw_buffer_blending);
     }
+    None => {
+      gfx_state.set_blending_state(BlendingState::Off);
+    }
+  }
 
-    let tess_gate = TessGate {
-      ctx: self.ctx,
-    };
+  if let Some(depth_comparison) = rdr_st.depth_test {
+    gfx_state.set_depth_test(DepthTest::On);
+    gfx_state.set_depth_test_comparison(depth_comparison);
+  } else {
+    gfx_state.set_depth_test(DepthTest::Off);
+  }
 
-    f(tess_gate);
+  gfx_state.set_depth_clamp(rdr_st.depth_clamp);
+  gfx_state.set_polygon_offset(rdr_st.polygon_offset);
+
+  match rdr_st.stencil_test {
+    Some(stencil_test) => {
+      gfx_state.set_stencil_test_state(StencilTestState::On);
+      gfx_state.set_stencil_test(stencil_test);
+      gfx_state.set_stencil_operations(rdr_st.stencil_operations);
+    }
+    None => {
+      gfx_state.set_stencil_test_state(StencilTestState::Off);
+    }
+  }
+
+  match rdr_st.face_culling {
+    Some(face_culling) => {
+      gfx_state.set_face_culling_state(FaceCullingState::On);
+      gfx_state.set_face_culling_order(face_culling.order);
+      gfx_state.set_face_culling_mode(face_culling.mode);
+    }
+    None => {
+      gfx_state.set_face_culling_state(FaceCullingState::Off);
+    }
+  }
+
+  match rdr_st.point_size {
+    PointSize::Fixed(size) => {
+      gfx_state.set_program_point_size(false);
+      gfx_state.set_point_size(size);
+    }
+    PointSize::Program => {
+      gfx_state.set_program_point_size(true);
+    }
   }
+
+  gfx_state.set_line_width(rdr_st.line_width);
+  gfx_state.set_polygon_mode(rdr_st.polygon_mode);
+  gfx_state.set_dithering(rdr_st.dithering);
 }
 
 /// Render tessellations.
@@ -609,6 +1510,28 @@ pub struct TessGate<'a, C> where C: ?Sized {
 impl<'a, C> TessGate<'a, C> where C: ?Sized + GraphicsContext {
   /// Render a tessellation.
   pub fn render<'b, T>(&'b mut self, tess: T) where T: Into<TessSlice<'b>> {
+    profile_zone!("luminance::tess");
+
     tess.into().render(self.ctx);
   }
+
+  /// Render a tessellation with the first `count` draw commands of a [`DrawIndirectBuffer`].
+  ///
+  /// The vertices, indices and instances of `tess` are used, but what gets rendered is driven by
+  /// the draw commands: [`DrawArraysCommand`] for non-indexed tessellations and
+  /// [`DrawElementsCommand`] for indexed ones.
+  ///
+  /// [`DrawArraysCommand`]: crate::indirect::DrawArraysCommand
+  /// [`DrawElementsCommand`]: crate::indirect::DrawElementsCommand
+  pub fn render_indirect<T>(
+    &mut self,
+    tess: &Tess,
+    commands: &DrawIndirectBuffer<T>,
+    count: usize,
+  ) -> Result<(), IndirectDrawError>
+  where T: DrawCommand {
+    profile_zone!("luminance::tess_indirect");
+
+    tess.render_indirect(self.ctx, commands, count)
+  }
 }
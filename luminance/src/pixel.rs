@@ -2,6 +2,22 @@
 //!
 //! The `Pixel` trait is used to reify a pixel type at runtime via `PixelFormat`.
 //!
+//! # Color spaces
+//!
+//! Pixel formats store colors in linear space by default. [`Srgb`] wraps an 8-bit normalized RGB
+//! or RGBA pixel format to store colors encoded in the sRGB color space instead: texels are
+//! decoded to linear space when sampled and linear colors are encoded when rendered to the
+//! texture, so shaders always deal with linear colors. [`Linear`] wraps any color pixel format to
+//! make the linear color space explicit; it’s the same format as the wrapped one.
+//!
+//! `Srgb` is only implemented for pixel formats OpenGL has an sRGB counterpart for, so
+//! `Srgb<RGBA32F>` is a compile error.
+
+#[cfg(feature = "std")]
+use std::marker::PhantomData;
+
+#[cfg(not(feature = "std"))]
+use core::marker::PhantomData;
 
 use crate::metagl::*;
 
@@ -38,6 +54,11 @@ pub unsafe trait RenderablePixel: Pixel {}
 pub unsafe trait SamplerType {
   /// Underlying type of the sampler.
   fn sample_type() -> Type;
+
+  /// Whether the sampler compares depths instead of returning texels; see [`Shadow`].
+  fn is_shadow() -> bool {
+    false
+  }
 }
 
 /// A `PixelFormat` gathers a `Type` along with a `Format`.
@@ -63,6 +84,11 @@ impl PixelFormat {
     !self.is_color_pixel()
   }
 
+  /// Does a [`PixelFormat`] store colors encoded in the sRGB color space?
+  pub fn is_srgb(self) -> bool {
+    matches!(self.format, Format::SRGB(_, _, _) | Format::SRGBA(_, _, _, _))
+  }
+
   /// Return the number of canals.
   pub fn canals_len(self) -> usize {
     match self.format {
@@ -71,6 +97,8 @@ impl PixelFormat {
       Format::RGB(_, _, _) => 3,
       Format::RGBA(_, _, _, _) => 4,
       Format::BGRA(_, _, _, _) => 4,
+      Format::SRGB(_, _, _) => 3,
+      Format::SRGBA(_, _, _, _) => 4,
       Format::Depth(_) => 1,
     }
   }
@@ -107,6 +135,10 @@ pub enum Format {
   RGBA(Size, Size, Size, Size),
   /// Holds blue, green, red and alpha channels.
   BGRA(Size, Size, Size, Size),
+  /// Holds red, green and blue channels in the sRGB color space.
+  SRGB(Size, Size, Size),
+  /// Holds red, green and blue channels in the sRGB color space and a linear alpha channel.
+  SRGBA(Size, Size, Size, Size),
   /// Holds a depth channel.
   Depth(Size),
 }
@@ -120,6 +152,8 @@ impl Format {
       Format::RGB(r, g, b) => r.bits() + g.bits() + b.bits(),
       Format::RGBA(r, g, b, a) => r.bits() + g.bits() + b.bits() + a.bits(),
       Format::BGRA(b, g, r, a) => b.bits() + g.bits() + r.bits() + a.bits(),
+      Format::SRGB(r, g, b) => r.bits() + g.bits() + b.bits(),
+      Format::SRGBA(r, g, b, a) => r.bits() + g.bits() + b.bits() + a.bits(),
       Format::Depth(d) => d.bits(),
     };
 
@@ -130,6 +164,8 @@ impl Format {
 /// Size in bits a pixel channel can be.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Size {
+  /// 2-bit.
+  Two,
   /// 8-bit.
   Eight,
   /// 10-bit.
@@ -146,6 +182,7 @@ impl Size {
   /// Size (in bits).
   pub fn bits(self) -> usize {
     match self {
+      Size::Two => 2,
       Size::Eight => 8,
       Size::Ten => 10,
       Size::Eleven => 11,
@@ -205,6 +242,27 @@ unsafe impl SamplerType for Floating {
   }
 }
 
+/// The depth comparison sample type, used by shadow samplers.
+///
+/// Depth textures bound with [`Pipeline::bind_shadow_texture`] are sampled with
+/// `sampler2DShadow` – or `samplerCubeShadow`, etc. – which return the result of the depth
+/// comparison of their sampler rather than the depth itself: with linear filtering, hardware
+/// percentage-closer filtering of the four nearest texels.
+///
+/// [`Pipeline::bind_shadow_texture`]: crate::pipeline::Pipeline::bind_shadow_texture
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shadow;
+
+unsafe impl SamplerType for Shadow {
+  fn sample_type() -> Type {
+    Type::Floating
+  }
+
+  fn is_shadow() -> bool {
+    true
+  }
+}
+
 macro_rules! impl_Pixel {
   ($t:ty, $encoding:ty, $raw_encoding:ty, $encoding_ty:ident, $format:expr) => {
     unsafe impl Pixel for $t {
@@ -336,6 +394,16 @@ impl_Pixel!(NormR32UI, u32, u32, NormUnsigned, Format::R(Size::ThirtyTwo));
 impl_ColorPixel!(NormR32UI);
 impl_RenderablePixel!(NormR32UI);
 
+/// A red 16-bit floating pixel format.
+///
+/// Texels are uploaded and read back as [`f32`], converted from and to half floats by the GPU.
+#[derive(Clone, Copy, Debug)]
+pub struct R16F;
+
+impl_Pixel!(R16F, f32, f32, Floating, Format::R(Size::Sixteen));
+impl_ColorPixel!(R16F);
+impl_RenderablePixel!(R16F);
+
 /// A red 32-bit floating pixel format.
 #[derive(Clone, Copy, Debug)]
 pub struct R32F;
@@ -476,6 +544,22 @@ impl_Pixel!(NormRG32UI, (u32, u32), u32, NormUnsigned, Format::RG(Size::ThirtyTw
 impl_ColorPixel!(NormRG32UI);
 impl_RenderablePixel!(NormRG32UI);
 
+/// A red and green 16-bit floating pixel format.
+///
+/// Texels are uploaded and read back as [`f32`], converted from and to half floats by the GPU.
+#[derive(Clone, Copy, Debug)]
+pub struct RG16F;
+
+impl_Pixel!(
+  RG16F,
+  (f32, f32),
+  f32,
+  Floating,
+  Format::RG(Size::Sixteen, Size::Sixteen)
+);
+impl_ColorPixel!(RG16F);
+impl_RenderablePixel!(RG16F);
+
 /// A red and green 32-bit floating pixel format.
 #[derive(Clone, Copy, Debug)]
 pub struct RG32F;
@@ -664,6 +748,23 @@ impl_Pixel!(
 impl_ColorPixel!(NormRGB32UI);
 impl_RenderablePixel!(NormRGB32UI);
 
+/// A red, green and blue 16-bit floating pixel format.
+///
+/// Texels are uploaded and read back as [`f32`], converted from and to half floats by the GPU.
+/// OpenGL doesn’t require this format to be renderable: render to [`RGBA16F`] or [`R11G11B10F`]
+/// instead.
+#[derive(Clone, Copy, Debug)]
+pub struct RGB16F;
+
+impl_Pixel!(
+  RGB16F,
+  (f32, f32, f32),
+  f32,
+  Floating,
+  Format::RGB(Size::Sixteen, Size::Sixteen, Size::Sixteen)
+);
+impl_ColorPixel!(RGB16F);
+
 /// A red, green and blue 32-bit floating pixel format.
 #[derive(Clone, Copy, Debug)]
 pub struct RGB32F;
@@ -852,6 +953,22 @@ impl_Pixel!(
 impl_ColorPixel!(NormRGBA32UI);
 impl_RenderablePixel!(NormRGBA32UI);
 
+/// A red, green, blue and alpha 16-bit floating pixel format.
+///
+/// Texels are uploaded and read back as [`f32`], converted from and to half floats by the GPU.
+#[derive(Clone, Copy, Debug)]
+pub struct RGBA16F;
+
+impl_Pixel!(
+  RGBA16F,
+  (f32, f32, f32, f32),
+  f32,
+  Floating,
+  Format::RGBA(Size::Sixteen, Size::Sixteen, Size::Sixteen, Size::Sixteen)
+);
+impl_ColorPixel!(RGBA16F);
+impl_RenderablePixel!(RGBA16F);
+
 /// A red, green, blue and alpha 32-bit floating pixel format.
 #[derive(Clone, Copy, Debug)]
 pub struct RGBA32F;
@@ -876,7 +993,7 @@ pub struct R11G11B10F;
 
 impl_Pixel!(
   R11G11B10F,
-  (f32, f32, f32, f32),
+  (f32, f32, f32),
   f32,
   Floating,
   Format::RGB(Size::Eleven, Size::Eleven, Size::Ten)
@@ -884,6 +1001,27 @@ impl_Pixel!(
 impl_ColorPixel!(R11G11B10F);
 impl_RenderablePixel!(R11G11B10F);
 
+/// A red, green, blue and alpha pixel format packed in 32 bits, accessed as normalized floating
+/// pixels, in which:
+///
+///   - The red, green and blue channels are on 10 bits.
+///   - The alpha channel is on 2 bits.
+///
+/// Texels are encoded as [`u32`], the red channel in the lowest bits and the alpha channel in the
+/// highest ones.
+#[derive(Clone, Copy, Debug)]
+pub struct NormRGB10A2UI;
+
+impl_Pixel!(
+  NormRGB10A2UI,
+  u32,
+  u32,
+  NormUnsigned,
+  Format::RGBA(Size::Ten, Size::Ten, Size::Ten, Size::Two)
+);
+impl_ColorPixel!(NormRGB10A2UI);
+impl_RenderablePixel!(NormRGB10A2UI);
+
 /// A blue, green, red and alpha 8-bit unsigned integral pixel format.
 #[derive(Clone, Copy, Debug)]
 pub struct NormBGRA8UI;
@@ -905,6 +1043,61 @@ pub struct Depth32F;
 impl_Pixel!(Depth32F, f32, f32, Floating, Format::Depth(Size::ThirtyTwo));
 impl_DepthPixel!(Depth32F);
 
+/// Pixel formats which have an sRGB counterpart.
+///
+/// # Safety
+///
+/// The sRGB counterpart of the pixel format must exist in OpenGL.
+pub unsafe trait SrgbEncodable: ColorPixel {}
+
+unsafe impl SrgbEncodable for NormRGB8UI {}
+unsafe impl SrgbEncodable for NormRGBA8UI {}
+
+/// A pixel format storing colors encoded in the sRGB color space.
+///
+/// The encoding is the same as `P`’s; the texels are decoded to linear space when sampled.
+#[derive(Clone, Copy, Debug)]
+pub struct Srgb<P>(PhantomData<P>);
+
+unsafe impl<P> Pixel for Srgb<P> where P: SrgbEncodable {
+  type Encoding = P::Encoding;
+  type RawEncoding = P::RawEncoding;
+  type SamplerType = P::SamplerType;
+
+  fn pixel_format() -> PixelFormat {
+    let pf = P::pixel_format();
+    let format = match pf.format {
+      Format::RGB(r, g, b) => Format::SRGB(r, g, b),
+      Format::RGBA(r, g, b, a) => Format::SRGBA(r, g, b, a),
+      format => format,
+    };
+
+    PixelFormat { format, ..pf }
+  }
+}
+
+unsafe impl<P> ColorPixel for Srgb<P> where P: SrgbEncodable {}
+unsafe impl<P> RenderablePixel for Srgb<P> where P: SrgbEncodable + RenderablePixel {}
+
+/// A pixel format storing colors in linear space.
+///
+/// This is the same pixel format as `P`, with an explicit color space.
+#[derive(Clone, Copy, Debug)]
+pub struct Linear<P>(PhantomData<P>);
+
+unsafe impl<P> Pixel for Linear<P> where P: ColorPixel {
+  type Encoding = P::Encoding;
+  type RawEncoding = P::RawEncoding;
+  type SamplerType = P::SamplerType;
+
+  fn pixel_format() -> PixelFormat {
+    P::pixel_format()
+  }
+}
+
+unsafe impl<P> ColorPixel for Linear<P> where P: ColorPixel {}
+unsafe impl<P> RenderablePixel for Linear<P> where P: ColorPixel + RenderablePixel {}
+
 // OpenGL format, internal sized-format and type.
 pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GLenum)> {
   match (pf.format, pf.encoding) {
@@ -914,10 +1107,11 @@ pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GL
     (Format::R(Size::Eight), Type::Integral) => Some((gl::RED_INTEGER, gl::R8I, gl::BYTE)),
     (Format::R(Size::Eight), Type::Unsigned) => Some((gl::RED_INTEGER, gl::R8UI, gl::UNSIGNED_BYTE)),
 
-    (Format::R(Size::Sixteen), Type::NormUnsigned) => Some((gl::RED_INTEGER, gl::R16, gl::UNSIGNED_SHORT)),
-    (Format::R(Size::Sixteen), Type::NormIntegral) => Some((gl::RED_INTEGER, gl::R16_SNORM, gl::SHORT)),
+    (Format::R(Size::Sixteen), Type::NormUnsigned) => Some((gl::RED, gl::R16, gl::UNSIGNED_SHORT)),
+    (Format::R(Size::Sixteen), Type::NormIntegral) => Some((gl::RED, gl::R16_SNORM, gl::SHORT)),
     (Format::R(Size::Sixteen), Type::Integral) => Some((gl::RED_INTEGER, gl::R16I, gl::SHORT)),
     (Format::R(Size::Sixteen), Type::Unsigned) => Some((gl::RED_INTEGER, gl::R16UI, gl::UNSIGNED_SHORT)),
+    (Format::R(Size::Sixteen), Type::Floating) => Some((gl::RED, gl::R16F, gl::FLOAT)),
 
     (Format::R(Size::ThirtyTwo), Type::NormUnsigned) => Some((gl::RED_INTEGER, gl::RED, gl::UNSIGNED_INT)),
     (Format::R(Size::ThirtyTwo), Type::NormIntegral) => Some((gl::RED_INTEGER, gl::RED, gl::INT)),
@@ -935,6 +1129,7 @@ pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GL
     (Format::RG(Size::Sixteen, Size::Sixteen), Type::NormIntegral) => Some((gl::RG, gl::RG16_SNORM, gl::SHORT)),
     (Format::RG(Size::Sixteen, Size::Sixteen), Type::Integral) => Some((gl::RG_INTEGER, gl::RG16I, gl::SHORT)),
     (Format::RG(Size::Sixteen, Size::Sixteen), Type::Unsigned) => Some((gl::RG_INTEGER, gl::RG16UI, gl::UNSIGNED_SHORT)),
+    (Format::RG(Size::Sixteen, Size::Sixteen), Type::Floating) => Some((gl::RG, gl::RG16F, gl::FLOAT)),
 
     (Format::RG(Size::ThirtyTwo, Size::ThirtyTwo), Type::NormUnsigned) => Some((gl::RG, gl::RG, gl::UNSIGNED_INT)),
     (Format::RG(Size::ThirtyTwo, Size::ThirtyTwo), Type::NormIntegral) => Some((gl::RG, gl::RG, gl::INT)),
@@ -952,6 +1147,7 @@ pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GL
     (Format::RGB(Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::NormIntegral) => Some((gl::RGB, gl::RGB16_SNORM, gl::SHORT)),
     (Format::RGB(Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Integral) => Some((gl::RGB_INTEGER, gl::RGB16I, gl::SHORT)),
     (Format::RGB(Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Unsigned) => Some((gl::RGB_INTEGER, gl::RGB16UI, gl::UNSIGNED_SHORT)),
+    (Format::RGB(Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Floating) => Some((gl::RGB, gl::RGB16F, gl::FLOAT)),
 
     (Format::RGB(Size::Eleven, Size::Eleven, Size::Ten), Type::Floating) => Some((gl::RGB, gl::R11F_G11F_B10F, gl::FLOAT)),
 
@@ -971,6 +1167,9 @@ pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GL
     (Format::RGBA(Size::Sixteen, Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::NormIntegral) => Some((gl::RGBA, gl::RGBA16_SNORM, gl::SHORT)),
     (Format::RGBA(Size::Sixteen, Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Integral) => Some((gl::RGBA_INTEGER, gl::RGBA16I, gl::SHORT)),
     (Format::RGBA(Size::Sixteen, Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Unsigned) => Some((gl::RGBA_INTEGER, gl::RGBA16UI, gl::UNSIGNED_SHORT)),
+    (Format::RGBA(Size::Sixteen, Size::Sixteen, Size::Sixteen, Size::Sixteen), Type::Floating) => Some((gl::RGBA, gl::RGBA16F, gl::FLOAT)),
+
+    (Format::RGBA(Size::Ten, Size::Ten, Size::Ten, Size::Two), Type::NormUnsigned) => Some((gl::RGBA, gl::RGB10_A2, gl::UNSIGNED_INT_2_10_10_10_REV)),
 
     (Format::RGBA(Size::ThirtyTwo, Size::ThirtyTwo, Size::ThirtyTwo, Size::ThirtyTwo), Type::NormUnsigned) => Some((gl::RGBA, gl::RGBA, gl::UNSIGNED_INT)),
     (Format::RGBA(Size::ThirtyTwo, Size::ThirtyTwo, Size::ThirtyTwo, Size::ThirtyTwo), Type::NormIntegral) => Some((gl::RGBA, gl::RGBA, gl::INT)),
@@ -981,6 +1180,10 @@ pub(crate) fn opengl_pixel_format(pf: PixelFormat) -> Option<(GLenum, GLenum, GL
     // blue, green, red, alpha channels
     (Format::BGRA(Size::Eight, Size::Eight, Size::Eight, Size::Eight), Type::NormUnsigned) => Some((gl::BGRA, gl::BGRA, gl::UNSIGNED_BYTE)),
 
+    // sRGB channels
+    (Format::SRGB(Size::Eight, Size::Eight, Size::Eight), Type::NormUnsigned) => Some((gl::RGB, gl::SRGB8, gl::UNSIGNED_BYTE)),
+    (Format::SRGBA(Size::Eight, Size::Eight, Size::Eight, Size::Eight), Type::NormUnsigned) => Some((gl::RGBA, gl::SRGB8_ALPHA8, gl::UNSIGNED_BYTE)),
+
     (Format::Depth(Size::ThirtyTwo), Type::Floating) => Some((gl::DEPTH_COMPONENT, gl::DEPTH_COMPONENT32F, gl::FLOAT)),
 
     _ => None
@@ -0,0 +1,20 @@
+//! Point size related features.
+//!
+//! Points can either have a fixed size, set in the render state, or a size computed by the shader
+//! program by writing to `gl_PointSize`.
+
+/// Size of rasterized points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PointSize {
+  /// Points have a fixed size, in pixels.
+  Fixed(f32),
+  /// Points have the size written by the shader program in `gl_PointSize`.
+  Program,
+}
+
+impl Default for PointSize {
+  /// Points are `1` pixel large.
+  fn default() -> Self {
+    PointSize::Fixed(1.)
+  }
+}
@@ -0,0 +1,34 @@
+//! Polygon mode related features.
+//!
+//! The polygon mode controls how polygons are rasterized: filled, as lines (wireframe) or as
+//! points at their vertices. It is mostly useful to debug meshes.
+
+use crate::metagl::*;
+
+/// How polygons are rasterized.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PolygonMode {
+  /// Polygons are filled.
+  Fill,
+  /// Only the edges of polygons are rasterized, as lines.
+  Line,
+  /// Only the vertices of polygons are rasterized, as points.
+  Point,
+}
+
+impl PolygonMode {
+  pub(crate) fn to_glenum(self) -> GLenum {
+    match self {
+      PolygonMode::Fill => gl::FILL,
+      PolygonMode::Line => gl::LINE,
+      PolygonMode::Point => gl::POINT,
+    }
+  }
+}
+
+impl Default for PolygonMode {
+  /// Polygons are filled.
+  fn default() -> Self {
+    PolygonMode::Fill
+  }
+}
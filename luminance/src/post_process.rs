@@ -0,0 +1,309 @@
+//! Fragment-only post-processing passes.
+//!
+//! Most post-processing passes – FXAA, bloom thresholds and blurs, tone mapping, etc. – shade every
+//! pixel of their output from a few textures and don’t need any geometry. The usual trick is to
+//! render an attributeless triangle covering the viewport, its vertex shader computing the
+//! positions and texture coordinates from `gl_VertexID`: [`Tess::fullscreen_triangle`] builds
+//! such a triangle and [`FULLSCREEN_VS`] is its vertex shader.
+//!
+//! A [`PostProcess`] goes one step further and chains such passes, only taking their fragment
+//! shaders. It renders them with an [`EffectChain`], which manages the intermediate framebuffers:
+//!
+//! ```ignore
+//! let passes = vec![
+//!   PostProcessPass::new(THRESHOLD_FS).set_scale(0.5),
+//!   PostProcessPass::new(BLUR_FS).set_scale(0.5),
+//!   PostProcessPass::new(COMBINE_FS).set_inputs(vec![EffectInput::Source, EffectInput::Previous]),
+//! ];
+//! let bloom = PostProcess::new(&mut surface, size, passes)?;
+//!
+//! bloom.render(&mut builder, scene.color_slot(), &back_buffer, &pipeline_state, |pass, iface| {
+//!   if pass == 2 {
+//!     iface.query().ask("strength").unwrap().update(0.8f32);
+//!   }
+//! })?;
+//! ```
+//!
+//! Fragment shaders read the texture coordinates of their output as `in vec2 v_uv;` and their
+//! inputs – in the order the pass declares them – as `uniform sampler2D input0;`, `input1`, etc.
+//!
+//! [`Tess::fullscreen_triangle`]: crate::tess::Tess::fullscreen_triangle
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::context::GraphicsContext;
+use crate::effect::{EffectChain, EffectError, EffectInput, EffectPass, TargetPool};
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::pipeline::{BindingError, BoundTexture, Builder, PipelineState};
+use crate::pixel::{ColorPixel, RenderablePixel, SamplerType};
+use crate::render_state::RenderState;
+use crate::shader::program::{
+  Program, ProgramError, ProgramInterface, Uniform, UniformBuilder, UniformInterface,
+};
+use crate::tess::{Tess, TessError};
+use crate::texture::{Dim2, Dimensionable, Flat, Layerable, Texture};
+
+/// Maximum number of inputs of a [`PostProcessPass`].
+pub const MAX_INPUTS: usize = 8;
+
+/// Vertex shader of [`Tess::fullscreen_triangle`].
+///
+/// It outputs the texture coordinates of the viewport as `v_uv`, from `(0, 0)` in the bottom-left
+/// corner to `(1, 1)` in the top-right one.
+///
+/// [`Tess::fullscreen_triangle`]: crate::tess::Tess::fullscreen_triangle
+pub const FULLSCREEN_VS: &str = "\
+out vec2 v_uv;
+
+void main() {
+  v_uv = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+  gl_Position = vec4(v_uv * 2. - 1., 0., 1.);
+}
+";
+
+/// Declaration of a pass of a [`PostProcess`]: its fragment shader and an [`EffectPass`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostProcessPass<'a> {
+  fragment: &'a str,
+  pass: EffectPass,
+}
+
+impl<'a> PostProcessPass<'a> {
+  /// Create a new pass shaded by `fragment`, reading the output of the previous pass at full
+  /// resolution.
+  pub fn new(fragment: &'a str) -> Self {
+    PostProcessPass {
+      fragment,
+      pass: EffectPass::new(),
+    }
+  }
+
+  /// Source of the fragment shader of the pass.
+  pub fn fragment(&self) -> &'a str {
+    self.fragment
+  }
+
+  /// Set the inputs of the pass, bound to `input0`, `input1`, etc.
+  ///
+  /// See [`EffectPass::set_inputs`].
+  pub fn set_inputs<I>(self, inputs: I) -> Self where I: IntoIterator<Item = EffectInput> {
+    PostProcessPass {
+      pass: self.pass.set_inputs(inputs),
+      ..self
+    }
+  }
+
+  /// Set the resolution of the pass output, relative to the size of the post-process.
+  ///
+  /// See [`EffectPass::set_scale`].
+  pub fn set_scale(self, scale: f32) -> Self {
+    PostProcessPass {
+      pass: self.pass.set_scale(scale),
+      ..self
+    }
+  }
+}
+
+/// A chain of fragment-only passes rendered with [`FULLSCREEN_VS`].
+///
+/// `P` is the pixel format of the source texture and of all the intermediate framebuffers.
+pub struct PostProcess<P>
+where P: ColorPixel + RenderablePixel,
+      P::SamplerType: 'static {
+  chain: EffectChain<P>,
+  programs: Vec<Program<(), (), PostProcessInterface<P::SamplerType>>>,
+  triangle: Tess,
+}
+
+impl<P> PostProcess<P>
+where P: ColorPixel + RenderablePixel,
+      P::SamplerType: 'static {
+  /// Create a new post-process for the given size, compiling the programs of its passes.
+  pub fn new<C>(
+    ctx: &mut C,
+    size: [u32; 2],
+    passes: Vec<PostProcessPass>,
+  ) -> Result<Self, PostProcessError>
+  where C: GraphicsContext {
+    Self::with_pool(ctx, &TargetPool::new(), size, passes)
+  }
+
+  /// Create a new post-process for the given size, taking its intermediate framebuffers from
+  /// `pool`.
+  ///
+  /// See [`EffectChain::with_pool`].
+  pub fn with_pool<C>(
+    ctx: &mut C,
+    pool: &TargetPool<P>,
+    size: [u32; 2],
+    passes: Vec<PostProcessPass>,
+  ) -> Result<Self, PostProcessError>
+  where C: GraphicsContext {
+    let mut programs = Vec::with_capacity(passes.len());
+
+    for (i, pass) in passes.iter().enumerate() {
+      if pass.pass.inputs().len() > MAX_INPUTS {
+        return Err(PostProcessError::TooManyInputs(i));
+      }
+
+      let program = Program::from_strings(None, FULLSCREEN_VS, None, pass.fragment)
+        .map_err(|e| PostProcessError::ProgramError(i, e))?
+        .ignore_warnings();
+
+      programs.push(program);
+    }
+
+    let passes = passes.into_iter().map(|pass| pass.pass).collect();
+    let chain = EffectChain::with_pool(ctx, pool, size, passes)?;
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+
+    Ok(PostProcess {
+      chain,
+      programs,
+      triangle,
+    })
+  }
+
+  /// Effect chain rendering the passes.
+  pub fn chain(&self) -> &EffectChain<P> {
+    &self.chain
+  }
+
+  /// Size of the post-process.
+  pub fn size(&self) -> [u32; 2] {
+    self.chain.size()
+  }
+
+  /// Change the size of the post-process, re-allocating the intermediate framebuffers.
+  pub fn resize<C>(&mut self, ctx: &mut C, size: [u32; 2]) -> Result<(), PostProcessError>
+  where C: GraphicsContext {
+    self.chain.resize(ctx, size)?;
+    Ok(())
+  }
+
+  /// Render all the passes.
+  ///
+  /// `f` is called once per pass with the index of the pass and its program interface, after its
+  /// inputs are bound: use it to set the other uniforms of the pass, with
+  /// [`ProgramInterface::query`]. The last pass renders to `output`; all passes use
+  /// `pipeline_state`, the viewport being set to their output.
+  ///
+  /// If the inputs of a pass cannot be bound, that pass and the following ones are not rendered.
+  pub fn render<C, L, D, CS, DS, F>(
+    &self,
+    builder: &mut Builder<C>,
+    source: &Texture<Flat, Dim2, P>,
+    output: &Framebuffer<L, D, CS, DS>,
+    pipeline_state: &PipelineState,
+    mut f: F,
+  ) -> Result<(), BindingError>
+  where C: ?Sized + GraphicsContext,
+        L: Layerable,
+        D: Dimensionable,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D>,
+        F: FnMut(usize, &ProgramInterface<PostProcessInterface<P::SamplerType>>) {
+    let programs = &self.programs;
+    let triangle = &self.triangle;
+    let render_state = RenderState::default().set_depth_test(None);
+    let mut result = Ok(());
+
+    self.chain.render(builder, source, output, pipeline_state, |i, inputs, pipeline, mut shd_gate| {
+      if result.is_err() {
+        return;
+      }
+
+      let bound = inputs.iter().map(|input| pipeline.bind_texture(*input));
+      let bound = match bound.collect::<Result<Vec<_>, _>>() {
+        Ok(bound) => bound,
+        Err(e) => {
+          result = Err(e);
+          return;
+        }
+      };
+
+      shd_gate.shade(&programs[i], |iface, mut rdr_gate| {
+        for (uniform, texture) in iface.inputs.iter().zip(&bound) {
+          uniform.update(texture);
+        }
+
+        f(i, &iface);
+        rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(triangle));
+      });
+    });
+
+    result
+  }
+}
+
+/// Uniform interface of the passes of a [`PostProcess`].
+///
+/// It only holds the inputs of the pass; other uniforms are set with
+/// [`ProgramInterface::query`].
+pub struct PostProcessInterface<S> where S: 'static + SamplerType {
+  inputs: Vec<Uniform<&'static BoundTexture<'static, Flat, Dim2, S>>>,
+}
+
+impl<S> UniformInterface for PostProcessInterface<S> where S: 'static + SamplerType {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    // inputs the shader doesn’t use are not active, hence unbound
+    let inputs = (0 .. MAX_INPUTS).map(|i| builder.ask_unbound(&format!("input{}", i))).collect();
+    Ok(PostProcessInterface { inputs })
+  }
+}
+
+/// Errors that can happen while creating a [`PostProcess`].
+#[derive(Debug)]
+pub enum PostProcessError {
+  /// The program of a pass failed to build.
+  ///
+  /// The [`usize`] is the index of the pass.
+  ProgramError(usize, ProgramError),
+  /// A pass has more than [`MAX_INPUTS`] inputs.
+  ///
+  /// The [`usize`] is the index of the pass.
+  TooManyInputs(usize),
+  /// The effect chain rendering the passes couldn’t be created.
+  EffectError(EffectError),
+  /// The fullscreen triangle couldn’t be created.
+  TessError(TessError),
+}
+
+impl fmt::Display for PostProcessError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      PostProcessError::ProgramError(pass, ref e) => {
+        write!(f, "post-processing pass {} program error: {}", pass, e)
+      }
+
+      PostProcessError::TooManyInputs(pass) => {
+        write!(f, "post-processing pass {} has more than {} inputs", pass, MAX_INPUTS)
+      }
+
+      PostProcessError::EffectError(ref e) => write!(f, "post-processing effect error: {}", e),
+      PostProcessError::TessError(ref e) => {
+        write!(f, "post-processing tessellation error: {:?}", e)
+      }
+    }
+  }
+}
+
+impl From<EffectError> for PostProcessError {
+  fn from(e: EffectError) -> Self {
+    PostProcessError::EffectError(e)
+  }
+}
+
+impl From<TessError> for PostProcessError {
+  fn from(e: TessError) -> Self {
+    PostProcessError::TessError(e)
+  }
+}
@@ -0,0 +1,137 @@
+//! CPU and GPU profiling zones.
+//!
+//! This module is only available with the `profiling` feature. When enabled, every gate –
+//! [`Builder::pipeline`], [`ShadingGate::shade`], [`RenderGate::render`] and [`TessGate::render`]
+//! – is wrapped in a *zone*: the CPU time spent in the gate closure is reported, as well as the
+//! GPU time spent executing its commands, measured with timer queries.
+//!
+//! Zones are reported to the [`Profiler`] installed with [`set_profiler`]. luminance doesn’t
+//! depend on any profiler; plugging one – [Tracy], [Optick], etc. – is a matter of implementing
+//! [`Profiler`] by forwarding the zones to the profiler’s client library. For instance, with
+//! Tracy, CPU zones map to Tracy zones and GPU zones to Tracy GPU zones, GPU timestamps being in
+//! nanoseconds.
+//!
+//! GPU timings are not available right away: they are collected – and reported – at the beginning
+//! of the next pipelines, once the GPU is done with them.
+//!
+//! [`Builder::pipeline`]: crate::pipeline::Builder::pipeline
+//! [`ShadingGate::shade`]: crate::pipeline::ShadingGate::shade
+//! [`RenderGate::render`]: crate::pipeline::RenderGate::render
+//! [`TessGate::render`]: crate::pipeline::TessGate::render
+//! [Tracy]: https://github.com/wolfpld/tracy
+//! [Optick]: https://github.com/bombomby/optick
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::metagl::*;
+
+/// A profiler zones are reported to.
+pub trait Profiler {
+  /// A CPU zone starts.
+  fn begin_cpu_zone(&self, name: &'static str);
+
+  /// The last CPU zone that was started ends.
+  fn end_cpu_zone(&self, name: &'static str);
+
+  /// A GPU zone was measured.
+  ///
+  /// `start` and `end` are GPU timestamps, in nanoseconds.
+  fn gpu_zone(&self, name: &'static str, start: u64, end: u64);
+}
+
+/// Install a profiler for the current thread, returning the previously installed one.
+///
+/// Pass `None` to stop profiling. Because OpenGL contexts are bound to a thread, profilers are
+/// installed per thread.
+pub fn set_profiler(profiler: Option<Box<dyn Profiler>>) -> Option<Box<dyn Profiler>> {
+  PROFILER.with(|p| p.replace(profiler))
+}
+
+thread_local! {
+  static PROFILER: RefCell<Option<Box<dyn Profiler>>> = RefCell::new(None);
+
+  // GPU zones waiting for their timer queries to be available
+  static PENDING: RefCell<VecDeque<PendingGpuZone>> = RefCell::new(VecDeque::new());
+}
+
+struct PendingGpuZone {
+  name: &'static str,
+  queries: [GLuint; 2],
+}
+
+/// A profiling zone, ending when dropped.
+pub(crate) struct Zone {
+  name: &'static str,
+  // None if no profiler was installed when the zone started
+  queries: Option<[GLuint; 2]>,
+}
+
+impl Zone {
+  pub(crate) fn new(name: &'static str) -> Self {
+    let queries = PROFILER.with(|p| {
+      p.borrow().as_ref().map(|profiler| {
+        profiler.begin_cpu_zone(name);
+
+        let mut queries = [0; 2];
+
+        unsafe {
+          gl::GenQueries(2, queries.as_mut_ptr());
+          gl::QueryCounter(queries[0], gl::TIMESTAMP);
+        }
+
+        queries
+      })
+    });
+
+    Zone { name, queries }
+  }
+}
+
+impl Drop for Zone {
+  fn drop(&mut self) {
+    if let Some(queries) = self.queries {
+      unsafe { gl::QueryCounter(queries[1], gl::TIMESTAMP) };
+
+      PENDING.with(|pending| pending.borrow_mut().push_back(PendingGpuZone { name: self.name, queries }));
+      PROFILER.with(|p| {
+        if let Some(ref profiler) = *p.borrow() {
+          profiler.end_cpu_zone(self.name);
+        }
+      });
+    }
+  }
+}
+
+/// Report the GPU zones which timings are available, in submission order.
+pub(crate) fn collect_gpu_zones() {
+  PENDING.with(|pending| {
+    let mut pending = pending.borrow_mut();
+
+    while let Some(zone) = pending.front() {
+      let mut available = gl::FALSE as GLint;
+      unsafe { gl::GetQueryObjectiv(zone.queries[1], gl::QUERY_RESULT_AVAILABLE, &mut available) };
+
+      if available == gl::FALSE as GLint {
+        break;
+      }
+
+      let mut start = 0;
+      let mut end = 0;
+
+      unsafe {
+        gl::GetQueryObjectui64v(zone.queries[0], gl::QUERY_RESULT, &mut start);
+        gl::GetQueryObjectui64v(zone.queries[1], gl::QUERY_RESULT, &mut end);
+        gl::DeleteQueries(2, zone.queries.as_ptr());
+      }
+
+      PROFILER.with(|p| {
+        if let Some(ref profiler) = *p.borrow() {
+          profiler.gpu_zone(zone.name, start, end);
+        }
+      });
+
+      pending.pop_front();
+    }
+  });
+}
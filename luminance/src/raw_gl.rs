@@ -0,0 +1,138 @@
+//! Scoped raw OpenGL access.
+//!
+//! luminance caches the OpenGL state it sets, so that it doesn’t issue the same calls twice. Raw
+//! OpenGL calls – to use a feature luminance doesn’t wrap, or to integrate a third-party renderer
+//! – change that state behind its back, and luminance then skips calls it believes redundant.
+//!
+//! [`GraphicsContext::with_raw_gl`] runs raw OpenGL code in a closure given a [`RawGl`], on which
+//! the code declares the [`TrackedStates`] it changes. When the closure returns – or panics –, the
+//! cached values of those states, and only those, are issued again, so that the cache matches the
+//! context:
+//!
+//! ```ignore
+//! surface.with_raw_gl(|raw| {
+//!   raw.touch(TrackedStates::BLENDING | TrackedStates::PROGRAM);
+//!
+//!   unsafe {
+//!     gl::Enable(gl::BLEND);
+//!     gl::UseProgram(my_program);
+//!     // …
+//!   }
+//! });
+//! ```
+//!
+//! States luminance doesn’t track – scissor tests, color masks, etc. – are not restored: raw
+//! OpenGL code changing them must reset them to their default values.
+//!
+//! [`GraphicsContext::with_raw_gl`]: crate::context::GraphicsContext::with_raw_gl
+
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+#[cfg(feature = "std")]
+use std::ops::BitOr;
+#[cfg(feature = "std")]
+use std::rc::Rc;
+
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+#[cfg(not(feature = "std"))]
+use core::ops::BitOr;
+
+use crate::state::GraphicsState;
+
+/// Groups of OpenGL states tracked by luminance.
+///
+/// Groups are combined with `|`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TrackedStates(u32);
+
+impl TrackedStates {
+  /// No state.
+  pub const NONE: Self = TrackedStates(0);
+  /// Viewport.
+  pub const VIEWPORT: Self = TrackedStates(1 << 0);
+  /// Clear color, depth and stencil values.
+  pub const CLEAR_VALUES: Self = TrackedStates(1 << 1);
+  /// Blending: whether it’s enabled, for every draw buffer, equations, factors and blend color.
+  pub const BLENDING: Self = TrackedStates(1 << 2);
+  /// Depth test, depth clamping and polygon offset.
+  pub const DEPTH: Self = TrackedStates(1 << 3);
+  /// Stencil test and operations.
+  pub const STENCIL: Self = TrackedStates(1 << 4);
+  /// Face culling.
+  pub const FACE_CULLING: Self = TrackedStates(1 << 5);
+  /// Primitive restart, point size, line width, polygon mode, dithering, sRGB framebuffer
+  /// conversion and patch size.
+  pub const RASTERIZATION: Self = TrackedStates(1 << 6);
+  /// Active texture unit and the textures bound to texture units.
+  pub const TEXTURES: Self = TrackedStates(1 << 7);
+  /// Bound vertex array and buffers, including uniform buffer bindings.
+  pub const BUFFERS: Self = TrackedStates(1 << 8);
+  /// Bound draw and read framebuffers.
+  pub const FRAMEBUFFERS: Self = TrackedStates(1 << 9);
+  /// Program in use.
+  pub const PROGRAM: Self = TrackedStates(1 << 10);
+  /// All the tracked states.
+  pub const ALL: Self = TrackedStates((1 << 11) - 1);
+
+  /// Whether all the states of `other` are part of these states.
+  pub fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+}
+
+impl BitOr for TrackedStates {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    TrackedStates(self.0 | rhs.0)
+  }
+}
+
+/// Raw OpenGL access, given by [`GraphicsContext::with_raw_gl`].
+///
+/// [`GraphicsContext::with_raw_gl`]: crate::context::GraphicsContext::with_raw_gl
+pub struct RawGl {
+  state: Rc<RefCell<GraphicsState>>,
+  touched: TrackedStates,
+}
+
+impl RawGl {
+  pub(crate) fn new(state: Rc<RefCell<GraphicsState>>) -> Self {
+    RawGl {
+      state,
+      touched: TrackedStates::NONE,
+    }
+  }
+
+  /// Declare states the raw OpenGL code changes.
+  ///
+  /// Their cached values are issued again once the raw OpenGL code is done.
+  pub fn touch(&mut self, states: TrackedStates) -> &mut Self {
+    self.touched = self.touched | states;
+    self
+  }
+
+  /// States declared so far.
+  pub fn touched(&self) -> TrackedStates {
+    self.touched
+  }
+
+  /// Graphics state of the context, to query its limits or extensions.
+  pub fn state(&self) -> &Rc<RefCell<GraphicsState>> {
+    &self.state
+  }
+}
+
+impl Drop for RawGl {
+  fn drop(&mut self) {
+    // the state might already be borrowed if a panic unwinds through luminance code
+    if let Ok(mut state) = self.state.try_borrow_mut() {
+      if state.check_usable().is_ok() {
+        unsafe { state.restore_tracked(self.touched) };
+      }
+    }
+  }
+}
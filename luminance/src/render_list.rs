@@ -0,0 +1,659 @@
+//! Recorded render commands.
+//!
+//! The pipeline gates issue their commands right away, on the thread of the context. A
+//! [`RenderList`] records the same commands – the programs [`ShadingGate::shade`] uses, the render
+//! states [`RenderGate::render`] sets, the uniform updates and the tessellations a [`TessGate`]
+//! renders – into a plain command list, which is replayed later with [`ShadingGate::replay`].
+//!
+//! A render list holds no OpenGL object: programs, tessellations and textures are referred to by
+//! identifiers, given by a [`RenderResources`] registry on the thread of the context, and uniform
+//! values are copied when recorded. Render lists are then [`Send`]: they can be built on any thread
+//! – one per part of the scene, for instance – and sent to the thread of the context:
+//!
+//! ```ignore
+//! // on the thread of the context
+//! let mut resources = RenderResources::new();
+//! let program_id = resources.add_program(&program)?;
+//! let tess_id = resources.add_tess(&tess);
+//! let time = program.interface().query().ask_handle::<f32>("t")?;
+//!
+//! // on any thread
+//! let mut list = RenderList::new();
+//! list
+//!   .use_program(program_id)
+//!   .set_render_state(RenderState::default())
+//!   .set_uniform(time, t)
+//!   .draw(tess_id);
+//!
+//! // back on the thread of the context
+//! builder.pipeline(&back_buffer, &PipelineState::default(), |_, mut shd_gate| {
+//!   shd_gate.replay(&list, &resources).expect("replay");
+//! });
+//! ```
+//!
+//! Commands are replayed in the order they were recorded. To have draws reordered so that the
+//! ones sharing a program and textures are submitted together, collect them in a
+//! [`BatchedShadingGate`] instead.
+//!
+//! [`ShadingGate::shade`]: crate::pipeline::ShadingGate::shade
+//! [`ShadingGate::replay`]: crate::pipeline::ShadingGate::replay
+//! [`RenderGate::render`]: crate::pipeline::RenderGate::render
+//! [`TessGate`]: crate::pipeline::TessGate
+
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::marker::PhantomData;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::marker::PhantomData;
+
+use crate::linear::{M22, M33, M44};
+use crate::metagl::*;
+use crate::pipeline::BindingError;
+use crate::pixel::Pixel;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, UniformHandle, UniformInterface};
+use crate::state::PoisonCause;
+use crate::tess::Tess;
+use crate::texture::{Dimensionable, Layerable, Texture};
+use crate::vertex::Semantics;
+
+/// Identifier of a program registered in a [`RenderResources`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ProgramId(usize);
+
+/// Identifier of a tessellation registered in a [`RenderResources`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TessId(usize);
+
+/// Identifier of a texture registered in a [`RenderResources`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TextureId(usize);
+
+/// Registry of the objects render lists refer to.
+///
+/// Objects are borrowed for the lifetime of the registry, which lives on the thread of the
+/// context; only their identifiers are given to the threads recording render lists.
+#[derive(Clone, Default)]
+pub struct RenderResources<'a> {
+  programs: Vec<GLuint>,
+  tessellations: Vec<&'a Tess>,
+  // (target, handle)
+  textures: Vec<(GLenum, GLuint)>,
+  // registry the objects of layered registries are looked up in first
+  parent: Option<&'a RenderResources<'a>>,
+  _borrow: PhantomData<&'a ()>,
+}
+
+impl<'a> RenderResources<'a> {
+  /// Create an empty registry.
+  pub fn new() -> Self {
+    RenderResources {
+      programs: Vec::new(),
+      tessellations: Vec::new(),
+      textures: Vec::new(),
+      parent: None,
+      _borrow: PhantomData,
+    }
+  }
+
+  // Create an empty registry on top of this one: its identifiers stay valid and the objects
+  // registered afterwards get new ones, without copying this registry.
+  pub(crate) fn layered(&'a self) -> Self {
+    RenderResources {
+      parent: Some(self),
+      ..RenderResources::new()
+    }
+  }
+
+  /// Register a program.
+  ///
+  /// With the `thread-check` feature, programs created on another thread are refused.
+  pub fn add_program<In, Out, Uni>(
+    &mut self,
+    program: &'a Program<In, Out, Uni>,
+  ) -> Result<ProgramId, PoisonCause>
+  where In: Semantics,
+        Uni: UniformInterface {
+    program.check_usable()?;
+    Ok(self.push_program(program.handle()))
+  }
+
+  // Register a program without checking it, for programs owned by objects that are not Send and
+  // hence stay on the thread of the context.
+  pub(crate) fn push_program(&mut self, handle: GLuint) -> ProgramId {
+    self.programs.push(handle);
+    ProgramId(self.program_count() - 1)
+  }
+
+  /// Register a tessellation.
+  pub fn add_tess(&mut self, tess: &'a Tess) -> TessId {
+    self.tessellations.push(tess);
+    TessId(self.tess_count() - 1)
+  }
+
+  /// Register a texture.
+  ///
+  /// Textures of a lost context are refused.
+  pub fn add_texture<L, D, P>(
+    &mut self,
+    texture: &'a Texture<L, D, P>,
+  ) -> Result<TextureId, PoisonCause>
+  where L: Layerable,
+        D: Dimensionable,
+        P: Pixel {
+    texture.check_usable()?;
+
+    self.textures.push((texture.target(), texture.handle()));
+    Ok(TextureId(self.texture_count() - 1))
+  }
+
+  /// Number of registered programs, tessellations and textures.
+  pub fn len(&self) -> usize {
+    self.program_count() + self.tess_count() + self.texture_count()
+  }
+
+  /// Whether nothing is registered.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub(crate) fn program(&self, id: ProgramId) -> Result<GLuint, RenderListError> {
+    match self.parent {
+      Some(parent) if id.0 < parent.program_count() => parent.program(id),
+      _ => {
+        let base = self.program_count() - self.programs.len();
+        let local = id.0.checked_sub(base).and_then(|i| self.programs.get(i));
+        local.cloned().ok_or(RenderListError::UnknownProgram(id))
+      }
+    }
+  }
+
+  pub(crate) fn tess(&self, id: TessId) -> Result<&'a Tess, RenderListError> {
+    match self.parent {
+      Some(parent) if id.0 < parent.tess_count() => parent.tess(id),
+      _ => {
+        let base = self.tess_count() - self.tessellations.len();
+        let local = id.0.checked_sub(base).and_then(|i| self.tessellations.get(i));
+        local.cloned().ok_or(RenderListError::UnknownTess(id))
+      }
+    }
+  }
+
+  pub(crate) fn texture(&self, id: TextureId) -> Result<(GLenum, GLuint), RenderListError> {
+    match self.parent {
+      Some(parent) if id.0 < parent.texture_count() => parent.texture(id),
+      _ => {
+        let base = self.texture_count() - self.textures.len();
+        let local = id.0.checked_sub(base).and_then(|i| self.textures.get(i));
+        local.cloned().ok_or(RenderListError::UnknownTexture(id))
+      }
+    }
+  }
+
+  fn program_count(&self) -> usize {
+    self.parent.map_or(0, RenderResources::program_count) + self.programs.len()
+  }
+
+  fn tess_count(&self) -> usize {
+    self.parent.map_or(0, RenderResources::tess_count) + self.tessellations.len()
+  }
+
+  fn texture_count(&self) -> usize {
+    self.parent.map_or(0, RenderResources::texture_count) + self.textures.len()
+  }
+}
+
+/// Value of a uniform recorded in a [`RenderList`].
+///
+/// Values are converted from the types they’re given with by [`RenderList::set_uniform`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+  /// 32-bit signed integer.
+  Int(i32),
+  /// 2D signed integral vector.
+  IVec2([i32; 2]),
+  /// 3D signed integral vector.
+  IVec3([i32; 3]),
+  /// 4D signed integral vector.
+  IVec4([i32; 4]),
+  /// 32-bit unsigned integer.
+  UInt(u32),
+  /// 2D unsigned integral vector.
+  UIVec2([u32; 2]),
+  /// 3D unsigned integral vector.
+  UIVec3([u32; 3]),
+  /// 4D unsigned integral vector.
+  UIVec4([u32; 4]),
+  /// 32-bit floating-point number.
+  Float(f32),
+  /// 2D floating-point vector.
+  Vec2([f32; 2]),
+  /// 3D floating-point vector.
+  Vec3([f32; 3]),
+  /// 4D floating-point vector.
+  Vec4([f32; 4]),
+  /// Boolean.
+  Bool(bool),
+  /// 2×2 floating-point matrix.
+  M22(M22),
+  /// 3×3 floating-point matrix.
+  M33(M33),
+  /// 4×4 floating-point matrix.
+  M44(M44),
+}
+
+impl UniformValue {
+  // Upload the value to the uniform at index `index` of the program in use; the calls are the ones
+  // of the matching Uniformable implementations.
+  pub(crate) unsafe fn upload(&self, index: GLint) {
+    match *self {
+      UniformValue::Int(x) => gl::Uniform1i(index, x),
+      UniformValue::IVec2(ref x) => gl::Uniform2iv(index, 1, x.as_ptr()),
+      UniformValue::IVec3(ref x) => gl::Uniform3iv(index, 1, x.as_ptr()),
+      UniformValue::IVec4(ref x) => gl::Uniform4iv(index, 1, x.as_ptr()),
+      UniformValue::UInt(x) => gl::Uniform1ui(index, x),
+      UniformValue::UIVec2(ref x) => gl::Uniform2uiv(index, 1, x.as_ptr()),
+      UniformValue::UIVec3(ref x) => gl::Uniform3uiv(index, 1, x.as_ptr()),
+      UniformValue::UIVec4(ref x) => gl::Uniform4uiv(index, 1, x.as_ptr()),
+      UniformValue::Float(x) => gl::Uniform1f(index, x),
+      UniformValue::Vec2(ref x) => gl::Uniform2fv(index, 1, x.as_ptr()),
+      UniformValue::Vec3(ref x) => gl::Uniform3fv(index, 1, x.as_ptr()),
+      UniformValue::Vec4(ref x) => gl::Uniform4fv(index, 1, x.as_ptr()),
+      UniformValue::Bool(x) => gl::Uniform1ui(index, x as GLuint),
+      UniformValue::M22(ref x) => gl::UniformMatrix2fv(index, 1, gl::FALSE, x.as_ptr() as _),
+      UniformValue::M33(ref x) => gl::UniformMatrix3fv(index, 1, gl::FALSE, x.as_ptr() as _),
+      UniformValue::M44(ref x) => gl::UniformMatrix4fv(index, 1, gl::FALSE, x.as_ptr() as _),
+    }
+  }
+}
+
+macro_rules! impl_from_for_uniform_value {
+  ($($t:ty => $variant:ident),* $(,)?) => {
+    $(
+      impl From<$t> for UniformValue {
+        fn from(x: $t) -> Self {
+          UniformValue::$variant(x)
+        }
+      }
+    )*
+  }
+}
+
+impl_from_for_uniform_value!(
+  i32 => Int,
+  [i32; 2] => IVec2,
+  [i32; 3] => IVec3,
+  [i32; 4] => IVec4,
+  u32 => UInt,
+  [u32; 2] => UIVec2,
+  [u32; 3] => UIVec3,
+  [u32; 4] => UIVec4,
+  f32 => Float,
+  [f32; 2] => Vec2,
+  [f32; 3] => Vec3,
+  [f32; 4] => Vec4,
+  bool => Bool,
+  M22 => M22,
+  M33 => M33,
+  M44 => M44,
+);
+
+/// A recorded draw.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Draw {
+  pub(crate) tess: TessId,
+  // (start vertex, number of vertices); the whole tessellation if None
+  pub(crate) range: Option<(usize, usize)>,
+  // number of instances; the ones of the tessellation if None
+  pub(crate) inst_nb: Option<usize>,
+}
+
+/// A recorded command.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Command {
+  UseProgram(ProgramId),
+  SetRenderState(RenderState),
+  // (program, index, value)
+  SetUniform(GLuint, GLint, UniformValue),
+  // (program, index, texture)
+  BindTexture(GLuint, GLint, TextureId),
+  Draw(Draw),
+}
+
+/// A list of render commands, recorded on any thread and replayed on the thread of the context
+/// with [`ShadingGate::replay`].
+///
+/// See the [module documentation](index.html) for further details.
+///
+/// [`ShadingGate::replay`]: crate::pipeline::ShadingGate::replay
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderList {
+  commands: Vec<Command>,
+}
+
+impl RenderList {
+  /// Create an empty render list.
+  pub fn new() -> Self {
+    RenderList {
+      commands: Vec::new(),
+    }
+  }
+
+  /// Record the use of a program for the next commands – what [`ShadingGate::shade`] does.
+  ///
+  /// The texture units bound with [`RenderList::bind_texture`] for the previous program are
+  /// released.
+  ///
+  /// [`ShadingGate::shade`]: crate::pipeline::ShadingGate::shade
+  pub fn use_program(&mut self, program: ProgramId) -> &mut Self {
+    self.commands.push(Command::UseProgram(program));
+    self
+  }
+
+  /// Record a render state for the next draws – what [`RenderGate::render`] does.
+  ///
+  /// [`RenderGate::render`]: crate::pipeline::RenderGate::render
+  pub fn set_render_state(&mut self, render_state: RenderState) -> &mut Self {
+    self.commands.push(Command::SetRenderState(render_state));
+    self
+  }
+
+  /// Record the update of a uniform of the program in use.
+  ///
+  /// The value is copied: later changes to the variable it comes from don’t affect the list.
+  pub fn set_uniform<T>(&mut self, uniform: UniformHandle<T>, value: T) -> &mut Self
+  where T: Into<UniformValue> {
+    let command = Command::SetUniform(uniform.program(), uniform.index(), value.into());
+    self.commands.push(command);
+    self
+  }
+
+  /// Record the binding of a texture to the sampler uniform `uniform` of the program in use.
+  ///
+  /// A texture unit is allocated when replaying; binding another texture to the same uniform
+  /// reuses it.
+  pub fn bind_texture<T>(&mut self, uniform: UniformHandle<T>, texture: TextureId) -> &mut Self {
+    let command = Command::BindTexture(uniform.program(), uniform.index(), texture);
+    self.commands.push(command);
+    self
+  }
+
+  /// Record the draw of a whole tessellation.
+  pub fn draw(&mut self, tess: TessId) -> &mut Self {
+    self.commands.push(Command::Draw(Draw {
+      tess,
+      range: None,
+      inst_nb: None,
+    }));
+    self
+  }
+
+  /// Record the draw of `nb` vertices of a tessellation, starting at vertex `start`, with `inst_nb`
+  /// instances.
+  ///
+  /// The slice is checked against the tessellation when replaying.
+  pub fn draw_slice(&mut self, tess: TessId, start: usize, nb: usize, inst_nb: usize) -> &mut Self {
+    self.commands.push(Command::Draw(Draw {
+      tess,
+      range: Some((start, nb)),
+      inst_nb: Some(inst_nb),
+    }));
+    self
+  }
+
+  /// Number of recorded commands.
+  pub fn len(&self) -> usize {
+    self.commands.len()
+  }
+
+  /// Whether no command is recorded.
+  pub fn is_empty(&self) -> bool {
+    self.commands.is_empty()
+  }
+
+  /// Remove all the recorded commands, keeping the allocated memory.
+  pub fn clear(&mut self) {
+    self.commands.clear();
+  }
+
+  /// Append the commands of another render list, typically recorded on another thread.
+  pub fn append(&mut self, other: &mut RenderList) {
+    self.commands.append(&mut other.commands);
+  }
+
+  pub(crate) fn commands(&self) -> &[Command] {
+    &self.commands
+  }
+}
+
+// A draw collected by a BatchedShadingGate, with everything it needs.
+#[derive(Clone, Debug, PartialEq)]
+struct BatchEntry {
+  program: ProgramId,
+  render_state: RenderState,
+  draw: Draw,
+  // (program, index, value)
+  uniforms: Vec<(GLuint, GLint, UniformValue)>,
+  // (program, index, texture), in the order they were bound
+  textures: Vec<(GLuint, GLint, TextureId)>,
+}
+
+/// A shading gate collecting draws and submitting them sorted by program and texture bindings.
+///
+/// Unlike the commands of a [`RenderList`], each draw is a whole entry – program, render state,
+/// tessellation, uniform values and texture bindings –, independent from the draws before it. The
+/// entries are then reordered to group the ones sharing a program, then the ones sharing textures,
+/// which saves the program switches and texture bindings of scenes with many materials. Entries
+/// comparing equal keep the order they were collected in.
+///
+/// A batched shading gate is given by [`ShadingGate::batched`], which submits the entries once the
+/// closure returns. Since they’re reordered, an entry must set all the uniforms it relies on:
+/// uniforms it doesn’t set keep the values of the entry submitted before it, whichever it is.
+///
+/// ```ignore
+/// shd_gate.batched(&resources, |batch| {
+///   for object in &scene {
+///     batch
+///       .draw(object.program, object.render_state, object.tess)
+///       .set_uniform(object.model_uniform, object.model)
+///       .bind_texture(object.albedo_uniform, object.albedo);
+///   }
+/// })?;
+/// ```
+///
+/// It holds no OpenGL object either and can be filled on any thread, then submitted with
+/// [`ShadingGate::submit_batch`].
+///
+/// [`ShadingGate::batched`]: crate::pipeline::ShadingGate::batched
+/// [`ShadingGate::submit_batch`]: crate::pipeline::ShadingGate::submit_batch
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchedShadingGate {
+  entries: Vec<BatchEntry>,
+}
+
+impl BatchedShadingGate {
+  /// Create an empty batched shading gate.
+  pub fn new() -> Self {
+    BatchedShadingGate {
+      entries: Vec::new(),
+    }
+  }
+
+  /// Collect the draw of a whole tessellation with `program` and `render_state`.
+  ///
+  /// The uniforms and textures of the draw are set on the returned [`BatchedDraw`].
+  pub fn draw(
+    &mut self,
+    program: ProgramId,
+    render_state: RenderState,
+    tess: TessId,
+  ) -> BatchedDraw<'_> {
+    self.push(program, render_state, Draw {
+      tess,
+      range: None,
+      inst_nb: None,
+    })
+  }
+
+  /// Collect the draw of `nb` vertices of a tessellation, starting at vertex `start`, with
+  /// `inst_nb` instances.
+  ///
+  /// The slice is checked against the tessellation when submitting.
+  pub fn draw_slice(
+    &mut self,
+    program: ProgramId,
+    render_state: RenderState,
+    tess: TessId,
+    (start, nb, inst_nb): (usize, usize, usize),
+  ) -> BatchedDraw<'_> {
+    self.push(program, render_state, Draw {
+      tess,
+      range: Some((start, nb)),
+      inst_nb: Some(inst_nb),
+    })
+  }
+
+  /// Number of collected draws.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Whether no draw is collected.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Remove all the collected draws, keeping the allocated memory.
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+
+  fn push(&mut self, program: ProgramId, render_state: RenderState, draw: Draw) -> BatchedDraw<'_> {
+    self.entries.push(BatchEntry {
+      program,
+      render_state,
+      draw,
+      uniforms: Vec::new(),
+      textures: Vec::new(),
+    });
+
+    BatchedDraw {
+      entry: self.entries.last_mut().unwrap(),
+    }
+  }
+
+  // Sort the collected draws and record them into a render list, without the redundant program
+  // switches, render states and texture bindings.
+  pub(crate) fn record_sorted(&mut self, list: &mut RenderList) {
+    self.entries.sort_by(|a, b| {
+      a.program.cmp(&b.program).then_with(|| {
+        let b_textures = b.textures.iter().map(|&(_, _, id)| id);
+        a.textures.iter().map(|&(_, _, id)| id).cmp(b_textures)
+      })
+    });
+
+    let mut program = None;
+    let mut render_state = None;
+    let mut textures: Vec<(GLint, TextureId)> = Vec::new();
+
+    for entry in &self.entries {
+      if program != Some(entry.program) {
+        list.commands.push(Command::UseProgram(entry.program));
+        program = Some(entry.program);
+        textures.clear();
+      }
+
+      if render_state.as_ref() != Some(&entry.render_state) {
+        list.commands.push(Command::SetRenderState(entry.render_state));
+        render_state = Some(entry.render_state);
+      }
+
+      for &(uni_program, index, value) in &entry.uniforms {
+        list.commands.push(Command::SetUniform(uni_program, index, value));
+      }
+
+      for &(uni_program, index, id) in &entry.textures {
+        match textures.iter_mut().find(|&&mut (i, _)| i == index) {
+          Some(&mut (_, bound)) if bound == id => continue,
+          Some(bound) => bound.1 = id,
+          None => textures.push((index, id)),
+        }
+
+        list.commands.push(Command::BindTexture(uni_program, index, id));
+      }
+
+      list.commands.push(Command::Draw(entry.draw));
+    }
+  }
+}
+
+/// A draw collected by a [`BatchedShadingGate`], to set its uniforms and textures.
+pub struct BatchedDraw<'a> {
+  entry: &'a mut BatchEntry,
+}
+
+impl<'a> BatchedDraw<'a> {
+  /// Set a uniform of the program of the draw.
+  pub fn set_uniform<T>(&mut self, uniform: UniformHandle<T>, value: T) -> &mut Self
+  where T: Into<UniformValue> {
+    self.entry.uniforms.push((uniform.program(), uniform.index(), value.into()));
+    self
+  }
+
+  /// Bind a texture to a sampler uniform of the program of the draw.
+  pub fn bind_texture<T>(&mut self, uniform: UniformHandle<T>, texture: TextureId) -> &mut Self {
+    self.entry.textures.push((uniform.program(), uniform.index(), texture));
+    self
+  }
+}
+
+/// Errors that might happen when replaying a [`RenderList`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RenderListError {
+  /// The program is not registered in the [`RenderResources`].
+  UnknownProgram(ProgramId),
+  /// The tessellation is not registered in the [`RenderResources`].
+  UnknownTess(TessId),
+  /// The texture is not registered in the [`RenderResources`].
+  UnknownTexture(TextureId),
+  /// A uniform was updated, a texture bound or a tessellation drawn before any program was used.
+  NoProgram,
+  /// A uniform handle of another program than the one in use was used.
+  ///
+  /// The handle program’s OpenGL handle is given.
+  UniformMismatch(GLuint),
+  /// The starting vertex and number of vertices of a draw don’t fit a tessellation.
+  InvalidSlice(TessId, usize, usize),
+  /// A texture couldn’t be bound.
+  Binding(BindingError),
+}
+
+impl fmt::Display for RenderListError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      RenderListError::UnknownProgram(id) => write!(f, "unknown program {:?}", id),
+      RenderListError::UnknownTess(id) => write!(f, "unknown tessellation {:?}", id),
+      RenderListError::UnknownTexture(id) => write!(f, "unknown texture {:?}", id),
+      RenderListError::NoProgram => f.write_str("no program in use"),
+      RenderListError::UniformMismatch(program) => {
+        write!(f, "uniform of program {} used with another program", program)
+      }
+      RenderListError::InvalidSlice(id, start, nb) => {
+        write!(f, "cannot draw {} vertices starting at vertex {} of {:?}", nb, start, id)
+      }
+      RenderListError::Binding(ref e) => write!(f, "binding error: {}", e),
+    }
+  }
+}
+
+impl From<BindingError> for RenderListError {
+  fn from(e: BindingError) -> Self {
+    RenderListError::Binding(e)
+  }
+}
@@ -1,24 +1,58 @@
 //! GPU render state.
 //!
 //! Such a state controls how the GPU must operate some fixed pipeline functionality, such as the
-//! blending, depth test or face culling operations.
+//! blending, depth test, depth clamping, polygon offset, stencil test or face culling operations,
+//! or the size of rasterized points and lines.
 
-use crate::blending::{Equation, Factor};
-use crate::depth_test::DepthComparison;
+use crate::blending::{Equation, Factor, SeparateBlending};
+use crate::depth_test::{DepthComparison, PolygonOffset};
 use crate::face_culling::FaceCulling;
+use crate::framebuffer::DrawBuffers;
+use crate::point_size::PointSize;
+use crate::polygon_mode::PolygonMode;
+use crate::stencil::{StencilOperations, StencilTest};
+
+/// Number of draw buffers which blending configuration can be overridden.
+///
+/// That’s the minimal number of draw buffers an implementation must support.
+pub const MAX_BLENDING_OVERRIDES: usize = 8;
 
 /// GPU render state.
 ///
 /// You can get a default value with `RenderState::default` and set the operations you want with the
 /// various `RenderState::set_*` methods.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct RenderState {
   /// Blending configuration.
   pub(crate) blending: Option<(Equation, Factor, Factor)>,
+  /// Blending configuration of the alpha channel, if different from the RGB one.
+  pub(crate) alpha_blending: Option<(Equation, Factor, Factor)>,
+  /// Constant blending color.
+  pub(crate) blend_color: [f32; 4],
+  /// Blending configuration overrides of the first draw buffers.
+  pub(crate) draw_buffer_blending: [Option<SeparateBlending>; MAX_BLENDING_OVERRIDES],
+  /// Draw buffers blending applies to.
+  pub(crate) blended_draw_buffers: DrawBuffers,
   /// Depth test configuration.
   pub(crate) depth_test: Option<DepthComparison>,
+  /// Depth clamping.
+  pub(crate) depth_clamp: bool,
+  /// Polygon offset.
+  pub(crate) polygon_offset: Option<PolygonOffset>,
   /// Face culling configuration.
   pub(crate) face_culling: Option<FaceCulling>,
+  /// Stencil test configuration.
+  pub(crate) stencil_test: Option<StencilTest>,
+  /// Stencil operations.
+  pub(crate) stencil_operations: StencilOperations,
+  /// Point size.
+  pub(crate) point_size: PointSize,
+  /// Line width.
+  pub(crate) line_width: f32,
+  /// Polygon mode.
+  pub(crate) polygon_mode: PolygonMode,
+  /// Dithering.
+  pub(crate) dithering: bool,
 }
 
 impl RenderState {
@@ -36,6 +70,85 @@ impl RenderState {
     self.blending
   }
 
+  /// Override the blending configuration of the alpha channel.
+  ///
+  /// With `None`, the alpha channel is blended like the RGB channels. This has no effect when
+  /// blending is disabled.
+  pub fn set_alpha_blending<B>(self, alpha_blending: B) -> Self
+  where B: Into<Option<(Equation, Factor, Factor)>> {
+    RenderState {
+      alpha_blending: alpha_blending.into(),
+      ..self
+    }
+  }
+
+  /// Blending configuration of the alpha channel, if different from the RGB one.
+  pub fn alpha_blending(self) -> Option<(Equation, Factor, Factor)> {
+    self.alpha_blending
+  }
+
+  /// Override the constant blending color, used by the `Factor::Constant*` factors.
+  pub fn set_blend_color(self, blend_color: [f32; 4]) -> Self {
+    RenderState {
+      blend_color,
+      ..self
+    }
+  }
+
+  /// Constant blending color.
+  pub fn blend_color(self) -> [f32; 4] {
+    self.blend_color
+  }
+
+  /// Override the blending configuration of a given draw buffer – color slot.
+  ///
+  /// With `None`, the draw buffer uses the configuration of [`RenderState::set_blending`] and
+  /// [`RenderState::set_alpha_blending`]. This has no effect when blending is disabled.
+  ///
+  /// > Note: overrides require *OpenGL 4.0* or `GL_ARB_draw_buffers_blend`, and are ignored
+  /// > without them.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index` is [`MAX_BLENDING_OVERRIDES`] or more.
+  pub fn set_draw_buffer_blending<B>(mut self, index: usize, blending: B) -> Self
+  where B: Into<Option<SeparateBlending>> {
+    assert!(
+      index < MAX_BLENDING_OVERRIDES,
+      "blending cannot be overridden past the first {} draw buffers",
+      MAX_BLENDING_OVERRIDES
+    );
+
+    self.draw_buffer_blending[index] = blending.into();
+    self
+  }
+
+  /// Blending configuration override of a given draw buffer.
+  ///
+  /// Draw buffers past the first [`MAX_BLENDING_OVERRIDES`] are never overridden.
+  pub fn draw_buffer_blending(self, index: usize) -> Option<SeparateBlending> {
+    self.draw_buffer_blending.get(index).copied().flatten()
+  }
+
+  /// Override the draw buffers – color slots – blending applies to.
+  ///
+  /// The other draw buffers are rendered to without blending. This has no effect when blending is
+  /// disabled.
+  ///
+  /// OpenGL ES 3.0 cannot blend some draw buffers only: there, all the draw buffers are blended
+  /// unless the set is empty.
+  pub fn set_blended_draw_buffers(self, blended_draw_buffers: DrawBuffers) -> Self {
+    RenderState {
+      blended_draw_buffers,
+      ..self
+    }
+  }
+
+  /// Draw buffers blending applies to.
+  pub fn blended_draw_buffers(self) -> DrawBuffers {
+    self.blended_draw_buffers
+  }
+
   /// Override the depth test configuration.
   pub fn set_depth_test<D>(self, depth_test: D) -> Self where D: Into<Option<DepthComparison>> {
     let depth_test = depth_test.into();
@@ -47,6 +160,35 @@ impl RenderState {
     self.depth_test
   }
 
+  /// Enable or disable depth clamping.
+  ///
+  /// When enabled, primitives aren’t clipped against the near and far planes: the depth of their
+  /// fragments is clamped to the depth range instead.
+  pub fn set_depth_clamp(self, depth_clamp: bool) -> Self {
+    RenderState { depth_clamp, ..self }
+  }
+
+  /// Depth clamping.
+  pub fn depth_clamp(self) -> bool {
+    self.depth_clamp
+  }
+
+  /// Override the polygon offset.
+  ///
+  /// The offset applies to polygons whatever the polygon mode – filled, lines or points.
+  pub fn set_polygon_offset<P>(self, polygon_offset: P) -> Self
+  where P: Into<Option<PolygonOffset>> {
+    RenderState {
+      polygon_offset: polygon_offset.into(),
+      ..self
+    }
+  }
+
+  /// Polygon offset.
+  pub fn polygon_offset(self) -> Option<PolygonOffset> {
+    self.polygon_offset
+  }
+
   /// Override the face culling configuration.
   pub fn set_face_culling<FC>(self, face_culling: FC) -> Self
   where FC: Into<Option<FaceCulling>> {
@@ -60,19 +202,141 @@ impl RenderState {
   pub fn face_culling(self) -> Option<FaceCulling> {
     self.face_culling
   }
+
+  /// Alter the face culling configuration, if face culling is enabled.
+  ///
+  /// This is typically used to change only the order or only the mode of a shared render state:
+  ///
+  /// ```
+  /// # use luminance::face_culling::{FaceCulling, FaceCullingMode};
+  /// # use luminance::render_state::RenderState;
+  /// let render_state = RenderState::default().set_face_culling(FaceCulling::default());
+  ///
+  /// // mirrored geometry has its winding order reversed
+  /// let mirrored = render_state.override_face_culling(FaceCulling::flipped);
+  /// let front_only = render_state.override_face_culling(|fc| fc.set_mode(FaceCullingMode::Back));
+  /// ```
+  pub fn override_face_culling<F>(self, f: F) -> Self where F: FnOnce(FaceCulling) -> FaceCulling {
+    RenderState {
+      face_culling: self.face_culling.map(f),
+      ..self
+    }
+  }
+
+  /// Override the stencil test configuration.
+  pub fn set_stencil_test<S>(self, stencil_test: S) -> Self where S: Into<Option<StencilTest>> {
+    RenderState {
+      stencil_test: stencil_test.into(),
+      ..self
+    }
+  }
+
+  /// Stencil test configuration.
+  pub fn stencil_test(self) -> Option<StencilTest> {
+    self.stencil_test
+  }
+
+  /// Override the stencil operations.
+  ///
+  /// Stencil operations are only performed when the stencil test is enabled.
+  pub fn set_stencil_operations(self, stencil_operations: StencilOperations) -> Self {
+    RenderState {
+      stencil_operations,
+      ..self
+    }
+  }
+
+  /// Stencil operations.
+  pub fn stencil_operations(self) -> StencilOperations {
+    self.stencil_operations
+  }
+
+  /// Override the point size.
+  pub fn set_point_size(self, point_size: PointSize) -> Self {
+    RenderState { point_size, ..self }
+  }
+
+  /// Point size.
+  pub fn point_size(self) -> PointSize {
+    self.point_size
+  }
+
+  /// Override the line width, in pixels.
+  ///
+  /// > Note: most implementations only support a line width of `1` with core profile contexts.
+  pub fn set_line_width(self, line_width: f32) -> Self {
+    RenderState { line_width, ..self }
+  }
+
+  /// Line width.
+  pub fn line_width(self) -> f32 {
+    self.line_width
+  }
+
+  /// Override the polygon mode.
+  ///
+  /// Use `PolygonMode::Line` to render meshes in wireframe.
+  pub fn set_polygon_mode(self, polygon_mode: PolygonMode) -> Self {
+    RenderState { polygon_mode, ..self }
+  }
+
+  /// Polygon mode.
+  pub fn polygon_mode(self) -> PolygonMode {
+    self.polygon_mode
+  }
+
+  /// Enable or disable dithering.
+  ///
+  /// When enabled, the implementation may dither colors written to framebuffers with less color
+  /// precision than the fragments. What – if anything – it does is implementation-defined; see
+  /// [`Dither`] for a dithering pass that behaves the same everywhere.
+  ///
+  /// [`Dither`]: crate::dither::Dither
+  pub fn set_dithering(self, dithering: bool) -> Self {
+    RenderState { dithering, ..self }
+  }
+
+  /// Dithering.
+  pub fn dithering(self) -> bool {
+    self.dithering
+  }
 }
 
 impl Default for RenderState {
   /// The default `RenderState`.
   ///
   ///   - `blending`: `None`
+  ///   - `alpha_blending`: `None`
+  ///   - `blend_color`: `[0., 0., 0., 0.]`
+  ///   - `draw_buffer_blending`: `None` for every draw buffer
+  ///   - `blended_draw_buffers`: `DrawBuffers::all()`
   ///   - `depth_test`: `Some(DepthComparison::Less)`
+  ///   - `depth_clamp`: `false`
+  ///   - `polygon_offset`: `None`
   ///   - `face_culling`: `None`
+  ///   - `stencil_test`: `None`
+  ///   - `stencil_operations`: `StencilOperations::default()`
+  ///   - `point_size`: `PointSize::Fixed(1.)`
+  ///   - `line_width`: `1.`
+  ///   - `polygon_mode`: `PolygonMode::Fill`
+  ///   - `dithering`: `true`
   fn default() -> Self {
     RenderState {
       blending: None,
+      alpha_blending: None,
+      blend_color: [0., 0., 0., 0.],
+      draw_buffer_blending: [None; MAX_BLENDING_OVERRIDES],
+      blended_draw_buffers: DrawBuffers::all(),
       depth_test: Some(DepthComparison::Less),
+      depth_clamp: false,
+      polygon_offset: None,
       face_culling: None,
+      stencil_test: None,
+      stencil_operations: StencilOperations::default(),
+      point_size: PointSize::default(),
+      line_width: 1.,
+      polygon_mode: PolygonMode::default(),
+      dithering: true,
     }
   }
 }
@@ -0,0 +1,89 @@
+//! Named sampler presets.
+//!
+//! Applications tend to define the same few samplers over and over – nearest filtering for pixel
+//! art, trilinear filtering for surface textures, etc. A [`SamplerPresets`] registry gives them
+//! names, so that texture constructors and data-driven material files can refer to samplers by
+//! name instead of duplicating their definition.
+//!
+//! Registries start with the following built-in presets, which can be replaced:
+//!
+//!   - `"nearest-clamp"`: [`Sampler::nearest_clamp`].
+//!   - `"linear-repeat"`: [`Sampler::linear_repeat`].
+//!   - `"trilinear-aniso16"`: [`Sampler::trilinear_aniso16`].
+//!   - `"shadow-pcf"`: [`Sampler::shadow_pcf`].
+//!
+//! ```ignore
+//! let mut presets = SamplerPresets::new();
+//! presets.insert("terrain", Sampler { max_anisotropy: 8., ..Sampler::trilinear_aniso16() });
+//!
+//! let texture = Texture::new(&mut surface, [512, 512], 4, presets.sampler("terrain")?)?;
+//! ```
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use crate::texture::{Sampler, TextureError};
+
+/// Registry of samplers, by name.
+#[derive(Clone, Debug)]
+pub struct SamplerPresets {
+  presets: BTreeMap<String, Sampler>,
+}
+
+impl SamplerPresets {
+  /// Create a registry holding the built-in presets.
+  pub fn new() -> Self {
+    let mut presets = Self::empty();
+
+    presets.insert("nearest-clamp", Sampler::nearest_clamp());
+    presets.insert("linear-repeat", Sampler::linear_repeat());
+    presets.insert("trilinear-aniso16", Sampler::trilinear_aniso16());
+    presets.insert("shadow-pcf", Sampler::shadow_pcf());
+
+    presets
+  }
+
+  /// Create a registry without any preset.
+  pub fn empty() -> Self {
+    SamplerPresets {
+      presets: BTreeMap::new(),
+    }
+  }
+
+  /// Register a preset, returning the one it replaces, if any.
+  pub fn insert<N>(&mut self, name: N, sampler: Sampler) -> Option<Sampler> where N: Into<String> {
+    self.presets.insert(name.into(), sampler)
+  }
+
+  /// Remove a preset.
+  pub fn remove(&mut self, name: &str) -> Option<Sampler> {
+    self.presets.remove(name)
+  }
+
+  /// Get a preset.
+  pub fn get(&self, name: &str) -> Option<Sampler> {
+    self.presets.get(name).cloned()
+  }
+
+  /// Get a preset, failing with [`TextureError::UnknownSamplerPreset`] if there’s none with that
+  /// name.
+  pub fn sampler(&self, name: &str) -> Result<Sampler, TextureError> {
+    self.get(name).ok_or_else(|| TextureError::UnknownSamplerPreset(name.into()))
+  }
+
+  /// Names of the registered presets, in alphabetical order.
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.presets.keys().map(String::as_str)
+  }
+}
+
+impl Default for SamplerPresets {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -0,0 +1,354 @@
+//! Signed distance field baking.
+//!
+//! A signed distance field (SDF) stores, for each texel, the distance to the nearest edge of a
+//! shape – positive inside, negative outside. Rendering glyphs out of an SDF atlas instead of a
+//! coverage atlas keeps them crisp at any scale, with a simple threshold in the fragment shader.
+//!
+//! [`SdfBaker`] bakes the SDF of a coverage texture – typically a font atlas rasterized on the
+//! CPU – on the GPU, with the *jump flooding* algorithm:
+//!
+//!   1. A seed pass writes, in an integer texture, the coordinates of every texel as the nearest
+//!      inside texel – if it’s covered – or as the nearest outside texel – if it’s not.
+//!   2. Jump passes, with steps halving from half the size of the texture down to one texel,
+//!      propagate the nearest seeds of the neighbors, ping-ponging between two framebuffers.
+//!   3. A resolve pass turns the nearest seeds into distances.
+//!
+//! Distances are normalized to the `[0; 1]` range: `0.5` is the edge and `spread` texels away
+//! from the edge map to `0` (outside) and `1` (inside). Mipmaps of the result are generated, so
+//! that the atlas can be minified.
+
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::mem;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::mem;
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{Framebuffer, FramebufferError};
+use crate::pipeline::{BindingError, BoundTexture, PipelineState};
+use crate::pixel::{NormR8UI, NormUnsigned, Unsigned, RGBA16UI};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Tess, TessError};
+use crate::texture::{Dim2, Flat, Sampler, Texture};
+
+/// An SDF atlas, which color slot holds the signed distance field.
+pub type SdfAtlas = Framebuffer<Flat, Dim2, NormR8UI, ()>;
+
+// nearest seeds, as (inside x, inside y, outside x, outside y)
+type Seeds = Framebuffer<Flat, Dim2, RGBA16UI, ()>;
+
+/// GPU signed distance field baker.
+pub struct SdfBaker {
+  seed_program: Program<(), (), SeedInterface>,
+  jump_program: Program<(), (), JumpInterface>,
+  resolve_program: Program<(), (), ResolveInterface>,
+  triangle: Tess,
+}
+
+impl SdfBaker {
+  /// Create a new SDF baker, compiling its shader programs.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, SdfError> where C: GraphicsContext {
+    let seed_program =
+      Program::from_strings(None, FULLSCREEN_VS, None, SEED_FS)?.ignore_warnings();
+    let jump_program =
+      Program::from_strings(None, FULLSCREEN_VS, None, JUMP_FS)?.ignore_warnings();
+    let resolve_program =
+      Program::from_strings(None, FULLSCREEN_VS, None, RESOLVE_FS)?.ignore_warnings();
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+
+    Ok(SdfBaker {
+      seed_program,
+      jump_program,
+      resolve_program,
+      triangle,
+    })
+  }
+
+  /// Bake the signed distance field of a coverage texture.
+  ///
+  /// Texels with a coverage of at least `0.5` are inside. `spread` is the distance, in texels,
+  /// after which the distance saturates; `mipmaps` is the number of mipmaps of the atlas, in
+  /// addition to the base level.
+  pub fn bake<C>(
+    &self,
+    ctx: &mut C,
+    coverage: &Texture<Flat, Dim2, NormR8UI>,
+    spread: f32,
+    mipmaps: usize,
+  ) -> Result<SdfAtlas, SdfError>
+  where C: GraphicsContext {
+    let size = coverage.size();
+    let mut ping = Seeds::new(ctx, size, 0)?;
+    let mut pong = Seeds::new(ctx, size, 0)?;
+    let atlas = SdfAtlas::new(ctx, size, mipmaps)?;
+
+    // integer textures must not be filtered
+    let nearest = Sampler::nearest_clamp();
+    ping.color_slot().set_sampler(nearest);
+    pong.color_slot().set_sampler(nearest);
+
+    // every texel is written by every pass, so there’s nothing to clear
+    let pipeline_state = PipelineState::default().enable_clear(false);
+    let render_state = RenderState::default().set_depth_test(None);
+    let mut builder = ctx.pipeline_builder();
+    let mut result = Ok(());
+
+    builder.pipeline(&ping, &pipeline_state, |pipeline, mut shd_gate| {
+      let coverage = match pipeline.bind_texture(coverage) {
+        Ok(coverage) => coverage,
+        Err(e) => {
+          result = Err(e);
+          return;
+        }
+      };
+
+      shd_gate.shade(&self.seed_program, |iface, mut rdr_gate| {
+        iface.coverage.update(&coverage);
+        rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(&self.triangle));
+      });
+    });
+
+    result?;
+
+    let mut step = size[0].max(size[1]).next_power_of_two() / 2;
+
+    while step > 0 {
+      let mut result = Ok(());
+
+      builder.pipeline(&pong, &pipeline_state, |pipeline, mut shd_gate| {
+        let seeds = match pipeline.bind_texture(ping.color_slot()) {
+          Ok(seeds) => seeds,
+          Err(e) => {
+            result = Err(e);
+            return;
+          }
+        };
+
+        shd_gate.shade(&self.jump_program, |iface, mut rdr_gate| {
+          iface.seeds.update(&seeds);
+          iface.step.update(step as i32);
+          rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(&self.triangle));
+        });
+      });
+
+      result?;
+
+      mem::swap(&mut ping, &mut pong);
+      step /= 2;
+    }
+
+    let mut result = Ok(());
+
+    builder.pipeline(&atlas, &pipeline_state, |pipeline, mut shd_gate| {
+      let seeds = match pipeline.bind_texture(ping.color_slot()) {
+        Ok(seeds) => seeds,
+        Err(e) => {
+          result = Err(e);
+          return;
+        }
+      };
+
+      shd_gate.shade(&self.resolve_program, |iface, mut rdr_gate| {
+        iface.seeds.update(&seeds);
+        iface.spread.update(spread);
+        rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(&self.triangle));
+      });
+    });
+
+    result?;
+
+    if mipmaps > 0 {
+      atlas.color_slot().generate_mipmaps();
+    }
+
+    Ok(atlas)
+  }
+}
+
+/// Errors that can happen while baking signed distance fields.
+#[derive(Debug)]
+pub enum SdfError {
+  /// A shader program of the baker failed to build.
+  ProgramError(ProgramError),
+  /// The triangle used to run the passes couldn’t be created.
+  TessError(TessError),
+  /// An intermediate framebuffer or the atlas couldn’t be created.
+  FramebufferError(FramebufferError),
+  /// No texture unit was left to bind the textures of a pass.
+  BindingError(BindingError),
+}
+
+impl fmt::Display for SdfError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      SdfError::ProgramError(ref e) => write!(f, "SDF program error: {}", e),
+      SdfError::TessError(ref e) => write!(f, "SDF tessellation error: {:?}", e),
+      SdfError::FramebufferError(ref e) => write!(f, "SDF framebuffer error: {}", e),
+      SdfError::BindingError(ref e) => write!(f, "SDF binding error: {}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for SdfError {
+  fn from(e: ProgramError) -> Self {
+    SdfError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for SdfError {
+  fn from(e: TessError) -> Self {
+    SdfError::TessError(e)
+  }
+}
+
+impl From<FramebufferError> for SdfError {
+  fn from(e: FramebufferError) -> Self {
+    SdfError::FramebufferError(e)
+  }
+}
+
+impl From<BindingError> for SdfError {
+  fn from(e: BindingError) -> Self {
+    SdfError::BindingError(e)
+  }
+}
+
+struct SeedInterface {
+  coverage: Uniform<&'static BoundTexture<'static, Flat, Dim2, NormUnsigned>>,
+}
+
+impl UniformInterface for SeedInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(SeedInterface {
+      coverage: builder.ask("coverage").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+struct JumpInterface {
+  seeds: Uniform<&'static BoundTexture<'static, Flat, Dim2, Unsigned>>,
+  step: Uniform<i32>,
+}
+
+impl UniformInterface for JumpInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(JumpInterface {
+      seeds: builder.ask("seeds").map_err(ProgramError::UniformWarning)?,
+      step: builder.ask("step").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+struct ResolveInterface {
+  seeds: Uniform<&'static BoundTexture<'static, Flat, Dim2, Unsigned>>,
+  spread: Uniform<f32>,
+}
+
+impl UniformInterface for ResolveInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(ResolveInterface {
+      seeds: builder.ask("seeds").map_err(ProgramError::UniformWarning)?,
+      spread: builder.ask("spread").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+const SEED_FS: &str = "
+uniform sampler2D coverage;
+
+out uvec4 seeds;
+
+const uint NONE = 65535u;
+
+void main() {
+  ivec2 p = ivec2(gl_FragCoord.xy);
+
+  if (texelFetch(coverage, p, 0).r >= .5) {
+    seeds = uvec4(uvec2(p), NONE, NONE);
+  } else {
+    seeds = uvec4(NONE, NONE, uvec2(p));
+  }
+}
+";
+
+const JUMP_FS: &str = "
+uniform usampler2D seeds;
+uniform int step;
+
+out uvec4 next_seeds;
+
+const uint NONE = 65535u;
+
+float dist2(uvec2 seed, ivec2 p) {
+  if (seed.x == NONE) {
+    return 1e30;
+  }
+
+  vec2 d = vec2(seed) - vec2(p);
+  return dot(d, d);
+}
+
+void main() {
+  ivec2 p = ivec2(gl_FragCoord.xy);
+  ivec2 size = textureSize(seeds, 0);
+  float best_inside = 1e30;
+  float best_outside = 1e30;
+
+  next_seeds = uvec4(NONE);
+
+  for (int y = -1; y <= 1; ++y) {
+    for (int x = -1; x <= 1; ++x) {
+      ivec2 q = p + ivec2(x, y) * step;
+
+      if (any(lessThan(q, ivec2(0))) || any(greaterThanEqual(q, size))) {
+        continue;
+      }
+
+      uvec4 s = texelFetch(seeds, q, 0);
+
+      float d = dist2(s.xy, p);
+      if (d < best_inside) {
+        best_inside = d;
+        next_seeds.xy = s.xy;
+      }
+
+      d = dist2(s.zw, p);
+      if (d < best_outside) {
+        best_outside = d;
+        next_seeds.zw = s.zw;
+      }
+    }
+  }
+}
+";
+
+const RESOLVE_FS: &str = "
+uniform usampler2D seeds;
+uniform float spread;
+
+out float sdf;
+
+const uint NONE = 65535u;
+
+void main() {
+  ivec2 p = ivec2(gl_FragCoord.xy);
+  uvec4 s = texelFetch(seeds, p, 0);
+  float d;
+
+  // inside texels are their own nearest inside seed, and conversely; the edge lies half a texel
+  // away from the texel centers
+  if (uvec2(s.xy) == uvec2(p)) {
+    d = s.z == NONE ? spread : distance(vec2(s.zw), vec2(p)) - .5;
+  } else {
+    d = s.x == NONE ? -spread : .5 - distance(vec2(s.xy), vec2(p));
+  }
+
+  sdf = clamp(.5 + d / (2. * spread), 0., 1.);
+}
+";
@@ -4,5 +4,8 @@
 //! GPU. Everything you need to know can be found in the `program` module. The `stage` modules
 //! contains types and functions that you’ll be linked from the `program` module.
 
+mod interface;
 pub mod program;
 pub mod stage;
+#[cfg(feature = "wgsl")]
+mod wgsl;
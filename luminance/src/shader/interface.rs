@@ -0,0 +1,427 @@
+//! Shader stage interfaces.
+//!
+//! This module extracts the `in` and `out` declarations of shader stages out of their sources so
+//! that the outputs of a stage can be checked against the inputs of the next one, and that the
+//! interpolation of fragment inputs can be checked against the intent of semantics. It’s only used
+//! to explain link failures and warn: it doesn’t run the preprocessor and ignores arrays, so it’s
+//! not meant to replace the driver’s own validation.
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::shader::program::InterfaceMismatch;
+use crate::shader::stage::Type;
+use crate::vertex::Interpolation;
+
+/// A variable of a stage interface.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Var {
+  name: String,
+  ty: String,
+}
+
+/// A named interface block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Block {
+  name: String,
+  members: Vec<Var>,
+}
+
+/// Inputs or outputs of a stage.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Interface {
+  vars: Vec<Var>,
+  blocks: Vec<Block>,
+}
+
+/// Check that every input of `input_stage` is provided by `output_stage`.
+///
+/// Variables with an explicit location and built-ins are not checked.
+pub(crate) fn validate(
+  output_stage: Type,
+  output_src: &str,
+  input_stage: Type,
+  input_src: &str,
+) -> Result<(), InterfaceMismatch> {
+  let outputs = parse_interface(output_src, "out");
+  let inputs = parse_interface(input_src, "in");
+
+  for input in &inputs.vars {
+    match outputs.vars.iter().find(|output| output.name == input.name) {
+      Some(output) if output.ty != input.ty => {
+        return Err(InterfaceMismatch::TypeMismatch(
+          output_stage,
+          input_stage,
+          input.name.clone(),
+          output.ty.clone(),
+          input.ty.clone(),
+        ));
+      }
+
+      Some(_) => (),
+
+      None => {
+        return Err(InterfaceMismatch::MissingOutput(output_stage, input_stage, input.name.clone()));
+      }
+    }
+  }
+
+  for input in &inputs.blocks {
+    match outputs.blocks.iter().find(|output| output.name == input.name) {
+      Some(output) if output.members != input.members => {
+        return Err(InterfaceMismatch::BlockMismatch(output_stage, input_stage, input.name.clone()));
+      }
+
+      Some(_) => (),
+
+      None => {
+        return Err(InterfaceMismatch::MissingOutputBlock(
+          output_stage,
+          input_stage,
+          input.name.clone(),
+        ));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Interpolation of the variable `name` declared with `storage` (`"in"` or `"out"`), if it’s
+/// declared outside of an interface block.
+pub(crate) fn interpolation(src: &str, storage: &str, name: &str) -> Option<Interpolation> {
+  global_declarations(&strip_comments(src))
+    .iter()
+    .filter(|decl| !decl.contains('{'))
+    .map(|decl| split_qualifiers(decl))
+    .filter(|(qualifiers, _)| qualifiers.iter().any(|q| q == storage))
+    .find(|(_, rest)| parse_vars(rest).iter().any(|var| var.name == name))
+    .map(|(qualifiers, _)| {
+      let has = |qualifier: &str| qualifiers.iter().any(|q| q == qualifier);
+
+      if has("flat") {
+        Interpolation::Flat
+      } else if has("noperspective") {
+        Interpolation::NoPerspective
+      } else if has("sample") {
+        Interpolation::Sample
+      } else if has("centroid") {
+        Interpolation::Centroid
+      } else {
+        Interpolation::Smooth
+      }
+    })
+}
+
+/// Extract the global declarations qualified with `storage` (`"in"` or `"out"`).
+fn parse_interface(src: &str, storage: &str) -> Interface {
+  let mut iface = Interface::default();
+
+  for decl in global_declarations(&strip_comments(src)) {
+    let (head, body) = match decl.find('{') {
+      Some(i) => (&decl[.. i], Some(&decl[i + 1 .. decl.rfind('}').unwrap_or(decl.len())])),
+      None => (&decl[..], None),
+    };
+
+    let (qualifiers, rest) = split_qualifiers(head);
+
+    if !qualifiers.iter().any(|q| q == storage) || qualifiers.iter().any(|q| q == "location") {
+      continue;
+    }
+
+    match body {
+      Some(body) => {
+        let name = rest.trim().to_owned();
+
+        if name.is_empty() || name.starts_with("gl_") {
+          continue;
+        }
+
+        let members = body
+          .split(';')
+          .flat_map(|member| parse_vars(split_qualifiers(member).1))
+          .collect();
+        iface.blocks.push(Block { name, members });
+      }
+
+      None => {
+        iface.vars.extend(parse_vars(rest).into_iter().filter(|var| !var.name.starts_with("gl_")));
+      }
+    }
+  }
+
+  iface
+}
+
+/// Remove comments and preprocessor directives.
+fn strip_comments(src: &str) -> String {
+  let mut out = String::with_capacity(src.len());
+  let mut chars = src.chars().peekable();
+  let mut line_start = true;
+
+  while let Some(c) = chars.next() {
+    match c {
+      '/' if chars.peek() == Some(&'/') => {
+        while chars.next_if(|&c| c != '\n').is_some() {}
+      }
+
+      '/' if chars.peek() == Some(&'*') => {
+        chars.next();
+
+        let mut prev = ' ';
+        for c in chars.by_ref() {
+          if prev == '*' && c == '/' {
+            break;
+          }
+
+          prev = c;
+        }
+
+        out.push(' ');
+      }
+
+      '#' if line_start => {
+        while chars.next_if(|&c| c != '\n').is_some() {}
+      }
+
+      _ => {
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+        out.push(c);
+      }
+    }
+  }
+
+  out
+}
+
+/// Split a source into its global declarations, dropping function definitions.
+fn global_declarations(src: &str) -> Vec<String> {
+  let mut decls = Vec::new();
+  let mut decl = String::new();
+  let mut depth = 0usize;
+
+  for c in src.chars() {
+    match c {
+      '{' => {
+        depth += 1;
+        decl.push(c);
+      }
+
+      '}' => {
+        depth = depth.saturating_sub(1);
+        decl.push(c);
+
+        // the end of a function definition is not followed by a semicolon
+        if depth == 0 && decl[.. decl.find('{').unwrap_or(0)].contains('(') {
+          decl.clear();
+        }
+      }
+
+      ';' if depth == 0 => {
+        decls.push(decl.trim().to_owned());
+        decl.clear();
+      }
+
+      _ => decl.push(c),
+    }
+  }
+
+  decls
+}
+
+/// Split the qualifiers out of a declaration head.
+///
+/// Layout qualifiers are returned as their identifiers (e.g. `location`). The remaining of the
+/// declaration is returned as-is.
+fn split_qualifiers(head: &str) -> (Vec<String>, &str) {
+  const QUALIFIERS: &[&str] = &[
+    "in", "out", "uniform", "buffer", "const", "flat", "smooth", "noperspective", "centroid",
+    "sample", "patch", "invariant", "precise", "highp", "mediump", "lowp",
+  ];
+
+  let mut qualifiers = Vec::new();
+  let mut rest = head.trim_start();
+
+  loop {
+    if rest.starts_with("layout") {
+      let after = rest["layout".len() ..].trim_start();
+
+      if after.starts_with('(') {
+        let end = after.find(')').unwrap_or(after.len());
+        let ids = after[1 .. end].split(',').filter_map(|arg| arg.split('=').next());
+
+        qualifiers.extend(ids.map(|id| id.trim().to_owned()));
+        rest = after.get(end + 1 ..).unwrap_or("").trim_start();
+        continue;
+      }
+    }
+
+    let word_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+    let word = &rest[.. word_len];
+
+    if word_len > 0 && QUALIFIERS.contains(&word) {
+      qualifiers.push(word.to_owned());
+      rest = rest[word_len ..].trim_start();
+    } else {
+      return (qualifiers, rest);
+    }
+  }
+}
+
+/// Parse `type name0, name1, …` declarations. Array specifiers are ignored.
+fn parse_vars(decl: &str) -> Vec<Var> {
+  let decl = strip_arrays(decl);
+  let mut parts = decl.split(',');
+
+  let first = match parts.next() {
+    Some(first) => first.trim(),
+    None => return Vec::new(),
+  };
+
+  let (ty, first_name) = match first.rfind(char::is_whitespace) {
+    Some(i) => (first[.. i].trim(), first[i ..].trim()),
+    None => return Vec::new(),
+  };
+
+  let ty = ty.split_whitespace().collect::<Vec<_>>().join(" ");
+
+  Some(first_name)
+    .into_iter()
+    .chain(parts.map(str::trim))
+    .filter(|name| !name.is_empty())
+    .map(|name| Var { name: name.to_owned(), ty: ty.clone() })
+    .collect()
+}
+
+fn strip_arrays(decl: &str) -> String {
+  let mut out = String::with_capacity(decl.len());
+  let mut depth = 0usize;
+
+  for c in decl.chars() {
+    match c {
+      '[' => depth += 1,
+      ']' => depth = depth.saturating_sub(1),
+      _ if depth == 0 => out.push(c),
+      _ => (),
+    }
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VS: Type = Type::VertexShader;
+  const FS: Type = Type::FragmentShader;
+
+  fn check(vs: &str, fs: &str) -> Result<(), InterfaceMismatch> {
+    validate(VS, vs, FS, fs)
+  }
+
+  #[test]
+  fn matching_qualified_vars() {
+    let vs = "
+      smooth centroid out vec2 uv;
+      out highp   vec3 normal;
+      flat out int id;
+    ";
+    let fs = "
+      in vec2 uv;
+      in vec3 normal;
+      flat in int id;
+    ";
+
+    assert_eq!(check(vs, fs), Ok(()));
+  }
+
+  #[test]
+  fn several_names_and_arrays() {
+    let vs = "out vec3 a, b;\nout float weights[4];";
+    let fs = "in vec3 b;\nin float weights[4];";
+
+    assert_eq!(check(vs, fs), Ok(()));
+  }
+
+  #[test]
+  fn located_and_builtin_inputs_are_not_checked() {
+    let fs = "
+      layout (location = 0) in vec3 co;
+      in vec4 gl_FragCoord;
+      in gl_PerVertex { vec4 gl_Position; } gl_in[];
+    ";
+
+    assert_eq!(check("", fs), Ok(()));
+  }
+
+  #[test]
+  fn missing_output() {
+    let vs = "out vec2 uv;";
+    let fs = "in vec2 uv;\nin vec3 color;";
+
+    assert_eq!(check(vs, fs), Err(InterfaceMismatch::MissingOutput(VS, FS, "color".to_owned())));
+  }
+
+  #[test]
+  fn type_mismatch() {
+    let vs = "out vec3 color;";
+    let fs = "in vec4 color;";
+    let (name, output_ty, input_ty) = ("color".to_owned(), "vec3".to_owned(), "vec4".to_owned());
+    let mismatch = InterfaceMismatch::TypeMismatch(VS, FS, name, output_ty, input_ty);
+
+    assert_eq!(check(vs, fs), Err(mismatch));
+  }
+
+  #[test]
+  fn comments_directives_and_functions() {
+    let vs = "
+      #define out_color
+      // out vec3 color;
+      /* out vec3
+         color; */
+      void f(out vec3 color) { color = vec3(0.); }
+    ";
+    let fs = "in vec3 color; // from the vertex shader";
+
+    assert_eq!(check(vs, fs), Err(InterfaceMismatch::MissingOutput(VS, FS, "color".to_owned())));
+  }
+
+  #[test]
+  fn blocks() {
+    let vs = "out VsOut { vec2 uv; flat int id; } vs_out;";
+    let fs = "in VsOut {\n  vec2 uv;\n  flat int id;\n} fs_in;";
+
+    assert_eq!(check(vs, fs), Ok(()));
+    assert_eq!(
+      check(vs, "in VsOut { vec2 uv; int layer; } fs_in;"),
+      Err(InterfaceMismatch::BlockMismatch(VS, FS, "VsOut".to_owned()))
+    );
+    assert_eq!(
+      check(vs, "in Varyings { vec2 uv; } fs_in;"),
+      Err(InterfaceMismatch::MissingOutputBlock(VS, FS, "Varyings".to_owned()))
+    );
+  }
+
+  #[test]
+  fn interpolations() {
+    let fs = "
+      in vec2 uv;
+      flat in int id;
+      noperspective in vec2 screen;
+      centroid in vec2 atlas;
+      sample in vec3 normal;
+      in Block { flat in int layer; } block;
+    ";
+
+    assert_eq!(interpolation(fs, "in", "uv"), Some(Interpolation::Smooth));
+    assert_eq!(interpolation(fs, "in", "id"), Some(Interpolation::Flat));
+    assert_eq!(interpolation(fs, "in", "screen"), Some(Interpolation::NoPerspective));
+    assert_eq!(interpolation(fs, "in", "atlas"), Some(Interpolation::Centroid));
+    assert_eq!(interpolation(fs, "in", "normal"), Some(Interpolation::Sample));
+    assert_eq!(interpolation(fs, "in", "layer"), None);
+    assert_eq!(interpolation(fs, "out", "uv"), None);
+  }
+}
@@ -38,7 +38,11 @@ use std::marker::PhantomData;
 use std::ops::Deref;
 #[cfg(feature = "std")]
 use std::ptr::null_mut;
+#[cfg(feature = "thread-check")]
+use std::thread::{self, ThreadId};
 
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
 #[cfg(not(feature = "std"))]
 use alloc::prelude::ToOwned;
 #[cfg(not(feature = "std"))]
@@ -54,10 +58,15 @@ use core::ops::Deref;
 #[cfg(not(feature = "std"))]
 use core::ptr::null_mut;
 
-use crate::linear::{M22, M33, M44};
+use crate::debug;
+use crate::linear::{RowMajor, M22, M33, M44};
 use crate::metagl::*;
+use crate::shader::interface;
 use crate::shader::stage::{self, Stage, StageError};
-use crate::vertex::Semantics;
+#[cfg(feature = "wgsl")]
+use crate::shader::wgsl;
+use crate::state::PoisonCause;
+use crate::vertex::{Interpolation, Semantics, SemanticsDesc};
 
 /// A raw shader program.
 ///
@@ -65,6 +74,11 @@ use crate::vertex::Semantics;
 #[derive(Debug)]
 pub struct RawProgram {
   handle: GLuint,
+  // (source name, GLSL name) of the identifiers renamed by the front ends of the stages
+  names: Vec<(String, String)>,
+  // thread the program was created on, which its context is current on
+  #[cfg(feature = "thread-check")]
+  owner: ThreadId,
 }
 
 impl RawProgram {
@@ -72,24 +86,49 @@ impl RawProgram {
   fn new<'a, T, G>(tess: T, vertex: &Stage, geometry: G, fragment: &Stage) -> Result<Self, ProgramError>
   where T: Into<Option<(&'a Stage, &'a Stage)>>,
         G: Into<Option<&'a Stage>> {
+    let tess = tess.into();
+    let geometry = geometry.into();
+
     unsafe {
       let handle = gl::CreateProgram();
+      log_event!(created(Program, handle));
 
-      if let Some((tcs, tes)) = tess.into() {
+      if let Some((tcs, tes)) = tess {
         gl::AttachShader(handle, tcs.handle());
         gl::AttachShader(handle, tes.handle());
       }
 
       gl::AttachShader(handle, vertex.handle());
 
-      if let Some(geometry) = geometry.into() {
+      if let Some(geometry) = geometry {
         gl::AttachShader(handle, geometry.handle());
       }
 
       gl::AttachShader(handle, fragment.handle());
 
-      let program = RawProgram { handle };
-      program.link().map(move |_| program)
+      let mut stages = Vec::new();
+      stages.extend(tess.iter().flat_map(|&(tcs, tes)| vec![tcs, tes]));
+      stages.push(vertex);
+      stages.extend(geometry);
+      stages.push(fragment);
+
+      let program = RawProgram {
+        handle,
+        names: stage_names(&stages),
+        #[cfg(feature = "thread-check")]
+        owner: thread::current().id(),
+      };
+
+      program.link().map(move |_| program).map_err(|e| match e {
+        // try to point at the faulty variable, along with the driver’s log
+        ProgramError::LinkFailed(log) => {
+          match validate_interfaces(tess, vertex, geometry, fragment) {
+            Err(mismatch) => ProgramError::InterfaceMismatch(Box::new(mismatch), log),
+            Ok(_) => ProgramError::LinkFailed(log),
+          }
+        }
+        _ => e,
+      })
     }
   }
 
@@ -126,11 +165,114 @@ impl RawProgram {
   pub(crate) fn handle(&self) -> GLuint {
     self.handle
   }
+
+  // GLSL name of an identifier of the sources of the stages.
+  fn glsl_name<'a>(&'a self, name: &'a str) -> &'a str {
+    self.names.iter().find(|(n, _)| n == name).map_or(name, |(_, glsl)| glsl)
+  }
+
+  // Name in the sources of the stages of a GLSL identifier.
+  fn source_name<'a>(&'a self, glsl_name: &'a str) -> &'a str {
+    self.names.iter().find(|(_, g)| g == glsl_name).map_or(glsl_name, |(n, _)| n)
+  }
+
+  // Check whether the program is used from the thread it was created on. Programs don’t know their
+  // context, which is checked by the shading gates instead.
+  pub(crate) fn check_usable(&self) -> Result<(), PoisonCause> {
+    #[cfg(feature = "thread-check")]
+    {
+      if thread::current().id() != self.owner {
+        return Err(PoisonCause::WrongThread);
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Label the program, so that graphics debuggers show it by that name.
+  pub fn set_label(&self, label: &str) {
+    debug::label(gl::PROGRAM, self.handle, label);
+  }
+
+  /// Active uniforms of the program.
+  ///
+  /// Uniforms optimized out by the driver are not active and are not listed. Uniforms declared in
+  /// uniform blocks are listed with their block-qualified name and no location; arrays are listed
+  /// once, as `name[0]`.
+  pub fn uniforms(&self) -> Vec<UniformDescriptor> {
+    let mut uniforms = Vec::new();
+
+    unsafe {
+      let mut count = 0;
+      gl::GetProgramiv(self.handle, gl::ACTIVE_UNIFORMS, &mut count);
+
+      let mut max_len = 0;
+      gl::GetProgramiv(self.handle, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+
+      let mut name = vec![0u8; max_len.max(1) as usize];
+
+      for i in 0 .. count.max(0) as GLuint {
+        let mut len = 0;
+        let mut size = 0;
+        let mut glty = 0;
+
+        gl::GetActiveUniform(
+          self.handle,
+          i,
+          name.len() as GLsizei,
+          &mut len,
+          &mut size,
+          &mut glty,
+          name.as_mut_ptr() as *mut GLchar,
+        );
+
+        let name = String::from_utf8_lossy(&name[.. len.max(0) as usize]).into_owned();
+
+        #[cfg(feature = "std")]
+        let location = {
+          let c_name = CString::new(name.as_bytes()).unwrap();
+          gl::GetUniformLocation(self.handle, c_name.as_ptr() as *const GLchar)
+        };
+
+        #[cfg(not(feature = "std"))]
+        let location =
+          with_cstring(&name, |c_name| gl::GetUniformLocation(self.handle, c_name)).unwrap_or(-1);
+
+        uniforms.push(UniformDescriptor {
+          name,
+          ty: uniform_type_from_gl(glty),
+          location: if location < 0 { None } else { Some(location) },
+          array_len: size.max(0) as usize,
+        });
+      }
+    }
+
+    uniforms
+  }
+}
+
+/// Description of an active uniform, as returned by [`RawProgram::uniforms`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UniformDescriptor {
+  /// Name of the uniform.
+  pub name: String,
+  /// Type of the uniform; `None` if luminance doesn’t support it (e.g. `samplerCubeArray`).
+  pub ty: Option<Type>,
+  /// Location of the uniform; `None` for uniforms declared in uniform blocks.
+  pub location: Option<i32>,
+  /// Number of elements, `1` for non-array uniforms.
+  pub array_len: usize,
 }
 
 impl Drop for RawProgram {
   fn drop(&mut self) {
+    // a foreign thread has no business with the context; the program leaks instead
+    if self.check_usable().is_err() {
+      return;
+    }
+
     unsafe { gl::DeleteProgram(self.handle) }
+    log_event!(destroyed(Program, self.handle));
   }
 }
 
@@ -186,10 +328,26 @@ impl<S, Out, Uni> Program<S, Out, Uni> where S: Semantics {
         G: Into<Option<&'a Stage>> {
     let raw = RawProgram::new(tess, vertex, geometry, fragment)?;
 
-    let mut warnings = bind_vertex_attribs_locations::<S>(&raw);
+    let (mut warnings, bound) = bind_vertex_attribs_locations::<S>(&raw);
 
     raw.link()?;
 
+    let report = SemanticsReport {
+      expected: S::semantics_set(),
+      bound,
+      active: get_active_vertex_attribs(&raw),
+    };
+
+    warnings.extend(
+      interpolation_mismatches(&report.expected, fragment)
+        .into_iter()
+        .map(ProgramWarning::VertexAttrib),
+    );
+
+    if !report.is_consistent() {
+      warnings.push(ProgramWarning::VertexAttrib(VertexAttribWarning::SemanticsMismatch(report)));
+    }
+
     let (uni_iface, uniform_warnings) = create_uniform_interface(&raw, env)?;
     warnings.extend(uniform_warnings.into_iter().map(ProgramWarning::Uniform));
 
@@ -244,6 +402,39 @@ impl<S, Out, Uni> Program<S, Out, Uni> where S: Semantics {
     )
   }
 
+  /// Create a new program out of the vertex and fragment entry points of a *WGSL* module.
+  ///
+  /// See the [`stage`](crate::shader::stage#wgsl) module for how *WGSL* resources are looked up.
+  #[cfg(feature = "wgsl")]
+  pub fn from_wgsl(
+    src: &str,
+    vertex_entry: &str,
+    fragment_entry: &str,
+  ) -> Result<BuiltProgram<S, Out, Uni>, ProgramError>
+  where Uni: UniformInterface {
+    Self::from_wgsl_env(src, vertex_entry, fragment_entry, ())
+  }
+
+  /// Create a new program out of the vertex and fragment entry points of a *WGSL* module and by
+  /// looking up an environment.
+  #[cfg(feature = "wgsl")]
+  pub fn from_wgsl_env<E>(
+    src: &str,
+    vertex_entry: &str,
+    fragment_entry: &str,
+    env: E,
+  ) -> Result<BuiltProgram<S, Out, Uni>, ProgramError>
+  where Uni: UniformInterface<E> {
+    let (module, info) =
+      wgsl::parse(src).map_err(|e| ProgramError::StageError(StageError::WgslError(e)))?;
+    let vs = Stage::from_wgsl_module(&module, &info, stage::Type::VertexShader, vertex_entry)
+      .map_err(ProgramError::StageError)?;
+    let fs = Stage::from_wgsl_module(&module, &info, stage::Type::FragmentShader, fragment_entry)
+      .map_err(ProgramError::StageError)?;
+
+    Self::from_stages_env(None, &vs, None, &fs, env)
+  }
+
   /// Get the program interface associated with this program.
   pub(crate) fn interface(&self) -> ProgramInterface<Uni> {
     let raw_program = &self.raw;
@@ -396,16 +587,26 @@ impl<'a> UniformBuilder<'a> {
   /// uniform, head over to the `ask_unbound` function.
   pub fn ask<T>(&self, name: &str) -> Result<Uniform<T>, UniformWarning>
   where T: Uniformable {
+    let glsl_name = self.raw.glsl_name(name);
     let uniform = match T::ty() {
-      Type::BufferBinding => self.ask_uniform_block(name)?,
-      _ => self.ask_uniform(name)?,
+      Type::BufferBinding => self.ask_uniform_block(name, glsl_name)?,
+      _ => self.ask_uniform(name, glsl_name)?,
     };
 
-    uniform_type_match(self.raw.handle, name, T::ty())?;
+    uniform_type_match(self.raw.handle, glsl_name, T::ty())
+      .map_err(|_| UniformWarning::type_mismatch(name, T::ty()))?;
 
     Ok(uniform)
   }
 
+  /// Have the builder hand you a [`UniformHandle`] of the type of your choice.
+  ///
+  /// This is the same as [`UniformBuilder::ask`] but returns a copyable handle instead.
+  pub fn ask_handle<T>(&self, name: &str) -> Result<UniformHandle<T>, UniformWarning>
+  where T: Uniformable {
+    self.ask(name).map(|u: Uniform<T>| u.handle())
+  }
+
   /// Get an unbound [`Uniform`].
   ///
   /// Unbound [`Uniform`]s are not any different from typical [`Uniform`]s but when resolving
@@ -426,18 +627,18 @@ impl<'a> UniformBuilder<'a> {
     }
   }
 
-  fn ask_uniform<T>(&self, name: &str) -> Result<Uniform<T>, UniformWarning>
+  fn ask_uniform<T>(&self, name: &str, glsl_name: &str) -> Result<Uniform<T>, UniformWarning>
   where T: Uniformable {
     let location = {
       #[cfg(feature = "std")]
       {
-        let c_name = CString::new(name.as_bytes()).unwrap();
+        let c_name = CString::new(glsl_name.as_bytes()).unwrap();
         unsafe { gl::GetUniformLocation(self.raw.handle, c_name.as_ptr() as *const GLchar) }
       }
 
       #[cfg(not(feature = "std"))]
       {
-        unsafe { with_cstring(name, |c_name| gl::GetUniformLocation(self.raw.handle, c_name)).unwrap_or(-1) }
+        unsafe { with_cstring(glsl_name, |c_name| gl::GetUniformLocation(self.raw.handle, c_name)).unwrap_or(-1) }
       }
     };
 
@@ -448,19 +649,19 @@ impl<'a> UniformBuilder<'a> {
     }
   }
 
-  fn ask_uniform_block<T>(&self, name: &str) -> Result<Uniform<T>, UniformWarning>
+  fn ask_uniform_block<T>(&self, name: &str, glsl_name: &str) -> Result<Uniform<T>, UniformWarning>
   where T: Uniformable {
     let location = {
       #[cfg(feature = "std")]
       {
-        let c_name = CString::new(name.as_bytes()).unwrap();
+        let c_name = CString::new(glsl_name.as_bytes()).unwrap();
         unsafe { gl::GetUniformBlockIndex(self.raw.handle, c_name.as_ptr() as *const GLchar) }
       }
 
       #[cfg(not(feature = "std"))]
       {
         unsafe {
-          with_cstring(name, |c_name| gl::GetUniformBlockIndex(self.raw.handle, c_name))
+          with_cstring(glsl_name, |c_name| gl::GetUniformBlockIndex(self.raw.handle, c_name))
             .unwrap_or(gl::INVALID_INDEX)
         }
       }
@@ -506,6 +707,133 @@ impl<'a, Uni> ProgramInterface<'a, Uni> {
   pub fn query(&'a self) -> UniformBuilder<'a> {
     UniformBuilder::new(self.raw_program)
   }
+
+  /// Resolve a [`UniformHandle`] into a [`Uniform`].
+  ///
+  /// `None` is returned if the handle was retrieved from another program.
+  pub fn uniform<T>(&self, handle: UniformHandle<T>) -> Option<Uniform<T>> where T: Uniformable {
+    if handle.program == self.raw_program.handle {
+      Some(Uniform::new(handle.program, handle.index))
+    } else {
+      None
+    }
+  }
+
+  /// Update the uniform a [`UniformHandle`] points to.
+  ///
+  /// Return `false` – and don’t update anything – if the handle was retrieved from another
+  /// program.
+  pub fn update<T>(&self, handle: UniformHandle<T>, x: T) -> bool where T: Uniformable {
+    self.uniform(handle).map(|u| u.update(x)).is_some()
+  }
+
+  /// Set a uniform by name, with a value which type is only known at runtime.
+  ///
+  /// The uniform is looked up on each call and its type is checked against the value’s: prefer
+  /// the typed uniform interface – or [`UniformHandle`] – when the uniforms are known at compile
+  /// time.
+  pub fn set_dynamic(&self, name: &str, value: UniformValue) -> Result<(), UniformWarning> {
+    let builder = UniformBuilder::new(self.raw_program);
+
+    fn set<T>(builder: &UniformBuilder, name: &str, x: T) -> Result<(), UniformWarning>
+    where T: Uniformable {
+      builder.ask(name).map(|u: Uniform<T>| u.update(x))
+    }
+
+    match value {
+      UniformValue::Int(x) => set(&builder, name, x),
+      UniformValue::UInt(x) => set(&builder, name, x),
+      UniformValue::Float(x) => set(&builder, name, x),
+      UniformValue::Bool(x) => set(&builder, name, x),
+      UniformValue::IVec2(x) => set(&builder, name, x),
+      UniformValue::IVec3(x) => set(&builder, name, x),
+      UniformValue::IVec4(x) => set(&builder, name, x),
+      UniformValue::UIVec2(x) => set(&builder, name, x),
+      UniformValue::UIVec3(x) => set(&builder, name, x),
+      UniformValue::UIVec4(x) => set(&builder, name, x),
+      UniformValue::Vec2(x) => set(&builder, name, x),
+      UniformValue::Vec3(x) => set(&builder, name, x),
+      UniformValue::Vec4(x) => set(&builder, name, x),
+      UniformValue::BVec2(x) => set(&builder, name, x),
+      UniformValue::BVec3(x) => set(&builder, name, x),
+      UniformValue::BVec4(x) => set(&builder, name, x),
+      UniformValue::M22(x) => set(&builder, name, x),
+      UniformValue::M33(x) => set(&builder, name, x),
+      UniformValue::M44(x) => set(&builder, name, x),
+    }
+  }
+}
+
+/// A uniform value which type is known at runtime, for [`ProgramInterface::set_dynamic`].
+///
+/// Samplers and buffer bindings are not supported, as they require bound resources.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+  /// 32-bit signed integer.
+  Int(i32),
+  /// 32-bit unsigned integer.
+  UInt(u32),
+  /// 32-bit floating-point number.
+  Float(f32),
+  /// Boolean.
+  Bool(bool),
+  /// 2D signed integral vector.
+  IVec2([i32; 2]),
+  /// 3D signed integral vector.
+  IVec3([i32; 3]),
+  /// 4D signed integral vector.
+  IVec4([i32; 4]),
+  /// 2D unsigned integral vector.
+  UIVec2([u32; 2]),
+  /// 3D unsigned integral vector.
+  UIVec3([u32; 3]),
+  /// 4D unsigned integral vector.
+  UIVec4([u32; 4]),
+  /// 2D floating-point vector.
+  Vec2([f32; 2]),
+  /// 3D floating-point vector.
+  Vec3([f32; 3]),
+  /// 4D floating-point vector.
+  Vec4([f32; 4]),
+  /// 2D boolean vector.
+  BVec2([bool; 2]),
+  /// 3D boolean vector.
+  BVec3([bool; 3]),
+  /// 4D boolean vector.
+  BVec4([bool; 4]),
+  /// 2×2 floating-point matrix.
+  M22(M22),
+  /// 3×3 floating-point matrix.
+  M33(M33),
+  /// 4×4 floating-point matrix.
+  M44(M44),
+}
+
+impl UniformValue {
+  /// Type of the value.
+  pub fn ty(&self) -> Type {
+    match *self {
+      UniformValue::Int(_) => Type::Int,
+      UniformValue::UInt(_) => Type::UInt,
+      UniformValue::Float(_) => Type::Float,
+      UniformValue::Bool(_) => Type::Bool,
+      UniformValue::IVec2(_) => Type::IVec2,
+      UniformValue::IVec3(_) => Type::IVec3,
+      UniformValue::IVec4(_) => Type::IVec4,
+      UniformValue::UIVec2(_) => Type::UIVec2,
+      UniformValue::UIVec3(_) => Type::UIVec3,
+      UniformValue::UIVec4(_) => Type::UIVec4,
+      UniformValue::Vec2(_) => Type::Vec2,
+      UniformValue::Vec3(_) => Type::Vec3,
+      UniformValue::Vec4(_) => Type::Vec4,
+      UniformValue::BVec2(_) => Type::BVec2,
+      UniformValue::BVec3(_) => Type::BVec3,
+      UniformValue::BVec4(_) => Type::BVec4,
+      UniformValue::M22(_) => Type::M22,
+      UniformValue::M33(_) => Type::M33,
+      UniformValue::M44(_) => Type::M44,
+    }
+  }
 }
 
 /// Errors that a `Program` can generate.
@@ -519,7 +847,13 @@ pub enum ProgramError {
   /// type, etc. Check the `UniformWarning` type for more information.
   UniformWarning(UniformWarning),
   /// Some vertex attribute is ill-formed.
-  VertexAttribWarning(VertexAttribWarning)
+  VertexAttribWarning(VertexAttribWarning),
+  /// Program link failed and the outputs of a stage seem not to match the inputs of the next one.
+  ///
+  /// The mismatch is found by a light parse of the GLSL sources, which doesn’t run the
+  /// preprocessor, so it’s only a hint: the [`String`] is the link log of the driver, as carried
+  /// by [`ProgramError::LinkFailed`].
+  InterfaceMismatch(Box<InterfaceMismatch>, String),
 }
 
 impl fmt::Display for ProgramError {
@@ -531,6 +865,51 @@ impl fmt::Display for ProgramError {
 
       ProgramError::UniformWarning(ref e) => write!(f, "shader program contains uniform warning(s): {}", e),
       ProgramError::VertexAttribWarning(ref e) => write!(f, "shader program contains vertex attribute warning(s): {}", e),
+
+      ProgramError::InterfaceMismatch(ref e, ref log) => {
+        write!(f, "shader program failed to link: {}\n{}", e, log)
+      }
+    }
+  }
+}
+
+/// Mismatches between the outputs of a shader stage and the inputs of the next stage.
+///
+/// The first two [`Type`](stage::Type)s are respectively the stage writing the outputs and the
+/// stage reading the inputs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InterfaceMismatch {
+  /// An input variable is not written by the previous stage.
+  MissingOutput(stage::Type, stage::Type, String),
+  /// An input variable doesn’t have the same type as the corresponding output. The last two
+  /// `String`s are the output type and the input type.
+  TypeMismatch(stage::Type, stage::Type, String, String, String),
+  /// An input block is not written by the previous stage.
+  MissingOutputBlock(stage::Type, stage::Type, String),
+  /// An input block doesn’t have the same members as the corresponding output block.
+  BlockMismatch(stage::Type, stage::Type, String),
+}
+
+impl fmt::Display for InterfaceMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      InterfaceMismatch::MissingOutput(ref o, ref i, ref n) => {
+        write!(f, "input {} of the {} is not written by the {}", n, i, o)
+      }
+
+      InterfaceMismatch::TypeMismatch(ref o, ref i, ref n, ref ot, ref it) => write!(
+        f,
+        "input {} of the {} has type {} but the {} writes a {}",
+        n, i, it, o, ot
+      ),
+
+      InterfaceMismatch::MissingOutputBlock(ref o, ref i, ref n) => {
+        write!(f, "input block {} of the {} is not written by the {}", n, i, o)
+      }
+
+      InterfaceMismatch::BlockMismatch(ref o, ref i, ref n) => {
+        write!(f, "input block {} of the {} doesn’t match the output block of the {}", n, i, o)
+      }
     }
   }
 }
@@ -592,17 +971,104 @@ impl fmt::Display for UniformWarning {
 #[derive(Debug)]
 pub enum VertexAttribWarning {
   /// Inactive vertex attribute (not read).
-  Inactive(String)
+  Inactive(String),
+  /// The vertex attributes used by the shader don’t match the program’s [`Semantics`].
+  ///
+  /// This typically happens when an attribute has a fixed location in the shader that conflicts
+  /// with the semantics index, or isn’t declared by the [`Semantics`]. Attributes of the
+  /// [`Semantics`] the shader doesn’t use are reported as [`VertexAttribWarning::Inactive`] only.
+  SemanticsMismatch(SemanticsReport),
+  /// A fragment shader input isn’t interpolated as its semantics declares.
+  ///
+  /// The [`String`] is the name of the input; the first [`Interpolation`] is the expected one and
+  /// the second one is the one the input is declared with.
+  InterpolationMismatch(String, Interpolation, Interpolation),
 }
 
 impl fmt::Display for VertexAttribWarning {
   fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
     match *self {
-      VertexAttribWarning::Inactive(ref s) => write!(f, "inactive {} vertex attribute", s)
+      VertexAttribWarning::Inactive(ref s) => write!(f, "inactive {} vertex attribute", s),
+
+      VertexAttribWarning::SemanticsMismatch(ref r) => write!(f, "semantics mismatch: {}", r),
+
+      VertexAttribWarning::InterpolationMismatch(ref n, ref e, ref d) => write!(
+        f,
+        "{} fragment input is expected to be {}, but is declared {}",
+        n, e, d
+      ),
     }
   }
 }
 
+/// Vertex attribute locations of a program, as expected by its [`Semantics`] and as seen by the
+/// shader once linked.
+///
+/// The [`SemanticsDesc::index`] of each attribute is its location.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SemanticsReport {
+  /// Attributes declared by the [`Semantics`].
+  pub expected: Vec<SemanticsDesc>,
+  /// Attributes that were bound to their semantics index before linking.
+  pub bound: Vec<SemanticsDesc>,
+  /// Attributes the shader actually uses, with their final location.
+  pub active: Vec<SemanticsDesc>,
+}
+
+impl SemanticsReport {
+  /// Expected attributes that are not used by the shader.
+  pub fn missing(&self) -> Vec<&SemanticsDesc> {
+    self.expected.iter().filter(|e| !self.active.iter().any(|a| a.name == e.name)).collect()
+  }
+
+  /// Attributes used by the shader at a location other than their semantics index.
+  pub fn mislocated(&self) -> Vec<&SemanticsDesc> {
+    self
+      .active
+      .iter()
+      .filter(|a| self.expected.iter().any(|e| e.name == a.name && e.index != a.index))
+      .collect()
+  }
+
+  /// Attributes used by the shader that the [`Semantics`] doesn’t know about.
+  pub fn unexpected(&self) -> Vec<&SemanticsDesc> {
+    self.active.iter().filter(|a| !self.expected.iter().any(|e| e.name == a.name)).collect()
+  }
+
+  /// Whether the shader uses only expected attributes, at the expected locations.
+  ///
+  /// Shaders may use a subset of the expected attributes – e.g. a position-only shadow pass – so
+  /// [`SemanticsReport::missing`] attributes are not taken into account.
+  pub fn is_consistent(&self) -> bool {
+    self.mislocated().is_empty() && self.unexpected().is_empty()
+  }
+}
+
+impl fmt::Display for SemanticsReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    fn write_list(f: &mut fmt::Formatter, list: &[SemanticsDesc]) -> Result<(), fmt::Error> {
+      f.write_str("[")?;
+
+      for (i, desc) in list.iter().enumerate() {
+        if i > 0 {
+          f.write_str(", ")?;
+        }
+
+        write!(f, "{}@{}", desc.name, desc.index)?;
+      }
+
+      f.write_str("]")
+    }
+
+    f.write_str("expected ")?;
+    write_list(f, &self.expected)?;
+    f.write_str(", bound ")?;
+    write_list(f, &self.bound)?;
+    f.write_str(", active ")?;
+    write_list(f, &self.active)
+  }
+}
+
 /// A contravariant shader uniform. `Uniform<T>` doesn’t hold any value. It’s more like a mapping
 /// between the host code and the shader the uniform was retrieved from.
 #[derive(Debug)]
@@ -637,12 +1103,79 @@ impl<T> Uniform<T> where T: Uniformable {
     self.index
   }
 
+  /// Get a copyable [`UniformHandle`] to this uniform.
+  pub fn handle(&self) -> UniformHandle<T> {
+    UniformHandle::new(self.program, self.index)
+  }
+
   /// Update the value pointed by this uniform.
   pub fn update(&self, x: T) {
     x.update(self);
   }
 }
 
+/// A copyable handle to a [`Uniform`].
+///
+/// Unlike [`Uniform`], which is only reachable through the uniform interface borrowed inside a
+/// shading gate, a `UniformHandle` is a plain value that can be stored anywhere – typically in
+/// your scene objects – and resolved back to a [`Uniform`] with [`ProgramInterface::uniform`] when
+/// the program is in use.
+///
+/// A handle remembers the program it was retrieved from: resolving it against another program
+/// fails.
+#[derive(Debug)]
+pub struct UniformHandle<T> {
+  program: GLuint,
+  index: GLint,
+  _t: PhantomData<*const T>,
+}
+
+// manual implementations so that T doesn’t have to be Clone / Copy
+impl<T> Clone for UniformHandle<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for UniformHandle<T> {}
+
+impl<T> PartialEq for UniformHandle<T> {
+  fn eq(&self, rhs: &Self) -> bool {
+    self.program == rhs.program && self.index == rhs.index
+  }
+}
+
+impl<T> Eq for UniformHandle<T> {}
+
+// a handle is only a program and a location; it can be sent to another thread, to record render
+// lists for instance
+unsafe impl<T> Send for UniformHandle<T> {}
+
+unsafe impl<T> Sync for UniformHandle<T> {}
+
+impl<T> UniformHandle<T> {
+  fn new(program: GLuint, index: GLint) -> Self {
+    UniformHandle {
+      program,
+      index,
+      _t: PhantomData,
+    }
+  }
+
+  /// Whether this handle points to an unbound uniform.
+  pub fn is_unbound(&self) -> bool {
+    self.index < 0
+  }
+
+  pub(crate) fn program(&self) -> GLuint {
+    self.program
+  }
+
+  pub(crate) fn index(&self) -> GLint {
+    self.index
+  }
+}
+
 /// Type of a uniform.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Type {
@@ -715,6 +1248,26 @@ pub enum Type {
   UICubemap,
   /// Floating-point cubemap sampler.
   Cubemap,
+  /// Signed integral 2D array texture sampler.
+  ISampler2DArray,
+  /// Unsigned integral 2D array texture sampler.
+  UISampler2DArray,
+  /// Floating-point 2D array texture sampler.
+  Sampler2DArray,
+  /// 1D depth texture sampler with depth comparison.
+  Sampler1DShadow,
+  /// 2D depth texture sampler with depth comparison.
+  Sampler2DShadow,
+  /// Cubemap depth texture sampler with depth comparison.
+  CubemapShadow,
+  /// 2D array depth texture sampler with depth comparison.
+  Sampler2DArrayShadow,
+  /// Signed integral buffer texture sampler.
+  IBufferSampler,
+  /// Unsigned integral buffer texture sampler.
+  UIBufferSampler,
+  /// Floating-point buffer texture sampler.
+  BufferSampler,
 
   // buffer
   /// Buffer binding; used for UBOs.
@@ -755,6 +1308,16 @@ impl fmt::Display for Type {
       Type::ICubemap => f.write_str("isamplerCube"),
       Type::UICubemap => f.write_str("usamplerCube"),
       Type::Cubemap => f.write_str("samplerCube"),
+      Type::ISampler2DArray => f.write_str("isampler2DArray"),
+      Type::UISampler2DArray => f.write_str("usampler2DArray"),
+      Type::Sampler2DArray => f.write_str("sampler2DArray"),
+      Type::Sampler1DShadow => f.write_str("sampler1DShadow"),
+      Type::Sampler2DShadow => f.write_str("sampler2DShadow"),
+      Type::CubemapShadow => f.write_str("samplerCubeShadow"),
+      Type::Sampler2DArrayShadow => f.write_str("sampler2DArrayShadow"),
+      Type::IBufferSampler => f.write_str("isamplerBuffer"),
+      Type::UIBufferSampler => f.write_str("usamplerBuffer"),
+      Type::BufferSampler => f.write_str("samplerBuffer"),
       Type::BufferBinding => f.write_str("buffer binding"),
     }
   }
@@ -1092,6 +1655,90 @@ unsafe impl<'a> Uniformable for &'a [M44] {
   }
 }
 
+unsafe impl Uniformable for RowMajor<M22> {
+  fn update(self, u: &Uniform<Self>) {
+    let v = [self.0];
+    unsafe { gl::UniformMatrix2fv(u.index, 1, gl::TRUE, v.as_ptr() as *const f32) }
+  }
+
+  fn ty() -> Type {
+    Type::M22
+  }
+}
+
+unsafe impl Uniformable for RowMajor<M33> {
+  fn update(self, u: &Uniform<Self>) {
+    let v = [self.0];
+    unsafe { gl::UniformMatrix3fv(u.index, 1, gl::TRUE, v.as_ptr() as *const f32) }
+  }
+
+  fn ty() -> Type {
+    Type::M33
+  }
+}
+
+unsafe impl Uniformable for RowMajor<M44> {
+  fn update(self, u: &Uniform<Self>) {
+    let v = [self.0];
+    unsafe { gl::UniformMatrix4fv(u.index, 1, gl::TRUE, v.as_ptr() as *const f32) }
+  }
+
+  fn ty() -> Type {
+    Type::M44
+  }
+}
+
+unsafe impl Uniformable for &[RowMajor<M22>] {
+  fn update(self, u: &Uniform<Self>) {
+    unsafe {
+      gl::UniformMatrix2fv(
+        u.index,
+        self.len() as GLsizei,
+        gl::TRUE,
+        self.as_ptr() as *const f32,
+      )
+    }
+  }
+
+  fn ty() -> Type {
+    Type::M22
+  }
+}
+
+unsafe impl Uniformable for &[RowMajor<M33>] {
+  fn update(self, u: &Uniform<Self>) {
+    unsafe {
+      gl::UniformMatrix3fv(
+        u.index,
+        self.len() as GLsizei,
+        gl::TRUE,
+        self.as_ptr() as *const f32,
+      )
+    }
+  }
+
+  fn ty() -> Type {
+    Type::M33
+  }
+}
+
+unsafe impl Uniformable for &[RowMajor<M44>] {
+  fn update(self, u: &Uniform<Self>) {
+    unsafe {
+      gl::UniformMatrix4fv(
+        u.index,
+        self.len() as GLsizei,
+        gl::TRUE,
+        self.as_ptr() as *const f32,
+      )
+    }
+  }
+
+  fn ty() -> Type {
+    Type::M44
+  }
+}
+
 unsafe impl Uniformable for bool {
   fn update(self, u: &Uniform<Self>) {
     unsafe { gl::Uniform1ui(u.index, self as GLuint) }
@@ -1185,6 +1832,20 @@ unsafe impl<'a> Uniformable for &'a [[bool; 4]] {
   }
 }
 
+// Identifiers renamed by the front ends of stages, without duplicates: the resources shared by
+// stages are renamed the same way in each.
+fn stage_names(stages: &[&Stage]) -> Vec<(String, String)> {
+  let mut names: Vec<(String, String)> = Vec::new();
+
+  for name in stages.iter().flat_map(|stage| stage.names()) {
+    if !names.contains(name) {
+      names.push(name.clone());
+    }
+  }
+
+  names
+}
+
 // Check whether a shader program’s uniform type matches the type we have chosen.
 fn uniform_type_match(program: GLuint, name: &str, ty: Type) -> Result<(), UniformWarning> {
   let mut size: GLint = 0;
@@ -1285,10 +1946,68 @@ fn check_types_match(name: &str, ty: Type, glty: GLuint) -> Result<(), UniformWa
     Type::ICubemap if glty != gl::INT_SAMPLER_CUBE => Err(UniformWarning::type_mismatch(name, ty)),
     Type::UICubemap if glty != gl::UNSIGNED_INT_SAMPLER_CUBE => Err(UniformWarning::type_mismatch(name, ty)),
     Type::Cubemap if glty != gl::SAMPLER_CUBE => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::ISampler2DArray if glty != gl::INT_SAMPLER_2D_ARRAY => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::UISampler2DArray if glty != gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::Sampler2DArray if glty != gl::SAMPLER_2D_ARRAY => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::Sampler1DShadow if glty != gl::SAMPLER_1D_SHADOW => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::Sampler2DShadow if glty != gl::SAMPLER_2D_SHADOW => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::CubemapShadow if glty != gl::SAMPLER_CUBE_SHADOW => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::Sampler2DArrayShadow if glty != gl::SAMPLER_2D_ARRAY_SHADOW => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::IBufferSampler if glty != gl::INT_SAMPLER_BUFFER => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::UIBufferSampler if glty != gl::UNSIGNED_INT_SAMPLER_BUFFER => Err(UniformWarning::type_mismatch(name, ty)),
+    Type::BufferSampler if glty != gl::SAMPLER_BUFFER => Err(UniformWarning::type_mismatch(name, ty)),
     _ => Ok(()),
   }
 }
 
+// Uniform type of an OpenGL uniform type; the reverse of check_types_match.
+fn uniform_type_from_gl(glty: GLenum) -> Option<Type> {
+  match glty {
+    gl::INT => Some(Type::Int),
+    gl::UNSIGNED_INT => Some(Type::UInt),
+    gl::FLOAT => Some(Type::Float),
+    gl::BOOL => Some(Type::Bool),
+    gl::INT_VEC2 => Some(Type::IVec2),
+    gl::INT_VEC3 => Some(Type::IVec3),
+    gl::INT_VEC4 => Some(Type::IVec4),
+    gl::UNSIGNED_INT_VEC2 => Some(Type::UIVec2),
+    gl::UNSIGNED_INT_VEC3 => Some(Type::UIVec3),
+    gl::UNSIGNED_INT_VEC4 => Some(Type::UIVec4),
+    gl::FLOAT_VEC2 => Some(Type::Vec2),
+    gl::FLOAT_VEC3 => Some(Type::Vec3),
+    gl::FLOAT_VEC4 => Some(Type::Vec4),
+    gl::BOOL_VEC2 => Some(Type::BVec2),
+    gl::BOOL_VEC3 => Some(Type::BVec3),
+    gl::BOOL_VEC4 => Some(Type::BVec4),
+    gl::FLOAT_MAT2 => Some(Type::M22),
+    gl::FLOAT_MAT3 => Some(Type::M33),
+    gl::FLOAT_MAT4 => Some(Type::M44),
+    gl::INT_SAMPLER_1D => Some(Type::ISampler1D),
+    gl::INT_SAMPLER_2D => Some(Type::ISampler2D),
+    gl::INT_SAMPLER_3D => Some(Type::ISampler3D),
+    gl::UNSIGNED_INT_SAMPLER_1D => Some(Type::UISampler1D),
+    gl::UNSIGNED_INT_SAMPLER_2D => Some(Type::UISampler2D),
+    gl::UNSIGNED_INT_SAMPLER_3D => Some(Type::UISampler3D),
+    gl::SAMPLER_1D => Some(Type::Sampler1D),
+    gl::SAMPLER_2D => Some(Type::Sampler2D),
+    gl::SAMPLER_3D => Some(Type::Sampler3D),
+    gl::INT_SAMPLER_CUBE => Some(Type::ICubemap),
+    gl::UNSIGNED_INT_SAMPLER_CUBE => Some(Type::UICubemap),
+    gl::SAMPLER_CUBE => Some(Type::Cubemap),
+    gl::INT_SAMPLER_2D_ARRAY => Some(Type::ISampler2DArray),
+    gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => Some(Type::UISampler2DArray),
+    gl::SAMPLER_2D_ARRAY => Some(Type::Sampler2DArray),
+    gl::SAMPLER_1D_SHADOW => Some(Type::Sampler1DShadow),
+    gl::SAMPLER_2D_SHADOW => Some(Type::Sampler2DShadow),
+    gl::SAMPLER_CUBE_SHADOW => Some(Type::CubemapShadow),
+    gl::SAMPLER_2D_ARRAY_SHADOW => Some(Type::Sampler2DArrayShadow),
+    gl::INT_SAMPLER_BUFFER => Some(Type::IBufferSampler),
+    gl::UNSIGNED_INT_SAMPLER_BUFFER => Some(Type::UIBufferSampler),
+    gl::SAMPLER_BUFFER => Some(Type::BufferSampler),
+    _ => None,
+  }
+}
+
 // Generate a uniform interface and collect warnings.
 fn create_uniform_interface<Uni, E>(
   raw: &RawProgram,
@@ -1300,22 +2019,90 @@ where Uni: UniformInterface<E> {
   Ok((iface, builder.warnings))
 }
 
+// Check the interpolation of the fragment inputs carrying semantics that declare it; inputs that
+// cannot be found are not checked.
+fn interpolation_mismatches(
+  expected: &[SemanticsDesc],
+  fragment: &Stage,
+) -> Vec<VertexAttribWarning> {
+  let intents: Vec<_> = expected.iter().filter_map(|desc| desc.interpolation.as_ref()).collect();
+
+  if intents.is_empty() {
+    return Vec::new();
+  }
+
+  let src = fragment.source_code();
+
+  intents
+    .into_iter()
+    .filter_map(|intent| {
+      let declared = interface::interpolation(&src, "in", fragment.glsl_name(&intent.varying))?;
+
+      if declared == intent.interpolation {
+        None
+      } else {
+        Some(VertexAttribWarning::InterpolationMismatch(
+          intent.varying.clone(),
+          intent.interpolation,
+          declared,
+        ))
+      }
+    })
+    .collect()
+}
+
+// Check the interfaces between each pair of consecutive stages.
+fn validate_interfaces(
+  tess: Option<(&Stage, &Stage)>,
+  vertex: &Stage,
+  geometry: Option<&Stage>,
+  fragment: &Stage,
+) -> Result<(), InterfaceMismatch> {
+  let mut stages = vec![vertex];
+
+  if let Some((tcs, tes)) = tess {
+    stages.push(tcs);
+    stages.push(tes);
+  }
+
+  stages.extend(geometry);
+  stages.push(fragment);
+
+  for pair in stages.windows(2) {
+    let (output, input) = (pair[0], pair[1]);
+    let (output_src, input_src) = (output.source_code(), input.source_code());
+
+    // SPIR-V stages have no source, and separate shader objects match variables by location
+    let unchecked = |src: &str| src.is_empty() || src.contains("GL_ARB_separate_shader_objects");
+
+    if unchecked(&output_src) || unchecked(&input_src) {
+      continue;
+    }
+
+    interface::validate(output.ty(), &output_src, input.ty(), &input_src)?;
+  }
+
+  Ok(())
+}
+
 fn bind_vertex_attribs_locations<S>(
   raw: &RawProgram
-) -> Vec<ProgramWarning>
+) -> (Vec<ProgramWarning>, Vec<SemanticsDesc>)
 where S: Semantics {
   let mut warnings = Vec::new();
+  let mut bound = Vec::new();
 
   for desc in S::semantics_set() {
     match get_vertex_attrib_location(raw, &desc.name) {
       Ok(_) => {
         let index = desc.index as GLuint;
+        let glsl_name = raw.glsl_name(&desc.name);
 
         // we are not interested in the location as we’re about to change it to what we’ve
         // decided in the semantics
         #[cfg(feature = "std")]
         {
-          let c_name = CString::new(desc.name.as_bytes()).unwrap();
+          let c_name = CString::new(glsl_name.as_bytes()).unwrap();
           unsafe { gl::BindAttribLocation(raw.handle, index, c_name.as_ptr() as *const GLchar) };
         }
 
@@ -1327,30 +2114,83 @@ where S: Semantics {
             });
           }
         }
+
+        bound.push(desc);
       }
 
       Err(warning) => warnings.push(ProgramWarning::VertexAttrib(warning))
     }
   }
 
-  warnings
+  (warnings, bound)
+}
+
+// Vertex attributes used by a linked program, along with their locations. Built-in attributes
+// (gl_VertexID, etc.) are ignored.
+fn get_active_vertex_attribs(raw: &RawProgram) -> Vec<SemanticsDesc> {
+  let mut active = Vec::new();
+
+  unsafe {
+    let mut count = 0;
+    gl::GetProgramiv(raw.handle, gl::ACTIVE_ATTRIBUTES, &mut count);
+
+    let mut max_len = 0;
+    gl::GetProgramiv(raw.handle, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_len);
+
+    let mut name = vec![0u8; max_len.max(1) as usize];
+
+    for i in 0 .. count.max(0) as GLuint {
+      let mut len = 0;
+      let mut size = 0;
+      let mut ty = 0;
+
+      gl::GetActiveAttrib(
+        raw.handle,
+        i,
+        name.len() as GLsizei,
+        &mut len,
+        &mut size,
+        &mut ty,
+        name.as_mut_ptr() as *mut GLchar,
+      );
+
+      let name = String::from_utf8_lossy(&name[.. len.max(0) as usize]);
+
+      if name.starts_with("gl_") {
+        continue;
+      }
+
+      let name = raw.source_name(&name).to_owned();
+
+      if let Ok(location) = get_vertex_attrib_location(raw, &name) {
+        active.push(SemanticsDesc {
+          index: location as usize,
+          name,
+          interpolation: None,
+        });
+      }
+    }
+  }
+
+  active
 }
 
 fn get_vertex_attrib_location(
   raw: &RawProgram,
   name: &str
 ) -> Result<GLuint, VertexAttribWarning> {
+  let glsl_name = raw.glsl_name(name);
   let location = {
     #[cfg(feature = "std")]
     {
-      let c_name = CString::new(name.as_bytes()).unwrap();
+      let c_name = CString::new(glsl_name.as_bytes()).unwrap();
       unsafe { gl::GetAttribLocation(raw.handle, c_name.as_ptr() as *const GLchar) }
     }
 
     #[cfg(not(feature = "std"))]
     {
       unsafe {
-        with_cstring(name, |c_name| gl::GetAttribLocation(raw.handle, c_name)).unwrap_or(-1)
+        with_cstring(glsl_name, |c_name| gl::GetAttribLocation(raw.handle, c_name)).unwrap_or(-1)
       }
     }
   };
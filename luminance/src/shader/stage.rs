@@ -3,13 +3,59 @@
 //! A shader stage is a part of a _shader program_. Typically, _shader programs_ are comprised of
 //! _several_ shader stages. The minimal configuration implies at least a _vertex shader_ and a
 //! _fragment shader_.
+//!
+//! # Includes
+//!
+//! Shader stages can share code via `#include "path"` directives, resolved with
+//! [`Stage::new_with_includes`] and a [`ShaderSourceProvider`] – any
+//! `FnMut(&str) -> Option<String>` closure is one. Included sources are inlined and `#line`
+//! directives are inserted so that line numbers in compilation errors refer to the original
+//! files. *GLSL* only identifies source strings by number: the root source is `0` and included
+//! files are numbered in order of first inclusion. Compilation errors of stages created with
+//! includes list the file behind each source string number.
+//!
+//! # SPIR-V
+//!
+//! Stages can also be created out of *SPIR-V* modules – pre-compiled with *glslang* or *shaderc*
+//! for instance – with [`Stage::from_spirv`], if the context supports `GL_ARB_gl_spirv`.
+//! Specialization constants are set with [`Stage::from_spirv_specialized`]. The extension
+//! functions are loaded by the windowing crates with [`load_spirv_with`]. *SPIR-V* modules don’t
+//! necessarily carry names, so uniforms of programs made of *SPIR-V* stages should be declared
+//! with explicit locations and names preserved by the compiler.
+//!
+//! # WGSL
+//!
+//! With the `wgsl` feature, stages can be authored in *WGSL* and translated to *GLSL* by *naga*
+//! with [`Stage::from_wgsl`] – or [`Program::from_wgsl`] for both stages of a program at once.
+//! Only vertex and fragment entry points exist in *WGSL*. Programs look resources up by their
+//! *WGSL* names:
+//!
+//!   - `@location(n)` vertex inputs are bound to the attribute `n`, which must be the index of the
+//!     semantics of the same name.
+//!   - `var<uniform>` variables are uniform blocks, to bind *std140* [`UniformBlock`] buffers to.
+//!   - Textures are combined with the samplers they are sampled with and set like GLSL samplers;
+//!     the sampling parameters are the ones of the bound texture, not of the *WGSL* sampler.
+//!
+//! Storage buffers, storage textures and push constants have no *GLSL 3.30* equivalent and are
+//! rejected. Clip space is the *OpenGL* one: depth goes from `-1` to `1`.
+//!
+//! [`Program::from_wgsl`]: crate::shader::program::Program::from_wgsl
+//! [`UniformBlock`]: crate::buffer::UniformBlock
 #[cfg(feature = "std")]
 use std::ffi::CString;
 #[cfg(feature = "std")]
+use std::mem;
+#[cfg(feature = "std")]
+use std::os::raw::c_void;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
 use std::fmt;
 #[cfg(feature = "std")]
 use std::ptr::{null, null_mut};
 
+#[cfg(not(feature = "std"))]
+use alloc::format;
 #[cfg(not(feature = "std"))]
 use alloc::prelude::ToOwned;
 #[cfg(not(feature = "std"))]
@@ -17,11 +63,20 @@ use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
+use core::ffi::c_void;
+#[cfg(not(feature = "std"))]
 use core::fmt;
 #[cfg(not(feature = "std"))]
+use core::mem;
+#[cfg(not(feature = "std"))]
 use core::ptr::{null, null_mut};
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::capabilities::{current_profile, get_ctx_extensions, Extension, Profile};
 use crate::metagl::*;
+#[cfg(feature = "wgsl")]
+use crate::shader::wgsl;
 
 /// A shader stage type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -55,11 +110,15 @@ impl fmt::Display for Type {
 pub struct Stage {
   handle: GLuint,
   ty: Type,
+  // (source name, GLSL name) of the identifiers renamed by the front end the stage comes from
+  names: Vec<(String, String)>,
 }
 
 impl Stage {
   /// Create a new shader stage.
   pub fn new(ty: Type, src: &str) -> Result<Self, StageError> {
+    check_strict_type(ty)?;
+
     unsafe {
       let handle = gl::CreateShader(opengl_shader_type(ty));
 
@@ -73,27 +132,148 @@ impl Stage {
       Self::source(handle, src);
       gl::CompileShader(handle);
 
-      let mut compiled: GLint = gl::FALSE.into();
-      gl::GetShaderiv(handle, gl::COMPILE_STATUS, &mut compiled);
+      Self::check_compiled(handle, ty)
+    }
+  }
 
-      if compiled == gl::TRUE.into() {
-        Ok(Stage { handle, ty })
-      } else {
-        let mut log_len: GLint = 0;
-        gl::GetShaderiv(handle, gl::INFO_LOG_LENGTH, &mut log_len);
+  /// Create a new shader stage out of a *SPIR-V* module, which entry point is `main`.
+  pub fn from_spirv(ty: Type, binary: &[u8]) -> Result<Self, StageError> {
+    Self::from_spirv_specialized(ty, binary, &Specialization::default())
+  }
 
-        let mut log: Vec<u8> = Vec::with_capacity(log_len as usize);
-        gl::GetShaderInfoLog(handle, log_len, null_mut(), log.as_mut_ptr() as *mut GLchar);
+  /// Create a new shader stage out of a *SPIR-V* module, with a given entry point and values for
+  /// its specialization constants.
+  pub fn from_spirv_specialized(
+    ty: Type,
+    binary: &[u8],
+    specialization: &Specialization,
+  ) -> Result<Self, StageError> {
+    let specialize_shader = SPECIALIZE_SHADER.load(Ordering::Relaxed);
+
+    if cfg!(feature = "gl33-strict")
+      || specialize_shader == 0
+      || !unsafe { get_ctx_extensions() }.has(Extension::GlSpirv)
+    {
+      return Err(StageError::SpirvUnsupported);
+    }
+
+    check_strict_type(ty)?;
+
+    // the entry point is passed as a nul-terminated string
+    if specialization.entry_point.contains('\0') {
+      return Err(StageError::InvalidEntryPoint(specialization.entry_point.clone()));
+    }
 
-        gl::DeleteShader(handle);
+    let specialize_shader: SpecializeShaderFn = unsafe { mem::transmute(specialize_shader) };
 
-        log.set_len(log_len as usize);
+    let mut entry_point = specialization.entry_point.clone().into_bytes();
+    entry_point.push(0);
 
-        Err(StageError::CompilationFailed(ty, String::from_utf8(log).unwrap()))
+    let (indices, values): (Vec<GLuint>, Vec<GLuint>) =
+      specialization.constants.iter().cloned().unzip();
+
+    unsafe {
+      let handle = gl::CreateShader(opengl_shader_type(ty));
+
+      if handle == 0 {
+        return Err(StageError::CompilationFailed(
+          ty,
+          "unable to create shader stage".to_owned(),
+        ));
       }
+
+      gl::ShaderBinary(
+        1,
+        &handle,
+        SHADER_BINARY_FORMAT_SPIR_V,
+        binary.as_ptr() as *const c_void,
+        binary.len() as GLsizei,
+      );
+      specialize_shader(
+        handle,
+        entry_point.as_ptr() as *const GLchar,
+        indices.len() as GLuint,
+        indices.as_ptr(),
+        values.as_ptr(),
+      );
+
+      Self::check_compiled(handle, ty)
     }
   }
 
+  // Check the compilation status of a shader stage, deleting it if it failed.
+  unsafe fn check_compiled(handle: GLuint, ty: Type) -> Result<Self, StageError> {
+    let mut compiled: GLint = gl::FALSE.into();
+    gl::GetShaderiv(handle, gl::COMPILE_STATUS, &mut compiled);
+
+    if compiled == gl::TRUE.into() {
+      log_event!(created(Stage, handle));
+      Ok(Stage { handle, ty, names: Vec::new() })
+    } else {
+      let mut log_len: GLint = 0;
+      gl::GetShaderiv(handle, gl::INFO_LOG_LENGTH, &mut log_len);
+
+      let mut log: Vec<u8> = Vec::with_capacity(log_len as usize);
+      gl::GetShaderInfoLog(handle, log_len, null_mut(), log.as_mut_ptr() as *mut GLchar);
+
+      gl::DeleteShader(handle);
+
+      log.set_len(log_len as usize);
+
+      Err(StageError::CompilationFailed(ty, String::from_utf8(log).unwrap()))
+    }
+  }
+
+  /// Create a new shader stage, resolving its `#include` directives with `provider`.
+  ///
+  /// Included sources can include other sources too. Recursive inclusions are rejected; guard
+  /// sources included several times with `#ifndef` if needed.
+  pub fn new_with_includes<P>(ty: Type, src: &str, provider: &mut P) -> Result<Self, StageError>
+  where P: ShaderSourceProvider {
+    let preprocessed = preprocess(src, provider)?;
+
+    Self::new(ty, &preprocessed.code).map_err(|e| match e {
+      StageError::CompilationFailed(ty, log) => {
+        StageError::CompilationFailed(ty, preprocessed.annotate_log(log))
+      }
+
+      e => e,
+    })
+  }
+
+  /// Create a new shader stage out of the entry point `entry_point` of a *WGSL* module.
+  ///
+  /// See the [module documentation](index.html#wgsl) for the mapping of the *WGSL* resources.
+  #[cfg(feature = "wgsl")]
+  pub fn from_wgsl(ty: Type, src: &str, entry_point: &str) -> Result<Self, StageError> {
+    let (module, info) = wgsl::parse(src).map_err(StageError::WgslError)?;
+    Self::from_wgsl_module(&module, &info, ty, entry_point)
+  }
+
+  #[cfg(feature = "wgsl")]
+  pub(crate) fn from_wgsl_module(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    ty: Type,
+    entry_point: &str,
+  ) -> Result<Self, StageError> {
+    let translated =
+      wgsl::translate(module, info, ty, entry_point).map_err(StageError::WgslError)?;
+
+    let mut stage = Self::new(ty, &translated.glsl).map_err(|e| match e {
+      // the errors refer to the generated GLSL
+      StageError::CompilationFailed(ty, log) => StageError::CompilationFailed(
+        ty,
+        format!("{}\ngenerated GLSL:\n{}", log, translated.glsl),
+      ),
+
+      e => e,
+    })?;
+
+    stage.names = translated.names;
+    Ok(stage)
+  }
+
   // Source a shader stage with the given shader stage handle and the source.
   #[inline(always)]
   fn source(handle: GLuint, src: &str) {
@@ -118,11 +298,47 @@ impl Stage {
   pub(crate) fn handle(&self) -> GLuint {
     self.handle
   }
+
+  #[inline]
+  pub(crate) fn ty(&self) -> Type {
+    self.ty
+  }
+
+  // Identifiers renamed by the front end of the stage, as (source name, GLSL name).
+  pub(crate) fn names(&self) -> &[(String, String)] {
+    &self.names
+  }
+
+  // GLSL name of an identifier of the source of the stage.
+  pub(crate) fn glsl_name<'a>(&'a self, name: &'a str) -> &'a str {
+    self.names.iter().find(|(n, _)| n == name).map_or(name, |(_, glsl)| glsl)
+  }
+
+  // Source code of the stage, as sent to the driver.
+  pub(crate) fn source_code(&self) -> String {
+    unsafe {
+      let mut len: GLint = 0;
+      gl::GetShaderiv(self.handle, gl::SHADER_SOURCE_LENGTH, &mut len);
+
+      let mut src = vec![0u8; len.max(1) as usize];
+      let mut written = 0;
+      gl::GetShaderSource(
+        self.handle,
+        src.len() as GLsizei,
+        &mut written,
+        src.as_mut_ptr() as *mut GLchar,
+      );
+      src.truncate(written.max(0) as usize);
+
+      String::from_utf8_lossy(&src).into_owned()
+    }
+  }
 }
 
 impl Drop for Stage {
   fn drop(&mut self) {
     unsafe { gl::DeleteShader(self.handle) }
+    log_event!(destroyed(Stage, self.handle));
   }
 }
 
@@ -133,6 +349,16 @@ pub enum StageError {
   CompilationFailed(Type, String),
   /// Occurs when you try to create a shader which type is not supported on the current hardware.
   UnsupportedType(Type),
+  /// Occurs when an `#include` directive cannot be resolved.
+  IncludeFailed(IncludeError),
+  /// Occurs when creating a stage out of a *SPIR-V* module while `GL_ARB_gl_spirv` is not
+  /// supported or not loaded.
+  SpirvUnsupported,
+  /// Occurs when the entry point of a *SPIR-V* stage contains a nul character.
+  InvalidEntryPoint(String),
+  /// Occurs when a *WGSL* module cannot be parsed, validated or translated to *GLSL*.
+  #[cfg(feature = "wgsl")]
+  WgslError(String),
 }
 
 impl fmt::Display for StageError {
@@ -141,12 +367,270 @@ impl fmt::Display for StageError {
       StageError::CompilationFailed(ref ty, ref r) => write!(f, "{} compilation error: {}", ty, r),
 
       StageError::UnsupportedType(ty) => write!(f, "unsupported {}", ty),
+
+      StageError::IncludeFailed(ref e) => write!(f, "shader include error: {}", e),
+
+      StageError::SpirvUnsupported => f.write_str("SPIR-V shader stages are not supported"),
+
+      StageError::InvalidEntryPoint(ref e) => write!(f, "invalid SPIR-V entry point: {:?}", e),
+
+      #[cfg(feature = "wgsl")]
+      StageError::WgslError(ref e) => write!(f, "WGSL error: {}", e),
+    }
+  }
+}
+
+impl From<IncludeError> for StageError {
+  fn from(e: IncludeError) -> Self {
+    StageError::IncludeFailed(e)
+  }
+}
+
+/// Entry point and specialization constants of a *SPIR-V* stage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Specialization {
+  entry_point: String,
+  constants: Vec<(GLuint, GLuint)>,
+}
+
+impl Specialization {
+  /// Create a specialization for a given entry point, with no specialization constant set.
+  ///
+  /// Entry points containing a nul character are rejected with [`StageError::InvalidEntryPoint`]
+  /// when creating the stage.
+  pub fn new<S>(entry_point: S) -> Self where S: Into<String> {
+    Specialization {
+      entry_point: entry_point.into(),
+      constants: Vec::new(),
     }
   }
+
+  /// Set the value of the specialization constant which ID is `id`.
+  ///
+  /// Constants not set keep the default value they have in the module.
+  pub fn set_constant<T>(mut self, id: u32, value: T) -> Self where T: SpecializationConstant {
+    let bits = value.to_bits();
+
+    match self.constants.iter_mut().find(|(i, _)| *i == id) {
+      Some(constant) => constant.1 = bits,
+      None => self.constants.push((id, bits)),
+    }
+
+    self
+  }
+
+  /// Entry point of the stage.
+  pub fn entry_point(&self) -> &str {
+    &self.entry_point
+  }
+}
+
+/// Default specialization: `main` entry point and no specialization constant set.
+impl Default for Specialization {
+  fn default() -> Self {
+    Specialization::new("main")
+  }
+}
+
+/// Types that specialization constants can have.
+pub trait SpecializationConstant {
+  /// 32-bit representation of the value, as read by the driver.
+  fn to_bits(self) -> u32;
+}
+
+impl SpecializationConstant for u32 {
+  fn to_bits(self) -> u32 {
+    self
+  }
+}
+
+impl SpecializationConstant for i32 {
+  fn to_bits(self) -> u32 {
+    self as u32
+  }
+}
+
+impl SpecializationConstant for f32 {
+  fn to_bits(self) -> u32 {
+    f32::to_bits(self)
+  }
+}
+
+impl SpecializationConstant for bool {
+  fn to_bits(self) -> u32 {
+    self as u32
+  }
+}
+
+/// Load the `GL_ARB_gl_spirv` functions with a function loader.
+///
+/// The windowing crates call this function right after loading *OpenGL*; you only need to call
+/// it if you create the graphics context yourself.
+pub fn load_spirv_with<F>(mut loader: F) where F: FnMut(&str) -> *const c_void {
+  let mut f = loader("glSpecializeShader");
+
+  if f.is_null() {
+    f = loader("glSpecializeShaderARB");
+  }
+
+  SPECIALIZE_SHADER.store(f as usize, Ordering::Relaxed);
+}
+
+// glSpecializeShader(ARB), loaded by load_spirv_with
+static SPECIALIZE_SHADER: AtomicUsize = AtomicUsize::new(0);
+
+type SpecializeShaderFn =
+  extern "system" fn(GLuint, *const GLchar, GLuint, *const GLuint, *const GLuint);
+
+const SHADER_BINARY_FORMAT_SPIR_V: GLenum = 0x9551;
+
+/// Provider of the sources included by shader stages.
+pub trait ShaderSourceProvider {
+  /// Get the source code behind an include path, if any.
+  fn source(&mut self, path: &str) -> Option<String>;
+}
+
+impl<F> ShaderSourceProvider for F where F: FnMut(&str) -> Option<String> {
+  fn source(&mut self, path: &str) -> Option<String> {
+    self(path)
+  }
+}
+
+/// Errors that can happen while resolving `#include` directives.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IncludeError {
+  /// The provider has no source for the given path.
+  NotFound(String),
+  /// A source includes itself, directly or not.
+  Recursive(String),
+  /// An `#include` directive is not followed by a quoted path. Holds the source string number
+  /// and the line of the directive.
+  Malformed(usize, usize),
+}
+
+impl fmt::Display for IncludeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      IncludeError::NotFound(ref path) => write!(f, "cannot find \"{}\"", path),
+      IncludeError::Recursive(ref path) => write!(f, "\"{}\" includes itself", path),
+      IncludeError::Malformed(source, line) => {
+        write!(f, "malformed #include directive at {}:{}", source, line)
+      }
+    }
+  }
+}
+
+// Source code with includes resolved, along with the paths of the source strings.
+struct Preprocessed {
+  code: String,
+  // paths of the included sources; source string number i + 1 is paths[i]
+  paths: Vec<String>,
+}
+
+impl Preprocessed {
+  // Append the source string numbers to a compilation log.
+  fn annotate_log(&self, mut log: String) -> String {
+    if !self.paths.is_empty() {
+      log.push_str("\nsource strings: 0 = <root>");
+
+      for (i, path) in self.paths.iter().enumerate() {
+        log.push_str(&format!(", {} = {}", i + 1, path));
+      }
+    }
+
+    log
+  }
+}
+
+// Resolve the #include directives of a source.
+fn preprocess<P>(src: &str, provider: &mut P) -> Result<Preprocessed, IncludeError>
+where P: ShaderSourceProvider {
+  let mut preprocessed = Preprocessed {
+    code: String::from("#line 1 0\n"),
+    paths: Vec::new(),
+  };
+  let mut stack = Vec::new();
+
+  preprocess_source(src, 0, provider, &mut preprocessed, &mut stack)?;
+
+  Ok(preprocessed)
+}
+
+fn preprocess_source<P>(
+  src: &str,
+  source_nb: usize,
+  provider: &mut P,
+  preprocessed: &mut Preprocessed,
+  stack: &mut Vec<String>,
+) -> Result<(), IncludeError>
+where P: ShaderSourceProvider {
+  for (i, line) in src.lines().enumerate() {
+    let path = match include_path(line) {
+      Some(path) => path.ok_or(IncludeError::Malformed(source_nb, i + 1))?,
+
+      None => {
+        preprocessed.code.push_str(line);
+        preprocessed.code.push('\n');
+        continue;
+      }
+    };
+
+    if stack.iter().any(|p| p == path) {
+      return Err(IncludeError::Recursive(path.to_owned()));
+    }
+
+    let included = provider.source(path).ok_or_else(|| IncludeError::NotFound(path.to_owned()))?;
+    let included_nb = match preprocessed.paths.iter().position(|p| p == path) {
+      Some(index) => index + 1,
+
+      None => {
+        preprocessed.paths.push(path.to_owned());
+        preprocessed.paths.len()
+      }
+    };
+
+    preprocessed.code.push_str(&format!("#line 1 {}\n", included_nb));
+
+    stack.push(path.to_owned());
+    preprocess_source(&included, included_nb, provider, preprocessed, stack)?;
+    stack.pop();
+
+    // resume the including source on the line following the directive
+    preprocessed.code.push_str(&format!("#line {} {}\n", i + 2, source_nb));
+  }
+
+  Ok(())
+}
+
+// Path of an #include directive. None if the line is not a directive, Some(None) if it’s malformed.
+fn include_path(line: &str) -> Option<Option<&str>> {
+  let line = line.trim_start();
+
+  if !line.starts_with('#') {
+    return None;
+  }
+
+  let arg = line[1 ..].trim_start().strip_prefix("include")?;
+
+  // #include_foo is another directive
+  if !arg.is_empty() && !arg.starts_with(|c: char| c.is_whitespace() || c == '"') {
+    return None;
+  }
+
+  let arg = arg.trim();
+
+  if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') {
+    Some(Some(&arg[1 .. arg.len() - 1]))
+  } else {
+    Some(None)
+  }
 }
 
 fn glsl_pragma_src(src: &str) -> String {
-  let mut pragma = String::from(GLSL_PRAGMA);
+  let mut pragma = String::from(match current_profile() {
+    Profile::Core => GLSL_PRAGMA,
+    Profile::Es => GLSL_ES_PRAGMA,
+  });
   pragma.push_str(src);
   pragma
 }
@@ -155,6 +639,45 @@ const GLSL_PRAGMA: &str =
 "#version 330 core\n\
 #extension GL_ARB_separate_shader_objects : require\n";
 
+// OpenGL ES has no or low default precisions for some types; use the highest one for all of them
+const GLSL_ES_PRAGMA: &str =
+"#version 300 es\n\
+precision highp float;\n\
+precision highp int;\n\
+precision highp sampler2D;\n\
+precision highp sampler3D;\n\
+precision highp samplerCube;\n\
+precision highp sampler2DShadow;\n\
+precision highp samplerCubeShadow;\n\
+precision highp sampler2DArray;\n\
+precision highp sampler2DArrayShadow;\n\
+precision highp isampler2D;\n\
+precision highp isampler3D;\n\
+precision highp isamplerCube;\n\
+precision highp isampler2DArray;\n\
+precision highp usampler2D;\n\
+precision highp usampler3D;\n\
+precision highp usamplerCube;\n\
+precision highp usampler2DArray;\n";
+
+// Tessellation shaders require OpenGL 4.0, which the gl33-strict feature forbids; OpenGL ES 3.0
+// has neither tessellation nor geometry shaders.
+fn check_strict_type(ty: Type) -> Result<(), StageError> {
+  let es = current_profile() == Profile::Es;
+
+  match ty {
+    Type::TessellationControlShader | Type::TessellationEvaluationShader
+      if cfg!(feature = "gl33-strict") || es =>
+    {
+      Err(StageError::UnsupportedType(ty))
+    }
+
+    Type::GeometryShader if es => Err(StageError::UnsupportedType(ty)),
+
+    _ => Ok(()),
+  }
+}
+
 fn opengl_shader_type(t: Type) -> GLenum {
   match t {
     Type::TessellationControlShader => gl::TESS_CONTROL_SHADER,
@@ -164,3 +687,102 @@ fn opengl_shader_type(t: Type) -> GLenum {
     Type::FragmentShader => gl::FRAGMENT_SHADER,
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sources(path: &str) -> Option<String> {
+    let src = match path {
+      "common.glsl" => "#include \"math.glsl\"\nfloat common() { return pi(); }",
+      "math.glsl" => "float pi() { return 3.14; }",
+      "loop_a.glsl" => "#include \"loop_b.glsl\"",
+      "loop_b.glsl" => "#include \"loop_a.glsl\"",
+      "self.glsl" => "  # include \"self.glsl\"",
+      _ => return None,
+    };
+
+    Some(src.to_owned())
+  }
+
+  fn preprocess_src(src: &str) -> Result<Preprocessed, IncludeError> {
+    preprocess(src, &mut sources)
+  }
+
+  #[test]
+  fn without_includes() {
+    let preprocessed = preprocess_src("void main() {}\n").unwrap();
+
+    assert_eq!(preprocessed.code, "#line 1 0\nvoid main() {}\n");
+    assert!(preprocessed.paths.is_empty());
+  }
+
+  #[test]
+  fn nested_includes() {
+    let src = "#version 330\n#include \"common.glsl\"\nvoid main() {}\n";
+    let preprocessed = preprocess_src(src).unwrap();
+    let expected = "\
+#line 1 0
+#version 330
+#line 1 1
+#line 1 2
+float pi() { return 3.14; }
+#line 2 1
+float common() { return pi(); }
+#line 3 0
+void main() {}
+";
+
+    assert_eq!(preprocessed.code, expected);
+    assert_eq!(preprocessed.paths, vec!["common.glsl", "math.glsl"]);
+  }
+
+  #[test]
+  fn repeated_include_keeps_its_source_string() {
+    let src = "#include \"math.glsl\"\n#include \"math.glsl\"\n";
+    let preprocessed = preprocess_src(src).unwrap();
+
+    assert_eq!(preprocessed.paths, vec!["math.glsl"]);
+    assert_eq!(preprocessed.code.matches("#line 1 1\n").count(), 2);
+  }
+
+  #[test]
+  fn recursive_includes() {
+    assert_eq!(
+      preprocess_src("#include \"loop_a.glsl\"").err(),
+      Some(IncludeError::Recursive("loop_a.glsl".to_owned()))
+    );
+    assert_eq!(
+      preprocess_src("#include \"self.glsl\"").err(),
+      Some(IncludeError::Recursive("self.glsl".to_owned()))
+    );
+  }
+
+  #[test]
+  fn include_not_found() {
+    assert_eq!(
+      preprocess_src("#include \"missing.glsl\"").err(),
+      Some(IncludeError::NotFound("missing.glsl".to_owned()))
+    );
+  }
+
+  #[test]
+  fn malformed_includes() {
+    for line in &["#include", "#include common.glsl", "#include \"", "#include <common.glsl>"] {
+      let src = format!("void f() {{}}\n{}\n", line);
+
+      assert_eq!(preprocess_src(&src).err(), Some(IncludeError::Malformed(0, 2)), "{}", line);
+    }
+  }
+
+  #[test]
+  fn include_directive_boundary() {
+    assert_eq!(include_path("#include \"a\""), Some(Some("a")));
+    assert_eq!(include_path("#include\"a\""), Some(Some("a")));
+    assert_eq!(include_path("\t#  include\t\"a\"  "), Some(Some("a")));
+    assert_eq!(include_path("#include_foo \"a\""), None);
+    assert_eq!(include_path("#includes \"a\""), None);
+    assert_eq!(include_path("#define include"), None);
+    assert_eq!(include_path("include \"a\""), None);
+  }
+}
@@ -0,0 +1,295 @@
+// Translation of WGSL modules to GLSL, with naga.
+//
+// naga names the resources, vertex attributes and varyings it writes after their bindings and
+// their stage rather than after their WGSL names. Stage-dependent names are rewritten so that
+// both stages of a program declare the same resources, and the WGSL names of the rest are kept
+// alongside the GLSL, for programs to look them up.
+
+use naga::back::glsl;
+use naga::proc::BoundsCheckPolicies;
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use naga::{AddressSpace, Binding, Handle, Module, ShaderStage, TypeInner};
+
+use crate::capabilities::{current_profile, Profile};
+use crate::shader::stage::Type;
+
+// A GLSL stage translated from a WGSL entry point.
+pub(crate) struct Translated {
+  // GLSL source, without its #version line
+  pub(crate) glsl: String,
+  // (WGSL name, GLSL name)
+  pub(crate) names: Vec<(String, String)>,
+}
+
+// Parse and validate a WGSL module.
+pub(crate) fn parse(src: &str) -> Result<(Module, ModuleInfo), String> {
+  let module = naga::front::wgsl::parse_str(src).map_err(|e| e.emit_to_string(src))?;
+  let info = Validator::new(ValidationFlags::all(), Capabilities::empty())
+    .validate(&module)
+    .map_err(|e| e.emit_to_string(src))?;
+
+  Ok((module, info))
+}
+
+// Write the GLSL of an entry point of a parsed module.
+pub(crate) fn translate(
+  module: &Module,
+  info: &ModuleInfo,
+  ty: Type,
+  entry_point: &str,
+) -> Result<Translated, String> {
+  let stage = match ty {
+    Type::VertexShader => ShaderStage::Vertex,
+    Type::FragmentShader => ShaderStage::Fragment,
+    _ => return Err(format!("WGSL has no {}", ty)),
+  };
+
+  let function = module
+    .entry_points
+    .iter()
+    .find(|ep| ep.name == entry_point && ep.stage == stage)
+    .map(|ep| &ep.function)
+    .ok_or_else(|| format!("no {} entry point named {}", ty, entry_point))?;
+
+  let version = match current_profile() {
+    Profile::Core => glsl::Version::Desktop(330),
+    Profile::Es => glsl::Version::new_gles(300),
+  };
+  let options = glsl::Options {
+    version,
+    writer_flags: glsl::WriterFlags::empty(),
+    ..glsl::Options::default()
+  };
+  let pipeline_options = glsl::PipelineOptions {
+    shader_stage: stage,
+    entry_point: entry_point.to_owned(),
+    multiview: None,
+  };
+
+  let mut out = String::new();
+  let reflection = glsl::Writer::new(
+    &mut out,
+    module,
+    info,
+    &options,
+    &pipeline_options,
+    BoundsCheckPolicies::default(),
+  )
+  .and_then(|mut writer| writer.write())
+  .map_err(|e| e.to_string())?;
+
+  // the stage header is written by Stage::new
+  let mut glsl = out
+    .lines()
+    .filter(|line| !line.starts_with("#version"))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let mut names = Vec::new();
+  let suffix = match stage {
+    ShaderStage::Vertex => "vs",
+    _ => "fs",
+  };
+
+  for (handle, var) in module.global_variables.iter() {
+    let binding = match var.binding {
+      Some(ref binding) => binding,
+      None => continue,
+    };
+
+    let generic = format!("_group_{}_binding_{}", binding.group, binding.binding);
+    glsl = replace_ident(&glsl, &format!("{}_{}", generic, suffix), &generic);
+
+    let name = match var.name {
+      Some(ref name) => name.clone(),
+      None => continue,
+    };
+
+    match var.space {
+      // uniform buffers are uniform blocks named after their type, their handle and the stage
+      AddressSpace::Uniform => {
+        if let Some(block) = reflection.uniforms.get(&handle) {
+          let generic_block = block.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+          glsl = replace_ident(&glsl, block, generic_block);
+          names.push((name, generic_block.to_owned()));
+        }
+      }
+
+      // textures are combined with their samplers, named after the texture
+      AddressSpace::Handle if is_texture(module, var.ty) => names.push((name, generic)),
+
+      _ => (),
+    }
+  }
+
+  let mut inputs = Vec::new();
+  let mut outputs = Vec::new();
+
+  for arg in &function.arguments {
+    flatten(module, arg.ty, arg.name.as_deref(), arg.binding.as_ref(), &mut inputs);
+  }
+
+  if let Some(ref result) = function.result {
+    flatten(module, result.ty, None, result.binding.as_ref(), &mut outputs);
+  }
+
+  match stage {
+    ShaderStage::Vertex => {
+      push_locations(&mut names, &inputs, "p2vs");
+      push_locations(&mut names, &outputs, "vs2fs");
+    }
+
+    _ => push_locations(&mut names, &inputs, "vs2fs"),
+  }
+
+  Ok(Translated { glsl, names })
+}
+
+// A named input or output of an entry point.
+type Item<'a> = (Option<&'a str>, Option<&'a Binding>);
+
+fn is_texture(module: &Module, ty: Handle<naga::Type>) -> bool {
+  match module.types[ty].inner {
+    TypeInner::Image { .. } => true,
+    TypeInner::BindingArray { base, .. } => is_texture(module, base),
+    _ => false,
+  }
+}
+
+// Collect an input or output, or the members of a structure without binding.
+fn flatten<'a>(
+  module: &'a Module,
+  ty: Handle<naga::Type>,
+  name: Option<&'a str>,
+  binding: Option<&'a Binding>,
+  items: &mut Vec<Item<'a>>,
+) {
+  match (binding, &module.types[ty].inner) {
+    (None, TypeInner::Struct { members, .. }) => {
+      items.extend(members.iter().map(|m| (m.name.as_deref(), m.binding.as_ref())));
+    }
+
+    _ => items.push((name, binding)),
+  }
+}
+
+// Vertex attributes and varyings are named _{prefix}_location{location} by naga.
+fn push_locations(names: &mut Vec<(String, String)>, items: &[Item], prefix: &str) {
+  for &(name, binding) in items {
+    if let (Some(name), Some(&Binding::Location { location, .. })) = (name, binding) {
+      names.push((name.to_owned(), format!("_{}_location{}", prefix, location)));
+    }
+  }
+}
+
+// Replace the occurrences of an identifier.
+fn replace_ident(src: &str, from: &str, to: &str) -> String {
+  let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+  let mut result = String::with_capacity(src.len());
+  let mut last = 0;
+
+  for (i, _) in src.match_indices(from) {
+    let before = src[.. i].chars().next_back();
+    let after = src[i + from.len() ..].chars().next();
+
+    if before.filter(|&c| is_ident(c)).is_none() && after.filter(|&c| is_ident(c)).is_none() {
+      result.push_str(&src[last .. i]);
+      result.push_str(to);
+      last = i + from.len();
+    }
+  }
+
+  result.push_str(&src[last ..]);
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SRC: &str = "
+    struct Globals { tint: vec4<f32>, scale: f32 }
+    @group(0) @binding(0) var<uniform> globals: Globals;
+    @group(0) @binding(1) var tex: texture_2d<f32>;
+    @group(0) @binding(2) var samp: sampler;
+
+    struct VsOut { @builtin(position) pos: vec4<f32>, @location(0) uv: vec2<f32> }
+
+    @vertex
+    fn vs_main(@location(0) co: vec2<f32>, @location(1) color: vec3<f32>) -> VsOut {
+      var o: VsOut;
+      o.pos = vec4<f32>(co * globals.scale, 0.0, 1.0);
+      o.uv = co + color.xy;
+      return o;
+    }
+
+    @fragment
+    fn fs_main(v: VsOut) -> @location(0) vec4<f32> {
+      return textureSample(tex, samp, v.uv) * globals.tint;
+    }
+  ";
+
+  fn translate_src(ty: Type, entry_point: &str) -> Translated {
+    let (module, info) = parse(SRC).unwrap();
+    translate(&module, &info, ty, entry_point).unwrap()
+  }
+
+  fn name<'a>(translated: &'a Translated, name: &str) -> Option<&'a str> {
+    translated.names.iter().find(|(n, _)| n == name).map(|(_, glsl)| glsl.as_str())
+  }
+
+  #[test]
+  fn vertex_names() {
+    let vs = translate_src(Type::VertexShader, "vs_main");
+
+    assert!(!vs.glsl.contains("#version"));
+    assert_eq!(name(&vs, "co"), Some("_p2vs_location0"));
+    assert_eq!(name(&vs, "color"), Some("_p2vs_location1"));
+    assert_eq!(name(&vs, "uv"), Some("_vs2fs_location0"));
+    assert_eq!(name(&vs, "globals"), Some("Globals_block_0"));
+    assert_eq!(name(&vs, "pos"), None);
+  }
+
+  #[test]
+  fn fragment_names() {
+    let fs = translate_src(Type::FragmentShader, "fs_main");
+
+    assert_eq!(name(&fs, "uv"), Some("_vs2fs_location0"));
+    assert_eq!(name(&fs, "globals"), Some("Globals_block_0"));
+    assert_eq!(name(&fs, "tex"), Some("_group_0_binding_1"));
+    assert_eq!(name(&fs, "samp"), None);
+  }
+
+  #[test]
+  fn stages_share_resource_names() {
+    let vs = translate_src(Type::VertexShader, "vs_main");
+    let fs = translate_src(Type::FragmentShader, "fs_main");
+
+    for glsl in &[&vs.glsl, &fs.glsl] {
+      let block = "uniform Globals_block_0 { Globals _group_0_binding_0; };";
+      assert!(glsl.contains(block), "{}", glsl);
+      assert!(!glsl.contains("_vs;") && !glsl.contains("_fs;"), "{}", glsl);
+    }
+
+    assert!(fs.glsl.contains("uniform sampler2D _group_0_binding_1;"), "{}", fs.glsl);
+  }
+
+  #[test]
+  fn unknown_entry_point() {
+    let (module, info) = parse(SRC).unwrap();
+
+    assert!(translate(&module, &info, Type::VertexShader, "main").is_err());
+    assert!(translate(&module, &info, Type::FragmentShader, "vs_main").is_err());
+    assert!(translate(&module, &info, Type::GeometryShader, "vs_main").is_err());
+  }
+
+  #[test]
+  fn invalid_module() {
+    assert!(parse("fn main() -> f32 { return 1; }").is_err());
+    assert!(parse("@vertex fn vs_main( {").is_err());
+  }
+
+  #[test]
+  fn replace_whole_identifiers() {
+    assert_eq!(replace_ident("a ab a_ (a) ba", "a", "x"), "x ab a_ (x) ba");
+  }
+}
@@ -0,0 +1,389 @@
+//! 2D sprite batches.
+//!
+//! Rendering sprites one tessellation at a time costs a draw call – and often a texture binding –
+//! per sprite. A [`SpriteBatch`] accumulates textured quads – [`Sprite`]s – during the frame and
+//! renders them with as few draw calls as possible when flushed: sprites are grouped by layer, then
+//! by texture, and each group of sprites sharing a texture is a single draw call. When sprites come
+//! from a few atlases, that’s a handful of draw calls for the whole frame.
+//!
+//! Textures are registered in a [`RenderResources`] and sprites refer to them by [`TextureId`];
+//! texture units are allocated when flushing.
+//!
+//! ```ignore
+//! let mut batch = SpriteBatch::new(&mut surface)?;
+//! let mut resources = RenderResources::new();
+//! let characters = resources.add_texture(&characters_atlas)?;
+//!
+//! // each frame
+//! for enemy in &enemies {
+//!   batch.push(Sprite::new(characters, enemy.position, [32., 32.]).set_uv(enemy.frame_uv()));
+//! }
+//!
+//! builder.pipeline(&back_buffer, &PipelineState::default(), |_, mut shd_gate| {
+//!   batch.flush(&mut shd_gate, &resources, size).expect("sprite batch");
+//! });
+//! ```
+//!
+//! Sprites of the same layer sharing a texture are rendered in the order they were pushed; sprites
+//! of the same layer using different textures are not ordered relatively to each other. Sprites
+//! that overlap and must be blended in a given order must then use different layers.
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::blending::{Equation, Factor};
+use crate::context::GraphicsContext;
+use crate::pipeline::{BoundTexture, ShadingGate};
+use crate::pixel::NormUnsigned;
+use crate::render_list::{RenderList, RenderListError, RenderResources, TextureId};
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::tess::{Mode, Tess, TessBuilder, TessError, TessMapError};
+use crate::texture::{Dim2, Flat};
+use crate::vertex::{Vertex, VertexAttrib, VertexBufferDesc, VertexDesc, VertexInstancing};
+
+/// A textured quad rendered by a [`SpriteBatch`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+  /// Texture of the sprite, registered in the [`RenderResources`] the batch is flushed with.
+  pub texture: TextureId,
+  /// Position of the center of the sprite, in pixels from the top-left corner of the framebuffer.
+  pub position: [f32; 2],
+  /// Size of the sprite, in pixels.
+  pub size: [f32; 2],
+  /// Rotation of the sprite around its center, in radians, clockwise.
+  pub rotation: f32,
+  /// Texture coordinates of the top-left and bottom-right corners of the sprite.
+  pub uv: [[f32; 2]; 2],
+  /// Color multiplied with the texture, as sRGBA.
+  pub color: [u8; 4],
+  /// Layer of the sprite: sprites of higher layers are rendered after – on top of – the others.
+  pub layer: i32,
+}
+
+impl Sprite {
+  /// Create a sprite showing the whole `texture`, without rotation nor tint, on layer `0`.
+  pub fn new(texture: TextureId, position: [f32; 2], size: [f32; 2]) -> Self {
+    Sprite {
+      texture,
+      position,
+      size,
+      rotation: 0.,
+      uv: [[0., 0.], [1., 1.]],
+      color: [255, 255, 255, 255],
+      layer: 0,
+    }
+  }
+
+  /// Change the rotation of the sprite.
+  pub fn set_rotation(self, rotation: f32) -> Self {
+    Sprite { rotation, ..self }
+  }
+
+  /// Change the texture coordinates of the sprite, typically to show a part of an atlas.
+  pub fn set_uv(self, uv: [[f32; 2]; 2]) -> Self {
+    Sprite { uv, ..self }
+  }
+
+  /// Change the color of the sprite.
+  pub fn set_color(self, color: [u8; 4]) -> Self {
+    Sprite { color, ..self }
+  }
+
+  /// Change the layer of the sprite.
+  pub fn set_layer(self, layer: i32) -> Self {
+    Sprite { layer, ..self }
+  }
+}
+
+/// A batch of sprites, rendered with a few draw calls.
+///
+/// See the [module documentation](index.html) for further details.
+pub struct SpriteBatch {
+  program: Program<(), (), SpriteInterface>,
+  tess: Tess,
+  render_state: RenderState,
+  sprites: Vec<Sprite>,
+  vertices: Vec<SpriteVertex>,
+  indices: Vec<u32>,
+}
+
+impl SpriteBatch {
+  /// Create an empty sprite batch, compiling its shader program.
+  ///
+  /// Sprites are alpha-blended over the framebuffer, without depth test; see
+  /// [`SpriteBatch::set_render_state`] to change that.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, SpriteBatchError> where C: GraphicsContext {
+    let program = Program::from_strings(None, SPRITE_VS, None, SPRITE_FS)?.ignore_warnings();
+
+    // the storage grows with the first flushes
+    let empty = [SpriteVertex::default(); 4];
+    let tess = TessBuilder::new(ctx)
+      .add_vertices(empty)
+      .set_indices([0u32, 1, 2, 0, 2, 3])
+      .set_mode(Mode::Triangle)
+      .build()?;
+
+    let render_state = RenderState::default()
+      .set_blending((Equation::Additive, Factor::SrcAlpha, Factor::SrcAlphaComplement))
+      .set_depth_test(None);
+
+    Ok(SpriteBatch {
+      program,
+      tess,
+      render_state,
+      sprites: Vec::new(),
+      vertices: Vec::new(),
+      indices: Vec::new(),
+    })
+  }
+
+  /// Render state the sprites are rendered with.
+  pub fn render_state(&self) -> &RenderState {
+    &self.render_state
+  }
+
+  /// Change the render state the sprites are rendered with.
+  pub fn set_render_state(&mut self, render_state: RenderState) {
+    self.render_state = render_state;
+  }
+
+  /// Add a sprite to the batch.
+  pub fn push(&mut self, sprite: Sprite) {
+    self.sprites.push(sprite);
+  }
+
+  /// Number of sprites in the batch.
+  pub fn len(&self) -> usize {
+    self.sprites.len()
+  }
+
+  /// Whether the batch has no sprite.
+  pub fn is_empty(&self) -> bool {
+    self.sprites.is_empty()
+  }
+
+  /// Remove all the sprites without rendering them.
+  pub fn clear(&mut self) {
+    self.sprites.clear();
+  }
+
+  /// Render the sprites of the batch and remove them from it.
+  ///
+  /// `viewport` is the size, in pixels, of the framebuffer the shading gate renders to. The
+  /// textures of the sprites are resolved with `resources`. Textures must have normalized or
+  /// floating-point formats.
+  pub fn flush<C>(
+    &mut self,
+    shd_gate: &mut ShadingGate<C>,
+    resources: &RenderResources,
+    viewport: [u32; 2],
+  ) -> Result<(), SpriteBatchError>
+  where C: ?Sized + GraphicsContext {
+    if self.sprites.is_empty() {
+      return Ok(());
+    }
+
+    // the sort is stable: sprites sharing a layer and a texture keep their order
+    self.sprites.sort_by_key(|sprite| (sprite.layer, sprite.texture));
+
+    self.vertices.clear();
+    self.indices.clear();
+
+    for sprite in &self.sprites {
+      push_quad(&mut self.vertices, &mut self.indices, sprite);
+    }
+
+    self.tess.set_vertices(&self.vertices)?;
+    self.tess.set_indices(&self.indices)?;
+
+    // the program and tessellation of the batch are registered on top of the registry of the user
+    let mut resources = resources.layered();
+    let program = resources.push_program(self.program.handle());
+    let tess = resources.add_tess(&self.tess);
+    let iface = self.program.interface();
+
+    let mut list = RenderList::new();
+    list
+      .use_program(program)
+      .set_render_state(self.render_state)
+      .set_uniform(iface.viewport.handle(), [viewport[0] as f32, viewport[1] as f32]);
+
+    // a draw call per run of sprites sharing a texture
+    let mut start = 0;
+
+    while start < self.sprites.len() {
+      let texture = self.sprites[start].texture;
+      let run = self.sprites[start..].iter().take_while(|sprite| sprite.texture == texture).count();
+
+      list
+        .bind_texture(iface.texture.handle(), texture)
+        .draw_slice(tess, start * 6, run * 6, 1);
+      start += run;
+    }
+
+    self.sprites.clear();
+
+    shd_gate.replay(&list, &resources)?;
+    Ok(())
+  }
+}
+
+// Add the four vertices and two triangles of a sprite.
+fn push_quad(vertices: &mut Vec<SpriteVertex>, indices: &mut Vec<u32>, sprite: &Sprite) {
+  let first = vertices.len() as u32;
+  let [hw, hh] = [sprite.size[0] * 0.5, sprite.size[1] * 0.5];
+  let [[u0, v0], [u1, v1]] = sprite.uv;
+  let corners = [
+    ([-hw, -hh], [u0, v0]),
+    ([hw, -hh], [u1, v0]),
+    ([hw, hh], [u1, v1]),
+    ([-hw, hh], [u0, v1]),
+  ];
+
+  for &(offset, uv) in &corners {
+    vertices.push(SpriteVertex {
+      center: sprite.position,
+      offset,
+      rotation: sprite.rotation,
+      uv,
+      color: sprite.color,
+    });
+  }
+
+  indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+}
+
+/// Errors that might happen when creating or flushing a [`SpriteBatch`].
+#[derive(Debug)]
+pub enum SpriteBatchError {
+  /// The shader program of the batch failed to build.
+  ProgramError(ProgramError),
+  /// The tessellation of the batch couldn’t be created.
+  TessError(TessError),
+  /// The tessellation of the batch couldn’t be updated.
+  TessMapError(TessMapError),
+  /// The sprites couldn’t be rendered.
+  RenderListError(RenderListError),
+}
+
+impl fmt::Display for SpriteBatchError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      SpriteBatchError::ProgramError(ref e) => write!(f, "sprite batch program error: {}", e),
+      SpriteBatchError::TessError(ref e) => write!(f, "sprite batch tessellation error: {:?}", e),
+      SpriteBatchError::TessMapError(ref e) => write!(f, "sprite batch update error: {}", e),
+      SpriteBatchError::RenderListError(ref e) => write!(f, "sprite batch render error: {}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for SpriteBatchError {
+  fn from(e: ProgramError) -> Self {
+    SpriteBatchError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for SpriteBatchError {
+  fn from(e: TessError) -> Self {
+    SpriteBatchError::TessError(e)
+  }
+}
+
+impl From<TessMapError> for SpriteBatchError {
+  fn from(e: TessMapError) -> Self {
+    SpriteBatchError::TessMapError(e)
+  }
+}
+
+impl From<RenderListError> for SpriteBatchError {
+  fn from(e: RenderListError) -> Self {
+    SpriteBatchError::RenderListError(e)
+  }
+}
+
+// A corner of a sprite; the attribute indices are the locations of SPRITE_VS.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SpriteVertex {
+  center: [f32; 2],
+  offset: [f32; 2],
+  rotation: f32,
+  uv: [f32; 2],
+  color: [u8; 4],
+}
+
+unsafe impl Vertex for SpriteVertex {
+  fn vertex_desc() -> VertexDesc {
+    let attrib = |index, name, attrib_desc| VertexBufferDesc {
+      index,
+      name,
+      instancing: VertexInstancing::Off,
+      attrib_desc,
+    };
+
+    vec![
+      attrib(0, "center", <[f32; 2] as VertexAttrib>::VERTEX_ATTRIB_DESC),
+      attrib(1, "offset", <[f32; 2] as VertexAttrib>::VERTEX_ATTRIB_DESC),
+      attrib(2, "rotation", <f32 as VertexAttrib>::VERTEX_ATTRIB_DESC),
+      attrib(3, "uv", <[f32; 2] as VertexAttrib>::VERTEX_ATTRIB_DESC),
+      attrib(4, "color", <[u8; 4] as VertexAttrib>::VERTEX_ATTRIB_DESC.normalize()),
+    ]
+  }
+}
+
+struct SpriteInterface {
+  viewport: Uniform<[f32; 2]>,
+  texture: Uniform<&'static BoundTexture<'static, Flat, Dim2, NormUnsigned>>,
+}
+
+impl UniformInterface for SpriteInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(SpriteInterface {
+      viewport: builder.ask("viewport").map_err(ProgramError::UniformWarning)?,
+      texture: builder.ask("sprite_texture").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+const SPRITE_VS: &str = "\
+layout (location = 0) in vec2 center;
+layout (location = 1) in vec2 offset;
+layout (location = 2) in float rotation;
+layout (location = 3) in vec2 uv;
+layout (location = 4) in vec4 color;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+uniform vec2 viewport;
+
+void main() {
+  float c = cos(rotation);
+  float s = sin(rotation);
+  vec2 p = center + vec2(c * offset.x - s * offset.y, s * offset.x + c * offset.y);
+  p = p / viewport * 2. - 1.;
+
+  gl_Position = vec4(p.x, -p.y, 0., 1.);
+  v_uv = uv;
+  v_color = color;
+}
+";
+
+const SPRITE_FS: &str = "\
+in vec2 v_uv;
+in vec4 v_color;
+
+out vec4 frag;
+
+uniform sampler2D sprite_texture;
+
+void main() {
+  frag = texture(sprite_texture, v_uv) * v_color;
+}
+";
@@ -6,6 +6,10 @@ use std::cell::RefCell;
 use std::fmt;
 #[cfg(feature = "std")]
 use std::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::os::raw::c_void;
+#[cfg(feature = "std")]
+use std::ptr;
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -14,10 +18,21 @@ use core::fmt;
 #[cfg(not(feature = "std"))]
 use core::marker::PhantomData;
 
-use crate::blending::{BlendingState, Equation, Factor};
-use crate::depth_test::{DepthComparison, DepthTest};
+use crate::blending::{BlendingState, Equation, Factor, SeparateBlending};
+use crate::capabilities::{
+  get_ctx_extensions, get_ctx_gl_version, get_ctx_limits, get_ctx_profile, Extensions, Feature,
+  Limits, NotSupported, Profile,
+};
+#[cfg(feature = "std")]
+use crate::debug::{debug_callback, DebugHandler, DebugMessage, DebugSeverity};
+use crate::depth_test::{DepthComparison, DepthTest, PolygonOffset};
 use crate::face_culling::{FaceCullingMode, FaceCullingOrder, FaceCullingState};
+use crate::framebuffer::DrawBuffers;
 use crate::metagl::*;
+use crate::polygon_mode::PolygonMode;
+use crate::raw_gl::TrackedStates;
+use crate::render_state::MAX_BLENDING_OVERRIDES;
+use crate::stencil::{StencilOperations, StencilTest, StencilTestState};
 use crate::vertex_restart::VertexRestart;
 
 // TLS synchronization barrier for `GraphicsState`.
@@ -40,16 +55,35 @@ pub struct GraphicsState {
 
   // clear buffers
   clear_color: [GLfloat; 4],
+  clear_depth: GLfloat,
+  clear_stencil: GLint,
 
   // blending
   blending_state: BlendingState,
-  blending_equation: Equation,
-  blending_func: (Factor, Factor),
+  // draw buffers blending is enabled for, when not enabled for all of them at once
+  blended_draw_buffers: Option<DrawBuffers>,
+  max_draw_buffers: u32,
+  blending: SeparateBlending,
+  blend_color: [GLfloat; 4],
+  // blending configurations of draw buffers overriding the one above
+  draw_buffer_blending: [Option<SeparateBlending>; MAX_BLENDING_OVERRIDES],
 
   // depth test
   depth_test: DepthTest,
   depth_test_comparison: DepthComparison,
 
+  // depth clamp
+  depth_clamp: bool,
+
+  // polygon offset
+  polygon_offset_enabled: bool,
+  polygon_offset: PolygonOffset,
+
+  // stencil test
+  stencil_test_state: StencilTestState,
+  stencil_test: StencilTest,
+  stencil_operations: StencilOperations,
+
   // face culling
   face_culling_state: FaceCullingState,
   face_culling_order: FaceCullingOrder,
@@ -57,13 +91,29 @@ pub struct GraphicsState {
 
   // vertex restart
   vertex_restart: VertexRestart,
+  primitive_restart_index: GLuint,
+
+  // point size
+  point_size: GLfloat,
+  program_point_size: bool,
+
+  // line width
+  line_width: GLfloat,
+
+  // polygon mode
+  polygon_mode: PolygonMode,
+
+  // dithering
+  dithering: bool,
 
   // patch primitive vertex number
   patch_vertex_nb: usize,
 
   // texture
+  max_texture_units: u32,
   current_texture_unit: GLenum,
   bound_textures: Vec<(GLenum, GLuint)>,
+  texture_unit_cache: TextureUnitCache,
 
   // uniform buffer
   bound_uniform_buffers: Vec<GLuint>,
@@ -74,8 +124,15 @@ pub struct GraphicsState {
   // element buffer
   bound_element_array_buffer: GLuint,
 
+  // indirect draw buffer
+  bound_draw_indirect_buffer: GLuint,
+
+  // pixel pack buffer
+  bound_pixel_pack_buffer: GLuint,
+
   // framebuffer
   bound_draw_framebuffer: GLuint,
+  bound_read_framebuffer: GLuint,
 
   // vertex array
   bound_vertex_array: GLuint,
@@ -85,6 +142,23 @@ pub struct GraphicsState {
 
   // framebuffer sRGB
   srgb_framebuffer_enabled: bool,
+
+  // debug output
+  #[cfg(feature = "std")]
+  debug_handler: Option<Box<DebugHandler>>,
+
+  // numeric limits, profile, version and extensions of the context
+  limits: Limits,
+  profile: Profile,
+  gl_version: (u32, u32),
+  extensions: Extensions,
+
+  // frame counters of the current and of the last frame
+  counters: FrameCounters,
+  last_frame_counters: FrameCounters,
+
+  // whether the context was lost; objects of a lost context cannot be used anymore
+  context_lost: bool,
 }
 
 impl GraphicsState {
@@ -119,51 +193,110 @@ impl GraphicsState {
   /// Get a `GraphicsContext` from the current OpenGL context.
   pub(crate) fn get_from_context() -> Result<Self, StateQueryError> {
     unsafe {
+      // OpenGL ES lacks some of the queried states; they’re given their only possible value
+      let profile = get_ctx_profile();
+      let es = profile == Profile::Es;
       let viewport = get_ctx_viewport()?;
       let clear_color = get_ctx_clear_color()?;
+      let clear_depth = get_ctx_clear_depth()?;
+      let clear_stencil = get_ctx_clear_stencil()?;
       let blending_state = get_ctx_blending_state()?;
-      let blending_equation = get_ctx_blending_equation()?;
-      let blending_func = get_ctx_blending_factors()?;
+      let blended_draw_buffers = None;
+      let max_draw_buffers = get_ctx_max_draw_buffers()?;
+      let blending = get_ctx_blending()?;
+      let blend_color = get_ctx_blend_color()?;
+      let draw_buffer_blending = [None; MAX_BLENDING_OVERRIDES];
       let depth_test = get_ctx_depth_test()?;
       let depth_test_comparison = DepthComparison::Less;
+      let depth_clamp = !es && get_ctx_depth_clamp();
+      let polygon_offset_enabled = get_ctx_polygon_offset_enabled();
+      let polygon_offset = get_ctx_polygon_offset();
+      let stencil_test_state = get_ctx_stencil_test_state()?;
+      let stencil_test = StencilTest::default();
+      let stencil_operations = StencilOperations::default();
       let face_culling_state = get_ctx_face_culling_state()?;
       let face_culling_order = get_ctx_face_culling_order()?;
       let face_culling_mode = get_ctx_face_culling_mode()?;
-      let vertex_restart = get_ctx_vertex_restart()?;
+      let vertex_restart = get_ctx_vertex_restart(profile)?;
+      let primitive_restart_index =
+        if es { GLuint::MAX } else { get_ctx_primitive_restart_index()? };
+      let point_size = if es { 1. } else { get_ctx_point_size()? };
+      let program_point_size = es || get_ctx_program_point_size()?;
+      let line_width = get_ctx_line_width()?;
+      let polygon_mode = if es { PolygonMode::Fill } else { get_ctx_polygon_mode()? };
+      let dithering = get_ctx_dithering();
       let patch_vertex_nb = 0;
+      let max_texture_units = get_ctx_max_texture_units()?;
       let current_texture_unit = get_ctx_current_texture_unit()?;
       let bound_textures = vec![(gl::TEXTURE_2D, 0); 48]; // 48 is the platform minimal requirement
       let bound_uniform_buffers = vec![0; 36]; // 36 is the platform minimal requirement
       let bound_array_buffer = 0;
       let bound_element_array_buffer = 0;
+      let bound_draw_indirect_buffer = 0;
+      let bound_pixel_pack_buffer = 0;
       let bound_draw_framebuffer = get_ctx_bound_draw_framebuffer()?;
+      let bound_read_framebuffer = get_ctx_bound_read_framebuffer()?;
       let bound_vertex_array = get_ctx_bound_vertex_array()?;
       let current_program = get_ctx_current_program()?;
-      let srgb_framebuffer_enabled = get_ctx_srgb_framebuffer_enabled()?;
+      let srgb_framebuffer_enabled = !es && get_ctx_srgb_framebuffer_enabled()?;
+      let limits = get_ctx_limits();
+      let gl_version = get_ctx_gl_version();
+      let extensions = get_ctx_extensions();
 
       Ok(GraphicsState {
         _a: PhantomData,
         viewport,
         clear_color,
+        clear_depth,
+        clear_stencil,
         blending_state,
-        blending_equation,
-        blending_func,
+        blended_draw_buffers,
+        max_draw_buffers,
+        blending,
+        blend_color,
+        draw_buffer_blending,
         depth_test,
         depth_test_comparison,
+        depth_clamp,
+        polygon_offset_enabled,
+        polygon_offset,
+        stencil_test_state,
+        stencil_test,
+        stencil_operations,
         face_culling_state,
         face_culling_order,
         face_culling_mode,
         vertex_restart,
+        primitive_restart_index,
+        point_size,
+        program_point_size,
+        line_width,
+        polygon_mode,
+        dithering,
         patch_vertex_nb,
+        max_texture_units,
         current_texture_unit,
         bound_textures,
+        texture_unit_cache: TextureUnitCache::default(),
         bound_uniform_buffers,
         bound_array_buffer,
         bound_element_array_buffer,
+        bound_draw_indirect_buffer,
+        bound_pixel_pack_buffer,
         bound_draw_framebuffer,
+        bound_read_framebuffer,
         bound_vertex_array,
         current_program,
         srgb_framebuffer_enabled,
+        #[cfg(feature = "std")]
+        debug_handler: None,
+        limits,
+        profile,
+        gl_version,
+        extensions,
+        counters: FrameCounters::default(),
+        last_frame_counters: FrameCounters::default(),
+        context_lost: false,
       })
     }
   }
@@ -172,6 +305,7 @@ impl GraphicsState {
     if self.viewport != viewport {
       gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
       self.viewport = viewport;
+      gl_check!("Viewport");
     }
   }
 
@@ -179,34 +313,153 @@ impl GraphicsState {
     if self.clear_color != clear_color {
       gl::ClearColor(clear_color[0], clear_color[1], clear_color[2], clear_color[3]);
       self.clear_color = clear_color;
+      gl_check!("ClearColor");
+    }
+  }
+
+  pub(crate) unsafe fn set_clear_depth(&mut self, clear_depth: GLfloat) {
+    if self.clear_depth != clear_depth {
+      if self.profile == Profile::Es {
+        gl::ClearDepthf(clear_depth);
+      } else {
+        gl::ClearDepth(clear_depth as GLdouble);
+      }
+
+      self.clear_depth = clear_depth;
+      gl_check!("ClearDepth");
+    }
+  }
+
+  pub(crate) unsafe fn set_clear_stencil(&mut self, clear_stencil: GLint) {
+    if self.clear_stencil != clear_stencil {
+      gl::ClearStencil(clear_stencil);
+      self.clear_stencil = clear_stencil;
+      gl_check!("ClearStencil");
     }
   }
 
   pub(crate) unsafe fn set_blending_state(&mut self, state: BlendingState) {
-    if self.blending_state != state {
+    // glEnable and glDisable override the per draw buffer blending states
+    if self.blending_state != state || self.blended_draw_buffers.is_some() {
       match state {
         BlendingState::On => gl::Enable(gl::BLEND),
         BlendingState::Off => gl::Disable(gl::BLEND),
       }
 
       self.blending_state = state;
+      self.blended_draw_buffers = None;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  pub(crate) unsafe fn set_blended_draw_buffers(&mut self, draw_buffers: DrawBuffers) {
+    // glEnablei is not part of OpenGL ES 3.0: blend all the draw buffers unless none is selected
+    if self.profile == Profile::Es {
+      let state = if draw_buffers == DrawBuffers::none() {
+        BlendingState::Off
+      } else {
+        BlendingState::On
+      };
+
+      self.set_blending_state(state);
+      return;
+    }
+
+    if self.blended_draw_buffers != Some(draw_buffers) {
+      for i in 0 .. self.max_draw_buffers {
+        if draw_buffers.contains(i as usize) {
+          gl::Enablei(gl::BLEND, i);
+        } else {
+          gl::Disablei(gl::BLEND, i);
+        }
+      }
+
+      self.blended_draw_buffers = Some(draw_buffers);
+      gl_check!("Enablei/Disablei");
     }
   }
 
-  pub(crate) unsafe fn set_blending_equation(&mut self, equation: Equation) {
-    if self.blending_equation != equation {
-      gl::BlendEquation(from_blending_equation(equation));
-      self.blending_equation = equation;
+  pub(crate) unsafe fn set_blending(&mut self, blending: SeparateBlending) {
+    let equation_changed =
+      self.blending.rgb.0 != blending.rgb.0 || self.blending.alpha.0 != blending.alpha.0;
+    let func_changed = self.blending.rgb.1 != blending.rgb.1
+      || self.blending.rgb.2 != blending.rgb.2
+      || self.blending.alpha.1 != blending.alpha.1
+      || self.blending.alpha.2 != blending.alpha.2;
+
+    if !equation_changed && !func_changed {
+      return;
+    }
+
+    // the global configuration applies to every draw buffer, so overridden draw buffers must be
+    // reset entirely
+    let overridden = self.draw_buffer_blending.iter().any(Option::is_some);
+
+    if equation_changed || overridden {
+      gl::BlendEquationSeparate(
+        from_blending_equation(blending.rgb.0),
+        from_blending_equation(blending.alpha.0),
+      );
+      gl_check!("BlendEquationSeparate");
     }
+
+    if func_changed || overridden {
+      gl::BlendFuncSeparate(
+        from_blending_factor(blending.rgb.1),
+        from_blending_factor(blending.rgb.2),
+        from_blending_factor(blending.alpha.1),
+        from_blending_factor(blending.alpha.2),
+      );
+      gl_check!("BlendFuncSeparate");
+    }
+
+    self.blending = blending;
+    self.draw_buffer_blending = [None; MAX_BLENDING_OVERRIDES];
   }
 
-  pub(crate) unsafe fn set_blending_func(&mut self, src: Factor, dest: Factor) {
-    if self.blending_func != (src, dest) {
-      gl::BlendFunc(from_blending_factor(src), from_blending_factor(dest));
-      self.blending_func = (src, dest);
+  pub(crate) unsafe fn set_blend_color(&mut self, blend_color: [GLfloat; 4]) {
+    if self.blend_color != blend_color {
+      gl::BlendColor(blend_color[0], blend_color[1], blend_color[2], blend_color[3]);
+      self.blend_color = blend_color;
+      gl_check!("BlendColor");
     }
   }
 
+  pub(crate) unsafe fn set_draw_buffer_blending(
+    &mut self,
+    draw_buffer_blending: &[Option<SeparateBlending>; MAX_BLENDING_OVERRIDES],
+  ) {
+    if self.draw_buffer_blending == *draw_buffer_blending || !gl_post33!(BlendFuncSeparatei) {
+      return;
+    }
+
+    let nb = MAX_BLENDING_OVERRIDES.min(self.max_draw_buffers as usize);
+
+    for (i, &blending) in draw_buffer_blending.iter().enumerate().take(nb) {
+      if self.draw_buffer_blending[i] != blending {
+        // draw buffers which override is removed go back to the global configuration
+        let b = blending.unwrap_or(self.blending);
+
+        gl::BlendEquationSeparatei(
+          i as GLuint,
+          from_blending_equation(b.rgb.0),
+          from_blending_equation(b.alpha.0),
+        );
+        gl::BlendFuncSeparatei(
+          i as GLuint,
+          from_blending_factor(b.rgb.1),
+          from_blending_factor(b.rgb.2),
+          from_blending_factor(b.alpha.1),
+          from_blending_factor(b.alpha.2),
+        );
+
+        self.draw_buffer_blending[i] = blending;
+      }
+    }
+
+    gl_check!("BlendFuncSeparatei");
+  }
+
   pub(crate) unsafe fn set_depth_test(&mut self, depth_test: DepthTest) {
     if self.depth_test != depth_test {
       match depth_test {
@@ -215,6 +468,7 @@ impl GraphicsState {
       }
 
       self.depth_test = depth_test;
+      gl_check!("Enable/Disable");
     }
   }
 
@@ -225,6 +479,91 @@ impl GraphicsState {
     if self.depth_test_comparison != depth_test_comparison {
       gl::DepthFunc(depth_test_comparison.to_glenum());
       self.depth_test_comparison = depth_test_comparison;
+      gl_check!("DepthFunc");
+    }
+  }
+
+  pub(crate) unsafe fn set_depth_clamp(&mut self, depth_clamp: bool) {
+    if self.depth_clamp != depth_clamp && self.profile == Profile::Core {
+      if depth_clamp {
+        gl::Enable(gl::DEPTH_CLAMP);
+      } else {
+        gl::Disable(gl::DEPTH_CLAMP);
+      }
+
+      self.depth_clamp = depth_clamp;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  pub(crate) unsafe fn set_polygon_offset(&mut self, polygon_offset: Option<PolygonOffset>) {
+    let enabled = polygon_offset.is_some();
+
+    if self.polygon_offset_enabled != enabled {
+      // the offset applies to every polygon mode; OpenGL ES only has the fill one
+      let modes: &[GLenum] = match self.profile {
+        Profile::Core => {
+          &[gl::POLYGON_OFFSET_FILL, gl::POLYGON_OFFSET_LINE, gl::POLYGON_OFFSET_POINT]
+        }
+        Profile::Es => &[gl::POLYGON_OFFSET_FILL],
+      };
+
+      for &mode in modes {
+        if enabled {
+          gl::Enable(mode);
+        } else {
+          gl::Disable(mode);
+        }
+      }
+
+      self.polygon_offset_enabled = enabled;
+      gl_check!("Enable/Disable");
+    }
+
+    match polygon_offset {
+      Some(polygon_offset) if self.polygon_offset != polygon_offset => {
+        gl::PolygonOffset(polygon_offset.factor, polygon_offset.units);
+        self.polygon_offset = polygon_offset;
+        gl_check!("PolygonOffset");
+      }
+
+      _ => (),
+    }
+  }
+
+  pub(crate) unsafe fn set_stencil_test_state(&mut self, state: StencilTestState) {
+    if self.stencil_test_state != state {
+      match state {
+        StencilTestState::On => gl::Enable(gl::STENCIL_TEST),
+        StencilTestState::Off => gl::Disable(gl::STENCIL_TEST),
+      }
+
+      self.stencil_test_state = state;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  pub(crate) unsafe fn set_stencil_test(&mut self, stencil_test: StencilTest) {
+    if self.stencil_test != stencil_test {
+      gl::StencilFunc(
+        stencil_test.comparison.to_glenum(),
+        stencil_test.reference as GLint,
+        stencil_test.mask as GLuint,
+      );
+      self.stencil_test = stencil_test;
+      gl_check!("StencilFunc");
+    }
+  }
+
+  pub(crate) unsafe fn set_stencil_operations(&mut self, stencil_operations: StencilOperations) {
+    if self.stencil_operations != stencil_operations {
+      gl::StencilOp(
+        stencil_operations.stencil_fails.to_glenum(),
+        stencil_operations.depth_fails.to_glenum(),
+        stencil_operations.depth_passes.to_glenum(),
+      );
+      self.stencil_operations = stencil_operations;
+      gl_check!("StencilOp");
     }
   }
 
@@ -236,6 +575,7 @@ impl GraphicsState {
       }
 
       self.face_culling_state = state;
+      gl_check!("Enable/Disable");
     }
   }
 
@@ -247,6 +587,7 @@ impl GraphicsState {
       }
 
       self.face_culling_order = order;
+      gl_check!("FrontFace");
     }
   }
 
@@ -259,29 +600,105 @@ impl GraphicsState {
       }
 
       self.face_culling_mode = mode;
+      gl_check!("CullFace");
     }
   }
 
   pub(crate) unsafe fn set_vertex_restart(&mut self, state: VertexRestart) {
     if self.vertex_restart != state {
+      // OpenGL ES always restarts at the maximum index
+      let cap = match self.profile {
+        Profile::Core => gl::PRIMITIVE_RESTART,
+        Profile::Es => gl::PRIMITIVE_RESTART_FIXED_INDEX,
+      };
+
       match state {
-        VertexRestart::On => gl::Enable(gl::PRIMITIVE_RESTART),
-        VertexRestart::Off => gl::Disable(gl::PRIMITIVE_RESTART),
+        VertexRestart::On => gl::Enable(cap),
+        VertexRestart::Off => gl::Disable(cap),
       }
+
+      self.vertex_restart = state;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  pub(crate) unsafe fn set_primitive_restart_index(&mut self, index: GLuint) {
+    if self.primitive_restart_index != index && self.profile == Profile::Core {
+      gl::PrimitiveRestartIndex(index);
+      self.primitive_restart_index = index;
+      gl_check!("PrimitiveRestartIndex");
+    }
+  }
+
+  pub(crate) unsafe fn set_point_size(&mut self, point_size: GLfloat) {
+    if self.point_size != point_size && self.profile == Profile::Core {
+      gl::PointSize(point_size);
+      self.point_size = point_size;
+      gl_check!("PointSize");
+    }
+  }
+
+  pub(crate) unsafe fn set_program_point_size(&mut self, program_point_size: bool) {
+    if self.program_point_size != program_point_size && self.profile == Profile::Core {
+      if program_point_size {
+        gl::Enable(gl::PROGRAM_POINT_SIZE);
+      } else {
+        gl::Disable(gl::PROGRAM_POINT_SIZE);
+      }
+
+      self.program_point_size = program_point_size;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  pub(crate) unsafe fn set_line_width(&mut self, line_width: GLfloat) {
+    if self.line_width != line_width {
+      gl::LineWidth(line_width);
+      self.line_width = line_width;
+      gl_check!("LineWidth");
+    }
+  }
+
+  pub(crate) unsafe fn set_polygon_mode(&mut self, polygon_mode: PolygonMode) {
+    if self.polygon_mode != polygon_mode && self.profile == Profile::Core {
+      gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode.to_glenum());
+      self.polygon_mode = polygon_mode;
+      gl_check!("PolygonMode");
+    }
+  }
+
+  pub(crate) unsafe fn set_dithering(&mut self, dithering: bool) {
+    if self.dithering != dithering {
+      if dithering {
+        gl::Enable(gl::DITHER);
+      } else {
+        gl::Disable(gl::DITHER);
+      }
+
+      self.dithering = dithering;
+      gl_check!("Enable/Disable");
     }
   }
 
   pub(crate) unsafe fn set_patch_vertex_nb(&mut self, nb: usize) {
     if self.patch_vertex_nb != nb {
+      gl33_strict_check!("PatchParameteri", "4.0");
       gl::PatchParameteri(gl::PATCH_VERTICES, nb as GLint);
       self.patch_vertex_nb = nb;
+      gl_check!("PatchParameteri");
     }
   }
 
+  // Number of texture units usable at once by a shader program.
+  pub(crate) fn max_texture_units(&self) -> u32 {
+    self.max_texture_units
+  }
+
   pub(crate) unsafe fn set_texture_unit(&mut self, unit: u32) {
     if self.current_texture_unit != unit {
       gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
       self.current_texture_unit = unit;
+      gl_check!("ActiveTexture");
     }
   }
 
@@ -292,20 +709,74 @@ impl GraphicsState {
       Some((target_, handle_)) if target != target_ || handle != handle_ => {
         gl::BindTexture(target, handle);
         self.bound_textures[unit] = (target, handle);
+        self.counters.texture_binds += 1;
+        gl_check!("BindTexture", handle);
       }
 
       None => {
         gl::BindTexture(target, handle);
+        self.counters.texture_binds += 1;
 
         // not enough registered texture units; let’s grow a bit more
         self.bound_textures.resize(unit + 1, (gl::TEXTURE_2D, 0));
         self.bound_textures[unit] = (target, handle);
+        gl_check!("BindTexture", handle);
       }
 
       _ => (), // cached
     }
   }
 
+  // Forget about a deleted texture; OpenGL unbinds it from every texture unit.
+  pub(crate) fn forget_texture(&mut self, handle: GLuint) {
+    for binding in self.bound_textures.iter_mut().filter(|&&mut (_, h)| h == handle) {
+      binding.1 = 0;
+    }
+
+    self.texture_unit_cache.forget(handle);
+  }
+
+  /// Dedicate the last `units` texture units to long-lived textures.
+  ///
+  /// Textures bound with [`Pipeline::bind_texture_cached`] are assigned one of those units and stay
+  /// bound to it across pipelines and frames, the least recently used unit being reassigned when
+  /// running out of them. Other bindings cannot use those units anymore. `0` – the default –
+  /// disables the cache.
+  ///
+  /// Changing the number of cached units forgets the current assignments; it must not be done
+  /// while a pipeline is running.
+  ///
+  /// [`Pipeline::bind_texture_cached`]: crate::pipeline::Pipeline::bind_texture_cached
+  pub fn set_cached_texture_units(&mut self, units: u32) {
+    self.texture_unit_cache.resize(self.max_texture_units, units);
+  }
+
+  /// Number of texture units dedicated to long-lived textures.
+  ///
+  /// See [`GraphicsState::set_cached_texture_units`].
+  pub fn cached_texture_units(&self) -> u32 {
+    self.texture_unit_cache.len()
+  }
+
+  pub(crate) fn texture_unit_cache(&mut self) -> &mut TextureUnitCache {
+    &mut self.texture_unit_cache
+  }
+
+  // Cached texture units in use by live bindings.
+  pub(crate) fn cached_texture_units_in_use(&self) -> impl Iterator<Item = u32> + '_ {
+    self.texture_unit_cache.units_in_use()
+  }
+
+  // Texture bound to a texture unit, along with its target.
+  pub(crate) fn bound_texture(&self, unit: u32) -> Option<(GLenum, GLuint)> {
+    self.bound_textures.get(unit as usize).cloned().filter(|&(_, handle)| handle != 0)
+  }
+
+  // Uniform buffer bound to a binding point.
+  pub(crate) fn bound_uniform_buffer(&self, binding: u32) -> Option<GLuint> {
+    self.bound_uniform_buffers.get(binding as usize).cloned().filter(|&handle| handle != 0)
+  }
+
   pub(crate) unsafe fn bind_buffer_base(&mut self, handle: GLuint, binding: u32) {
     let binding_ = binding as usize;
 
@@ -313,6 +784,7 @@ impl GraphicsState {
       Some(handle_) if handle != handle_ => {
         gl::BindBufferBase(gl::UNIFORM_BUFFER, binding as GLuint, handle);
         self.bound_uniform_buffers[binding_] = handle;
+        gl_check!("BindBufferBase", handle);
       }
 
       None => {
@@ -321,6 +793,7 @@ impl GraphicsState {
         // not enough registered buffer bindings; let’s grow a bit more
         self.bound_uniform_buffers.resize(binding_ + 1, 0);
         self.bound_uniform_buffers[binding_] = handle;
+        gl_check!("BindBufferBase", handle);
       }
 
       _ => (), // cached
@@ -331,6 +804,7 @@ impl GraphicsState {
     if bind == Bind::Forced || self.bound_array_buffer != handle {
       gl::BindBuffer(gl::ARRAY_BUFFER, handle);
       self.bound_array_buffer = handle;
+      gl_check!("BindBuffer", handle);
     }
   }
 
@@ -338,10 +812,35 @@ impl GraphicsState {
     if bind == Bind::Forced || self.bound_element_array_buffer != handle {
       gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, handle);
       self.bound_element_array_buffer = handle;
+      gl_check!("BindBuffer", handle);
+    }
+  }
+
+  pub(crate) unsafe fn bind_draw_indirect_buffer(&mut self, handle: GLuint, bind: Bind) {
+    if bind == Bind::Forced || self.bound_draw_indirect_buffer != handle {
+      gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, handle);
+      self.bound_draw_indirect_buffer = handle;
+      gl_check!("BindBuffer", handle);
+    }
+  }
+
+  pub(crate) unsafe fn bind_pixel_pack_buffer(&mut self, handle: GLuint, bind: Bind) {
+    if bind == Bind::Forced || self.bound_pixel_pack_buffer != handle {
+      gl::BindBuffer(gl::PIXEL_PACK_BUFFER, handle);
+      self.bound_pixel_pack_buffer = handle;
+      gl_check!("BindBuffer", handle);
     }
   }
 
   pub(crate) unsafe fn unbind_buffer(&mut self, handle: GLuint) {
+    if self.bound_draw_indirect_buffer == handle {
+      self.bind_draw_indirect_buffer(0, Bind::Cached);
+    }
+
+    if self.bound_pixel_pack_buffer == handle {
+      self.bind_pixel_pack_buffer(0, Bind::Cached);
+    }
+
     if self.bound_array_buffer == handle {
       self.bind_array_buffer(0, Bind::Cached);
     } else if self.bound_element_array_buffer == handle {
@@ -355,6 +854,31 @@ impl GraphicsState {
     if self.bound_draw_framebuffer != handle {
       gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, handle);
       self.bound_draw_framebuffer = handle;
+      gl_check!("BindFramebuffer", handle);
+    }
+  }
+
+  // Bound draw and read framebuffers.
+  pub(crate) fn bound_framebuffers(&self) -> (GLuint, GLuint) {
+    (self.bound_draw_framebuffer, self.bound_read_framebuffer)
+  }
+
+  // deleting a framebuffer unbinds it
+  pub(crate) fn forget_framebuffer(&mut self, handle: GLuint) {
+    if self.bound_draw_framebuffer == handle {
+      self.bound_draw_framebuffer = 0;
+    }
+
+    if self.bound_read_framebuffer == handle {
+      self.bound_read_framebuffer = 0;
+    }
+  }
+
+  pub(crate) unsafe fn bind_read_framebuffer(&mut self, handle: GLuint) {
+    if self.bound_read_framebuffer != handle {
+      gl::BindFramebuffer(gl::READ_FRAMEBUFFER, handle);
+      self.bound_read_framebuffer = handle;
+      gl_check!("BindFramebuffer", handle);
     }
   }
 
@@ -362,6 +886,7 @@ impl GraphicsState {
     if bind == Bind::Forced || self.bound_vertex_array != handle {
       gl::BindVertexArray(handle);
       self.bound_vertex_array = handle;
+      gl_check!("BindVertexArray", handle);
     }
   }
 
@@ -373,11 +898,14 @@ impl GraphicsState {
     if self.current_program != handle {
       gl::UseProgram(handle);
       self.current_program = handle;
+      self.counters.program_switches += 1;
+      gl_check!("UseProgram", handle);
     }
   }
 
   pub(crate) unsafe fn enable_srgb_framebuffer(&mut self, srgb_framebuffer_enabled: bool) {
-    if self.srgb_framebuffer_enabled != srgb_framebuffer_enabled {
+    // OpenGL ES always encodes to sRGB attachments
+    if self.srgb_framebuffer_enabled != srgb_framebuffer_enabled && self.profile == Profile::Core {
       if srgb_framebuffer_enabled {
         gl::Enable(gl::FRAMEBUFFER_SRGB);
       } else {
@@ -385,6 +913,963 @@ impl GraphicsState {
       }
 
       self.srgb_framebuffer_enabled = srgb_framebuffer_enabled;
+      gl_check!("Enable/Disable");
+    }
+  }
+
+  /// Forward the debug messages of the driver to `handler`, ignoring the messages less severe than
+  /// `min_severity`. Any previous handler is replaced.
+  ///
+  /// Messages are sent synchronously, by the very command causing them, so that a breakpoint in
+  /// the handler shows the faulty call. The handler must not use the graphics context.
+  ///
+  /// Debug output requires *OpenGL 4.3* or `GL_KHR_debug`; [`NotSupported`] is returned – and
+  /// nothing happens – if the context doesn’t support it. Contexts created with the debug flag send
+  /// more messages. See the [`debug`](crate::debug) module for further details.
+  #[cfg(feature = "std")]
+  pub fn enable_debug_output<F>(
+    &mut self,
+    min_severity: DebugSeverity,
+    handler: F,
+  ) -> Result<(), NotSupported>
+  where F: 'static + FnMut(&DebugMessage) {
+    self.require(Feature::DebugOutput)?;
+
+    if !gl_post33!(DebugMessageCallback) || !gl_post33!(DebugMessageControl) {
+      return Err(NotSupported(Feature::DebugOutput));
+    }
+
+    // double boxing, so that the callback gets a thin pointer
+    let mut handler: Box<DebugHandler> = Box::new(Box::new(handler));
+
+    unsafe {
+      gl::Enable(gl::DEBUG_OUTPUT);
+      gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+      gl::DebugMessageCallback(debug_callback, &mut *handler as *mut DebugHandler as *const c_void);
+
+      gl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, gl::DONT_CARE, 0, ptr::null(), gl::TRUE);
+
+      let severities = [DebugSeverity::Notification, DebugSeverity::Low, DebugSeverity::Medium];
+
+      for severity in severities.iter().filter(|&&severity| severity < min_severity) {
+        gl::DebugMessageControl(
+          gl::DONT_CARE,
+          gl::DONT_CARE,
+          severity.to_glenum(),
+          0,
+          ptr::null(),
+          gl::FALSE,
+        );
+      }
+    }
+
+    // the previous handler, if any, is not used by the callback anymore
+    self.debug_handler = Some(handler);
+    Ok(())
+  }
+
+  /// Forward the debug messages of the driver to the [log](https://crates.io/crates/log) crate,
+  /// ignoring the messages less severe than `min_severity`.
+  ///
+  /// Messages are logged with the `luminance::debug` target, and a level matching their severity.
+  /// See [`GraphicsState::enable_debug_output`] for further details.
+  #[cfg(all(feature = "std", feature = "log"))]
+  pub fn enable_debug_log(&mut self, min_severity: DebugSeverity) -> Result<(), NotSupported> {
+    self.enable_debug_output(min_severity, crate::debug::log_message)
+  }
+
+  /// Stop forwarding the debug messages of the driver.
+  ///
+  /// The context must still be current. The graphics state disables its handler when dropped, so
+  /// contexts created by hand must outlive it, as they do in the windowing crates.
+  #[cfg(feature = "std")]
+  pub fn disable_debug_output(&mut self) {
+    if self.debug_handler.take().is_some() {
+      unsafe {
+        gl::DebugMessageCallback(debug_callback, ptr::null());
+        gl::Disable(gl::DEBUG_OUTPUT);
+      }
+    }
+  }
+
+  /// Check whether an OpenGL error happened since the last check.
+  ///
+  /// Only the oldest error is returned; call this function again to get the next ones. With the
+  /// `error-check` feature, this is done after every call and errors panic instead.
+  pub fn check_error(&self) -> Result<(), GlError> {
+    get_gl_error()
+  }
+
+  /// Numeric limits of the context, queried when the state was created.
+  pub fn limits(&self) -> Limits {
+    self.limits
+  }
+
+  /// Flavor of OpenGL of the context.
+  pub fn profile(&self) -> Profile {
+    self.profile
+  }
+
+  /// Version of OpenGL of the context, as `(major, minor)`.
+  ///
+  /// For OpenGL ES contexts, this is the OpenGL ES version.
+  pub fn gl_version(&self) -> (u32, u32) {
+    self.gl_version
+  }
+
+  /// Extensions supported by the context, queried when the state was created.
+  pub fn extensions(&self) -> &Extensions {
+    &self.extensions
+  }
+
+  /// Check whether an extension is supported by its raw name (e.g. `"GL_ARB_bindless_texture"`).
+  pub fn has_extension(&self, name: &str) -> bool {
+    self.extensions.has_raw(name)
+  }
+
+  /// Check whether an optional feature is supported, either by the version of the context or by
+  /// an extension.
+  ///
+  /// With the `gl33-strict` feature, features introduced after OpenGL 3.3 are never supported.
+  pub fn supports(&self, feature: Feature) -> bool {
+    if cfg!(feature = "gl33-strict") && feature.core_version() > (3, 3) {
+      return false;
+    }
+
+    self.gl_version >= feature.core_version() || self.extensions.has(feature.extension())
+  }
+
+  /// Same as [`GraphicsState::supports`], but fail with [`NotSupported`] if the feature is not
+  /// supported.
+  pub fn require(&self, feature: Feature) -> Result<(), NotSupported> {
+    if self.supports(feature) {
+      Ok(())
+    } else {
+      Err(NotSupported(feature))
+    }
+  }
+
+  /// Counters of the last frame.
+  ///
+  /// Frames end with [`GraphicsState::end_frame`], called when swapping buffers by the surfaces
+  /// of the windowing crates.
+  pub fn frame_counters(&self) -> FrameCounters {
+    self.last_frame_counters
+  }
+
+  /// End the current frame, making its counters available with [`GraphicsState::frame_counters`]
+  /// and resetting them for the next frame.
+  ///
+  /// Applications swapping buffers themselves – with a [`GlContext`] for instance – must call this
+  /// once per frame.
+  ///
+  /// [`GlContext`]: crate::context::GlContext
+  pub fn end_frame(&mut self) {
+    self.last_frame_counters = self.counters;
+    self.counters = FrameCounters::default();
+  }
+
+  pub(crate) fn count_draw_calls(&mut self, draw_calls: u64) {
+    self.counters.draw_calls += draw_calls;
+  }
+
+  pub(crate) fn count_buffer_upload(&mut self) {
+    self.counters.buffer_uploads += 1;
+  }
+
+  /// Whether the context was lost.
+  ///
+  /// Once lost, the objects of the context are poisoned: their fallible operations fail with
+  /// [`PoisonCause::ContextLost`] instead of calling into the driver.
+  pub fn is_context_lost(&self) -> bool {
+    self.context_lost
+  }
+
+  /// Mark the context as lost, poisoning its objects.
+  ///
+  /// This is for windowing crates and applications detecting the loss on their own – a mobile
+  /// surface being destroyed, for instance. See [`GraphicsState::poll_context_lost`] otherwise.
+  pub fn mark_context_lost(&mut self) {
+    self.context_lost = true;
+  }
+
+  /// Ask the driver whether the context was lost – because of a GPU reset, for instance – and
+  /// mark it as such if so.
+  ///
+  /// This requires `glGetGraphicsResetStatus` (OpenGL 4.5 or `GL_KHR_robustness`); without it,
+  /// only [`GraphicsState::mark_context_lost`] poisons objects. Returns whether the context is lost.
+  pub fn poll_context_lost(&mut self) -> bool {
+    if !self.context_lost && gl_post33!(GetGraphicsResetStatus) {
+      self.context_lost = unsafe { gl::GetGraphicsResetStatus() } != gl::NO_ERROR;
+    }
+
+    self.context_lost
+  }
+
+  /// Save the OpenGL state of the host application, before rendering with luminance in a context
+  /// it doesn’t own – a plugin rendering in the context of a DAW or of a game editor, for instance.
+  ///
+  /// Every state and binding luminance might change is saved. Since the host changed them behind
+  /// the back of luminance, the states luminance tracks are issued again afterwards, so that its
+  /// cache matches the context. Hand the returned [`HostState`] back to
+  /// [`GraphicsState::restore_host_state`] once done rendering.
+  pub fn save_host_state(&mut self) -> HostState {
+    unsafe {
+      let host = HostState::capture(self);
+      self.restore();
+      host
+    }
+  }
+
+  /// Restore the OpenGL state of the host application saved by
+  /// [`GraphicsState::save_host_state`].
+  ///
+  /// The context doesn’t match the states luminance tracks anymore: luminance must not be used
+  /// until the next call to [`GraphicsState::save_host_state`].
+  pub fn restore_host_state(&mut self, host: HostState) {
+    unsafe { host.apply() }
+  }
+
+  // Check whether the objects of this context can be used: the context must not be lost. Objects
+  // sharing the state are not Send, so they cannot be used from another thread.
+  pub(crate) fn check_usable(&self) -> Result<(), PoisonCause> {
+    if self.context_lost {
+      return Err(PoisonCause::ContextLost);
+    }
+
+    Ok(())
+  }
+
+  // Issue every cached state to OpenGL again, so that the context matches the cache after a
+  // panic interrupted code – raw OpenGL calls, for instance – that might have desynchronized them.
+  pub(crate) unsafe fn restore(&mut self) {
+    if self.check_usable().is_err() {
+      return;
+    }
+
+    self.restore_tracked(TrackedStates::ALL);
+
+    // the errors generated by the interrupted code are not ours to report; there’s one flag per
+    // kind of error, but a lost context might report forever
+    for _ in 0 .. 8 {
+      if gl::GetError() == gl::NO_ERROR {
+        break;
+      }
+    }
+  }
+
+  // Issue the cached states of the given groups to OpenGL again.
+  pub(crate) unsafe fn restore_tracked(&mut self, states: TrackedStates) {
+    let core = self.profile == Profile::Core;
+
+    if states.contains(TrackedStates::VIEWPORT) {
+      let [x, y, w, h] = self.viewport;
+      gl::Viewport(x, y, w, h);
+    }
+
+    if states.contains(TrackedStates::CLEAR_VALUES) {
+      let [r, g, b, a] = self.clear_color;
+      gl::ClearColor(r, g, b, a);
+
+      if core {
+        gl::ClearDepth(self.clear_depth as GLdouble);
+      } else {
+        gl::ClearDepthf(self.clear_depth);
+      }
+
+      gl::ClearStencil(self.clear_stencil);
+    }
+
+    if states.contains(TrackedStates::BLENDING) {
+      self.restore_blending(core);
+    }
+
+    if states.contains(TrackedStates::DEPTH) {
+      set_capability(gl::DEPTH_TEST, self.depth_test == DepthTest::On);
+      gl::DepthFunc(self.depth_test_comparison.to_glenum());
+
+      set_capability(gl::POLYGON_OFFSET_FILL, self.polygon_offset_enabled);
+      gl::PolygonOffset(self.polygon_offset.factor, self.polygon_offset.units);
+
+      if core {
+        set_capability(gl::DEPTH_CLAMP, self.depth_clamp);
+        set_capability(gl::POLYGON_OFFSET_LINE, self.polygon_offset_enabled);
+        set_capability(gl::POLYGON_OFFSET_POINT, self.polygon_offset_enabled);
+      }
+    }
+
+    if states.contains(TrackedStates::STENCIL) {
+      set_capability(gl::STENCIL_TEST, self.stencil_test_state == StencilTestState::On);
+      gl::StencilFunc(
+        self.stencil_test.comparison.to_glenum(),
+        self.stencil_test.reference as GLint,
+        self.stencil_test.mask as GLuint,
+      );
+      gl::StencilOp(
+        self.stencil_operations.stencil_fails.to_glenum(),
+        self.stencil_operations.depth_fails.to_glenum(),
+        self.stencil_operations.depth_passes.to_glenum(),
+      );
+    }
+
+    if states.contains(TrackedStates::FACE_CULLING) {
+      set_capability(gl::CULL_FACE, self.face_culling_state == FaceCullingState::On);
+
+      match self.face_culling_order {
+        FaceCullingOrder::CW => gl::FrontFace(gl::CW),
+        FaceCullingOrder::CCW => gl::FrontFace(gl::CCW),
+      }
+
+      match self.face_culling_mode {
+        FaceCullingMode::Front => gl::CullFace(gl::FRONT),
+        FaceCullingMode::Back => gl::CullFace(gl::BACK),
+        FaceCullingMode::Both => gl::CullFace(gl::FRONT_AND_BACK),
+      }
+    }
+
+    if states.contains(TrackedStates::RASTERIZATION) {
+      let restart_cap =
+        if core { gl::PRIMITIVE_RESTART } else { gl::PRIMITIVE_RESTART_FIXED_INDEX };
+      set_capability(restart_cap, self.vertex_restart == VertexRestart::On);
+
+      gl::LineWidth(self.line_width);
+      set_capability(gl::DITHER, self.dithering);
+
+      // core-only states
+      if core {
+        gl::PrimitiveRestartIndex(self.primitive_restart_index);
+        gl::PointSize(self.point_size);
+        set_capability(gl::PROGRAM_POINT_SIZE, self.program_point_size);
+        gl::PolygonMode(gl::FRONT_AND_BACK, self.polygon_mode.to_glenum());
+        set_capability(gl::FRAMEBUFFER_SRGB, self.srgb_framebuffer_enabled);
+      }
+
+      if self.patch_vertex_nb != 0 && gl_post33!(PatchParameteri) {
+        gl::PatchParameteri(gl::PATCH_VERTICES, self.patch_vertex_nb as GLint);
+      }
+    }
+
+    if states.contains(TrackedStates::TEXTURES) {
+      for (unit, &(target, handle)) in self.bound_textures.iter().enumerate() {
+        gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+        gl::BindTexture(target, handle);
+      }
+
+      gl::ActiveTexture(gl::TEXTURE0 + self.current_texture_unit);
+    }
+
+    if states.contains(TrackedStates::BUFFERS) {
+      for (binding, &handle) in self.bound_uniform_buffers.iter().enumerate() {
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, binding as GLuint, handle);
+      }
+
+      // the element array buffer binding belongs to the vertex array
+      gl::BindVertexArray(self.bound_vertex_array);
+      gl::BindBuffer(gl::ARRAY_BUFFER, self.bound_array_buffer);
+      gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.bound_element_array_buffer);
+      gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.bound_pixel_pack_buffer);
+
+      if self.bound_draw_indirect_buffer != 0 {
+        gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, self.bound_draw_indirect_buffer);
+      }
+    }
+
+    if states.contains(TrackedStates::FRAMEBUFFERS) {
+      gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.bound_draw_framebuffer);
+      gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.bound_read_framebuffer);
+    }
+
+    if states.contains(TrackedStates::PROGRAM) {
+      gl::UseProgram(self.current_program);
+    }
+  }
+
+  unsafe fn restore_blending(&self, core: bool) {
+    match self.blended_draw_buffers {
+      Some(draw_buffers) if core => {
+        for i in 0 .. self.max_draw_buffers {
+          if draw_buffers.contains(i as usize) {
+            gl::Enablei(gl::BLEND, i);
+          } else {
+            gl::Disablei(gl::BLEND, i);
+          }
+        }
+      }
+
+      _ => set_capability(gl::BLEND, self.blending_state == BlendingState::On),
+    }
+
+    let blending = self.blending;
+    gl::BlendEquationSeparate(
+      from_blending_equation(blending.rgb.0),
+      from_blending_equation(blending.alpha.0),
+    );
+    gl::BlendFuncSeparate(
+      from_blending_factor(blending.rgb.1),
+      from_blending_factor(blending.rgb.2),
+      from_blending_factor(blending.alpha.1),
+      from_blending_factor(blending.alpha.2),
+    );
+
+    if gl_post33!(BlendFuncSeparatei) {
+      for (i, b) in self.draw_buffer_blending.iter().enumerate() {
+        if let Some(b) = b {
+          gl::BlendEquationSeparatei(
+            i as GLuint,
+            from_blending_equation(b.rgb.0),
+            from_blending_equation(b.alpha.0),
+          );
+          gl::BlendFuncSeparatei(
+            i as GLuint,
+            from_blending_factor(b.rgb.1),
+            from_blending_factor(b.rgb.2),
+            from_blending_factor(b.alpha.1),
+            from_blending_factor(b.alpha.2),
+          );
+        }
+      }
+    }
+
+    let [r, g, b, a] = self.blend_color;
+    gl::BlendColor(r, g, b, a);
+  }
+}
+
+/// OpenGL state of a host application, saved with [`GraphicsState::save_host_state`].
+///
+/// States are saved as raw OpenGL values, so that values luminance doesn’t know about – legacy
+/// blending factors, for instance – are restored as well.
+#[derive(Clone, Debug)]
+pub struct HostState {
+  core: bool,
+  viewport: [GLint; 4],
+  clear_color: [GLfloat; 4],
+  clear_depth: GLfloat,
+  clear_stencil: GLint,
+  // capabilities, along with whether they’re enabled
+  capabilities: Vec<(GLenum, bool)>,
+  // whether blending is enabled, per draw buffer
+  draw_buffers_blending: Vec<bool>,
+  // equations of RGB and alpha, then source and destination factors of RGB and alpha
+  blending: [GLint; 6],
+  // blending per draw buffer, when it can be set per draw buffer
+  draw_buffer_blending: Vec<[GLint; 6]>,
+  blend_color: [GLfloat; 4],
+  depth_func: GLint,
+  polygon_offset: [GLfloat; 2],
+  // function, reference, mask and operations of the front and back faces
+  stencil: [[GLint; 6]; 2],
+  front_face: GLint,
+  cull_face: GLint,
+  primitive_restart_index: GLint,
+  point_size: GLfloat,
+  line_width: GLfloat,
+  polygon_mode: GLint,
+  patch_vertices: Option<GLint>,
+  pack_alignment: GLint,
+  unpack_alignment: GLint,
+  active_texture: GLint,
+  // textures bound to the targets luminance uses, per unit
+  textures: Vec<Vec<(GLenum, GLint)>>,
+  uniform_buffers: Vec<GLint>,
+  // buffer bindings, along with their target
+  buffers: Vec<(GLenum, GLint)>,
+  vertex_array: GLint,
+  draw_framebuffer: GLint,
+  read_framebuffer: GLint,
+  renderbuffer: GLint,
+  program: GLint,
+}
+
+// Capabilities luminance changes, on both profiles.
+const HOST_CAPABILITIES: [GLenum; 6] = [
+  gl::BLEND,
+  gl::DEPTH_TEST,
+  gl::POLYGON_OFFSET_FILL,
+  gl::STENCIL_TEST,
+  gl::CULL_FACE,
+  gl::DITHER,
+];
+
+// Capabilities luminance changes, on the core profile only.
+const HOST_CORE_CAPABILITIES: [GLenum; 6] = [
+  gl::PRIMITIVE_RESTART,
+  gl::DEPTH_CLAMP,
+  gl::POLYGON_OFFSET_LINE,
+  gl::POLYGON_OFFSET_POINT,
+  gl::PROGRAM_POINT_SIZE,
+  gl::FRAMEBUFFER_SRGB,
+];
+
+// Texture targets luminance binds, along with their binding query, on both profiles.
+const HOST_TEXTURE_TARGETS: [(GLenum, GLenum); 4] = [
+  (gl::TEXTURE_2D, gl::TEXTURE_BINDING_2D),
+  (gl::TEXTURE_2D_ARRAY, gl::TEXTURE_BINDING_2D_ARRAY),
+  (gl::TEXTURE_3D, gl::TEXTURE_BINDING_3D),
+  (gl::TEXTURE_CUBE_MAP, gl::TEXTURE_BINDING_CUBE_MAP),
+];
+
+// Texture targets luminance binds, along with their binding query, on the core profile only.
+const HOST_CORE_TEXTURE_TARGETS: [(GLenum, GLenum); 3] = [
+  (gl::TEXTURE_1D, gl::TEXTURE_BINDING_1D),
+  (gl::TEXTURE_1D_ARRAY, gl::TEXTURE_BINDING_1D_ARRAY),
+  (gl::TEXTURE_BUFFER, gl::TEXTURE_BINDING_BUFFER),
+];
+
+impl HostState {
+  // Query the states `state` might change.
+  unsafe fn capture(state: &GraphicsState) -> Self {
+    let core = state.profile == Profile::Core;
+
+    let profile_capabilities: &[GLenum] =
+      if core { &HOST_CORE_CAPABILITIES } else { &[gl::PRIMITIVE_RESTART_FIXED_INDEX] };
+    let capabilities = HOST_CAPABILITIES.iter().chain(profile_capabilities).map(|&cap| {
+      (cap, gl::IsEnabled(cap) == gl::TRUE)
+    }).collect();
+
+    let draw_buffers_blending = if core {
+      (0 .. state.max_draw_buffers).map(|i| gl::IsEnabledi(gl::BLEND, i) == gl::TRUE).collect()
+    } else {
+      Vec::new()
+    };
+
+    let blending = get_host_integers([
+      gl::BLEND_EQUATION_RGB,
+      gl::BLEND_EQUATION_ALPHA,
+      gl::BLEND_SRC_RGB,
+      gl::BLEND_DST_RGB,
+      gl::BLEND_SRC_ALPHA,
+      gl::BLEND_DST_ALPHA,
+    ]);
+
+    let draw_buffer_blending = if gl_post33!(BlendFuncSeparatei) {
+      (0 .. MAX_BLENDING_OVERRIDES as GLuint).map(|i| {
+        let mut blending = [0; 6];
+
+        gl::GetIntegeri_v(gl::BLEND_EQUATION_RGB, i, &mut blending[0]);
+        gl::GetIntegeri_v(gl::BLEND_EQUATION_ALPHA, i, &mut blending[1]);
+        gl::GetIntegeri_v(gl::BLEND_SRC_RGB, i, &mut blending[2]);
+        gl::GetIntegeri_v(gl::BLEND_DST_RGB, i, &mut blending[3]);
+        gl::GetIntegeri_v(gl::BLEND_SRC_ALPHA, i, &mut blending[4]);
+        gl::GetIntegeri_v(gl::BLEND_DST_ALPHA, i, &mut blending[5]);
+
+        blending
+      }).collect()
+    } else {
+      Vec::new()
+    };
+
+    let mut polygon_offset = [0.; 2];
+    gl::GetFloatv(gl::POLYGON_OFFSET_FACTOR, &mut polygon_offset[0]);
+    gl::GetFloatv(gl::POLYGON_OFFSET_UNITS, &mut polygon_offset[1]);
+
+    let stencil = [
+      get_host_integers([
+        gl::STENCIL_FUNC,
+        gl::STENCIL_REF,
+        gl::STENCIL_VALUE_MASK,
+        gl::STENCIL_FAIL,
+        gl::STENCIL_PASS_DEPTH_FAIL,
+        gl::STENCIL_PASS_DEPTH_PASS,
+      ]),
+      get_host_integers([
+        gl::STENCIL_BACK_FUNC,
+        gl::STENCIL_BACK_REF,
+        gl::STENCIL_BACK_VALUE_MASK,
+        gl::STENCIL_BACK_FAIL,
+        gl::STENCIL_BACK_PASS_DEPTH_FAIL,
+        gl::STENCIL_BACK_PASS_DEPTH_PASS,
+      ]),
+    ];
+
+    // only the units luminance might have used are saved
+    let mut targets = HOST_TEXTURE_TARGETS.to_vec();
+
+    if core {
+      targets.extend_from_slice(&HOST_CORE_TEXTURE_TARGETS);
+    }
+
+    let active_texture = get_host_integer(gl::ACTIVE_TEXTURE);
+    let units = state.bound_textures.len().min(state.max_texture_units as usize);
+    let textures = (0 .. units).map(|unit| {
+      gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+      targets.iter().map(|&(target, binding)| (target, get_host_integer(binding))).collect()
+    }).collect();
+    gl::ActiveTexture(active_texture as GLenum);
+
+    let uniform_buffers = (0 .. state.bound_uniform_buffers.len() as GLuint).map(|binding| {
+      let mut handle = 0;
+      gl::GetIntegeri_v(gl::UNIFORM_BUFFER_BINDING, binding, &mut handle);
+      handle
+    }).collect();
+
+    // the element array buffer binding belongs to the vertex array
+    let mut buffers = vec![
+      (gl::ARRAY_BUFFER, get_host_integer(gl::ARRAY_BUFFER_BINDING)),
+      (gl::UNIFORM_BUFFER, get_host_integer(gl::UNIFORM_BUFFER_BINDING)),
+      (gl::PIXEL_PACK_BUFFER, get_host_integer(gl::PIXEL_PACK_BUFFER_BINDING)),
+      (gl::COPY_READ_BUFFER, get_host_integer(gl::COPY_READ_BUFFER_BINDING)),
+      (gl::COPY_WRITE_BUFFER, get_host_integer(gl::COPY_WRITE_BUFFER_BINDING)),
+    ];
+
+    if state.supports(Feature::IndirectDraw) {
+      let handle = get_host_integer(gl::DRAW_INDIRECT_BUFFER_BINDING);
+      buffers.push((gl::DRAW_INDIRECT_BUFFER, handle));
+    }
+
+    let patch_vertices = if gl_post33!(PatchParameteri) {
+      Some(get_host_integer(gl::PATCH_VERTICES))
+    } else {
+      None
+    };
+
+    let (primitive_restart_index, point_size, polygon_mode) = if core {
+      let mut point_size = 1.;
+      gl::GetFloatv(gl::POINT_SIZE, &mut point_size);
+
+      // front and back modes are the same in a core profile
+      let mut polygon_mode = [gl::FILL as GLint; 2];
+      gl::GetIntegerv(gl::POLYGON_MODE, polygon_mode.as_mut_ptr());
+
+      (get_host_integer(gl::PRIMITIVE_RESTART_INDEX), point_size, polygon_mode[0])
+    } else {
+      (0, 1., gl::FILL as GLint)
+    };
+
+    let mut clear_depth = 1.;
+    gl::GetFloatv(gl::DEPTH_CLEAR_VALUE, &mut clear_depth);
+
+    let mut line_width = 1.;
+    gl::GetFloatv(gl::LINE_WIDTH, &mut line_width);
+
+    let mut viewport = [0; 4];
+    gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+    let mut clear_color = [0.; 4];
+    gl::GetFloatv(gl::COLOR_CLEAR_VALUE, clear_color.as_mut_ptr());
+
+    let mut blend_color = [0.; 4];
+    gl::GetFloatv(gl::BLEND_COLOR, blend_color.as_mut_ptr());
+
+    HostState {
+      core,
+      viewport,
+      clear_color,
+      clear_depth,
+      clear_stencil: get_host_integer(gl::STENCIL_CLEAR_VALUE),
+      capabilities,
+      draw_buffers_blending,
+      blending,
+      draw_buffer_blending,
+      blend_color,
+      depth_func: get_host_integer(gl::DEPTH_FUNC),
+      polygon_offset,
+      stencil,
+      front_face: get_host_integer(gl::FRONT_FACE),
+      cull_face: get_host_integer(gl::CULL_FACE_MODE),
+      primitive_restart_index,
+      point_size,
+      line_width,
+      polygon_mode,
+      patch_vertices,
+      pack_alignment: get_host_integer(gl::PACK_ALIGNMENT),
+      unpack_alignment: get_host_integer(gl::UNPACK_ALIGNMENT),
+      active_texture,
+      textures,
+      uniform_buffers,
+      buffers,
+      vertex_array: get_host_integer(gl::VERTEX_ARRAY_BINDING),
+      draw_framebuffer: get_host_integer(gl::DRAW_FRAMEBUFFER_BINDING),
+      read_framebuffer: get_host_integer(gl::READ_FRAMEBUFFER_BINDING),
+      renderbuffer: get_host_integer(gl::RENDERBUFFER_BINDING),
+      program: get_host_integer(gl::CURRENT_PROGRAM),
+    }
+  }
+
+  // Issue the saved states.
+  unsafe fn apply(&self) {
+    let [x, y, w, h] = self.viewport;
+    gl::Viewport(x, y, w, h);
+
+    let [r, g, b, a] = self.clear_color;
+    gl::ClearColor(r, g, b, a);
+
+    if self.core {
+      gl::ClearDepth(self.clear_depth as GLdouble);
+    } else {
+      gl::ClearDepthf(self.clear_depth);
+    }
+
+    gl::ClearStencil(self.clear_stencil);
+
+    for &(cap, enabled) in &self.capabilities {
+      set_capability(cap, enabled);
+    }
+
+    for (i, &enabled) in self.draw_buffers_blending.iter().enumerate() {
+      if enabled {
+        gl::Enablei(gl::BLEND, i as GLuint);
+      } else {
+        gl::Disablei(gl::BLEND, i as GLuint);
+      }
+    }
+
+    let [rgb, alpha, src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blending;
+    gl::BlendEquationSeparate(rgb as GLenum, alpha as GLenum);
+    gl::BlendFuncSeparate(
+      src_rgb as GLenum,
+      dst_rgb as GLenum,
+      src_alpha as GLenum,
+      dst_alpha as GLenum,
+    );
+
+    for (i, b) in self.draw_buffer_blending.iter().enumerate() {
+      let [rgb, alpha, src_rgb, dst_rgb, src_alpha, dst_alpha] = *b;
+      gl::BlendEquationSeparatei(i as GLuint, rgb as GLenum, alpha as GLenum);
+      gl::BlendFuncSeparatei(
+        i as GLuint,
+        src_rgb as GLenum,
+        dst_rgb as GLenum,
+        src_alpha as GLenum,
+        dst_alpha as GLenum,
+      );
+    }
+
+    let [r, g, b, a] = self.blend_color;
+    gl::BlendColor(r, g, b, a);
+
+    gl::DepthFunc(self.depth_func as GLenum);
+    gl::PolygonOffset(self.polygon_offset[0], self.polygon_offset[1]);
+
+    for (&face, s) in [gl::FRONT, gl::BACK].iter().zip(&self.stencil) {
+      gl::StencilFuncSeparate(face, s[0] as GLenum, s[1], s[2] as GLuint);
+      gl::StencilOpSeparate(face, s[3] as GLenum, s[4] as GLenum, s[5] as GLenum);
+    }
+
+    gl::FrontFace(self.front_face as GLenum);
+    gl::CullFace(self.cull_face as GLenum);
+    gl::LineWidth(self.line_width);
+
+    if self.core {
+      gl::PrimitiveRestartIndex(self.primitive_restart_index as GLuint);
+      gl::PointSize(self.point_size);
+      gl::PolygonMode(gl::FRONT_AND_BACK, self.polygon_mode as GLenum);
+    }
+
+    if let Some(patch_vertices) = self.patch_vertices {
+      gl::PatchParameteri(gl::PATCH_VERTICES, patch_vertices);
+    }
+
+    gl::PixelStorei(gl::PACK_ALIGNMENT, self.pack_alignment);
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, self.unpack_alignment);
+
+    // bindings
+    for (unit, textures) in self.textures.iter().enumerate() {
+      gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+
+      for &(target, handle) in textures {
+        gl::BindTexture(target, handle as GLuint);
+      }
+    }
+
+    gl::ActiveTexture(self.active_texture as GLenum);
+
+    for (binding, &handle) in self.uniform_buffers.iter().enumerate() {
+      gl::BindBufferBase(gl::UNIFORM_BUFFER, binding as GLuint, handle as GLuint);
+    }
+
+    gl::BindVertexArray(self.vertex_array as GLuint);
+
+    // binding buffers to indexed targets also binds them to the generic ones
+    for &(target, handle) in &self.buffers {
+      gl::BindBuffer(target, handle as GLuint);
+    }
+
+    gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.draw_framebuffer as GLuint);
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_framebuffer as GLuint);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, self.renderbuffer as GLuint);
+    gl::UseProgram(self.program as GLuint);
+  }
+}
+
+// Query an integer state.
+unsafe fn get_host_integer(pname: GLenum) -> GLint {
+  let mut value = 0;
+  gl::GetIntegerv(pname, &mut value);
+  value
+}
+
+// Query several integer states.
+unsafe fn get_host_integers(pnames: [GLenum; 6]) -> [GLint; 6] {
+  let mut values = [0; 6];
+
+  for (value, &pname) in values.iter_mut().zip(&pnames) {
+    *value = get_host_integer(pname);
+  }
+
+  values
+}
+
+// Enable or disable an OpenGL capability.
+unsafe fn set_capability(cap: GLenum, enabled: bool) {
+  if enabled {
+    gl::Enable(cap);
+  } else {
+    gl::Disable(cap);
+  }
+}
+
+// Texture units kept bound to long-lived textures across pipelines, from the least to the most
+// recently used.
+#[derive(Debug, Default)]
+pub(crate) struct TextureUnitCache {
+  // texture unit, texture bound to it and number of live bindings using it
+  entries: Vec<(u32, GLuint, u32)>,
+}
+
+impl TextureUnitCache {
+  // Dedicate the `units` last texture units, forgetting the current assignments.
+  fn resize(&mut self, max_texture_units: u32, units: u32) {
+    let first = max_texture_units - units.min(max_texture_units);
+    self.entries = (first .. max_texture_units).map(|unit| (unit, 0, 0)).collect();
+  }
+
+  fn len(&self) -> u32 {
+    self.entries.len() as u32
+  }
+
+  // Get the unit a texture is assigned, assigning it the least recently used unit not in use if
+  // it has none. `None` is returned if all the units are in use.
+  pub(crate) fn acquire(&mut self, handle: GLuint) -> Option<u32> {
+    let i = self.entries.iter().position(|&(_, h, _)| h == handle)
+      .or_else(|| self.entries.iter().position(|&(_, _, live)| live == 0))?;
+    let (unit, _, live) = self.entries.remove(i);
+    self.entries.push((unit, handle, live + 1));
+
+    Some(unit)
+  }
+
+  // Release a unit returned by `acquire`; the texture stays assigned to it.
+  pub(crate) fn release(&mut self, unit: u32) {
+    if let Some(entry) = self.entries.iter_mut().find(|&&mut (u, _, _)| u == unit) {
+      entry.2 = entry.2.saturating_sub(1);
+    }
+  }
+
+  // Units in use by live bindings.
+  fn units_in_use(&self) -> impl Iterator<Item = u32> + '_ {
+    self.entries.iter().filter(|&&(_, _, live)| live != 0).map(|&(unit, _, _)| unit)
+  }
+
+  // Unassign a deleted texture; its unit is the first to be reassigned.
+  fn forget(&mut self, handle: GLuint) {
+    if let Some(i) = self.entries.iter().position(|&(_, h, _)| h == handle) {
+      let (unit, _, live) = self.entries.remove(i);
+      self.entries.insert(0, (unit, 0, live));
+    }
+  }
+}
+
+/// Number of GPU commands issued during a frame.
+///
+/// Counters don’t include the commands skipped because they would have left the state
+/// unchanged. They’re cheap to maintain, and meant to feed in-app stats overlays.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FrameCounters {
+  /// Draw calls; each command of an indirect draw counts as one.
+  pub draw_calls: u64,
+  /// Shader program switches.
+  pub program_switches: u64,
+  /// Texture binds.
+  pub texture_binds: u64,
+  /// Uploads of data to buffers, including uniform buffers.
+  pub buffer_uploads: u64,
+}
+
+#[cfg(feature = "std")]
+impl Drop for GraphicsState {
+  fn drop(&mut self) {
+    // the driver must not call the handler once it’s dropped; surfaces drop their graphics state
+    // before their context for that reason
+    self.disable_debug_output();
+  }
+}
+
+/// An OpenGL error.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GlError {
+  /// An unacceptable value was passed as enumeration.
+  InvalidEnum,
+  /// A numeric argument is out of range.
+  InvalidValue,
+  /// The operation is not allowed in the current state.
+  InvalidOperation,
+  /// The framebuffer object is not complete.
+  InvalidFramebufferOperation,
+  /// There is not enough memory left to execute the command.
+  OutOfMemory,
+  /// Any other error, holding its OpenGL code.
+  Other(u32),
+}
+
+impl fmt::Display for GlError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      GlError::InvalidEnum => f.write_str("invalid enum"),
+      GlError::InvalidValue => f.write_str("invalid value"),
+      GlError::InvalidOperation => f.write_str("invalid operation"),
+      GlError::InvalidFramebufferOperation => f.write_str("invalid framebuffer operation"),
+      GlError::OutOfMemory => f.write_str("out of memory"),
+      GlError::Other(code) => write!(f, "error {:#x}", code),
+    }
+  }
+}
+
+/// Why an object cannot be used anymore.
+///
+/// Using such an object would call into the driver with an invalid context, which is undefined
+/// behavior; its fallible operations fail with this cause instead.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PoisonCause {
+  /// The context of the object was lost.
+  ///
+  /// See [`GraphicsState::mark_context_lost`] and [`GraphicsState::poll_context_lost`].
+  ContextLost,
+  /// The object is used from another thread than the one its context is current on.
+  ///
+  /// Only programs, which can be sent to other threads, are checked, with the `thread-check`
+  /// feature. Buffers and textures can’t leave the thread of their context.
+  WrongThread,
+}
+
+impl fmt::Display for PoisonCause {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      PoisonCause::ContextLost => f.write_str("the context was lost"),
+      PoisonCause::WrongThread => f.write_str("used from another thread than its context’s"),
+    }
+  }
+}
+
+fn get_gl_error() -> Result<(), GlError> {
+  match unsafe { gl::GetError() } {
+    gl::NO_ERROR => Ok(()),
+    gl::INVALID_ENUM => Err(GlError::InvalidEnum),
+    gl::INVALID_VALUE => Err(GlError::InvalidValue),
+    gl::INVALID_OPERATION => Err(GlError::InvalidOperation),
+    gl::INVALID_FRAMEBUFFER_OPERATION => Err(GlError::InvalidFramebufferOperation),
+    gl::OUT_OF_MEMORY => Err(GlError::OutOfMemory),
+    code => Err(GlError::Other(code)),
+  }
+}
+
+// Panic if the OpenGL call `call`, made on `object`, failed; used by gl_check!.
+#[cfg(feature = "error-check")]
+pub(crate) fn check_gl_call(call: &str, object: Option<u32>) {
+  if let Err(e) = get_gl_error() {
+    match object {
+      Some(object) => panic!("gl{} failed on object {}: {}", call, object, e),
+      None => panic!("gl{} failed: {}", call, e),
     }
   }
 }
@@ -421,6 +1906,10 @@ fn from_blending_factor(factor: Factor) -> GLenum {
     Factor::DstAlpha => gl::DST_ALPHA,
     Factor::DstAlphaComplement => gl::ONE_MINUS_DST_ALPHA,
     Factor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+    Factor::ConstantColor => gl::CONSTANT_COLOR,
+    Factor::ConstantColorComplement => gl::ONE_MINUS_CONSTANT_COLOR,
+    Factor::ConstantAlpha => gl::CONSTANT_ALPHA,
+    Factor::ConstantAlphaComplement => gl::ONE_MINUS_CONSTANT_ALPHA,
   }
 }
 
@@ -442,12 +1931,18 @@ pub enum StateQueryError {
   UnknownBlendingDstFactor(GLenum),
   /// Corrupted depth test state.
   UnknownDepthTestState(GLboolean),
+  /// Corrupted stencil test state.
+  UnknownStencilTestState(GLboolean),
   /// Corrupted face culling state.
   UnknownFaceCullingState(GLboolean),
   /// Corrupted face culling order.
   UnknownFaceCullingOrder(GLenum),
   /// Corrupted face culling mode.
   UnknownFaceCullingMode(GLenum),
+  /// Corrupted program point size state.
+  UnknownProgramPointSizeState(GLboolean),
+  /// Corrupted polygon mode.
+  UnknownPolygonMode(GLenum),
   /// Corrupted vertex restart state.
   UnknownVertexRestartState(GLboolean),
   /// Corrupted sRGB framebuffer state.
@@ -465,9 +1960,14 @@ impl fmt::Display for StateQueryError {
         write!(f, "unknown blending destination factor: {}", k)
       }
       StateQueryError::UnknownDepthTestState(ref s) => write!(f, "unknown depth test state: {}", s),
+      StateQueryError::UnknownStencilTestState(ref s) => write!(f, "unknown stencil test state: {}", s),
       StateQueryError::UnknownFaceCullingState(ref s) => write!(f, "unknown face culling state: {}", s),
       StateQueryError::UnknownFaceCullingOrder(ref o) => write!(f, "unknown face culling order: {}", o),
       StateQueryError::UnknownFaceCullingMode(ref m) => write!(f, "unknown face culling mode: {}", m),
+      StateQueryError::UnknownProgramPointSizeState(ref s) => {
+        write!(f, "unknown program point size state: {}", s)
+      }
+      StateQueryError::UnknownPolygonMode(ref m) => write!(f, "unknown polygon mode: {}", m),
       StateQueryError::UnknownVertexRestartState(ref s) => write!(f, "unknown vertex restart state: {}", s),
       StateQueryError::UnknownSRGBFramebufferState(ref s) => write!(f, "unknown sRGB framebuffer state: {}", s),
     }
@@ -486,6 +1986,18 @@ unsafe fn get_ctx_clear_color() -> Result<[GLfloat; 4], StateQueryError> {
   Ok(data)
 }
 
+unsafe fn get_ctx_clear_depth() -> Result<GLfloat, StateQueryError> {
+  let mut data = 1.;
+  gl::GetFloatv(gl::DEPTH_CLEAR_VALUE, &mut data);
+  Ok(data)
+}
+
+unsafe fn get_ctx_clear_stencil() -> Result<GLint, StateQueryError> {
+  let mut data = 0;
+  gl::GetIntegerv(gl::STENCIL_CLEAR_VALUE, &mut data);
+  Ok(data)
+}
+
 unsafe fn get_ctx_blending_state() -> Result<BlendingState, StateQueryError> {
   let state = gl::IsEnabled(gl::BLEND);
 
@@ -496,9 +2008,24 @@ unsafe fn get_ctx_blending_state() -> Result<BlendingState, StateQueryError> {
   }
 }
 
-unsafe fn get_ctx_blending_equation() -> Result<Equation, StateQueryError> {
+unsafe fn get_ctx_blending() -> Result<SeparateBlending, StateQueryError> {
+  let rgb = (
+    get_ctx_blending_equation(gl::BLEND_EQUATION_RGB)?,
+    get_ctx_blending_src_factor(gl::BLEND_SRC_RGB)?,
+    get_ctx_blending_dst_factor(gl::BLEND_DST_RGB)?,
+  );
+  let alpha = (
+    get_ctx_blending_equation(gl::BLEND_EQUATION_ALPHA)?,
+    get_ctx_blending_src_factor(gl::BLEND_SRC_ALPHA)?,
+    get_ctx_blending_dst_factor(gl::BLEND_DST_ALPHA)?,
+  );
+
+  Ok(SeparateBlending::new(rgb, alpha))
+}
+
+unsafe fn get_ctx_blending_equation(pname: GLenum) -> Result<Equation, StateQueryError> {
   let mut data = gl::FUNC_ADD as GLint;
-  gl::GetIntegerv(gl::BLEND_EQUATION_RGB, &mut data);
+  gl::GetIntegerv(pname, &mut data);
 
   let data = data as GLenum;
   match data {
@@ -511,17 +2038,24 @@ unsafe fn get_ctx_blending_equation() -> Result<Equation, StateQueryError> {
   }
 }
 
-unsafe fn get_ctx_blending_factors() -> Result<(Factor, Factor), StateQueryError> {
+unsafe fn get_ctx_blending_src_factor(pname: GLenum) -> Result<Factor, StateQueryError> {
   let mut src = gl::ONE as GLint;
-  let mut dst = gl::ZERO as GLint;
+  gl::GetIntegerv(pname, &mut src);
 
-  gl::GetIntegerv(gl::BLEND_SRC_RGB, &mut src);
-  gl::GetIntegerv(gl::BLEND_DST_RGB, &mut dst);
+  from_gl_blending_factor(src as GLenum).map_err(StateQueryError::UnknownBlendingSrcFactor)
+}
 
-  let src_k = from_gl_blending_factor(src as GLenum).map_err(StateQueryError::UnknownBlendingSrcFactor)?;
-  let dst_k = from_gl_blending_factor(dst as GLenum).map_err(StateQueryError::UnknownBlendingDstFactor)?;
+unsafe fn get_ctx_blending_dst_factor(pname: GLenum) -> Result<Factor, StateQueryError> {
+  let mut dst = gl::ZERO as GLint;
+  gl::GetIntegerv(pname, &mut dst);
 
-  Ok((src_k, dst_k))
+  from_gl_blending_factor(dst as GLenum).map_err(StateQueryError::UnknownBlendingDstFactor)
+}
+
+unsafe fn get_ctx_blend_color() -> Result<[GLfloat; 4], StateQueryError> {
+  let mut data = [0.; 4];
+  gl::GetFloatv(gl::BLEND_COLOR, data.as_mut_ptr());
+  Ok(data)
 }
 
 #[inline]
@@ -538,6 +2072,10 @@ fn from_gl_blending_factor(factor: GLenum) -> Result<Factor, GLenum> {
     gl::DST_ALPHA => Ok(Factor::DstAlpha),
     gl::ONE_MINUS_DST_ALPHA => Ok(Factor::DstAlphaComplement),
     gl::SRC_ALPHA_SATURATE => Ok(Factor::SrcAlphaSaturate),
+    gl::CONSTANT_COLOR => Ok(Factor::ConstantColor),
+    gl::ONE_MINUS_CONSTANT_COLOR => Ok(Factor::ConstantColorComplement),
+    gl::CONSTANT_ALPHA => Ok(Factor::ConstantAlpha),
+    gl::ONE_MINUS_CONSTANT_ALPHA => Ok(Factor::ConstantAlphaComplement),
     _ => Err(factor),
   }
 }
@@ -552,6 +2090,33 @@ unsafe fn get_ctx_depth_test() -> Result<DepthTest, StateQueryError> {
   }
 }
 
+unsafe fn get_ctx_depth_clamp() -> bool {
+  gl::IsEnabled(gl::DEPTH_CLAMP) == gl::TRUE
+}
+
+unsafe fn get_ctx_polygon_offset_enabled() -> bool {
+  gl::IsEnabled(gl::POLYGON_OFFSET_FILL) == gl::TRUE
+}
+
+unsafe fn get_ctx_polygon_offset() -> PolygonOffset {
+  let mut factor = 0.;
+  let mut units = 0.;
+  gl::GetFloatv(gl::POLYGON_OFFSET_FACTOR, &mut factor);
+  gl::GetFloatv(gl::POLYGON_OFFSET_UNITS, &mut units);
+
+  PolygonOffset { factor, units }
+}
+
+unsafe fn get_ctx_stencil_test_state() -> Result<StencilTestState, StateQueryError> {
+  let state = gl::IsEnabled(gl::STENCIL_TEST);
+
+  match state {
+    gl::TRUE => Ok(StencilTestState::On),
+    gl::FALSE => Ok(StencilTestState::Off),
+    _ => Err(StateQueryError::UnknownStencilTestState(state)),
+  }
+}
+
 unsafe fn get_ctx_face_culling_state() -> Result<FaceCullingState, StateQueryError> {
   let state = gl::IsEnabled(gl::CULL_FACE);
 
@@ -587,8 +2152,46 @@ unsafe fn get_ctx_face_culling_mode() -> Result<FaceCullingMode, StateQueryError
   }
 }
 
-unsafe fn get_ctx_vertex_restart() -> Result<VertexRestart, StateQueryError> {
-  let state = gl::IsEnabled(gl::PRIMITIVE_RESTART);
+unsafe fn get_ctx_point_size() -> Result<GLfloat, StateQueryError> {
+  let mut data = 1.;
+  gl::GetFloatv(gl::POINT_SIZE, &mut data);
+  Ok(data)
+}
+
+unsafe fn get_ctx_program_point_size() -> Result<bool, StateQueryError> {
+  let state = gl::IsEnabled(gl::PROGRAM_POINT_SIZE);
+
+  match state {
+    gl::TRUE => Ok(true),
+    gl::FALSE => Ok(false),
+    _ => Err(StateQueryError::UnknownProgramPointSizeState(state)),
+  }
+}
+
+unsafe fn get_ctx_line_width() -> Result<GLfloat, StateQueryError> {
+  let mut data = 1.;
+  gl::GetFloatv(gl::LINE_WIDTH, &mut data);
+  Ok(data)
+}
+
+unsafe fn get_ctx_polygon_mode() -> Result<PolygonMode, StateQueryError> {
+  // some implementations return both the front and back modes
+  let mut data = [gl::FILL as GLint; 2];
+  gl::GetIntegerv(gl::POLYGON_MODE, data.as_mut_ptr());
+
+  match data[0] as GLenum {
+    gl::FILL => Ok(PolygonMode::Fill),
+    gl::LINE => Ok(PolygonMode::Line),
+    gl::POINT => Ok(PolygonMode::Point),
+    mode => Err(StateQueryError::UnknownPolygonMode(mode)),
+  }
+}
+
+unsafe fn get_ctx_vertex_restart(profile: Profile) -> Result<VertexRestart, StateQueryError> {
+  let state = match profile {
+    Profile::Core => gl::IsEnabled(gl::PRIMITIVE_RESTART),
+    Profile::Es => gl::IsEnabled(gl::PRIMITIVE_RESTART_FIXED_INDEX),
+  };
 
   match state {
     gl::TRUE => Ok(VertexRestart::On),
@@ -597,10 +2200,28 @@ unsafe fn get_ctx_vertex_restart() -> Result<VertexRestart, StateQueryError> {
   }
 }
 
+unsafe fn get_ctx_primitive_restart_index() -> Result<GLuint, StateQueryError> {
+  let mut index = 0 as GLint;
+  gl::GetIntegerv(gl::PRIMITIVE_RESTART_INDEX, &mut index);
+  Ok(index as GLuint)
+}
+
+unsafe fn get_ctx_max_draw_buffers() -> Result<u32, StateQueryError> {
+  let mut max = 0 as GLint;
+  gl::GetIntegerv(gl::MAX_DRAW_BUFFERS, &mut max);
+  Ok((max as u32).min(32))
+}
+
+unsafe fn get_ctx_max_texture_units() -> Result<u32, StateQueryError> {
+  let mut max = 0 as GLint;
+  gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max);
+  Ok(max as u32)
+}
+
 unsafe fn get_ctx_current_texture_unit() -> Result<GLenum, StateQueryError> {
   let mut active_texture = gl::TEXTURE0 as GLint;
   gl::GetIntegerv(gl::ACTIVE_TEXTURE, &mut active_texture);
-  Ok(active_texture as GLenum)
+  Ok(active_texture as GLenum - gl::TEXTURE0)
 }
 
 unsafe fn get_ctx_bound_draw_framebuffer() -> Result<GLuint, StateQueryError> {
@@ -609,6 +2230,12 @@ unsafe fn get_ctx_bound_draw_framebuffer() -> Result<GLuint, StateQueryError> {
   Ok(bound as GLuint)
 }
 
+unsafe fn get_ctx_bound_read_framebuffer() -> Result<GLuint, StateQueryError> {
+  let mut bound = 0 as GLint;
+  gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut bound);
+  Ok(bound as GLuint)
+}
+
 unsafe fn get_ctx_bound_vertex_array() -> Result<GLuint, StateQueryError> {
   let mut bound = 0 as GLint;
   gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut bound);
@@ -621,6 +2248,10 @@ unsafe fn get_ctx_current_program() -> Result<GLuint, StateQueryError> {
   Ok(used as GLuint)
 }
 
+unsafe fn get_ctx_dithering() -> bool {
+  gl::IsEnabled(gl::DITHER) == gl::TRUE
+}
+
 unsafe fn get_ctx_srgb_framebuffer_enabled() -> Result<bool, StateQueryError> {
   let state = gl::IsEnabled(gl::FRAMEBUFFER_SRGB);
 
@@ -630,3 +2261,82 @@ unsafe fn get_ctx_srgb_framebuffer_enabled() -> Result<bool, StateQueryError> {
     _ => Err(StateQueryError::UnknownSRGBFramebufferState(state)),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cache(max_texture_units: u32, units: u32) -> TextureUnitCache {
+    let mut cache = TextureUnitCache::default();
+    cache.resize(max_texture_units, units);
+    cache
+  }
+
+  #[test]
+  fn resize_dedicates_the_last_units() {
+    assert_eq!(cache(16, 4).entries, vec![(12, 0, 0), (13, 0, 0), (14, 0, 0), (15, 0, 0)]);
+    assert_eq!(cache(4, 8).len(), 4);
+    assert_eq!(cache(4, 8).entries[0], (0, 0, 0));
+    assert_eq!(cache(16, 0).len(), 0);
+    assert_eq!(cache(16, 0).acquire(1), None);
+  }
+
+  #[test]
+  fn acquire_keeps_assignments() {
+    let mut cache = cache(16, 2);
+    let unit = cache.acquire(1);
+
+    cache.release(unit.unwrap());
+
+    assert_eq!(cache.acquire(1), unit);
+    assert_eq!(cache.acquire(1), unit);
+    assert_eq!(cache.units_in_use().collect::<Vec<_>>(), vec![unit.unwrap()]);
+  }
+
+  #[test]
+  fn reassign_least_recently_used_unit() {
+    let mut cache = cache(16, 2);
+    let unit_1 = cache.acquire(1).unwrap();
+    let unit_2 = cache.acquire(2).unwrap();
+
+    cache.release(unit_2);
+    cache.release(unit_1);
+
+    // 1 was used less recently than 2
+    assert_eq!(cache.acquire(3), Some(unit_1));
+    assert_eq!(cache.acquire(2), Some(unit_2));
+  }
+
+  #[test]
+  fn units_in_use_are_not_reassigned() {
+    let mut cache = cache(16, 2);
+    let unit_1 = cache.acquire(1).unwrap();
+    let unit_2 = cache.acquire(2).unwrap();
+
+    cache.release(unit_1);
+
+    assert_eq!(cache.acquire(3), Some(unit_1));
+    assert_eq!(cache.acquire(4), None);
+
+    cache.release(unit_2);
+
+    assert_eq!(cache.acquire(4), Some(unit_2));
+    assert_eq!(cache.units_in_use().count(), 2);
+  }
+
+  #[test]
+  fn forgotten_unit_is_reassigned_first() {
+    let mut cache = cache(16, 3);
+    let units = [1, 2, 3].iter().map(|&h| cache.acquire(h).unwrap()).collect::<Vec<_>>();
+
+    for &unit in &units {
+      cache.release(unit);
+    }
+
+    cache.forget(3);
+
+    assert_eq!(cache.entries[0], (units[2], 0, 0));
+    assert_eq!(cache.acquire(4), Some(units[2]));
+    assert_eq!(cache.acquire(5), Some(units[0]));
+  }
+}
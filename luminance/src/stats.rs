@@ -0,0 +1,377 @@
+//! In-app stats overlay.
+//!
+//! A [`StatsOverlay`] draws, in the upper-left corner of a framebuffer – typically the back buffer
+//! – one bar per statistic of the last frame:
+//!
+//!   1. The GPU time spent rendering the frame, measured with a timer query.
+//!   2. The draw calls, program switches, texture binds and buffer uploads, as counted by the
+//!      graphics state – see [`GraphicsContext::frame_counters`].
+//!   3. The video memory in use, if the driver reports it (`GL_NVX_gpu_memory_info`).
+//!
+//! Bars aren’t labeled: they always come in that order, from the top.
+//!
+//! Each bar is scaled against a [`StatsBudget`]: a full bar means the budget is reached, and bars
+//! turn yellow at 75% of their budget and red past it. Rendering the overlay is a single call,
+//! after the frame is rendered and before swapping buffers:
+//!
+//! ```ignore
+//! let mut overlay = StatsOverlay::new(&mut surface)?;
+//!
+//! loop {
+//!   // render the frame…
+//!
+//!   overlay.render(&mut surface, &back_buffer);
+//!   surface.swap_buffers();
+//! }
+//! ```
+//!
+//! The timer query is running between two calls to [`StatsOverlay::render`], so no other
+//! `GL_TIME_ELAPSED` query – e.g. from another tool – can run meanwhile. GPU timings are available
+//! a few frames later, once the GPU is done with them.
+
+use std::fmt;
+
+use crate::blending::{Equation, Factor};
+use crate::capabilities::Capabilities;
+use crate::context::GraphicsContext;
+use crate::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
+use crate::metagl::*;
+use crate::pipeline::{PipelineState, Viewport};
+use crate::post_process::FULLSCREEN_VS;
+use crate::render_state::RenderState;
+use crate::shader::program::{Program, ProgramError, Uniform, UniformBuilder, UniformInterface};
+use crate::state::FrameCounters;
+use crate::tess::{Tess, TessError};
+use crate::texture::{Dimensionable, Layerable};
+
+// GL_NVX_gpu_memory_info, in kilobytes
+const GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX: GLenum = 0x9048;
+const GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: GLenum = 0x9049;
+
+// size of the overlay, in pixels
+const MARGIN: u32 = 8;
+const WIDTH: u32 = 200;
+const ROW_HEIGHT: u32 = 12;
+
+/// Budgets the bars of a [`StatsOverlay`] are scaled against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatsBudget {
+  /// GPU time per frame, in milliseconds.
+  pub gpu_time: f32,
+  /// Draw calls per frame.
+  pub draw_calls: u64,
+  /// Program switches per frame.
+  pub program_switches: u64,
+  /// Texture binds per frame.
+  pub texture_binds: u64,
+  /// Buffer uploads per frame.
+  pub buffer_uploads: u64,
+}
+
+impl Default for StatsBudget {
+  /// A budget for 60 frames per second:
+  ///
+  ///   - `gpu_time`: `16.6`
+  ///   - `draw_calls`: `1000`
+  ///   - `program_switches`: `100`
+  ///   - `texture_binds`: `500`
+  ///   - `buffer_uploads`: `100`
+  fn default() -> Self {
+    StatsBudget {
+      gpu_time: 16.6,
+      draw_calls: 1000,
+      program_switches: 100,
+      texture_binds: 500,
+      buffer_uploads: 100,
+    }
+  }
+}
+
+/// Video memory usage, in kilobytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MemoryUsage {
+  /// Memory in use.
+  pub used: u64,
+  /// Total memory.
+  pub total: u64,
+}
+
+/// An overlay drawing the stats of the last frame as bars.
+pub struct StatsOverlay {
+  program: Program<(), (), OverlayInterface>,
+  triangle: Tess,
+  budget: StatsBudget,
+  has_memory_info: bool,
+  // timer query measuring the current frame
+  running: Option<GLuint>,
+  // timer queries of the previous frames, oldest first
+  pending: Vec<GLuint>,
+  gpu_time: Option<f32>,
+}
+
+impl StatsOverlay {
+  /// Create an overlay, compiling its shader program.
+  pub fn new<C>(ctx: &mut C) -> Result<Self, StatsError> where C: GraphicsContext {
+    let program = Program::from_strings(None, FULLSCREEN_VS, None, OVERLAY_FS)?.ignore_warnings();
+    let triangle = Tess::fullscreen_triangle(ctx)?;
+    let has_memory_info = Capabilities::new(ctx)
+      .extensions()
+      .has_raw("GL_NVX_gpu_memory_info");
+
+    Ok(StatsOverlay {
+      program,
+      triangle,
+      budget: StatsBudget::default(),
+      has_memory_info,
+      running: None,
+      pending: Vec::new(),
+      gpu_time: None,
+    })
+  }
+
+  /// Budgets the bars are scaled against.
+  pub fn budget(&self) -> StatsBudget {
+    self.budget
+  }
+
+  /// Set the budgets the bars are scaled against.
+  pub fn set_budget(&mut self, budget: StatsBudget) {
+    self.budget = budget;
+  }
+
+  /// GPU time of the last measured frame, in milliseconds.
+  pub fn gpu_time(&self) -> Option<f32> {
+    self.gpu_time
+  }
+
+  /// Video memory usage, if the driver reports it.
+  pub fn memory_usage(&self) -> Option<MemoryUsage> {
+    if !self.has_memory_info {
+      return None;
+    }
+
+    let mut total: GLint = 0;
+    let mut available: GLint = 0;
+
+    unsafe {
+      gl::GetIntegerv(GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX, &mut total);
+      gl::GetIntegerv(GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX, &mut available);
+    }
+
+    Some(MemoryUsage {
+      used: (total - available).max(0) as u64,
+      total: total.max(0) as u64,
+    })
+  }
+
+  /// Draw the stats of the last frame over `framebuffer`.
+  ///
+  /// Call this once per frame, after rendering and before swapping buffers. The overlay’s own draw
+  /// call is counted in the next frame counters.
+  pub fn render<C, L, D, CS, DS>(&mut self, ctx: &mut C, framebuffer: &Framebuffer<L, D, CS, DS>)
+  where C: GraphicsContext,
+        L: Layerable,
+        D: Dimensionable,
+        D::Size: Copy,
+        CS: ColorSlot<L, D>,
+        DS: DepthSlot<L, D> {
+    self.measure_frame();
+
+    let counters = ctx.frame_counters();
+    let values = self.bar_values(&counters);
+    let height = ROW_HEIGHT * values.len() as u32;
+
+    // the overlay doesn’t fit
+    if framebuffer.width() < WIDTH + MARGIN || framebuffer.height() < height + MARGIN {
+      return;
+    }
+
+    let rect = [MARGIN, framebuffer.height() - MARGIN - height, WIDTH, height];
+    let pipeline_state = PipelineState::default()
+      .enable_clear(false)
+      .set_viewport(Viewport::Specific {
+        x: rect[0],
+        y: rect[1],
+        width: rect[2],
+        height: rect[3],
+      });
+    let render_state = RenderState::default()
+      .set_depth_test(None)
+      .set_blending((Equation::Additive, Factor::SrcAlpha, Factor::SrcAlphaComplement));
+
+    let mut packed = [0.; 8];
+    packed[.. values.len()].copy_from_slice(&values);
+
+    let program = &self.program;
+    let triangle = &self.triangle;
+
+    ctx.pipeline_builder().pipeline(framebuffer, &pipeline_state, |_, mut shd_gate| {
+      shd_gate.shade(program, |iface, mut rdr_gate| {
+        iface.rect.update([rect[0] as f32, rect[1] as f32, rect[2] as f32, rect[3] as f32]);
+        iface.bar_nb.update(values.len() as i32);
+        iface.values_a.update([packed[0], packed[1], packed[2], packed[3]]);
+        iface.values_b.update([packed[4], packed[5], packed[6], packed[7]]);
+        rdr_gate.render(render_state, |mut tess_gate| tess_gate.render(triangle));
+      });
+    });
+  }
+
+  // Fill ratios of the bars, relative to their budgets.
+  fn bar_values(&self, counters: &FrameCounters) -> Vec<f32> {
+    let ratio = |value: u64, budget: u64| value as f32 / budget.max(1) as f32;
+
+    let mut values = vec![
+      self.gpu_time.map_or(0., |t| t / self.budget.gpu_time.max(f32::EPSILON)),
+      ratio(counters.draw_calls, self.budget.draw_calls),
+      ratio(counters.program_switches, self.budget.program_switches),
+      ratio(counters.texture_binds, self.budget.texture_binds),
+      ratio(counters.buffer_uploads, self.budget.buffer_uploads),
+    ];
+
+    if let Some(memory) = self.memory_usage() {
+      values.push(ratio(memory.used, memory.total));
+    }
+
+    values
+  }
+
+  // End the timer query of the frame, start the one of the next frame and collect the available
+  // timings.
+  fn measure_frame(&mut self) {
+    unsafe {
+      if let Some(query) = self.running.take() {
+        gl::EndQuery(gl::TIME_ELAPSED);
+        self.pending.push(query);
+      }
+
+      while let Some(&query) = self.pending.first() {
+        let mut available: GLint = 0;
+        gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+
+        if available == 0 {
+          break;
+        }
+
+        let mut elapsed: GLuint64 = 0;
+        gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut elapsed);
+        gl::DeleteQueries(1, &query);
+        self.pending.remove(0);
+
+        self.gpu_time = Some(elapsed as f32 * 1e-6);
+      }
+
+      let mut query: GLuint = 0;
+      gl::GenQueries(1, &mut query);
+      gl::BeginQuery(gl::TIME_ELAPSED, query);
+      self.running = Some(query);
+    }
+  }
+}
+
+impl Drop for StatsOverlay {
+  fn drop(&mut self) {
+    unsafe {
+      if let Some(query) = self.running.take() {
+        gl::EndQuery(gl::TIME_ELAPSED);
+        self.pending.push(query);
+      }
+
+      gl::DeleteQueries(self.pending.len() as GLsizei, self.pending.as_ptr());
+    }
+  }
+}
+
+impl fmt::Debug for StatsOverlay {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    f.debug_struct("StatsOverlay")
+      .field("budget", &self.budget)
+      .field("gpu_time", &self.gpu_time)
+      .finish()
+  }
+}
+
+/// Errors that can happen while creating a stats overlay.
+#[derive(Debug)]
+pub enum StatsError {
+  /// The shader program of the overlay failed to build.
+  ProgramError(ProgramError),
+  /// The triangle the overlay is drawn with couldn’t be created.
+  TessError(TessError),
+}
+
+impl fmt::Display for StatsError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match *self {
+      StatsError::ProgramError(ref e) => write!(f, "stats overlay program error: {}", e),
+      StatsError::TessError(ref e) => write!(f, "stats overlay tessellation error: {:?}", e),
+    }
+  }
+}
+
+impl From<ProgramError> for StatsError {
+  fn from(e: ProgramError) -> Self {
+    StatsError::ProgramError(e)
+  }
+}
+
+impl From<TessError> for StatsError {
+  fn from(e: TessError) -> Self {
+    StatsError::TessError(e)
+  }
+}
+
+struct OverlayInterface {
+  rect: Uniform<[f32; 4]>,
+  bar_nb: Uniform<i32>,
+  values_a: Uniform<[f32; 4]>,
+  values_b: Uniform<[f32; 4]>,
+}
+
+impl UniformInterface for OverlayInterface {
+  fn uniform_interface<'a>(builder: &mut UniformBuilder<'a>, _: ()) -> Result<Self, ProgramError> {
+    Ok(OverlayInterface {
+      rect: builder.ask("rect").map_err(ProgramError::UniformWarning)?,
+      bar_nb: builder.ask("bar_nb").map_err(ProgramError::UniformWarning)?,
+      values_a: builder.ask("values_a").map_err(ProgramError::UniformWarning)?,
+      values_b: builder.ask("values_b").map_err(ProgramError::UniformWarning)?,
+    })
+  }
+}
+
+const OVERLAY_FS: &str = "
+uniform vec4 rect;
+uniform int bar_nb;
+uniform vec4 values_a;
+uniform vec4 values_b;
+
+out vec4 frag;
+
+const vec4 BACKGROUND = vec4(0., 0., 0., .6);
+const vec4 TRACK = vec4(.2, .2, .2, .8);
+
+void main() {
+  vec2 p = (gl_FragCoord.xy - rect.xy) / rect.zw;
+
+  // bars are laid out from the top
+  float row = (1. - p.y) * float(bar_nb);
+  int i = min(int(row), bar_nb - 1);
+  float in_row = fract(row);
+
+  if (in_row < .2 || in_row > .8 || p.x < .02 || p.x > .98) {
+    frag = BACKGROUND;
+    return;
+  }
+
+  float value = i < 4 ? values_a[i] : values_b[i - 4];
+
+  if ((p.x - .02) / .96 > min(value, 1.)) {
+    frag = TRACK;
+  } else if (value > 1.) {
+    frag = vec4(.9, .2, .2, .9);
+  } else if (value > .75) {
+    frag = vec4(.9, .8, .2, .9);
+  } else {
+    frag = vec4(.2, .8, .3, .9);
+  }
+}
+";
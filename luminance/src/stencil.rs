@@ -0,0 +1,182 @@
+//! Stencil test related features.
+//!
+//! The stencil test is a per-fragment operation that compares a reference value with the value
+//! stored in the stencil buffer and discards fragments that fail the comparison. The stencil
+//! buffer can also be updated depending on the outcome of the stencil and depth tests, which makes
+//! it possible to *mark* regions of the screen and then restrict rendering to (or away from) them.
+
+use crate::depth_test::DepthComparison;
+use crate::metagl::*;
+use crate::render_state::RenderState;
+
+/// Should the stencil test be enabled?
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum StencilTestState {
+  /// The stencil test is enabled.
+  On,
+  /// The stencil test is disabled.
+  Off,
+}
+
+/// Stencil test setup.
+///
+/// The comparison is performed between the reference value and the value stored in the stencil
+/// buffer, both masked with `mask`: `a` is `reference & mask` and `b` is `stencil & mask`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StencilTest {
+  /// Comparison to perform.
+  pub(crate) comparison: DepthComparison,
+  /// Reference value.
+  pub(crate) reference: u8,
+  /// Mask applied to both the reference and the stencil value before comparing.
+  pub(crate) mask: u8,
+}
+
+impl StencilTest {
+  /// Create a new [`StencilTest`].
+  pub fn new(comparison: DepthComparison, reference: u8, mask: u8) -> Self {
+    StencilTest {
+      comparison,
+      reference,
+      mask,
+    }
+  }
+}
+
+impl Default for StencilTest {
+  /// The test always passes, the reference is `0` and the mask is `0xFF`.
+  fn default() -> Self {
+    StencilTest::new(DepthComparison::Always, 0, 0xFF)
+  }
+}
+
+/// Operation to perform on the stencil buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StencilOp {
+  /// Keep the current value.
+  Keep,
+  /// Set the value to `0`.
+  Zero,
+  /// Replace the value with the reference value of the [`StencilTest`].
+  Replace,
+  /// Increment the value, clamping to the maximum.
+  Increment,
+  /// Increment the value, wrapping to `0` on overflow.
+  IncrementWrap,
+  /// Decrement the value, clamping to `0`.
+  Decrement,
+  /// Decrement the value, wrapping to the maximum on underflow.
+  DecrementWrap,
+  /// Bitwise-invert the value.
+  Invert,
+}
+
+impl StencilOp {
+  pub(crate) fn to_glenum(self) -> GLenum {
+    match self {
+      StencilOp::Keep => gl::KEEP,
+      StencilOp::Zero => gl::ZERO,
+      StencilOp::Replace => gl::REPLACE,
+      StencilOp::Increment => gl::INCR,
+      StencilOp::IncrementWrap => gl::INCR_WRAP,
+      StencilOp::Decrement => gl::DECR,
+      StencilOp::DecrementWrap => gl::DECR_WRAP,
+      StencilOp::Invert => gl::INVERT,
+    }
+  }
+}
+
+/// Operations to perform on the stencil buffer depending on the outcome of the stencil and depth
+/// tests.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StencilOperations {
+  /// Operation to perform when the stencil test fails.
+  pub(crate) stencil_fails: StencilOp,
+  /// Operation to perform when the stencil test passes but the depth test fails.
+  pub(crate) depth_fails: StencilOp,
+  /// Operation to perform when both the stencil and depth tests pass.
+  pub(crate) depth_passes: StencilOp,
+}
+
+impl StencilOperations {
+  /// Create a new [`StencilOperations`].
+  pub fn new(stencil_fails: StencilOp, depth_fails: StencilOp, depth_passes: StencilOp) -> Self {
+    StencilOperations {
+      stencil_fails,
+      depth_fails,
+      depth_passes,
+    }
+  }
+}
+
+impl Default for StencilOperations {
+  /// Keep the stencil buffer untouched in all cases.
+  fn default() -> Self {
+    StencilOperations::new(StencilOp::Keep, StencilOp::Keep, StencilOp::Keep)
+  }
+}
+
+/// Stencil-based outline rendering.
+///
+/// Rendering outlines around objects – e.g. to highlight selected objects in an editor – is done
+/// in two passes:
+///
+///   1. The objects are rendered as usual with [`StencilOutline::mask_render_state`]. Every
+///      fragment they cover gets the outline’s reference value written to the stencil buffer.
+///   2. The same objects are rendered again, *expanded* – scaled up or extruded along their normals
+///      in the vertex shader – with a flat color and [`StencilOutline::outline_render_state`].
+///      Only the fragments outside of the marked region pass, leaving the outline only.
+///
+/// The framebuffer must have a stencil buffer, and the stencil must be cleared before the first
+/// pass; see [`PipelineState::enable_clear_stencil`].
+///
+/// [`PipelineState::enable_clear_stencil`]: crate::pipeline::PipelineState::enable_clear_stencil
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StencilOutline {
+  reference: u8,
+}
+
+impl StencilOutline {
+  /// Create a new stencil outline using `reference` as marking value.
+  ///
+  /// `reference` must be different from the stencil clear value.
+  pub fn new(reference: u8) -> Self {
+    StencilOutline { reference }
+  }
+
+  /// Reference value used to mark the objects.
+  pub fn reference(&self) -> u8 {
+    self.reference
+  }
+
+  /// Render state to use to render the objects and mark them in the stencil buffer.
+  ///
+  /// The rest of `render_state` is kept untouched.
+  pub fn mask_render_state(&self, render_state: RenderState) -> RenderState {
+    render_state
+      .set_stencil_test(StencilTest::new(DepthComparison::Always, self.reference, 0xFF))
+      .set_stencil_operations(StencilOperations::new(
+        StencilOp::Keep,
+        StencilOp::Keep,
+        StencilOp::Replace,
+      ))
+  }
+
+  /// Render state to use to render the expanded objects as outlines.
+  ///
+  /// The depth test is disabled so that outlines are visible through other objects; re-enable it
+  /// on the returned value if you don’t want that. The rest of `render_state` is kept untouched.
+  pub fn outline_render_state(&self, render_state: RenderState) -> RenderState {
+    render_state
+      .set_depth_test(None)
+      .set_stencil_test(StencilTest::new(DepthComparison::NotEqual, self.reference, 0xFF))
+      .set_stencil_operations(StencilOperations::default())
+  }
+}
+
+impl Default for StencilOutline {
+  /// Use `1` as reference value.
+  fn default() -> Self {
+    StencilOutline::new(1)
+  }
+}
@@ -0,0 +1,215 @@
+//! Budgeted streaming uploads.
+//!
+//! Uploading a big texture or buffer in one go can take several milliseconds, which shows up as a
+//! frame hitch. The types of this module split such uploads in chunks spread over several frames:
+//!
+//!   - A [`StreamingBudget`] is created at the beginning of each frame, with the amount of time
+//!     that can be spent uploading during that frame.
+//!   - [`TextureUpload`] and [`BufferUpload`] hold the data to upload. Each of their `step` call
+//!     uploads chunks until the budget is exhausted.
+//!
+//! ```ignore
+//! let mut upload = TextureUpload::new(size, texels);
+//!
+//! loop {
+//!   let budget = StreamingBudget::from_millis(2);
+//!
+//!   if !upload.is_done() {
+//!     upload.step(&texture, &budget)?;
+//!   }
+//!
+//!   // render the frame…
+//! }
+//! ```
+//!
+//! The budget is measured on the CPU: it bounds the time spent in the driver while copying the
+//! data, not the time the GPU will take to consume it.
+
+use std::time::{Duration, Instant};
+
+use crate::buffer::{Buffer, BufferError};
+use crate::pixel::Pixel;
+use crate::texture::{Dim2, Flat, GenMipmaps, Texture, TextureError};
+
+/// Default size of the chunks uploaded at once, in bytes.
+pub const DEFAULT_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Time that can be spent uploading data during a frame.
+#[derive(Clone, Copy, Debug)]
+pub struct StreamingBudget {
+  deadline: Instant,
+}
+
+impl StreamingBudget {
+  /// Create a budget of `budget`, starting now.
+  pub fn new(budget: Duration) -> Self {
+    StreamingBudget {
+      deadline: Instant::now() + budget,
+    }
+  }
+
+  /// Create a budget of `ms` milliseconds, starting now.
+  pub fn from_millis(ms: u64) -> Self {
+    Self::new(Duration::from_millis(ms))
+  }
+
+  /// Time left before the budget is exhausted.
+  pub fn remaining(&self) -> Duration {
+    self.deadline.saturating_duration_since(Instant::now())
+  }
+
+  /// Check whether the budget is exhausted.
+  pub fn is_exhausted(&self) -> bool {
+    Instant::now() >= self.deadline
+  }
+}
+
+/// A 2D texture upload, split in bands of rows.
+pub struct TextureUpload<P> where P: Pixel {
+  size: [u32; 2],
+  texels: Vec<P::Encoding>,
+  rows_per_chunk: u32,
+  next_row: u32,
+  gen_mipmaps: GenMipmaps,
+}
+
+impl<P> TextureUpload<P> where P: Pixel {
+  /// Create an upload of `texels` to a texture of size `size`.
+  pub fn new(size: [u32; 2], texels: Vec<P::Encoding>) -> Self {
+    TextureUpload {
+      size,
+      texels,
+      rows_per_chunk: rows_per_chunk::<P>(size[0], DEFAULT_CHUNK_BYTES),
+      next_row: 0,
+      gen_mipmaps: GenMipmaps::No,
+    }
+  }
+
+  /// Set the approximate size of the chunks, in bytes. A chunk is at least one row.
+  pub fn set_chunk_bytes(self, bytes: usize) -> Self {
+    TextureUpload {
+      rows_per_chunk: rows_per_chunk::<P>(self.size[0], bytes),
+      ..self
+    }
+  }
+
+  /// Generate the mipmaps of the texture once the last chunk is uploaded.
+  pub fn set_gen_mipmaps(self, gen_mipmaps: GenMipmaps) -> Self {
+    TextureUpload { gen_mipmaps, ..self }
+  }
+
+  /// Check whether all the texels were uploaded.
+  pub fn is_done(&self) -> bool {
+    self.next_row >= self.size[1]
+  }
+
+  /// Ratio of uploaded texels, between `0` and `1`.
+  pub fn progress(&self) -> f32 {
+    if self.size[1] == 0 {
+      1.
+    } else {
+      self.next_row.min(self.size[1]) as f32 / self.size[1] as f32
+    }
+  }
+
+  /// Upload chunks to `texture` until `budget` is exhausted or everything is uploaded.
+  ///
+  /// At least one chunk is uploaded per call, so that uploads progress even when the budget is
+  /// already exhausted. Returns whether the upload is done.
+  pub fn step(
+    &mut self,
+    texture: &Texture<Flat, Dim2, P>,
+    budget: &StreamingBudget,
+  ) -> Result<bool, TextureError> {
+    let [width, height] = self.size;
+
+    while !self.is_done() {
+      let rows = self.rows_per_chunk.min(height - self.next_row);
+      let start = self.next_row as usize * width as usize;
+      let end = start + rows as usize * width as usize;
+      let texels = self.texels.get(start .. end).unwrap_or(&[]);
+      let gen_mipmaps = if self.next_row + rows == height { self.gen_mipmaps } else { GenMipmaps::No };
+
+      texture.upload_part(gen_mipmaps, [0, self.next_row], [width, rows], texels)?;
+      self.next_row += rows;
+
+      if budget.is_exhausted() {
+        break;
+      }
+    }
+
+    Ok(self.is_done())
+  }
+}
+
+/// A buffer upload, split in chunks of items.
+pub struct BufferUpload<T> {
+  values: Vec<T>,
+  offset: usize,
+  items_per_chunk: usize,
+  next: usize,
+}
+
+impl<T> BufferUpload<T> {
+  /// Create an upload of `values` to a buffer, starting at item `offset`.
+  pub fn new(offset: usize, values: Vec<T>) -> Self {
+    BufferUpload {
+      values,
+      offset,
+      items_per_chunk: items_per_chunk::<T>(DEFAULT_CHUNK_BYTES),
+      next: 0,
+    }
+  }
+
+  /// Set the approximate size of the chunks, in bytes. A chunk is at least one item.
+  pub fn set_chunk_bytes(self, bytes: usize) -> Self {
+    BufferUpload {
+      items_per_chunk: items_per_chunk::<T>(bytes),
+      ..self
+    }
+  }
+
+  /// Check whether all the values were uploaded.
+  pub fn is_done(&self) -> bool {
+    self.next >= self.values.len()
+  }
+
+  /// Ratio of uploaded values, between `0` and `1`.
+  pub fn progress(&self) -> f32 {
+    if self.values.is_empty() {
+      1.
+    } else {
+      self.next as f32 / self.values.len() as f32
+    }
+  }
+
+  /// Upload chunks to `buffer` until `budget` is exhausted or everything is uploaded.
+  ///
+  /// At least one chunk is uploaded per call, so that uploads progress even when the budget is
+  /// already exhausted. Returns whether the upload is done.
+  pub fn step(&mut self, buffer: &mut Buffer<T>, budget: &StreamingBudget) -> Result<bool, BufferError> {
+    while !self.is_done() {
+      let end = (self.next + self.items_per_chunk).min(self.values.len());
+
+      buffer.write_part(self.offset + self.next, &self.values[self.next .. end])?;
+      self.next = end;
+
+      if budget.is_exhausted() {
+        break;
+      }
+    }
+
+    Ok(self.is_done())
+  }
+}
+
+// Number of rows of a given width fitting in a chunk; at least one.
+fn rows_per_chunk<P>(width: u32, bytes: usize) -> u32 where P: Pixel {
+  let row_bytes = width as usize * P::pixel_format().format.size();
+  (bytes / row_bytes.max(1)).max(1) as u32
+}
+
+// Number of items fitting in a chunk; at least one.
+fn items_per_chunk<T>(bytes: usize) -> usize {
+  (bytes / std::mem::size_of::<T>().max(1)).max(1)
+}
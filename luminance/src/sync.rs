@@ -0,0 +1,198 @@
+//! GPU / CPU synchronization.
+//!
+//! The GPU runs asynchronously with the CPU: when you issue commands, they are queued by the
+//! driver and executed later. When the GPU is the bottleneck, the driver can queue several frames
+//! ahead, which increases the latency between an input and the frame showing its effect. This
+//! module provides [`FrameLatencyLimiter`] to bound the number of frames in flight.
+//!
+//! It also provides explicit synchronization points:
+//!
+//!   - [`GraphicsContext::flush`] sends the queued commands to the GPU without waiting for them.
+//!     Use it before waiting on a [`Fence`] from another context, or when the CPU is about to stay
+//!     idle for a while.
+//!   - [`GraphicsContext::finish`] blocks until the GPU is done executing all the queued
+//!     commands; mostly useful for benchmarks and debugging.
+//!   - [`Fence`] signals when the commands issued before it are done, which allows to poll for
+//!     the completion of some work – typically before reading back a buffer – without blocking.
+//!   - [`GraphicsContext::memory_barrier`] orders incoherent memory accesses – image stores,
+//!     shader storage writes – with later commands reading the written data.
+//!
+//! [`GraphicsContext::flush`]: crate::context::GraphicsContext::flush
+//! [`GraphicsContext::finish`]: crate::context::GraphicsContext::finish
+//! [`GraphicsContext::memory_barrier`]: crate::context::GraphicsContext::memory_barrier
+
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::ops::BitOr;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use core::ops::BitOr;
+
+use crate::context::GraphicsContext;
+use crate::metagl::*;
+
+/// A GPU fence.
+///
+/// A fence is inserted in the command stream and becomes signaled once the GPU has executed all
+/// the commands issued before it.
+///
+/// > Note: the OpenGL context the fence was inserted in must be current whenever you use or drop
+/// > the fence.
+pub struct Fence {
+  sync: GLsync,
+}
+
+impl Fence {
+  /// Insert a fence in the command stream of a context.
+  pub fn insert<C>(ctx: &mut C) -> Self where C: ?Sized + GraphicsContext {
+    // the state is borrowed to ensure we’re inserting the fence in the right context
+    let _state = ctx.state().borrow();
+    unsafe { Self::new() }
+  }
+
+  unsafe fn new() -> Self {
+    let sync = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+    Fence { sync }
+  }
+
+  /// Check whether the fence is signaled, without blocking.
+  pub fn is_signaled(&self) -> bool {
+    if self.sync.is_null() {
+      return true;
+    }
+
+    let status = unsafe { gl::ClientWaitSync(self.sync, gl::SYNC_FLUSH_COMMANDS_BIT, 0) };
+    status != gl::TIMEOUT_EXPIRED
+  }
+
+  /// Block until the fence gets signaled.
+  pub fn wait(&self) {
+    if self.sync.is_null() {
+      return;
+    }
+
+    // any other status – signaled, satisfied or failed – means there’s nothing to wait for anymore
+    unsafe {
+      while gl::ClientWaitSync(self.sync, gl::SYNC_FLUSH_COMMANDS_BIT, gl::TIMEOUT_IGNORED) == gl::TIMEOUT_EXPIRED {}
+    }
+  }
+}
+
+impl Drop for Fence {
+  fn drop(&mut self) {
+    if !self.sync.is_null() {
+      unsafe { gl::DeleteSync(self.sync) };
+    }
+  }
+}
+
+/// Frame latency limiter.
+///
+/// This object bounds the number of frames the GPU can lag behind the CPU. Every time a frame is
+/// ended, a fence is inserted in the command stream; if more than `max_frames_in_flight` frames
+/// are still pending, the CPU blocks until the oldest one is done.
+///
+/// Surfaces typically handle that for you when swapping their buffers; you should only need this
+/// type if you’re writing a windowing backend.
+///
+/// > Note: the OpenGL context the limiter is used with must be current whenever you call
+/// > [`FrameLatencyLimiter::end_frame`] or drop the limiter.
+pub struct FrameLatencyLimiter {
+  max_frames_in_flight: usize,
+  fences: VecDeque<Fence>,
+}
+
+impl FrameLatencyLimiter {
+  /// Create a limiter that allows at most `max_frames_in_flight` queued frames.
+  ///
+  /// A value of `0` is treated as `1`, which means the CPU waits for the GPU every frame.
+  pub fn new(max_frames_in_flight: usize) -> Self {
+    let max_frames_in_flight = max_frames_in_flight.max(1);
+
+    FrameLatencyLimiter {
+      max_frames_in_flight,
+      fences: VecDeque::with_capacity(max_frames_in_flight + 1),
+    }
+  }
+
+  /// Maximum number of frames in flight.
+  pub fn max_frames_in_flight(&self) -> usize {
+    self.max_frames_in_flight
+  }
+
+  /// Number of frames currently known to be in flight.
+  pub fn frames_in_flight(&self) -> usize {
+    self.fences.len()
+  }
+
+  /// Mark the end of a frame.
+  ///
+  /// This function must be called once per frame, typically right after swapping buffers. It
+  /// blocks if there are too many frames in flight.
+  pub fn end_frame(&mut self) {
+    unsafe {
+      self.fences.push_back(Fence::new());
+
+      while self.fences.len() > self.max_frames_in_flight {
+        if let Some(fence) = self.fences.pop_front() {
+          fence.wait();
+        }
+      }
+    }
+  }
+}
+
+/// Memory barriers, to order incoherent memory accesses with later commands.
+///
+/// Barriers are combined with `|`. Each one makes the writes done by previous commands visible
+/// to a given kind of later reads; for instance, [`MemoryBarrier::TEXTURE_FETCH`] makes image
+/// stores visible to texture fetches.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MemoryBarrier(GLbitfield);
+
+impl MemoryBarrier {
+  /// Vertex attributes sourced from buffers.
+  pub const VERTEX_ATTRIB_ARRAY: Self = MemoryBarrier(0x0000_0001);
+  /// Indices sourced from buffers.
+  pub const ELEMENT_ARRAY: Self = MemoryBarrier(0x0000_0002);
+  /// Uniform buffers.
+  pub const UNIFORM: Self = MemoryBarrier(0x0000_0004);
+  /// Texture fetches.
+  pub const TEXTURE_FETCH: Self = MemoryBarrier(0x0000_0008);
+  /// Image loads and stores.
+  pub const SHADER_IMAGE_ACCESS: Self = MemoryBarrier(0x0000_0020);
+  /// Indirect draw commands sourced from buffers.
+  pub const COMMAND: Self = MemoryBarrier(0x0000_0040);
+  /// Pixel transfers to and from buffers (e.g. readbacks into pixel buffers).
+  pub const PIXEL_BUFFER: Self = MemoryBarrier(0x0000_0080);
+  /// Texture uploads and readbacks.
+  pub const TEXTURE_UPDATE: Self = MemoryBarrier(0x0000_0100);
+  /// Buffer updates, copies and mappings.
+  pub const BUFFER_UPDATE: Self = MemoryBarrier(0x0000_0200);
+  /// Framebuffer reads and writes.
+  pub const FRAMEBUFFER: Self = MemoryBarrier(0x0000_0400);
+  /// Transform feedback writes.
+  pub const TRANSFORM_FEEDBACK: Self = MemoryBarrier(0x0000_0800);
+  /// Atomic counters.
+  pub const ATOMIC_COUNTER: Self = MemoryBarrier(0x0000_1000);
+  /// Shader storage buffers.
+  pub const SHADER_STORAGE: Self = MemoryBarrier(0x0000_2000);
+  /// All of the above.
+  pub const ALL: Self = MemoryBarrier(0xFFFF_FFFF);
+
+  /// Raw bits of the barriers, as passed to `glMemoryBarrier`.
+  pub fn bits(self) -> u32 {
+    self.0
+  }
+}
+
+impl BitOr for MemoryBarrier {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    MemoryBarrier(self.0 | rhs.0)
+  }
+}
@@ -54,6 +54,8 @@
 #[cfg(feature = "std")]
 use std::fmt;
 #[cfg(feature = "std")]
+use std::mem;
+#[cfg(feature = "std")]
 use std::ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
 #[cfg(feature = "std")]
 use std::os::raw::c_void;
@@ -68,12 +70,17 @@ use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use core::fmt;
 #[cfg(not(feature = "std"))]
+use core::mem;
+#[cfg(not(feature = "std"))]
 use core::ops::{Range, RangeFrom, RangeFull, RangeTo};
 #[cfg(not(feature = "std"))]
 use core::ptr;
 
 use crate::buffer::{Buffer, BufferError, BufferSlice, BufferSliceMut, RawBuffer};
+use crate::capabilities::Feature;
 use crate::context::GraphicsContext;
+use crate::debug;
+use crate::indirect::{DrawCommand, DrawIndirectBuffer, IndirectDrawError};
 use crate::metagl::*;
 use crate::state::{Bind, GraphicsState};
 use crate::vertex::{
@@ -164,6 +171,8 @@ pub enum TessMapError {
   /// The CPU mapping failed because currently, mapping deinterleaved buffers is not supported via
   /// a single slice.
   ForbiddenDeinterleavedMapping,
+  /// No vertex buffer of the tessellation holds the requested vertex attributes.
+  AttributeNotFound(VertexDesc),
 }
 
 impl fmt::Display for TessMapError {
@@ -179,11 +188,24 @@ impl fmt::Display for TessMapError {
        write!(f, "cannot map tessellation: index type mismatch between {:?} and {:?}", a, b),
       TessMapError::ForbiddenAttributelessMapping => f.write_str("cannot map an attributeless buffer"),
       TessMapError::ForbiddenDeinterleavedMapping =>
-        f.write_str("cannot map a deinterleaved buffer as interleaved")
+        f.write_str("cannot map a deinterleaved buffer as interleaved"),
+      TessMapError::AttributeNotFound(ref a) =>
+        write!(f, "cannot map tessellation: no vertex buffer holds {:?}", a),
     }
   }
 }
 
+/// How the vertices of a [`Tess`] are stored.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VertexStorage {
+  /// No vertex buffer; vertices are created in the vertex shader.
+  Attributeless,
+  /// A single vertex buffer holding all the vertex attributes, vertex by vertex.
+  Interleaved,
+  /// A vertex buffer per vertex attribute (or group of attributes).
+  Deinterleaved,
+}
+
 struct VertexBuffer {
   /// Indexed format of the buffer.
   fmt: VertexDesc,
@@ -222,6 +244,11 @@ struct VertexBuffer {
 /// deinterleaved vertex attributes. A coherency check is done by the [`TessBuilder`] to ensure
 /// the vertex data is correct.
 ///
+/// Deinterleaved memory is interesting when some attributes are used without the others – e.g. a
+/// depth pre-pass only needs positions – or updated more often than the others – e.g. morph
+/// targets. Each attribute buffer can be mapped on its own with [`Tess::as_attribute_slice`] and
+/// [`Tess::as_attribute_slice_mut`].
+///
 /// # Specifying indices
 ///
 /// By default, vertices are picked in the order you specify them in the vertex buffer(s). If you
@@ -316,6 +343,10 @@ impl<'a, C> TessBuilder<'a, C> where C: GraphicsContext {
   }
 
   /// Set vertex indices in order to specify how vertices should be picked by the GPU pipeline.
+  ///
+  /// Indices can be [`u8`], [`u16`] or [`u32`] – see [`TessIndex`]; their type is recorded and
+  /// used when rendering. [`TessIndexType::for_vertex_nb`] gives the smallest type able to index
+  /// a given number of vertices.
   pub fn set_indices<T, I>(mut self, indices: T) -> Self where T: AsRef<[I]>, I: TessIndex  {
     let indices = indices.as_ref();
 
@@ -355,6 +386,11 @@ impl<'a, C> TessBuilder<'a, C> where C: GraphicsContext {
   }
 
   /// Set the primitive restart index. The initial value is `None`, implying no primitive restart.
+  ///
+  /// Primitive restart allows to render several strips, fans or loops – typically one per
+  /// sub-mesh – with a single indexed draw call. [`TessIndexType::max_index`] is the usual choice
+  /// of index. The index is part of the graphics state, so it’s only changed when rendering a
+  /// [`Tess`] using a different one.
   pub fn set_primitive_restart_index(mut self, index: Option<u32>) -> Self {
     self.restart_index = index;
     self
@@ -381,6 +417,7 @@ impl<'a, C> TessBuilder<'a, C> where C: GraphicsContext {
       };
 
       gl::GenVertexArrays(1, &mut vao);
+      log_event!(created(Tess, vao));
 
       // force binding the vertex array so that previously bound vertex arrays (possibly the same
       // handle) don’t prevent us from binding here
@@ -557,6 +594,33 @@ pub enum TessIndexType {
 }
 
 impl TessIndexType {
+  /// Smallest index type able to index `vert_nb` vertices.
+  ///
+  /// [`TessIndexType::max_index`] is left unused by the vertices, so that it can serve as
+  /// primitive restart index. Small meshes can then use 8-bit or 16-bit indices and save memory,
+  /// while meshes with more than 65535 vertices need 32-bit indices.
+  pub fn for_vertex_nb(vert_nb: usize) -> Self {
+    if vert_nb <= u8::MAX as usize {
+      TessIndexType::U8
+    } else if vert_nb <= u16::MAX as usize {
+      TessIndexType::U16
+    } else {
+      TessIndexType::U32
+    }
+  }
+
+  /// Greatest index representable with this type.
+  ///
+  /// That value is a good choice of _primitive restart index_, as it’s very unlikely to be used to
+  /// index an actual vertex.
+  pub fn max_index(self) -> u32 {
+    match self {
+      TessIndexType::U8 => u8::MAX as u32,
+      TessIndexType::U16 => u16::MAX as u32,
+      TessIndexType::U32 => u32::MAX,
+    }
+  }
+
   fn to_glenum(self) -> GLenum {
     match self {
       TessIndexType::U8 => gl::UNSIGNED_BYTE,
@@ -640,51 +704,284 @@ pub struct Tess {
 }
 
 impl Tess {
-  fn render<C>(&self, ctx: &mut C, start_index: usize, vert_nb: usize, inst_nb: usize)
+  fn render<C>(
+    &self,
+    ctx: &mut C,
+    start_index: usize,
+    vert_nb: usize,
+    inst_nb: usize,
+    base_vertex: i32,
+    base_instance: u32,
+  )
   where C: ?Sized + GraphicsContext {
+    log_event!(draw(self.vao, start_index, vert_nb, inst_nb));
+
     let vert_nb = vert_nb as GLsizei;
     let inst_nb = inst_nb as GLsizei;
 
     unsafe {
       let mut gfx_st = ctx.state().borrow_mut();
-      gfx_st.bind_vertex_array(self.vao, Bind::Cached);
-
-      if self.mode == gl::PATCHES {
-          gfx_st.set_patch_vertex_nb(self.patch_vert_nb);
-      }
+      self.prepare(&mut gfx_st);
+      gfx_st.count_draw_calls(1);
 
       if let Some(index_state) = self.index_state.as_ref() {
         // indexed render
         let first = (index_state.index_type.bytes() * start_index) as *const c_void;
 
-        if let Some(restart_index) = index_state.restart_index {
-          gfx_st.set_vertex_restart(VertexRestart::On);
-          gl::PrimitiveRestartIndex(restart_index);
-        } else {
-          gfx_st.set_vertex_restart(VertexRestart::Off);
-        }
+        let index_type = index_state.index_type.to_glenum();
 
-        if inst_nb <= 1 {
-          gl::DrawElements(self.mode, vert_nb, index_state.index_type.to_glenum(), first);
-        } else {
-          gl::DrawElementsInstanced(
+        if base_instance != 0 && gl_post33!(DrawElementsInstancedBaseVertexBaseInstance) {
+          gl::DrawElementsInstancedBaseVertexBaseInstance(
             self.mode,
             vert_nb,
-            index_state.index_type.to_glenum(),
+            index_type,
             first,
-            inst_nb,
+            inst_nb.max(1),
+            base_vertex,
+            base_instance,
           );
+        } else if base_vertex != 0 {
+          if inst_nb <= 1 {
+            gl::DrawElementsBaseVertex(self.mode, vert_nb, index_type, first, base_vertex);
+          } else {
+            gl::DrawElementsInstancedBaseVertex(
+              self.mode,
+              vert_nb,
+              index_type,
+              first,
+              inst_nb,
+              base_vertex,
+            );
+          }
+        } else if inst_nb <= 1 {
+          gl::DrawElements(self.mode, vert_nb, index_type, first);
+        } else {
+          gl::DrawElementsInstanced(self.mode, vert_nb, index_type, first, inst_nb);
         }
       } else {
         // direct render
-        let first = start_index as GLint;
+        let first = start_index as GLint + base_vertex;
 
-        if inst_nb <= 1 {
+        if base_instance != 0 && gl_post33!(DrawArraysInstancedBaseInstance) {
+          gl::DrawArraysInstancedBaseInstance(self.mode, first, vert_nb, inst_nb.max(1), base_instance);
+        } else if inst_nb <= 1 {
           gl::DrawArrays(self.mode, first, vert_nb);
         } else {
           gl::DrawArraysInstanced(self.mode, first, vert_nb, inst_nb);
         }
       }
+
+      gl_check!("Draw", self.vao);
+    }
+  }
+
+  pub(crate) fn render_indirect<C, T>(
+    &self,
+    ctx: &mut C,
+    commands: &DrawIndirectBuffer<T>,
+    count: usize,
+  ) -> Result<(), IndirectDrawError>
+  where C: ?Sized + GraphicsContext,
+        T: DrawCommand {
+    if count > commands.len() {
+      return Err(IndirectDrawError::Overflow(count, commands.len()));
+    }
+
+    if T::INDEXED != self.index_state.is_some() {
+      return Err(IndirectDrawError::CommandMismatch);
+    }
+
+    // loaded functions aren’t enough: some drivers expose them without supporting them
+    let supported = ctx.state().borrow().supports(Feature::IndirectDraw);
+
+    if !supported || !gl_post33!(DrawArraysIndirect) || !gl_post33!(DrawElementsIndirect) {
+      return Err(IndirectDrawError::Unsupported);
+    }
+
+    let stride = mem::size_of::<T>();
+
+    unsafe {
+      let mut gfx_st = ctx.state().borrow_mut();
+      self.prepare(&mut gfx_st);
+      gfx_st.bind_draw_indirect_buffer(commands.buffer().handle(), Bind::Cached);
+      gfx_st.count_draw_calls(count as u64);
+
+      let multi_draw = gfx_st.supports(Feature::MultiDrawIndirect)
+        && if T::INDEXED {
+          gl_post33!(MultiDrawElementsIndirect)
+        } else {
+          gl_post33!(MultiDrawArraysIndirect)
+        };
+
+      match self.index_state {
+        Some(ref index_state) if multi_draw => {
+          gl::MultiDrawElementsIndirect(
+            self.mode,
+            index_state.index_type.to_glenum(),
+            ptr::null(),
+            count as GLsizei,
+            stride as GLsizei,
+          );
+        }
+
+        Some(ref index_state) => {
+          for i in 0 .. count {
+            let offset = (i * stride) as *const c_void;
+            gl::DrawElementsIndirect(self.mode, index_state.index_type.to_glenum(), offset);
+          }
+        }
+
+        None if multi_draw => {
+          gl::MultiDrawArraysIndirect(self.mode, ptr::null(), count as GLsizei, stride as GLsizei);
+        }
+
+        None => {
+          for i in 0 .. count {
+            gl::DrawArraysIndirect(self.mode, (i * stride) as *const c_void);
+          }
+        }
+      }
+
+      gl_check!("DrawIndirect", self.vao);
+    }
+
+    Ok(())
+  }
+
+  // Bind the vertex array and set the parts of the graphics state the tessellation depends on.
+  unsafe fn prepare(&self, gfx_st: &mut GraphicsState) {
+    gfx_st.bind_vertex_array(self.vao, Bind::Cached);
+
+    if self.mode == gl::PATCHES {
+        gfx_st.set_patch_vertex_nb(self.patch_vert_nb);
+    }
+
+    if let Some(index_state) = self.index_state.as_ref() {
+      if let Some(restart_index) = index_state.restart_index {
+        gfx_st.set_vertex_restart(VertexRestart::On);
+        gfx_st.set_primitive_restart_index(restart_index);
+      } else {
+        gfx_st.set_vertex_restart(VertexRestart::Off);
+      }
+    }
+  }
+
+  /// Create an attributeless triangle covering the whole viewport.
+  ///
+  /// The triangle has three vertices and no vertex attribute: its vertex shader computes their
+  /// positions from `gl_VertexID`, as [`FULLSCREEN_VS`] does. A single triangle spanning past the
+  /// viewport doesn’t have the diagonal of a quad, along which fragments get shaded twice.
+  ///
+  /// [`FULLSCREEN_VS`]: crate::post_process::FULLSCREEN_VS
+  pub fn fullscreen_triangle<C>(ctx: &mut C) -> Result<Self, TessError> where C: GraphicsContext {
+    TessBuilder::new(ctx).set_vertex_nb(3).set_mode(Mode::Triangle).build()
+  }
+
+  /// Label the vertex array of the tessellation, so that graphics debuggers show it by that name.
+  ///
+  /// The vertex, instance and index buffers are not labelled.
+  pub fn set_label(&self, label: &str) {
+    debug::label(gl::VERTEX_ARRAY, self.vao, label);
+  }
+
+  // Number of vertices – or indices – of the tessellation.
+  pub(crate) fn vert_nb(&self) -> usize {
+    self.vert_nb
+  }
+
+  /// Type of the indices of the tessellation, if it has any.
+  pub fn index_type(&self) -> Option<TessIndexType> {
+    self.index_state.as_ref().map(|index_state| index_state.index_type)
+  }
+
+  /// How the vertices of the tessellation are stored.
+  pub fn vertex_storage(&self) -> VertexStorage {
+    match self.vertex_buffers.len() {
+      0 => VertexStorage::Attributeless,
+      1 => VertexStorage::Interleaved,
+      _ => VertexStorage::Deinterleaved,
+    }
+  }
+
+  /// Obtain a slice over the vertex buffer holding the `V` attributes.
+  ///
+  /// This works with both interleaved and deinterleaved memory: `V` must be the type of one of the
+  /// slices passed to [`TessBuilder::add_vertices`].
+  pub fn as_attribute_slice<V>(&mut self) -> Result<BufferSlice<'_, V>, TessMapError> where V: Vertex {
+    self
+      .attribute_buffer::<V>()?
+      .as_slice()
+      .map_err(TessMapError::VertexBufferMapFailed)
+  }
+
+  /// Obtain a mutable slice over the vertex buffer holding the `V` attributes.
+  ///
+  /// This works with both interleaved and deinterleaved memory: `V` must be the type of one of the
+  /// slices passed to [`TessBuilder::add_vertices`].
+  pub fn as_attribute_slice_mut<V>(&mut self) -> Result<BufferSliceMut<'_, V>, TessMapError>
+  where V: Vertex {
+    self
+      .attribute_buffer::<V>()?
+      .as_slice_mut()
+      .map_err(TessMapError::VertexBufferMapFailed)
+  }
+
+  // Find the vertex buffer holding the `V` attributes.
+  fn attribute_buffer<V>(&mut self) -> Result<&mut RawBuffer, TessMapError> where V: Vertex {
+    if self.vertex_buffers.is_empty() {
+      return Err(TessMapError::ForbiddenAttributelessMapping);
+    }
+
+    let target_fmt = V::vertex_desc();
+
+    match self.vertex_buffers.iter_mut().find(|vb| vb.fmt == target_fmt) {
+      Some(vb) => Ok(&mut vb.buf),
+      None => Err(TessMapError::AttributeNotFound(target_fmt)),
+    }
+  }
+
+  /// Replace the `V` vertices of the tessellation.
+  ///
+  /// `V` must be the type of one of the slices passed to [`TessBuilder::add_vertices`]. The number
+  /// of vertices can change: the storage is then reallocated. Without indices, the number of
+  /// vertices to render is updated to the length of the shortest vertex buffer.
+  ///
+  /// This is meant for dynamic geometry – UI, text, particles, etc. – which can be updated every
+  /// frame without building a new [`Tess`]. To modify vertices in place, use
+  /// [`Tess::as_slice_mut`] or [`Tess::as_attribute_slice_mut`] instead.
+  pub fn set_vertices<V, W>(&mut self, vertices: W) -> Result<(), TessMapError>
+  where W: AsRef<[V]>,
+        V: Vertex {
+    self.attribute_buffer::<V>()?.reallocate(vertices.as_ref());
+
+    if self.index_state.is_none() {
+      self.vert_nb = self.vertex_buffers.iter().map(|vb| vb.buf.len()).min().unwrap_or(0);
+    }
+
+    Ok(())
+  }
+
+  /// Replace the indices of the tessellation.
+  ///
+  /// The number of indices and their type can change; the number of vertices to render is updated
+  /// to the number of indices. The primitive restart index is kept as is.
+  ///
+  /// This function fails if the [`Tess`] was built without indices.
+  pub fn set_indices<T, I>(&mut self, indices: T) -> Result<(), TessMapError>
+  where T: AsRef<[I]>,
+        I: TessIndex {
+    match self.index_state {
+      Some(IndexedDrawState { ref mut _buffer, ref mut index_type, .. }) => {
+        let indices = indices.as_ref();
+
+        _buffer.reallocate(indices);
+        *index_type = I::INDEX_TYPE;
+        self.vert_nb = indices.len();
+
+        Ok(())
+      }
+
+      None => Err(TessMapError::ForbiddenAttributelessMapping)
     }
   }
 
@@ -830,6 +1127,8 @@ impl Drop for Tess {
       self.state.borrow_mut().unbind_vertex_array();
       gl::DeleteVertexArrays(1, &self.vao);
     }
+
+    log_event!(destroyed(Tess, self.vao));
   }
 }
 
@@ -998,6 +1297,10 @@ pub struct TessSlice<'a> {
   vert_nb: usize,
   /// Number of instances to render.
   inst_nb: usize,
+  /// Value added to vertex indices.
+  base_vertex: i32,
+  /// Index of the first instance to render.
+  base_instance: u32,
 }
 
 impl<'a> TessSlice<'a> {
@@ -1009,6 +1312,8 @@ impl<'a> TessSlice<'a> {
       start_index: 0,
       vert_nb: tess.vert_nb,
       inst_nb: tess.inst_nb,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
@@ -1020,6 +1325,8 @@ impl<'a> TessSlice<'a> {
       start_index: 0,
       vert_nb: tess.vert_nb,
       inst_nb,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
@@ -1047,6 +1354,8 @@ impl<'a> TessSlice<'a> {
       start_index: 0,
       vert_nb,
       inst_nb: 1,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
@@ -1074,6 +1383,8 @@ impl<'a> TessSlice<'a> {
       start_index: 0,
       vert_nb,
       inst_nb,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
@@ -1107,6 +1418,8 @@ impl<'a> TessSlice<'a> {
       start_index: start,
       vert_nb: nb,
       inst_nb: 1,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
@@ -1140,14 +1453,40 @@ impl<'a> TessSlice<'a> {
       start_index: start,
       vert_nb: nb,
       inst_nb,
+      base_vertex: 0,
+      base_instance: 0,
     }
   }
 
+  /// Set the value added to the vertex indices before fetching vertices.
+  ///
+  /// This allows to store several meshes in the same vertex buffer – and their indices in the same
+  /// index buffer – and to render them with slices, each mesh keeping indices relative to its first
+  /// vertex. For non-indexed tessellations, the first rendered vertex is offset instead.
+  pub fn set_base_vertex(self, base_vertex: i32) -> Self {
+    TessSlice { base_vertex, ..self }
+  }
+
+  /// Set the index of the first instance to render, offsetting the instance attributes.
+  ///
+  /// `gl_InstanceID` is not affected. A non-zero base instance requires *OpenGL 4.2* or
+  /// `GL_ARB_base_instance` (see [`Extension::BaseInstance`]); it’s ignored otherwise.
+  ///
+  /// [`Extension::BaseInstance`]: crate::capabilities::Extension::BaseInstance
+  pub fn set_base_instance(self, base_instance: u32) -> Self {
+    TessSlice { base_instance, ..self }
+  }
+
   /// Render a tessellation.
   pub fn render<C>(&self, ctx: &mut C) where C: ?Sized + GraphicsContext {
-    self
-      .tess
-      .render(ctx, self.start_index, self.vert_nb, self.inst_nb);
+    self.tess.render(
+      ctx,
+      self.start_index,
+      self.vert_nb,
+      self.inst_nb,
+      self.base_vertex,
+      self.base_instance,
+    );
   }
 }
 
@@ -15,15 +15,25 @@
 //! Those combinations are encoded by several types. First of all, `Texture<L, D, P>` is the
 //! polymorphic type used to represent textures. The `L` type variable is the *layering type* of
 //! the texture. It can either be `Flat` or `Layered`. The `D` type variable is the dimension of the
-//! texture. It can either be `Dim1`, `Dim2`, `Dim3` or `Cubemap`. Finally, the `P` type variable
-//! is the pixel format the texture follows. See the `pixel` module for further details about pixel
-//! formats.
+//! texture. It can either be `Dim1`, `Dim2`, `Dim3`, `Cubemap` or `Dim2Array`. Finally, the `P`
+//! type variable is the pixel format the texture follows. See the `pixel` module for further
+//! details about pixel formats.
 //!
 //! Additionally, all textures have between 0 or several *mipmaps*. Mipmaps are additional layers of
 //! texels used to perform trilinear filtering in most applications. Those are low-definition images
 //! of the the base image used to smoothly interpolate texels when a projection kicks in. See
 //! [this](https://en.wikipedia.org/wiki/Mipmap) for more insight.
 //!
+//! ## Arrays of 2D textures
+//!
+//! Arrays of 2D textures – e.g. to hold the cascades of a shadow map or to render to many layers
+//! of a framebuffer at once – must be typed `Texture<Flat, Dim2Array, _>`. Their size is
+//! `([width, height], layers)` and they are sampled with `sampler2DArray`.
+//!
+//! `Texture<Layered, Dim2, _>` has the same OpenGL target, but the storage of `Layered` textures
+//! is not implemented yet: creating one fails with a [`TextureError`]. `Layered` textures of
+//! `Dim3` and `Dim2Array` have no OpenGL target at all and fail the same way.
+//!
 //! # Creating textures
 //!
 //! Textures are created by providing a size, the number of mipmaps that should be used and a
@@ -108,11 +118,13 @@ use core::ops::{Deref, DerefMut};
 #[cfg(not(feature = "std"))]
 use core::ptr;
 
+use crate::capabilities::{current_profile, Feature, Profile};
 use crate::context::GraphicsContext;
+use crate::debug;
 pub use crate::depth_test::DepthComparison;
 use crate::metagl::*;
-use crate::pixel::{opengl_pixel_format, Pixel, PixelFormat};
-use crate::state::GraphicsState;
+use crate::pixel::{opengl_pixel_format, Format, Pixel, PixelFormat, Size};
+use crate::state::{GraphicsState, PoisonCause};
 
 /// How to wrap texture coordinates while sampling textures?
 #[derive(Clone, Copy, Debug)]
@@ -129,6 +141,17 @@ pub enum Wrap {
   Repeat,
   /// Same as `Repeat` but it will alternatively repeat between *[0;1]* and *[1;0]*.
   MirroredRepeat,
+  /// Textures coordinates outside of *[0;1]* sample the border color of the sampler.
+  ///
+  /// Shadow maps typically use it with a white border, so that everything outside of them is
+  /// lit. OpenGL ES 3.0 doesn’t support it: [`Wrap::ClampToEdge`] is used instead.
+  ClampToBorder,
+  /// Textures coordinates are mirrored once, *[-1;0]* mapping to *[1;0]*, then clamped to the
+  /// edges.
+  ///
+  /// Without [`Feature::MirrorClampToEdge`], [`Wrap::MirroredRepeat`] is used instead, which
+  /// only differs outside of *[-1;1]*.
+  MirrorClampToEdge,
 }
 
 /// Minification filter.
@@ -206,6 +229,16 @@ pub trait Dimensionable {
   /// For 2D sizes, it represents the area; for 3D sizes, the volume; etc.
   /// For cubemaps, it represents the side length of the cube.
   fn count(size: Self::Size) -> usize;
+
+  /// Size of the mipmap at the given `level`, `0` being the base level.
+  ///
+  /// Every dimension is halved at each level, down to `1`.
+  fn mipmap_size(size: Self::Size, level: usize) -> Self::Size;
+}
+
+// Size of a single dimension at a given mipmap level.
+fn mipmap_dim(x: u32, level: usize) -> u32 {
+  x.checked_shr(level as u32).unwrap_or(0).max(1)
 }
 
 // Capacity of the dimension, which is the product of the width, height and depth.
@@ -224,6 +257,8 @@ pub enum Dim {
   Dim3,
   /// Cubemap (i.e. a cube defining 6 faces — akin to 4D).
   Cubemap,
+  /// Array of 2D layers.
+  Dim2Array,
 }
 
 /// 1D dimension.
@@ -251,6 +286,10 @@ impl Dimensionable for Dim1 {
   fn count(size: Self::Size) -> usize {
     size as usize
   }
+
+  fn mipmap_size(size: Self::Size, level: usize) -> Self::Size {
+    mipmap_dim(size, level)
+  }
 }
 
 /// 2D dimension.
@@ -286,6 +325,10 @@ impl Dimensionable for Dim2 {
   fn count([width, height]: Self::Size) -> usize {
     width as usize * height as usize
   }
+
+  fn mipmap_size([width, height]: Self::Size, level: usize) -> Self::Size {
+    [mipmap_dim(width, level), mipmap_dim(height, level)]
+  }
 }
 
 /// 3D dimension.
@@ -329,6 +372,10 @@ impl Dimensionable for Dim3 {
   fn count([width, height, depth]: Self::Size) -> usize {
     width as usize * height as usize * depth as usize
   }
+
+  fn mipmap_size([width, height, depth]: Self::Size, level: usize) -> Self::Size {
+    [mipmap_dim(width, level), mipmap_dim(height, level), mipmap_dim(depth, level)]
+  }
 }
 
 /// Cubemap dimension.
@@ -380,10 +427,14 @@ impl Dimensionable for Cubemap {
     let size = size as usize;
     size * size
   }
+
+  fn mipmap_size(size: Self::Size, level: usize) -> Self::Size {
+    mipmap_dim(size, level)
+  }
 }
 
 /// Faces of a cubemap.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum CubeFace {
   /// The +X face of the cube.
   PositiveX,
@@ -399,6 +450,68 @@ pub enum CubeFace {
   NegativeZ,
 }
 
+impl CubeFace {
+  /// All the faces, in the order of their OpenGL layers.
+  pub const ALL: [CubeFace; 6] = [
+    CubeFace::PositiveX,
+    CubeFace::NegativeX,
+    CubeFace::PositiveY,
+    CubeFace::NegativeY,
+    CubeFace::PositiveZ,
+    CubeFace::NegativeZ,
+  ];
+}
+
+/// 2D array dimension.
+///
+/// The size is the size of a layer along with the number of layers, and the offset the offset in
+/// a layer along with the first layer. Mipmaps don’t change the number of layers.
+#[derive(Clone, Copy, Debug)]
+pub struct Dim2Array;
+
+impl Dimensionable for Dim2Array {
+  type Offset = ([u32; 2], u32);
+  type Size = ([u32; 2], u32);
+
+  const ZERO_OFFSET: Self::Offset = ([0, 0], 0);
+
+  fn dim() -> Dim {
+    Dim::Dim2Array
+  }
+
+  fn width(size: Self::Size) -> u32 {
+    size.0[0]
+  }
+
+  fn height(size: Self::Size) -> u32 {
+    size.0[1]
+  }
+
+  fn depth(size: Self::Size) -> u32 {
+    size.1
+  }
+
+  fn x_offset(off: Self::Offset) -> u32 {
+    off.0[0]
+  }
+
+  fn y_offset(off: Self::Offset) -> u32 {
+    off.0[1]
+  }
+
+  fn z_offset(off: Self::Offset) -> u32 {
+    off.1
+  }
+
+  fn count(([width, height], layers): Self::Size) -> usize {
+    width as usize * height as usize * layers as usize
+  }
+
+  fn mipmap_size(([width, height], layers): Self::Size, level: usize) -> Self::Size {
+    ([mipmap_dim(width, level), mipmap_dim(height, level)], layers)
+  }
+}
+
 /// Trait used to reify a type into a `Layering`.
 pub trait Layerable {
   /// Reify to `Layering`.
@@ -469,12 +582,25 @@ impl RawTexture {
   pub(crate) fn target(&self) -> GLenum {
     self.target
   }
+
+  // Check whether the texture can be used: its context must not be lost.
+  pub(crate) fn check_usable(&self) -> Result<(), PoisonCause> {
+    self.state.borrow().check_usable()
+  }
+
+  /// Label the texture, so that graphics debuggers show it by that name.
+  pub fn set_label(&self, label: &str) {
+    debug::label(gl::TEXTURE, self.handle, label);
+  }
 }
 
 /// Texture.
 ///
 /// `L` refers to the layering type; `D` refers to the dimension; `P` is the pixel format for the
 /// texels.
+///
+/// Textures share the state of their context and are not [`Send`]: they statically stay on the
+/// thread of their context.
 pub struct Texture<L, D, P>
 where L: Layerable,
       D: Dimensionable,
@@ -512,6 +638,8 @@ where L: Layerable,
       P: Pixel {
   fn drop(&mut self) {
     unsafe { gl::DeleteTextures(1, &self.handle) }
+    self.state.borrow_mut().forget_texture(self.handle);
+    log_event!(destroyed(Texture, self.handle));
   }
 }
 
@@ -529,15 +657,20 @@ where L: Layerable,
   ///     shader stages. Refer to the documentation of [`Sampler`] for further details.
   pub fn new<C>(ctx: &mut C, size: D::Size, mipmaps: usize, sampler: Sampler) -> Result<Self, TextureError>
   where C: GraphicsContext {
+    check_target(L::layering(), D::dim())?;
+
     let mipmaps = mipmaps + 1; // + 1 prevent having 0 mipmaps
     let mut texture = 0;
     let target = opengl_target(L::layering(), D::dim());
 
     unsafe {
       gl::GenTextures(1, &mut texture);
+      log_event!(created(Texture, texture));
       ctx.state().borrow_mut().bind_texture(target, texture);
 
+      let sampler = supported_sampler(&ctx.state().borrow(), sampler);
       create_texture::<L, D>(target, size, mipmaps, P::pixel_format(), sampler)?;
+      gl_check!("TexImage", texture);
 
       let raw = RawTexture::new(ctx.state().clone(), texture, target);
 
@@ -582,6 +715,9 @@ where L: Layerable,
   /// The part being cleared is defined by a rectangle in which the `offset` represents the
   /// left-upper corner and the `size` gives the dimension of the rectangle. All the covered texels
   /// by this rectangle will be cleared to the `pixel` value.
+  ///
+  /// Texels are cleared on the GPU with `glClearTexSubImage` if available (OpenGL 4.4 or
+  /// `GL_ARB_clear_texture`). Otherwise, `pixel` is repeated over the rectangle and uploaded.
   pub fn clear_part(
     &self,
     gen_mipmaps: GenMipmaps,
@@ -590,12 +726,47 @@ where L: Layerable,
     pixel: P::Encoding
   ) -> Result<(), TextureError>
   where P::Encoding: Copy {
-    self.upload_part(
-      gen_mipmaps,
-      offset,
-      size,
-      &vec![pixel; dim_capacity::<D>(size) as usize],
-    )
+    let clear_texture = matches!(L::layering(), Layering::Flat)
+      && self.state.borrow().supports(Feature::ClearTexture)
+      && gl_post33!(ClearTexSubImage);
+
+    if !clear_texture {
+      return self.upload_part(
+        gen_mipmaps,
+        offset,
+        size,
+        &vec![pixel; dim_capacity::<D>(size) as usize],
+      );
+    }
+
+    self.check_usable()?;
+
+    let pf = P::pixel_format();
+    let (format, _, encoding) =
+      opengl_pixel_format(pf).ok_or(TextureError::UnsupportedPixelFormat(pf))?;
+
+    unsafe {
+      gl::ClearTexSubImage(
+        self.handle,
+        0,
+        D::x_offset(offset) as GLint,
+        D::y_offset(offset) as GLint,
+        D::z_offset(offset) as GLint,
+        D::width(size) as GLsizei,
+        D::height(size) as GLsizei,
+        copied_depth::<D>(size) as GLsizei,
+        format,
+        encoding,
+        &pixel as *const P::Encoding as *const c_void,
+      );
+      gl_check!("ClearTexSubImage", self.handle);
+    }
+
+    if gen_mipmaps == GenMipmaps::Yes {
+      self.generate_mipmaps();
+    }
+
+    Ok(())
   }
 
   /// Clear a whole texture with a `pixel` value.
@@ -604,6 +775,57 @@ where L: Layerable,
     self.clear_part(gen_mipmaps, D::ZERO_OFFSET, self.size, pixel)
   }
 
+  /// Copy a region of the base level of `src` to this texture, at `dst_offset`.
+  ///
+  /// The region starts at `src_offset` and has the size `size`; a single face is copied for
+  /// cubemaps. Texels are copied on the GPU, with `glCopyImageSubData` if available (OpenGL 4.3)
+  /// or with framebuffer blits otherwise.
+  pub fn copy_from(
+    &self,
+    src: &Texture<L, D, P>,
+    src_offset: D::Offset,
+    size: D::Size,
+    dst_offset: D::Offset,
+  ) -> Result<(), TextureError> {
+    let region = [D::width(size), D::height(size), copied_depth::<D>(size)];
+    let src_xyz = [D::x_offset(src_offset), D::y_offset(src_offset), D::z_offset(src_offset)];
+    let dst_xyz = [D::x_offset(dst_offset), D::y_offset(dst_offset), D::z_offset(dst_offset)];
+
+    if !region_fits::<D>(src.size, src_xyz, region) || !region_fits::<D>(self.size, dst_xyz, region) {
+      return Err(TextureError::InvalidCopyRegion);
+    }
+
+    src.check_usable()?;
+    self.check_usable()?;
+
+    unsafe {
+      if gl_post33!(CopyImageSubData) {
+        gl::CopyImageSubData(
+          src.handle,
+          src.target,
+          0,
+          src_xyz[0] as GLint,
+          src_xyz[1] as GLint,
+          src_xyz[2] as GLint,
+          self.handle,
+          self.target,
+          0,
+          dst_xyz[0] as GLint,
+          dst_xyz[1] as GLint,
+          dst_xyz[2] as GLint,
+          region[0] as GLsizei,
+          region[1] as GLsizei,
+          region[2] as GLsizei,
+        );
+        gl_check!("CopyImageSubData", self.handle);
+      } else {
+        blit_copy(&src.raw, &self.raw, P::pixel_format(), src_xyz, dst_xyz, region);
+      }
+    }
+
+    Ok(())
+  }
+
   /// Upload texels to a part of a texture.
   ///
   /// The part being updated is defined by a rectangle in which the `offset` represents the
@@ -616,21 +838,7 @@ where L: Layerable,
     size: D::Size,
     texels: &[P::Encoding],
   ) -> Result<(), TextureError> {
-    unsafe {
-      let mut gfx_state = self.state.borrow_mut();
-
-      gfx_state.bind_texture(self.target, self.handle);
-
-      upload_texels::<L, D, P, P::Encoding>(self.target, offset, size, texels)?;
-
-      if gen_mipmaps == GenMipmaps::Yes {
-        gl::GenerateMipmap(self.target);
-      }
-
-      gfx_state.bind_texture(self.target, 0);
-    }
-
-    Ok(())
+    self.upload_texels_level(gen_mipmaps, 0, offset, size, texels)
   }
 
   /// Upload `texels` to the whole texture.
@@ -654,34 +862,124 @@ where L: Layerable,
     size: D::Size,
     texels: &[P::RawEncoding],
   ) -> Result<(), TextureError> {
+    self.upload_texels_level(gen_mipmaps, 0, offset, size, texels)
+  }
+
+  /// Upload raw `texels` to the whole texture.
+  pub fn upload_raw(
+    &self,
+    gen_mipmaps: GenMipmaps,
+    texels: &[P::RawEncoding]
+  ) -> Result<(), TextureError> {
+    self.upload_part_raw(gen_mipmaps, D::ZERO_OFFSET, self.size, texels)
+  }
+
+  /// Upload texels to a part of a given mipmap level.
+  ///
+  /// `level` `0` is the base level; see [`Texture::mipmaps`] for the number of available levels.
+  /// `offset` and `size` are expressed in texels *of that level*; see
+  /// [`Dimensionable::mipmap_size`]. This is how hand-authored mipmap chains are provided.
+  pub fn upload_part_level(
+    &self,
+    level: usize,
+    offset: D::Offset,
+    size: D::Size,
+    texels: &[P::Encoding],
+  ) -> Result<(), TextureError> {
+    self.upload_texels_level(GenMipmaps::No, level, offset, size, texels)
+  }
+
+  /// Upload `texels` to a whole mipmap level.
+  pub fn upload_level(&self, level: usize, texels: &[P::Encoding]) -> Result<(), TextureError> {
+    self.upload_part_level(level, D::ZERO_OFFSET, D::mipmap_size(self.size, level), texels)
+  }
+
+  /// Upload raw `texels` to a part of a given mipmap level.
+  ///
+  /// This function is similar to `upload_part_level` but it works on `P::RawEncoding` instead of
+  /// `P::Encoding`.
+  pub fn upload_part_level_raw(
+    &self,
+    level: usize,
+    offset: D::Offset,
+    size: D::Size,
+    texels: &[P::RawEncoding],
+  ) -> Result<(), TextureError> {
+    self.upload_texels_level(GenMipmaps::No, level, offset, size, texels)
+  }
+
+  /// Upload raw `texels` to a whole mipmap level.
+  pub fn upload_level_raw(
+    &self,
+    level: usize,
+    texels: &[P::RawEncoding],
+  ) -> Result<(), TextureError> {
+    self.upload_part_level_raw(level, D::ZERO_OFFSET, D::mipmap_size(self.size, level), texels)
+  }
+
+  /// Change the sampler of the texture.
+  ///
+  /// This is typically needed for framebuffer color slots, which are created with the default
+  /// sampler: integer textures, for instance, must use nearest filters to be sampled.
+  pub fn set_sampler(&self, sampler: Sampler) {
     unsafe {
       let mut gfx_state = self.state.borrow_mut();
 
       gfx_state.bind_texture(self.target, self.handle);
+      let anisotropy = gfx_state.supports(Feature::AnisotropicFiltering);
+      let sampler = supported_sampler(&gfx_state, sampler);
+      apply_sampler_to_texture(self.target, sampler, anisotropy);
+      gfx_state.bind_texture(self.target, 0);
+    }
+  }
 
-      upload_texels::<L, D, P, P::RawEncoding>(self.target, offset, size, texels)?;
-
-      if gen_mipmaps == GenMipmaps::Yes {
-        gl::GenerateMipmap(self.target);
-      }
+  /// Generate all the mipmaps of the texture out of its base level.
+  pub fn generate_mipmaps(&self) {
+    unsafe {
+      let mut gfx_state = self.state.borrow_mut();
 
+      gfx_state.bind_texture(self.target, self.handle);
+      gl::GenerateMipmap(self.target);
       gfx_state.bind_texture(self.target, 0);
     }
-
-    Ok(())
   }
 
-  /// Upload raw `texels` to the whole texture.
-  pub fn upload_raw(
+  fn upload_texels_level<T>(
     &self,
     gen_mipmaps: GenMipmaps,
-    texels: &[P::RawEncoding]
+    level: usize,
+    offset: D::Offset,
+    size: D::Size,
+    texels: &[T],
   ) -> Result<(), TextureError> {
-    self.upload_part_raw(gen_mipmaps, D::ZERO_OFFSET, self.size, texels)
+    if level >= self.mipmaps {
+      return Err(TextureError::InvalidMipmapLevel(level, self.mipmaps));
+    }
+
+    self.check_usable()?;
+
+    unsafe {
+      let mut gfx_state = self.state.borrow_mut();
+
+      gfx_state.bind_texture(self.target, self.handle);
+
+      let uploaded = upload_texels::<L, D, P, T>(self.target, level, offset, size, texels);
+
+      if uploaded.is_ok() && gen_mipmaps == GenMipmaps::Yes {
+        gl::GenerateMipmap(self.target);
+      }
+
+      gfx_state.bind_texture(self.target, 0);
+
+      uploaded
+    }
   }
 
   // FIXME: cubemaps?
   /// Get the raw texels associated with this texture.
+  ///
+  /// On OpenGL ES, texels are read back through a framebuffer, which only works for the base
+  /// layer of color textures in formats the implementation can read pixels from.
   pub fn get_raw_texels(
     &self
   ) -> Vec<P::RawEncoding> where P: Pixel, P::RawEncoding: Copy + Default {
@@ -689,6 +987,18 @@ where L: Layerable,
     let pf = P::pixel_format();
     let (format, _, ty) = opengl_pixel_format(pf).unwrap();
 
+    if current_profile() == Profile::Es {
+      let w = D::width(self.size);
+      let h = D::height(self.size);
+      texels.resize_with((w * h) as usize * raw_texel_len(pf), Default::default);
+
+      unsafe {
+        read_texels_through_framebuffer(&self.raw, pf, format, ty, [w, h], &mut texels);
+      }
+
+      return texels;
+    }
+
     unsafe {
       let mut w = 0;
       let mut h = 0;
@@ -705,7 +1015,7 @@ where L: Layerable,
       set_pack_alignment(skip_bytes);
 
       // resize the vec to allocate enough space to host the returned texels
-      texels.resize_with((w * h) as usize * pf.canals_len(), Default::default);
+      texels.resize_with((w * h) as usize * raw_texel_len(pf), Default::default);
 
       gl::GetTexImage(self.target, 0, format, ty, texels.as_mut_ptr() as *mut c_void);
 
@@ -729,6 +1039,138 @@ where L: Layerable,
   }
 }
 
+impl<P> Texture<Flat, Dim3, P> where P: Pixel {
+  /// Upload `texels` to the slice `z` of the base level of a 3D texture.
+  ///
+  /// Volumes are usually uploaded slice by slice – one image per slice, for instance. Mipmaps are
+  /// not generated: call [`Texture::generate_mipmaps`] once all slices are uploaded.
+  pub fn upload_slice(&self, z: u32, texels: &[P::Encoding]) -> Result<(), TextureError> {
+    let [width, height, depth] = self.size;
+
+    if z >= depth {
+      return Err(TextureError::InvalidSlice(z, depth));
+    }
+
+    self.upload_part(GenMipmaps::No, [0, 0, z], [width, height, 1], texels)
+  }
+
+  /// Upload raw `texels` to the slice `z` of the base level of a 3D texture.
+  ///
+  /// See [`Texture::upload_slice`].
+  pub fn upload_slice_raw(&self, z: u32, texels: &[P::RawEncoding]) -> Result<(), TextureError> {
+    let [width, height, depth] = self.size;
+
+    if z >= depth {
+      return Err(TextureError::InvalidSlice(z, depth));
+    }
+
+    self.upload_part_raw(GenMipmaps::No, [0, 0, z], [width, height, 1], texels)
+  }
+}
+
+impl<P> Texture<Flat, Cubemap, P> where P: Pixel {
+  /// Create a cubemap out of its six faces, in the order of [`CubeFace::ALL`].
+  ///
+  /// Each face holds `size` × `size` texels, its top row first – as image decoders output them:
+  /// cubemaps, unlike 2D textures, don’t need to be flipped vertically. Seen from the inside of
+  /// the cube, the faces are respectively the right, left, top, bottom, front and back ones.
+  pub fn from_faces<C>(
+    ctx: &mut C,
+    size: u32,
+    mipmaps: usize,
+    sampler: Sampler,
+    gen_mipmaps: GenMipmaps,
+    faces: [&[P::Encoding]; 6],
+  ) -> Result<Self, TextureError>
+  where C: GraphicsContext {
+    let texture = Self::new(ctx, size, mipmaps, sampler)?;
+
+    for (&face, texels) in CubeFace::ALL.iter().zip(&faces) {
+      texture.upload_part(GenMipmaps::No, ([0, 0], face), size, texels)?;
+    }
+
+    if gen_mipmaps == GenMipmaps::Yes {
+      texture.generate_mipmaps();
+    }
+
+    Ok(texture)
+  }
+
+  /// Create a cubemap out of its six faces, as raw texels.
+  ///
+  /// See [`Texture::from_faces`].
+  pub fn from_faces_raw<C>(
+    ctx: &mut C,
+    size: u32,
+    mipmaps: usize,
+    sampler: Sampler,
+    gen_mipmaps: GenMipmaps,
+    faces: [&[P::RawEncoding]; 6],
+  ) -> Result<Self, TextureError>
+  where C: GraphicsContext {
+    let texture = Self::new(ctx, size, mipmaps, sampler)?;
+
+    for (&face, texels) in CubeFace::ALL.iter().zip(&faces) {
+      texture.upload_part_raw(GenMipmaps::No, ([0, 0], face), size, texels)?;
+    }
+
+    if gen_mipmaps == GenMipmaps::Yes {
+      texture.generate_mipmaps();
+    }
+
+    Ok(texture)
+  }
+
+  /// Copy the base level of a 2D texture to a face of the cubemap, on the GPU.
+  ///
+  /// `src` must have the size of the faces. This is how faces rendered to a framebuffer – e.g. by
+  /// [`EquirectToCubemap`] – end up in the cubemap.
+  ///
+  /// [`EquirectToCubemap`]: crate::cubemap::EquirectToCubemap
+  pub fn copy_face_from(
+    &self,
+    face: CubeFace,
+    src: &Texture<Flat, Dim2, P>,
+  ) -> Result<(), TextureError> {
+    if src.size != [self.size, self.size] {
+      return Err(TextureError::InvalidCopyRegion);
+    }
+
+    src.check_usable()?;
+    self.check_usable()?;
+
+    let layer = Cubemap::z_offset(([0, 0], face));
+    let region = [self.size, self.size, 1];
+
+    unsafe {
+      if gl_post33!(CopyImageSubData) {
+        gl::CopyImageSubData(
+          src.handle,
+          src.target,
+          0,
+          0,
+          0,
+          0,
+          self.handle,
+          self.target,
+          0,
+          0,
+          0,
+          layer as GLint,
+          region[0] as GLsizei,
+          region[1] as GLsizei,
+          1,
+        );
+        gl_check!("CopyImageSubData", self.handle);
+      } else {
+        blit_copy(&src.raw, &self.raw, P::pixel_format(), [0, 0, 0], [0, 0, layer], region);
+      }
+    }
+
+    Ok(())
+  }
+}
+
 /// Whether mipmaps should be generated.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum GenMipmaps {
@@ -740,6 +1182,19 @@ pub enum GenMipmaps {
   No
 }
 
+// Check that a layering and a dimension have an OpenGL texture target.
+pub(crate) fn check_target(l: Layering, d: Dim) -> Result<(), TextureError> {
+  let reason = match (l, d) {
+    (Layering::Layered, Dim::Dim3) => "arrays of 3D textures are not supported",
+    (Layering::Layered, Dim::Dim2Array) => {
+      "arrays of 2D texture arrays are not supported; use Texture<Flat, Dim2Array>"
+    }
+    _ => return Ok(()),
+  };
+
+  Err(TextureError::TextureStorageCreationFailed(reason.into()))
+}
+
 pub(crate) fn opengl_target(l: Layering, d: Dim) -> GLenum {
   match l {
     Layering::Flat => match d {
@@ -747,12 +1202,14 @@ pub(crate) fn opengl_target(l: Layering, d: Dim) -> GLenum {
       Dim::Dim2 => gl::TEXTURE_2D,
       Dim::Dim3 => gl::TEXTURE_3D,
       Dim::Cubemap => gl::TEXTURE_CUBE_MAP,
+      Dim::Dim2Array => gl::TEXTURE_2D_ARRAY,
     },
     Layering::Layered => match d {
       Dim::Dim1 => gl::TEXTURE_1D_ARRAY,
       Dim::Dim2 => gl::TEXTURE_2D_ARRAY,
-      Dim::Dim3 => unimplemented!("3D textures array not supported"),
       Dim::Cubemap => gl::TEXTURE_CUBE_MAP_ARRAY,
+      // rejected by check_target before any texture gets created
+      Dim::Dim3 | Dim::Dim2Array => gl::NONE,
     },
   }
 }
@@ -767,7 +1224,8 @@ pub(crate) unsafe fn create_texture<L, D>(
 where L: Layerable,
       D: Dimensionable {
   set_texture_levels(target, mipmaps);
-  apply_sampler_to_texture(target, sampler);
+  // textures are created without anisotropy
+  apply_sampler_to_texture(target, sampler, sampler.max_anisotropy > 1.);
   create_texture_storage::<L, D>(size, mipmaps, pf)
 }
 
@@ -779,6 +1237,12 @@ where L: Layerable,
       let (format, iformat, encoding) = glf;
 
       match (L::layering(), D::dim()) {
+        // OpenGL ES has no 1D textures
+        (Layering::Flat, Dim::Dim1) if current_profile() == Profile::Es => {
+          let reason = "1D textures are not supported on OpenGL ES";
+          Err(TextureError::TextureStorageCreationFailed(reason.into()))
+        }
+
         // 1D texture
         (Layering::Flat, Dim::Dim1) => {
           create_texture_1d_storage(format, iformat, encoding, D::width(size), mipmaps);
@@ -812,6 +1276,20 @@ where L: Layerable,
           Ok(())
         }
 
+        // 2D texture array
+        (Layering::Flat, Dim::Dim2Array) => {
+          create_texture_2d_array_storage(
+            format,
+            iformat,
+            encoding,
+            D::width(size),
+            D::height(size),
+            D::depth(size),
+            mipmaps,
+          );
+          Ok(())
+        }
+
         // cubemap
         (Layering::Flat, Dim::Cubemap) => {
           create_cubemap_storage(format, iformat, encoding, D::width(size), mipmaps);
@@ -942,6 +1420,37 @@ fn create_texture_3d_storage(
   }
 }
 
+fn create_texture_2d_array_storage(
+  format: GLenum,
+  iformat: GLenum,
+  encoding: GLenum,
+  w: u32,
+  h: u32,
+  layers: u32,
+  mipmaps: usize,
+) {
+  for level in 0..mipmaps {
+    let div = 2u32.pow(level as u32);
+    let w = w / div;
+    let h = h / div;
+
+    unsafe {
+      gl::TexImage3D(
+        gl::TEXTURE_2D_ARRAY,
+        level as GLint,
+        iformat as GLint,
+        w as GLsizei,
+        h as GLsizei,
+        layers as GLsizei,
+        0,
+        format,
+        encoding,
+        ptr::null(),
+      )
+    };
+  }
+}
+
 fn create_cubemap_storage(
   format: GLenum,
   iformat: GLenum,
@@ -977,7 +1486,9 @@ fn set_texture_levels(target: GLenum, mipmaps: usize) {
   }
 }
 
-fn apply_sampler_to_texture(target: GLenum, sampler: Sampler) {
+// Apply a sampler to the bound texture; its anisotropy is only applied if anisotropy is set, since
+// the parameter doesn’t exist without anisotropic filtering support.
+fn apply_sampler_to_texture(target: GLenum, sampler: Sampler, anisotropy: bool) {
   unsafe {
     gl::TexParameteri(target, gl::TEXTURE_WRAP_R, opengl_wrap(sampler.wrap_r) as GLint);
     gl::TexParameteri(target, gl::TEXTURE_WRAP_S, opengl_wrap(sampler.wrap_s) as GLint);
@@ -1009,14 +1520,27 @@ fn apply_sampler_to_texture(target: GLenum, sampler: Sampler) {
         gl::TexParameteri(target, gl::TEXTURE_COMPARE_MODE, gl::NONE as GLint);
       }
     }
+
+    if current_profile() == Profile::Core {
+      gl::TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, sampler.border_color.as_ptr());
+    }
+
+    if anisotropy {
+      gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, sampler.max_anisotropy.max(1.));
+    }
   }
 }
 
+// Anisotropic filtering parameter, missing from the OpenGL 4.5 bindings.
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+
 fn opengl_wrap(wrap: Wrap) -> GLenum {
   match wrap {
     Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
     Wrap::Repeat => gl::REPEAT,
     Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+    Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+    Wrap::MirrorClampToEdge => gl::MIRROR_CLAMP_TO_EDGE,
   }
 }
 
@@ -1065,6 +1589,7 @@ fn set_pack_alignment(skip_bytes: usize) {
 // Upload texels into the texture’s memory. Becareful of the type of texels you send down.
 fn upload_texels<L, D, P, T>(
   target: GLenum,
+  level: usize,
   off: D::Offset,
   size: D::Size,
   texels: &[T]
@@ -1095,7 +1620,7 @@ where L: Layerable,
         Dim::Dim1 => unsafe {
           gl::TexSubImage1D(
             target,
-            0,
+            level as GLint,
             D::x_offset(off) as GLint,
             D::width(size) as GLsizei,
             format,
@@ -1107,7 +1632,7 @@ where L: Layerable,
         Dim::Dim2 => unsafe {
           gl::TexSubImage2D(
             target,
-            0,
+            level as GLint,
             D::x_offset(off) as GLint,
             D::y_offset(off) as GLint,
             D::width(size) as GLsizei,
@@ -1118,10 +1643,10 @@ where L: Layerable,
           )
         }
 
-        Dim::Dim3 => unsafe {
+        Dim::Dim3 | Dim::Dim2Array => unsafe {
           gl::TexSubImage3D(
             target,
-            0,
+            level as GLint,
             D::x_offset(off) as GLint,
             D::y_offset(off) as GLint,
             D::z_offset(off) as GLint,
@@ -1137,7 +1662,7 @@ where L: Layerable,
         Dim::Cubemap => unsafe {
           gl::TexSubImage2D(
             gl::TEXTURE_CUBE_MAP_POSITIVE_X + D::z_offset(off),
-            0,
+            level as GLint,
             D::x_offset(off) as GLint,
             D::y_offset(off) as GLint,
             D::width(size) as GLsizei,
@@ -1155,6 +1680,8 @@ where L: Layerable,
     None => return Err(TextureError::UnsupportedPixelFormat(pf))
   }
 
+  gl_check!("TexSubImage");
+
   Ok(())
 }
 
@@ -1173,6 +1700,15 @@ pub struct Sampler {
   pub mag_filter: MagFilter,
   /// For depth textures, should we perform depth comparison and if so, how?
   pub depth_comparison: Option<DepthComparison>,
+  /// Color sampled outside of the texture with [`Wrap::ClampToBorder`], as RGBA.
+  ///
+  /// Depth textures use its red channel as depth.
+  pub border_color: [f32; 4],
+  /// Maximum degree of anisotropy used when filtering; `1.` disables anisotropic filtering.
+  ///
+  /// Values are clamped by the driver and ignored if [`Feature::AnisotropicFiltering`] is not
+  /// supported.
+  pub max_anisotropy: f32,
 }
 
 /// Default value is as following:
@@ -1185,7 +1721,204 @@ impl Default for Sampler {
       min_filter: MinFilter::NearestMipmapLinear,
       mag_filter: MagFilter::Linear,
       depth_comparison: None,
+      border_color: [0., 0., 0., 0.],
+      max_anisotropy: 1.,
+    }
+  }
+}
+
+impl Sampler {
+  /// Nearest filtering, clamping to edges. Typical for integer textures and pixel art.
+  pub fn nearest_clamp() -> Self {
+    Sampler {
+      min_filter: MinFilter::Nearest,
+      mag_filter: MagFilter::Nearest,
+      ..Sampler::default()
+    }
+  }
+
+  /// Linear filtering, repeating. Typical for tiled textures without mipmaps.
+  pub fn linear_repeat() -> Self {
+    Sampler {
+      wrap_r: Wrap::Repeat,
+      wrap_s: Wrap::Repeat,
+      wrap_t: Wrap::Repeat,
+      min_filter: MinFilter::Linear,
+      mag_filter: MagFilter::Linear,
+      ..Sampler::default()
+    }
+  }
+
+  /// Trilinear filtering with an anisotropy of 16, repeating. Typical for mipmapped surface
+  /// textures seen at grazing angles.
+  pub fn trilinear_aniso16() -> Self {
+    Sampler {
+      min_filter: MinFilter::LinearMipmapLinear,
+      max_anisotropy: 16.,
+      ..Sampler::linear_repeat()
+    }
+  }
+
+  /// Linear filtering with depth comparison, clamping to edges. Sampling a depth texture with a
+  /// shadow sampler then yields the 2×2 percentage-closer filtering of the shadow test.
+  pub fn shadow_pcf() -> Self {
+    Sampler {
+      min_filter: MinFilter::Linear,
+      mag_filter: MagFilter::Linear,
+      depth_comparison: Some(DepthComparison::LessOrEqual),
+      ..Sampler::default()
+    }
+  }
+}
+
+// Sampler without the settings the context doesn’t support.
+fn supported_sampler(state: &GraphicsState, sampler: Sampler) -> Sampler {
+  let border = current_profile() == Profile::Core;
+  let mirror_clamp = state.supports(Feature::MirrorClampToEdge);
+  let wrap = |wrap| match wrap {
+    Wrap::ClampToBorder if !border => Wrap::ClampToEdge,
+    Wrap::MirrorClampToEdge if !mirror_clamp => Wrap::MirroredRepeat,
+    _ => wrap,
+  };
+
+  let sampler = Sampler {
+    wrap_r: wrap(sampler.wrap_r),
+    wrap_s: wrap(sampler.wrap_s),
+    wrap_t: wrap(sampler.wrap_t),
+    ..sampler
+  };
+
+  if state.supports(Feature::AnisotropicFiltering) {
+    sampler
+  } else {
+    Sampler { max_anisotropy: 1., ..sampler }
+  }
+}
+
+// Depth of a copied region: cubemaps are copied face by face.
+fn copied_depth<D>(size: D::Size) -> u32 where D: Dimensionable {
+  match D::dim() {
+    Dim::Cubemap => 1,
+    _ => D::depth(size),
+  }
+}
+
+// Number of raw components of a texel: one for packed formats, one per channel otherwise.
+fn raw_texel_len(pf: PixelFormat) -> usize {
+  match pf.format {
+    Format::RGBA(Size::Ten, Size::Ten, Size::Ten, Size::Two) => 1,
+    _ => pf.canals_len(),
+  }
+}
+
+// Whether a region at offset fits in a texture of size tex_size.
+fn region_fits<D>(tex_size: D::Size, offset: [u32; 3], region: [u32; 3]) -> bool
+where D: Dimensionable {
+  let tex = [D::width(tex_size), D::height(tex_size), D::depth(tex_size)];
+  (0 .. 3).all(|i| offset[i] as u64 + region[i] as u64 <= tex[i] as u64)
+}
+
+// Copy a region between textures with framebuffer blits, layer by layer.
+unsafe fn blit_copy(
+  src: &RawTexture,
+  dst: &RawTexture,
+  pf: PixelFormat,
+  src_xyz: [u32; 3],
+  dst_xyz: [u32; 3],
+  region: [u32; 3],
+) {
+  let (attachment, mask) = if pf.is_depth_pixel() {
+    (gl::DEPTH_ATTACHMENT, gl::DEPTH_BUFFER_BIT)
+  } else {
+    (gl::COLOR_ATTACHMENT0, gl::COLOR_BUFFER_BIT)
+  };
+
+  let mut state = dst.state.borrow_mut();
+  let (draw_fb, read_fb) = state.bound_framebuffers();
+  let mut framebuffers: [GLuint; 2] = [0, 0];
+
+  gl::GenFramebuffers(2, framebuffers.as_mut_ptr());
+  state.bind_read_framebuffer(framebuffers[0]);
+  state.bind_draw_framebuffer(framebuffers[1]);
+
+  for z in 0 .. region[2] {
+    attach_layer(gl::READ_FRAMEBUFFER, attachment, src, src_xyz[2] + z);
+    attach_layer(gl::DRAW_FRAMEBUFFER, attachment, dst, dst_xyz[2] + z);
+
+    gl::BlitFramebuffer(
+      src_xyz[0] as GLint,
+      src_xyz[1] as GLint,
+      (src_xyz[0] + region[0]) as GLint,
+      (src_xyz[1] + region[1]) as GLint,
+      dst_xyz[0] as GLint,
+      dst_xyz[1] as GLint,
+      (dst_xyz[0] + region[0]) as GLint,
+      (dst_xyz[1] + region[1]) as GLint,
+      mask,
+      gl::NEAREST,
+    );
+    gl_check!("BlitFramebuffer", dst.handle);
+  }
+
+  state.bind_read_framebuffer(read_fb);
+  state.bind_draw_framebuffer(draw_fb);
+  gl::DeleteFramebuffers(2, framebuffers.as_ptr());
+}
+
+// Read the base layer of a texture with glReadPixels, which is the only readback path OpenGL ES
+// has.
+unsafe fn read_texels_through_framebuffer<T>(
+  raw: &RawTexture,
+  pf: PixelFormat,
+  format: GLenum,
+  ty: GLenum,
+  [w, h]: [u32; 2],
+  texels: &mut [T],
+) {
+  let mut state = raw.state.borrow_mut();
+  let (_, read_fb) = state.bound_framebuffers();
+  let mut framebuffer: GLuint = 0;
+
+  gl::GenFramebuffers(1, &mut framebuffer);
+  state.bind_read_framebuffer(framebuffer);
+  attach_layer(gl::READ_FRAMEBUFFER, gl::COLOR_ATTACHMENT0, raw, 0);
+
+  let skip_bytes = (pf.format.size() * w as usize) % 8;
+  set_pack_alignment(skip_bytes);
+
+  gl::ReadPixels(
+    0,
+    0,
+    w as GLsizei,
+    h as GLsizei,
+    format,
+    ty,
+    texels.as_mut_ptr() as *mut c_void,
+  );
+  gl_check!("ReadPixels", raw.handle);
+
+  state.bind_read_framebuffer(read_fb);
+  gl::DeleteFramebuffers(1, &framebuffer);
+}
+
+// Attach a layer – or a face, for cubemaps – of the base level of a texture to a framebuffer.
+unsafe fn attach_layer(fb_target: GLenum, attachment: GLenum, texture: &RawTexture, layer: u32) {
+  match texture.target {
+    gl::TEXTURE_1D => gl::FramebufferTexture(fb_target, attachment, texture.handle, 0),
+
+    gl::TEXTURE_2D => {
+      gl::FramebufferTexture2D(fb_target, attachment, gl::TEXTURE_2D, texture.handle, 0)
     }
+
+    gl::TEXTURE_CUBE_MAP => gl::FramebufferTexture2D(
+      fb_target,
+      attachment,
+      gl::TEXTURE_CUBE_MAP_POSITIVE_X + layer,
+      texture.handle,
+      0,
+    ),
+
+    _ => gl::FramebufferTextureLayer(fb_target, attachment, texture.handle, 0, layer as GLint),
   }
 }
 
@@ -1206,7 +1939,29 @@ pub enum TextureError {
   ///
   /// Sometimes, some hardware might not support a given pixel format (or the format exists on
   /// the interface side but doesn’t in the implementation). That error represents such a case.
-  UnsupportedPixelFormat(PixelFormat)
+  UnsupportedPixelFormat(PixelFormat),
+  /// Invalid mipmap level.
+  ///
+  /// The first [`usize`] is the level you asked for and the second one is the number of mipmaps
+  /// the texture has.
+  InvalidMipmapLevel(usize, usize),
+  /// A buffer cannot be viewed as texels by a buffer texture.
+  ///
+  /// The first [`usize`] is the size in bytes of the items of the buffer and the second one is the
+  /// size in bytes of a texel, which must divide the former.
+  UnalignedBufferTexels(usize, usize),
+  /// A copied region doesn’t fit in the source or destination texture.
+  InvalidCopyRegion,
+  /// A slice is out of a 3D texture.
+  ///
+  /// The first [`u32`] is the slice and the second one is the depth of the texture.
+  InvalidSlice(u32, u32),
+  /// The texture cannot be used anymore.
+  Poisoned(PoisonCause),
+  /// No sampler preset has the carried name; see [`SamplerPresets`].
+  ///
+  /// [`SamplerPresets`]: crate::sampler_presets::SamplerPresets
+  UnknownSamplerPreset(String),
 }
 
 impl fmt::Display for TextureError {
@@ -1223,6 +1978,34 @@ impl fmt::Display for TextureError {
       TextureError::UnsupportedPixelFormat(fmt) => {
         write!(f, "unsupported pixel format: {:?}", fmt)
       }
+
+      TextureError::InvalidMipmapLevel(level, mipmaps) => {
+        write!(f, "invalid mipmap level {}: the texture has {} mipmaps", level, mipmaps)
+      }
+
+      TextureError::UnalignedBufferTexels(item_bytes, texel_bytes) => write!(
+        f,
+        "buffer items of {} bytes cannot be viewed as texels of {} bytes",
+        item_bytes, texel_bytes
+      ),
+
+      TextureError::InvalidCopyRegion => {
+        f.write_str("copied region doesn’t fit in the source or destination texture")
+      }
+
+      TextureError::InvalidSlice(z, depth) => {
+        write!(f, "invalid slice {}: the texture has {} slices", z, depth)
+      }
+
+      TextureError::Poisoned(ref cause) => write!(f, "poisoned texture: {}", cause),
+
+      TextureError::UnknownSamplerPreset(ref name) => write!(f, "unknown sampler preset: {}", name),
     }
   }
 }
+
+impl From<PoisonCause> for TextureError {
+  fn from(cause: PoisonCause) -> Self {
+    TextureError::Poisoned(cause)
+  }
+}
@@ -8,7 +8,7 @@
 //! [`VertexAttribDim`]: crate::vertex::VertexAttribDim
 //! [`VertexAttribType`]: crate::vertex::VertexAttribType
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 /// A type that can be used as a [`Vertex`] has to implement that trait – it must provide an
 /// associated [`VertexDesc`] value via a function call. This associated value gives enough
@@ -226,6 +226,64 @@ pub struct SemanticsDesc {
   pub index: usize,
   /// Name of the semantics (used in shaders).
   pub name: String,
+  /// Interpolation intent of the semantics, if declared.
+  pub interpolation: Option<SemanticsInterpolation>,
+}
+
+/// Interpolation qualifier of a fragment shader input.
+///
+/// Multisampled rendering evaluates inputs at the center of pixels by default, even when that
+/// center is outside of the primitive – which shows as artifacts on the edges of primitives, e.g.
+/// when sampling texture atlases. `Centroid` and `Sample` evaluate them within the primitive.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Interpolation {
+  /// Perspective-correct interpolation (`smooth`, the default).
+  Smooth,
+  /// No interpolation: the value of the provoking vertex is used (`flat`). Integral inputs must be
+  /// flat.
+  Flat,
+  /// Linear interpolation in screen space (`noperspective`).
+  NoPerspective,
+  /// Perspective-correct interpolation at a location within the primitive (`centroid`).
+  Centroid,
+  /// Perspective-correct interpolation at each sample, running the fragment shader per sample
+  /// (`sample`).
+  Sample,
+}
+
+impl Interpolation {
+  /// GLSL qualifier of the interpolation.
+  pub fn qualifier(self) -> &'static str {
+    match self {
+      Interpolation::Smooth => "smooth",
+      Interpolation::Flat => "flat",
+      Interpolation::NoPerspective => "noperspective",
+      Interpolation::Centroid => "centroid",
+      Interpolation::Sample => "sample",
+    }
+  }
+}
+
+impl fmt::Display for Interpolation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    f.write_str(self.qualifier())
+  }
+}
+
+/// Interpolation intent of a semantics.
+///
+/// Vertex attributes are not interpolated themselves: the vertex shader forwards them to the
+/// fragment shader through _varyings_. This declares which fragment shader input carries the
+/// attribute and how it must be interpolated. Programs check it once linked, reporting
+/// mismatches as [`VertexAttribWarning::InterpolationMismatch`].
+///
+/// [`VertexAttribWarning::InterpolationMismatch`]: crate::shader::program::VertexAttribWarning::InterpolationMismatch
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SemanticsInterpolation {
+  /// Name of the fragment shader input carrying the attribute.
+  pub varying: String,
+  /// Expected interpolation of that input.
+  pub interpolation: Interpolation,
 }
 
 /// Class of types that have an associated value which type implements [`Semantics`], defining
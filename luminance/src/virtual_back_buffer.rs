@@ -0,0 +1,208 @@
+//! Virtual back buffers.
+//!
+//! Rendering offscreen and presenting the result to the back buffer at the end of the frame is
+//! needed for resolution scaling, post-processing anti-aliasing, HDR formats, etc. Switching
+//! between that and rendering to the back buffer directly usually means restructuring the frame
+//! code, since both framebuffers have different types.
+//!
+//! A [`VirtualBackBuffer`] hides that choice: the frame code renders to it with
+//! [`VirtualBackBuffer::pipeline`] and ends the frame with [`VirtualBackBuffer::present`], whether
+//! it renders to the back buffer directly or to an offscreen framebuffer – scaled by a factor and
+//! with the color and depth slots given as type parameters – blitted to the back buffer when
+//! presenting.
+//!
+//! ```ignore
+//! let mut vbb = VirtualBackBuffer::<RGBA16F, Depth32F>::offscreen(&mut surface, size, 0.75)?;
+//!
+//! // each frame
+//! vbb.pipeline(&mut surface.pipeline_builder(), &PipelineState::default(), |pipeline, shd_gate| {
+//!   // render the scene
+//! });
+//! vbb.present()?;
+//!
+//! // whenever the user toggles the option
+//! vbb.set_offscreen(&mut surface, false)?;
+//! ```
+
+use crate::context::GraphicsContext;
+use crate::framebuffer::{
+  BlitBuffers, ColorSlot, DepthSlot, Framebuffer, FramebufferError, RenderTarget,
+};
+use crate::pipeline::{Builder, Pipeline, PipelineState, ShadingGate};
+use crate::texture::{Dim2, Flat, MagFilter};
+
+/// A back buffer rendered to either directly or through an offscreen framebuffer.
+///
+/// The offscreen framebuffer has `CS` as color slot and `DS` as depth slot. Its color slot must
+/// be blittable to the back buffer: normalized and floating-point formats are, integral ones are
+/// not.
+pub struct VirtualBackBuffer<CS, DS>
+where CS: ColorSlot<Flat, Dim2>,
+      DS: DepthSlot<Flat, Dim2> {
+  back_buffer: Framebuffer<Flat, Dim2, (), ()>,
+  offscreen: Option<RenderTarget<Flat, Dim2, CS, DS>>,
+  scale: f32,
+  filter: MagFilter,
+}
+
+impl<CS, DS> VirtualBackBuffer<CS, DS>
+where CS: ColorSlot<Flat, Dim2>,
+      DS: DepthSlot<Flat, Dim2> {
+  /// Create a virtual back buffer rendering to the back buffer of size `size` directly.
+  ///
+  /// The scale is `1.` and the filter [`MagFilter::Linear`] when enabling offscreen rendering.
+  pub fn direct<C>(ctx: &mut C, size: [u32; 2]) -> Self where C: GraphicsContext {
+    VirtualBackBuffer {
+      back_buffer: Framebuffer::back_buffer(ctx, size),
+      offscreen: None,
+      scale: 1.,
+      filter: MagFilter::Linear,
+    }
+  }
+
+  /// Create a virtual back buffer rendering to an offscreen framebuffer, `scale` times the size of
+  /// the back buffer of size `size`.
+  pub fn offscreen<C>(ctx: &mut C, size: [u32; 2], scale: f32) -> Result<Self, FramebufferError>
+  where C: GraphicsContext {
+    let mut vbb = Self::direct(ctx, size);
+    vbb.scale = scale;
+    vbb.set_offscreen(ctx, true)?;
+
+    Ok(vbb)
+  }
+
+  /// Switch between offscreen and direct rendering.
+  ///
+  /// Enabling offscreen rendering allocates the offscreen framebuffer; disabling it drops it.
+  pub fn set_offscreen<C>(&mut self, ctx: &mut C, offscreen: bool) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    match (offscreen, self.offscreen.is_some()) {
+      (true, false) => {
+        let size = self.render_size();
+        self.offscreen = Some(RenderTarget::new(ctx, size, 0)?);
+      }
+
+      (false, true) => self.offscreen = None,
+
+      _ => (),
+    }
+
+    Ok(())
+  }
+
+  /// Whether rendering goes through the offscreen framebuffer.
+  #[inline]
+  pub fn is_offscreen(&self) -> bool {
+    self.offscreen.is_some()
+  }
+
+  /// Change the scale of the offscreen framebuffer relative to the back buffer.
+  ///
+  /// The offscreen framebuffer is re-created, losing its content, if its size changes.
+  pub fn set_scale<C>(&mut self, ctx: &mut C, scale: f32) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    self.scale = scale;
+    self.resize_offscreen(ctx)
+  }
+
+  /// Scale of the offscreen framebuffer relative to the back buffer.
+  #[inline]
+  pub fn scale(&self) -> f32 {
+    self.scale
+  }
+
+  /// Change the filter used to scale the offscreen framebuffer when presenting.
+  #[inline]
+  pub fn set_filter(&mut self, filter: MagFilter) {
+    self.filter = filter;
+  }
+
+  /// Filter used to scale the offscreen framebuffer when presenting.
+  #[inline]
+  pub fn filter(&self) -> MagFilter {
+    self.filter
+  }
+
+  /// Resize the back buffer, typically when the window gets resized.
+  ///
+  /// The offscreen framebuffer follows, losing its content if its size changes.
+  pub fn resize<C>(&mut self, ctx: &mut C, size: [u32; 2]) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    self.back_buffer = Framebuffer::back_buffer(ctx, size);
+    self.resize_offscreen(ctx)
+  }
+
+  /// Size of the back buffer.
+  pub fn size(&self) -> [u32; 2] {
+    [self.back_buffer.width(), self.back_buffer.height()]
+  }
+
+  /// Size frames are rendered at: the size of the offscreen framebuffer when rendering offscreen
+  /// – or the one it would have otherwise –, the size of the back buffer when not.
+  pub fn render_size(&self) -> [u32; 2] {
+    let [w, h] = self.size();
+    let scaled = |x| ((x as f32 * self.scale).round() as u32).max(1);
+
+    [scaled(w), scaled(h)]
+  }
+
+  /// The back buffer.
+  #[inline]
+  pub fn back_buffer(&self) -> &Framebuffer<Flat, Dim2, (), ()> {
+    &self.back_buffer
+  }
+
+  /// The offscreen framebuffer, if rendering offscreen.
+  ///
+  /// Post-processing passes read its color slot before presenting.
+  pub fn offscreen_framebuffer(&self) -> Option<&Framebuffer<Flat, Dim2, CS, DS>> {
+    self.offscreen.as_ref().map(RenderTarget::framebuffer)
+  }
+
+  /// Create a pipeline rendering to the virtual back buffer.
+  ///
+  /// This is [`Builder::pipeline`] called with either the offscreen framebuffer or the back buffer.
+  pub fn pipeline<'b, C, F>(
+    &self,
+    builder: &'b mut Builder<C>,
+    pipeline_state: &PipelineState,
+    f: F,
+  )
+  where C: ?Sized + GraphicsContext,
+        F: FnOnce(Pipeline<'b>, ShadingGate<'b, C>) {
+    match self.offscreen {
+      Some(ref offscreen) => builder.pipeline(offscreen.framebuffer(), pipeline_state, f),
+      None => builder.pipeline(&self.back_buffer, pipeline_state, f),
+    }
+  }
+
+  /// Present the frame: blit the offscreen framebuffer to the whole back buffer, scaling it with
+  /// the filter of the virtual back buffer.
+  ///
+  /// This does nothing when rendering to the back buffer directly. Buffers still have to be
+  /// swapped afterwards.
+  pub fn present(&self) -> Result<(), FramebufferError> {
+    match self.offscreen {
+      Some(ref offscreen) => offscreen.blit(
+        &self.back_buffer,
+        offscreen.rect(),
+        self.back_buffer.rect(),
+        BlitBuffers::COLOR,
+        self.filter,
+      ),
+
+      None => Ok(()),
+    }
+  }
+
+  // Make the offscreen framebuffer, if any, match the scaled back buffer size.
+  fn resize_offscreen<C>(&mut self, ctx: &mut C) -> Result<(), FramebufferError>
+  where C: GraphicsContext {
+    let size = self.render_size();
+
+    match self.offscreen {
+      Some(ref mut offscreen) => offscreen.resize(ctx, size),
+      None => Ok(()),
+    }
+  }
+}